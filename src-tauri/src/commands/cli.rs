@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::command;
+use chrono::{Duration, Utc};
+use crate::commands::registry::{filter_by_install_date, filter_by_version, get_installed_programs, import_reg_file, scan_external_drive};
+use crate::services::install_date::DateConfidence;
+use crate::services::version::ProgramVersion;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CliCommand {
@@ -24,12 +28,60 @@ pub async fn execute_cli_command(cli_command: CliCommand) -> Result<CliResponse,
     
     match cli_command.command.as_str() {
         "scan" => {
-            // Future: Implement actual scanning via CLI
-            Ok(CliResponse {
-                success: true,
-                output: "CLI scanning functionality will be implemented in future versions".to_string(),
-                error: None,
-            })
+            // Offline scan of another Windows installation's registry hives, or of a customer's .reg export;
+            // otherwise this machine
+            let scan = || if let Some(drive) = cli_command.options.get("external") {
+                scan_external_drive(drive.clone())
+            } else if let Some(file) = cli_command.options.get("reg-file") {
+                import_reg_file(file.clone())
+            } else {
+                get_installed_programs()
+            };
+
+            // Incident response audits: --installed-within 7 keeps what was installed in the last week
+            let installed_within = match cli_command.options.get("installed-within").map(|days| days.parse::<i64>()) {
+                Some(Ok(days)) => Some(days),
+                Some(Err(_)) => return Ok(CliResponse {
+                    success: false,
+                    output: String::new(),
+                    error: Some("--installed-within expects a number of days".to_string()),
+                }),
+                None => None,
+            };
+
+            // --older-than 2.0 keeps programs below that version
+            let older_than = match cli_command.options.get("older-than").map(|raw| ProgramVersion::parse(raw)) {
+                Some(Some(version)) => Some(version),
+                Some(None) => return Ok(CliResponse {
+                    success: false,
+                    output: String::new(),
+                    error: Some("--older-than expects a version such as 2.0.1".to_string()),
+                }),
+                None => None,
+            };
+
+            let result = scan().map(|programs| match installed_within {
+                Some(days) => {
+                    let since = Utc::now().date_naive() - Duration::days(days);
+                    filter_by_install_date(programs, Some(since), None, DateConfidence::Low)
+                }
+                None => programs,
+            }).map(|programs| match &older_than {
+                Some(version) => filter_by_version(programs, None, Some(version)),
+                None => programs,
+            });
+            match result {
+                Ok(programs) => Ok(CliResponse {
+                    success: true,
+                    output: serde_json::to_string_pretty(&programs).map_err(|e| e.to_string())?,
+                    error: None,
+                }),
+                Err(e) => Ok(CliResponse {
+                    success: false,
+                    output: String::new(),
+                    error: Some(e),
+                }),
+            }
         }
         "export" => {
             // Future: Implement export via CLI
@@ -47,7 +99,10 @@ SoftwareScope CLI Commands (Coming Soon):
 
   scan [options]           - Scan installed programs
     --remote <host>        - Scan remote computer
-    --external <drive>     - Scan external drive
+    --external <drive>     - Scan an offline Windows drive's registry hives
+    --reg-file <file>      - Scan a regedit export (.reg) of the Uninstall keys
+    --installed-within <n> - Only list programs installed in the last n days
+    --older-than <version> - Only list programs older than the given version
     --format <format>      - Output format (json, csv, xml)
     --output <file>        - Output file path
 
@@ -62,6 +117,10 @@ Examples:
   software-scope scan --format json --output programs.json
   software-scope export --format csv --output programs.csv
   software-scope scan --remote 192.168.1.100
+  software-scope scan --external E:\
+  software-scope scan --reg-file uninstall.reg
+  software-scope scan --external E:\ --installed-within 7
+  software-scope scan --installed-within 30 --older-than 2.0
 "#.to_string(),
                 error: None,
             })
@@ -0,0 +1,156 @@
+// What a program's binaries actually are. The registry view an entry sits in says where the installer
+// wrote it, not what it installed; the headers of the main executable and the uninstaller say which
+// machine they run on, whether they are .NET, and who signed them
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::commands::registry::{with_live_environments, ProgramEnvironments, ProgramInfo};
+use crate::services::command_line::{parse_command_line, CommandKind};
+use crate::services::pe::{read_image_info, ImageInfo};
+
+// Executables in an install folder that are not the program itself
+const AUXILIARY_NAMES: [&str; 12] = ["unins", "uninst", "setup", "install", "update", "updater", "crash", "report", "helper", "elevat", "vcredist", "dotnet"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutableRole {
+    MainExecutable,
+    Uninstaller,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExecutableAnalysis {
+    pub main_executable: Option<ImageInfo>,
+    pub uninstaller: Option<ImageInfo>,
+    pub errors: Vec<String>,               // Executables that were found but could not be read
+    pub architecture_mismatch: bool,       // The main executable is not built for the registry view it is listed in
+    pub analyzed_at: DateTime<Utc>,
+}
+
+/// Read the headers and signatures of the given programs' main executables and uninstallers, and fill
+/// in their `executables`
+#[tauri::command]
+pub async fn analyze_executables(programs: Vec<ProgramInfo>) -> Result<Vec<ProgramInfo>, String> {
+    // Signed executables are read whole; keep that off the async runtime's worker threads
+    tokio::task::spawn_blocking(move || {
+        let mut programs = programs;
+        with_live_environments(|environments| attach_executable_analysis(&mut programs, environments));
+        programs
+    })
+    .await
+    .map_err(|e| format!("Executable analysis did not complete: {}", e))
+}
+
+pub fn attach_executable_analysis(programs: &mut [ProgramInfo], environments: &ProgramEnvironments) {
+    for program in programs.iter_mut() {
+        program.executables = Some(analyze_program_executables(program, environments));
+    }
+}
+
+pub fn analyze_program_executables(program: &ProgramInfo, environments: &ProgramEnvironments) -> ExecutableAnalysis {
+    let mut errors = Vec::new();
+    let mut read = |path: Option<String>| -> Option<ImageInfo> {
+        match read_image_info(Path::new(&path?)) {
+            Ok(info) => Some(info),
+            Err(error) => {
+                errors.push(error);
+                None
+            }
+        }
+    };
+
+    let uninstaller_path = uninstaller_path(program, environments);
+    let main_executable = read(main_executable_path(program, environments, uninstaller_path.as_deref()));
+    let uninstaller = read(uninstaller_path);
+
+    let architecture_mismatch = main_executable.as_ref()
+        .map_or(false, |info| is_architecture_mismatch(&program.architecture, info));
+    if architecture_mismatch {
+        println!("DEBUG: {} is listed as {} but ships a {} executable", program.name, program.architecture, main_executable.as_ref().map(|info| info.architecture()).unwrap_or_default());
+    }
+
+    ExecutableAnalysis {
+        main_executable,
+        uninstaller,
+        errors,
+        architecture_mismatch,
+        analyzed_at: Utc::now(),
+    }
+}
+
+/// The program's own executable: DisplayIcon when it points at one, the portable executable itself,
+/// or the best match in the install folder. Installers often take the uninstaller's icon, so the
+/// uninstaller is passed in to be passed over
+pub fn main_executable_path(program: &ProgramInfo, environments: &ProgramEnvironments, uninstaller: Option<&str>) -> Option<String> {
+    let is_executable = |path: &str| path.to_lowercase().ends_with(".exe") && Path::new(path).is_file()
+        && uninstaller.map_or(true, |uninstaller| !uninstaller.eq_ignore_ascii_case(path));
+
+    if let Some(icon_path) = program.icon_path.as_deref().filter(|path| is_executable(path)) {
+        return Some(icon_path.to_string());
+    }
+    let location = program.install_location.as_deref()
+        .map(|location| environments.expand_path(program, location.trim().trim_matches('"')).trim_end_matches('\\').to_string())
+        .filter(|location| !location.is_empty())?;
+    // Portable entries record the executable itself as their location
+    if is_executable(&location) {
+        return Some(location);
+    }
+    if !Path::new(&location).is_dir() {
+        return None;
+    }
+    best_executable_in_folder(Path::new(&location), &program.name, &is_executable)
+}
+
+// The top-level executable whose name is closest to the program's; the largest one breaks ties, as
+// helpers tend to be small
+fn best_executable_in_folder(folder: &Path, program_name: &str, is_executable: &dyn Fn(&str) -> bool) -> Option<String> {
+    let squash = |text: &str| text.to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect::<String>();
+    let name = squash(program_name);
+    let words: Vec<String> = program_name.split_whitespace().map(squash).filter(|word| word.len() > 2).collect();
+
+    std::fs::read_dir(folder).ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path().to_string_lossy().to_string();
+            if !is_executable(&path) {
+                return None;
+            }
+            let stem = squash(&entry.path().file_stem()?.to_string_lossy());
+            if AUXILIARY_NAMES.iter().any(|auxiliary| stem.starts_with(auxiliary)) {
+                return None;
+            }
+            let score = if stem == name {
+                3
+            } else if words.iter().any(|word| stem.contains(word.as_str())) {
+                2
+            } else {
+                1
+            };
+            let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            Some((score, size, path))
+        })
+        .max_by_key(|(score, size, _)| (*score, *size))
+        .map(|(_, _, path)| path)
+}
+
+/// The executable the uninstall command runs; msiexec uninstalls have none of their own
+pub fn uninstaller_path(program: &ProgramInfo, environments: &ProgramEnvironments) -> Option<String> {
+    let command = parse_command_line(&environments.expand_path(program, program.uninstall_string.as_deref()?))?;
+    let path = match command.kind {
+        CommandKind::Executable => command.executable,
+        CommandKind::Rundll32 => command.dll_path?,
+        CommandKind::Msiexec => return None,
+    };
+    Some(path).filter(|path| Path::new(path).is_file())
+}
+
+// The 64-bit view should list 64-bit programs and the 32-bit view 32-bit ones. ARM64 Windows lists ARM64
+// programs in the 64-bit view, and "Any CPU" .NET programs fit either
+fn is_architecture_mismatch(listed: &str, info: &ImageInfo) -> bool {
+    match (listed, info.architecture()) {
+        (_, "Neutral") | (_, "Unknown") => false,
+        ("64-bit", found) => found == "32-bit" || found == "ARM",
+        ("32-bit", found) => found == "64-bit" || found == "ARM64",
+        _ => false,
+    }
+}
@@ -1,5 +1,14 @@
 use serde::{Serialize, Deserialize};
+use crate::commands::executables::{ExecutableAnalysis, ExecutableRole};
+use crate::commands::file_hashes::FileHashInventory;
+use crate::commands::health::{EntryHealth, EntryHealthInfo};
+use crate::commands::install_size::{format_bytes, InstallSizeInfo};
 use crate::commands::registry::ProgramInfo;
+use crate::commands::signature_trust::{SignatureTrustItem, SignatureTrustReport};
+use crate::commands::uninstall_queue::UninstallReport;
+use crate::services::install_date::InstallDateSource;
+use crate::services::pe::ImageInfo;
+use crate::services::registry_source::RegistryProvenance;
 use std::error::Error;
 use std::fs::File;
 use std::path::Path;
@@ -23,9 +32,14 @@ fn export_to_csv(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
         "Registry Name",
         "Publisher",
         "Version",
+        "Normalized Version",
         "Registry Time",
         "Install Date",
+        "Install Date (Parsed)",
+        "Install Date Source",
+        "Install Date Confidence",
         "Installed For",
+        "User SID",
         "Install Location",
         "Install Source",
         "Uninstall String",
@@ -40,28 +54,77 @@ fn export_to_csv(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
         "Release Type",
         "Icon Path",
         "MSI Filename",
-        "Estimated Size",
+        "Estimated Size (KB)",
+        "Measured Size (Bytes)",
+        "Allocated Size (Bytes)",
+        "Size Measurement Complete",
+        "Size Discrepancy",
         "Attributes",
         "Language",
         "Parent Key Name",
         "Registry Path",
+        "Registry Hive",
+        "Registry View",
+        "Registry Key Last Write",
+        "Registry Value Types",
         "Program Type",
         "Is Windows Installer",
+        "MSI Product Code",
+        "MSI Upgrade Code",
+        "MSI Local Package",
+        "MSI Context",
+        "Installer Technology",
+        "Installer Technology Confidence",
+        "Entry Health",
+        "Entry Issues",
         "Architecture",
+        "Main Executable",
+        "Executable Architecture",
+        "Executable Subsystem",
+        "Executable .NET Runtime",
+        "Architecture Mismatch",
+        "Signed By",
+        "Signature Issuer",
+        "Signature Timestamp",
+        "Signature Digest Algorithm",
+        "Uninstaller Architecture",
+        "Uninstaller Signed By",
+        "Main Executable SHA-256",
+        "Main Executable SHA-1",
+        "Main Executable MD5",
+        "Uninstaller SHA-256",
+        "IOC Hits",
         "Installation Source",
         "Is VF Managed",
     ])?;
 
     // Write data
     for program in programs {
+        let provenance = program.registry_provenance.as_ref();
+        let installed_on = program.installed_on.as_ref();
+        let msi = program.msi.as_ref();
+        let technology = program.installer_technology.as_ref();
+        let health = program.health.as_ref();
+        let measured = program.install_size.as_ref().and_then(|size| size.measured.as_ref());
+        let executables = program.executables.as_ref();
+        let main_executable = executables.and_then(|e| e.main_executable.as_ref());
+        let uninstaller = executables.and_then(|e| e.uninstaller.as_ref());
+        let signature = main_executable.and_then(|e| e.signature.as_ref());
+        let file_hashes = program.file_hashes.as_ref();
+        let main_hashes = file_hashes.and_then(|h| h.main_executable.as_ref()).map(|f| &f.hashes);
         writer.write_record(&[
             &program.name,
             &program.registry_name,
             program.publisher.as_deref().unwrap_or(""),
             program.version.as_deref().unwrap_or(""),
+            program.version_info.as_ref().map(|v| v.normalized.as_str()).unwrap_or(""),
             program.registry_time.as_deref().unwrap_or(""),
             program.install_date.as_deref().unwrap_or(""),
+            &installed_on.map(|d| d.date.to_string()).unwrap_or_default(),
+            &installed_on.map(|d| format!("{:?}", d.source)).unwrap_or_default(),
+            &installed_on.map(|d| format!("{:?}", d.confidence)).unwrap_or_default(),
             program.installed_for.as_deref().unwrap_or(""),
+            program.user_sid.as_deref().unwrap_or(""),
             program.install_location.as_deref().unwrap_or(""),
             program.install_source.as_deref().unwrap_or(""),
             program.uninstall_string.as_deref().unwrap_or(""),
@@ -77,13 +140,45 @@ fn export_to_csv(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
             program.icon_path.as_deref().unwrap_or(""),
             program.msi_filename.as_deref().unwrap_or(""),
             program.estimated_size.map(|s| s.to_string()).as_deref().unwrap_or(""),
+            &measured.map(|m| m.logical_bytes.to_string()).unwrap_or_default(),
+            &measured.map(|m| m.allocated_bytes.to_string()).unwrap_or_default(),
+            &measured.map(|m| m.complete.to_string()).unwrap_or_default(),
+            &program.install_size.as_ref().and_then(|size| size.discrepancy).map(|d| format!("{:?}", d)).unwrap_or_default(),
             program.attributes.as_deref().unwrap_or(""),
             program.language.as_deref().unwrap_or(""),
             program.parent_key_name.as_deref().unwrap_or(""),
             &program.registry_path,
+            provenance.map(|p| p.hive.name()).unwrap_or(""),
+            provenance.map(|p| p.view.name()).unwrap_or(""),
+            &provenance.and_then(|p| p.last_write_time).map(|t| t.to_rfc3339()).unwrap_or_default(),
+            &provenance.map(format_value_types).unwrap_or_default(),
             &program.program_type,
             &program.is_windows_installer.to_string(),
+            msi.map(|m| m.product_code.as_str()).unwrap_or(""),
+            msi.and_then(|m| m.upgrade_code.as_deref()).unwrap_or(""),
+            msi.and_then(|m| m.local_package.as_deref()).unwrap_or(""),
+            &msi.map(|m| format!("{:?}", m.context)).unwrap_or_default(),
+            &technology.map(|t| format!("{:?}", t.engine)).unwrap_or_default(),
+            &technology.map(|t| format!("{:?}", t.confidence)).unwrap_or_default(),
+            &health.map(|h| format!("{:?}", h.status)).unwrap_or_default(),
+            &health.map(format_entry_issues).unwrap_or_default(),
             &program.architecture,
+            main_executable.map(|e| e.path.as_str()).unwrap_or(""),
+            main_executable.map(|e| e.architecture()).unwrap_or(""),
+            &main_executable.map(|e| format!("{:?}", e.subsystem)).unwrap_or_default(),
+            main_executable.and_then(|e| e.dotnet.as_ref()).and_then(|d| d.runtime_version.as_deref()).unwrap_or(""),
+            &executables.map(|e| e.architecture_mismatch.to_string()).unwrap_or_default(),
+            signature.map(|s| s.signer_subject.as_str()).unwrap_or(""),
+            signature.map(|s| s.issuer.as_str()).unwrap_or(""),
+            &signature.and_then(|s| s.timestamp).map(|t| t.to_rfc3339()).unwrap_or_default(),
+            signature.map(|s| s.digest_algorithm.as_str()).unwrap_or(""),
+            uninstaller.map(|e| e.architecture()).unwrap_or(""),
+            uninstaller.and_then(|e| e.signature.as_ref()).map(|s| s.signer_subject.as_str()).unwrap_or(""),
+            main_hashes.map(|h| h.sha256.as_str()).unwrap_or(""),
+            main_hashes.map(|h| h.sha1.as_str()).unwrap_or(""),
+            main_hashes.map(|h| h.md5.as_str()).unwrap_or(""),
+            file_hashes.and_then(|h| h.uninstaller.as_ref()).map(|f| f.hashes.sha256.as_str()).unwrap_or(""),
+            &file_hashes.map(format_ioc_hits).unwrap_or_default(),
             &program.installation_source,
             &program.is_vf_deployed.to_string(),
         ])?;
@@ -108,7 +203,12 @@ fn export_to_txt(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
             writeln!(file, "Publisher: {}", publisher)?;
         }
         if let Some(version) = &program.version {
-            writeln!(file, "Version: {}", version)?;
+            match &program.version_info {
+                Some(version_info) if &version_info.normalized != version => {
+                    writeln!(file, "Version: {} (normalized: {})", version, version_info.normalized)?
+                }
+                _ => writeln!(file, "Version: {}", version)?,
+            }
         }
         if let Some(registry_time) = &program.registry_time {
             writeln!(file, "Registry Time: {}", registry_time)?;
@@ -116,9 +216,15 @@ fn export_to_txt(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
         if let Some(install_date) = &program.install_date {
             writeln!(file, "Install Date: {}", install_date)?;
         }
+        if let Some(installed_on) = &program.installed_on {
+            writeln!(file, "Install Date (Parsed): {} ({:?}, {:?} confidence)", installed_on.date, installed_on.source, installed_on.confidence)?;
+        }
         if let Some(installed_for) = &program.installed_for {
             writeln!(file, "Installed For: {}", installed_for)?;
         }
+        if let Some(user_sid) = &program.user_sid {
+            writeln!(file, "User SID: {}", user_sid)?;
+        }
         if let Some(location) = &program.install_location {
             writeln!(file, "Install Location: {}", location)?;
         }
@@ -138,17 +244,67 @@ fn export_to_txt(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
             writeln!(file, "Icon Path: {}", icon_path)?;
         }
         if let Some(estimated_size) = &program.estimated_size {
-            writeln!(file, "Estimated Size: {} bytes", estimated_size)?;
+            writeln!(file, "Estimated Size: {} KB ({})", estimated_size, format_bytes(*estimated_size as u64 * 1024))?;
+        }
+        if let Some(measured) = program.install_size.as_ref().and_then(|size| size.measured.as_ref()) {
+            writeln!(file, "Measured Size: {} on disk ({} of data in {} files){}", format_bytes(measured.allocated_bytes), format_bytes(measured.logical_bytes),
+                measured.file_count, if measured.complete { "" } else { ", at least; measurement incomplete" })?;
+        }
+        if let Some(discrepancy) = program.install_size.as_ref().and_then(|size| size.discrepancy) {
+            writeln!(file, "Size Discrepancy: {:?}", discrepancy)?;
         }
         if let Some(language) = &program.language {
             writeln!(file, "Language: {}", language)?;
         }
         writeln!(file, "Architecture: {}", program.architecture)?;
+        if let Some(executables) = &program.executables {
+            if let Some(main_executable) = &executables.main_executable {
+                writeln!(file, "Main Executable: {}", describe_image(main_executable))?;
+            }
+            if executables.architecture_mismatch {
+                writeln!(file, "Architecture Mismatch: listed as {}", program.architecture)?;
+            }
+            if let Some(uninstaller) = &executables.uninstaller {
+                writeln!(file, "Uninstaller: {}", describe_image(uninstaller))?;
+            }
+        }
+        if let Some(file_hashes) = &program.file_hashes {
+            for (label, hashed) in [("Main Executable", &file_hashes.main_executable), ("Uninstaller", &file_hashes.uninstaller)] {
+                if let Some(hashed) = hashed {
+                    writeln!(file, "{} SHA-256: {}", label, hashed.hashes.sha256)?;
+                }
+            }
+            if !file_hashes.ioc_hits.is_empty() {
+                writeln!(file, "IOC Hits: {}", format_ioc_hits(file_hashes))?;
+            }
+        }
         writeln!(file, "Program Type: {}", program.program_type)?;
         writeln!(file, "Is Windows Installer: {}", program.is_windows_installer)?;
+        if let Some(msi) = &program.msi {
+            writeln!(file, "MSI Product Code: {} ({:?})", msi.product_code, msi.context)?;
+            if let Some(upgrade_code) = &msi.upgrade_code {
+                writeln!(file, "MSI Upgrade Code: {}", upgrade_code)?;
+            }
+            if let Some(local_package) = &msi.local_package {
+                writeln!(file, "MSI Local Package: {}", local_package)?;
+            }
+        }
+        if let Some(technology) = &program.installer_technology {
+            writeln!(file, "Installer Technology: {:?} ({:?} confidence)", technology.engine, technology.confidence)?;
+        }
+        if let Some(health) = program.health.as_ref().filter(|health| health.status != EntryHealth::Healthy) {
+            writeln!(file, "Entry Health: {:?} ({})", health.status, format_entry_issues(health))?;
+        }
         writeln!(file, "Installation Source: {}", program.installation_source)?;
         writeln!(file, "Is VF Managed: {}", program.is_vf_deployed)?;
         writeln!(file, "Registry Path: {}", program.registry_path)?;
+        if let Some(provenance) = &program.registry_provenance {
+            writeln!(file, "Registry View: {}", provenance.view.name())?;
+            if let Some(last_write_time) = &provenance.last_write_time {
+                writeln!(file, "Registry Key Last Write: {}", last_write_time.format("%Y-%m-%d %H:%M:%S UTC"))?;
+            }
+            writeln!(file, "Registry Value Types: {}", format_value_types(provenance))?;
+        }
         writeln!(file, "\n{}", "=".repeat(50))?;
         writeln!(file)?;
     }
@@ -243,6 +399,7 @@ fn export_to_html(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<d
                 <th>Installation Source</th>
                 <th>VF Managed</th>
                 <th>Size</th>
+                <th>Registry Key</th>
             </tr>"#,
         chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
         programs.len(),
@@ -260,16 +417,28 @@ fn export_to_html(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<d
             _ => "type-unknown"
         };
         
-        let size_display = if let Some(size) = program.estimated_size {
-            if size > 1024 * 1024 {
-                format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
-            } else if size > 1024 {
-                format!("{:.1} KB", size as f64 / 1024.0)
-            } else {
-                format!("{} B", size)
-            }
-        } else {
-            "Unknown".to_string()
+        let size_display = program.install_size.as_ref()
+            .map(format_size_html)
+            .or_else(|| program.estimated_size.map(|kb| format_bytes(kb as u64 * 1024)))
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        // Prefer the parsed date; reconstructed ones say where they came from
+        let install_date_display = match &program.installed_on {
+            Some(installed_on) if installed_on.source == InstallDateSource::InstallDate => installed_on.date.to_string(),
+            Some(installed_on) => format!("{}<br><small>from {:?}</small>", installed_on.date, installed_on.source),
+            None => escape_html(program.install_date.as_deref().unwrap_or("")),
+        };
+
+        let registry_key_display = match &program.registry_provenance {
+            Some(provenance) => format!(
+                "{}<br><small>{} view, last written {}</small>",
+                escape_html(&provenance.full_path()),
+                provenance.view.name(),
+                provenance.last_write_time
+                    .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            ),
+            None => escape_html(&program.registry_path),
         };
 
         let vf_deployed_display = if program.is_vf_deployed {
@@ -278,9 +447,17 @@ fn export_to_html(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<d
             "No"
         };
 
+        let ioc_display = match program.file_hashes.as_ref().filter(|hashes| !hashes.ioc_hits.is_empty()) {
+            Some(hashes) => format!(
+                "<br><span style=\"color: #DC2626; font-weight: bold;\" title=\"{}\">IOC hit</span>",
+                escape_html(&format_ioc_hits(hashes))
+            ),
+            None => String::new(),
+        };
+
         write!(file, r#"
         <tr>
-            <td><strong>{}</strong></td>
+            <td><strong>{}</strong>{}</td>
             <td>{}</td>
             <td>{}</td>
             <td>{}</td>
@@ -290,18 +467,21 @@ fn export_to_html(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<d
             <td>{}</td>
             <td>{}</td>
             <td>{}</td>
+            <td>{}</td>
         </tr>"#,
             escape_html(&program.name),
+            ioc_display,
             escape_html(program.publisher.as_deref().unwrap_or("")),
             escape_html(program.version.as_deref().unwrap_or("")),
-            escape_html(program.install_date.as_deref().unwrap_or("")),
+            install_date_display,
             escape_html(program.install_location.as_deref().unwrap_or("")),
-            program.architecture,
+            program.executables.as_ref().map(|e| format_architecture_html(&program.architecture, e)).unwrap_or_else(|| escape_html(&program.architecture)),
             type_class,
             program.program_type,
             program.installation_source,
             vf_deployed_display,
-            size_display
+            size_display,
+            registry_key_display
         )?;
     }
 
@@ -330,7 +510,11 @@ fn export_to_xml(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
             writeln!(file, r#"        <Publisher>{}</Publisher>"#, escape_xml(publisher))?;
         }
         if let Some(version) = &program.version {
-            writeln!(file, r#"        <Version>{}</Version>"#, escape_xml(version))?;
+            match &program.version_info {
+                Some(version_info) => writeln!(file, r#"        <Version normalized="{}">{}</Version>"#,
+                    escape_xml(&version_info.normalized), escape_xml(version))?,
+                None => writeln!(file, r#"        <Version>{}</Version>"#, escape_xml(version))?,
+            }
         }
         if let Some(registry_time) = &program.registry_time {
             writeln!(file, r#"        <RegistryTime>{}</RegistryTime>"#, escape_xml(registry_time))?;
@@ -338,9 +522,19 @@ fn export_to_xml(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
         if let Some(install_date) = &program.install_date {
             writeln!(file, r#"        <InstallDate>{}</InstallDate>"#, escape_xml(install_date))?;
         }
+        if let Some(installed_on) = &program.installed_on {
+            let timestamp = installed_on.timestamp
+                .map(|t| format!(r#" timestamp="{}""#, t.to_rfc3339()))
+                .unwrap_or_default();
+            writeln!(file, r#"        <InstalledOn source="{:?}" confidence="{:?}"{}>{}</InstalledOn>"#,
+                installed_on.source, installed_on.confidence, timestamp, installed_on.date)?;
+        }
         if let Some(installed_for) = &program.installed_for {
             writeln!(file, r#"        <InstalledFor>{}</InstalledFor>"#, escape_xml(installed_for))?;
         }
+        if let Some(user_sid) = &program.user_sid {
+            writeln!(file, r#"        <UserSID>{}</UserSID>"#, escape_xml(user_sid))?;
+        }
         if let Some(location) = &program.install_location {
             writeln!(file, r#"        <InstallLocation>{}</InstallLocation>"#, escape_xml(location))?;
         }
@@ -384,7 +578,12 @@ fn export_to_xml(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
             writeln!(file, r#"        <MSIFilename>{}</MSIFilename>"#, escape_xml(msi_filename))?;
         }
         if let Some(estimated_size) = &program.estimated_size {
-            writeln!(file, r#"        <EstimatedSize>{}</EstimatedSize>"#, estimated_size)?;
+            writeln!(file, r#"        <EstimatedSize unit="KB">{}</EstimatedSize>"#, estimated_size)?;
+        }
+        if let Some(measured) = program.install_size.as_ref().and_then(|size| size.measured.as_ref()) {
+            writeln!(file, r#"        <MeasuredSize logicalBytes="{}" allocatedBytes="{}" files="{}" complete="{}"{} />"#,
+                measured.logical_bytes, measured.allocated_bytes, measured.file_count, measured.complete,
+                program.install_size.as_ref().and_then(|size| size.discrepancy).map(|d| format!(r#" discrepancy="{:?}""#, d)).unwrap_or_default())?;
         }
         if let Some(attributes) = &program.attributes {
             writeln!(file, r#"        <Attributes>{}</Attributes>"#, escape_xml(attributes))?;
@@ -396,11 +595,72 @@ fn export_to_xml(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
             writeln!(file, r#"        <ParentKeyName>{}</ParentKeyName>"#, escape_xml(parent_key_name))?;
         }
         writeln!(file, r#"        <Architecture>{}</Architecture>"#, escape_xml(&program.architecture))?;
+        if let Some(executables) = &program.executables {
+            writeln!(file, r#"        <Executables architectureMismatch="{}">"#, executables.architecture_mismatch)?;
+            for (element, image) in [("MainExecutable", &executables.main_executable), ("Uninstaller", &executables.uninstaller)] {
+                if let Some(image) = image {
+                    write_image_xml(&mut file, element, image)?;
+                }
+            }
+            writeln!(file, r#"        </Executables>"#)?;
+        }
+        if let Some(file_hashes) = &program.file_hashes {
+            writeln!(file, r#"        <FileHashes iocChecked="{}" hashedAt="{}">"#, file_hashes.ioc_checked, file_hashes.hashed_at.to_rfc3339())?;
+            for (element, hashed) in [("MainExecutable", &file_hashes.main_executable), ("Uninstaller", &file_hashes.uninstaller)] {
+                if let Some(hashed) = hashed {
+                    writeln!(
+                        file,
+                        r#"            <{} path="{}" size="{}" sha256="{}" sha1="{}" md5="{}"/>"#,
+                        element, escape_xml(&hashed.path), hashed.size, hashed.hashes.sha256, hashed.hashes.sha1, hashed.hashes.md5
+                    )?;
+                }
+            }
+            for hit in &file_hashes.ioc_hits {
+                writeln!(
+                    file,
+                    r#"            <IocHit role="{:?}" hashType="{}" hash="{}" source="{}">{}</IocHit>"#,
+                    hit.role, hit.hash_type.name(), hit.hash, escape_xml(&hit.ioc_source), escape_xml(hit.indicator.as_deref().unwrap_or(""))
+                )?;
+            }
+            writeln!(file, r#"        </FileHashes>"#)?;
+        }
         writeln!(file, r#"        <ProgramType>{}</ProgramType>"#, escape_xml(&program.program_type))?;
         writeln!(file, r#"        <IsWindowsInstaller>{}</IsWindowsInstaller>"#, program.is_windows_installer)?;
+        if let Some(msi) = &program.msi {
+            let upgrade_code = msi.upgrade_code.as_ref()
+                .map(|code| format!(r#" upgradeCode="{}""#, code))
+                .unwrap_or_default();
+            match &msi.local_package {
+                Some(local_package) => {
+                    writeln!(file, r#"        <Msi productCode="{}"{} context="{:?}">"#, msi.product_code, upgrade_code, msi.context)?;
+                    writeln!(file, r#"            <LocalPackage>{}</LocalPackage>"#, escape_xml(local_package))?;
+                    writeln!(file, r#"        </Msi>"#)?;
+                }
+                None => writeln!(file, r#"        <Msi productCode="{}"{} context="{:?}"/>"#, msi.product_code, upgrade_code, msi.context)?,
+            }
+        }
+        if let Some(technology) = &program.installer_technology {
+            writeln!(file, r#"        <InstallerTechnology engine="{:?}" confidence="{:?}">"#, technology.engine, technology.confidence)?;
+            for evidence in &technology.evidence {
+                writeln!(file, r#"            <Evidence>{}</Evidence>"#, escape_xml(evidence))?;
+            }
+            writeln!(file, r#"        </InstallerTechnology>"#)?;
+        }
         writeln!(file, r#"        <InstallationSource>{}</InstallationSource>"#, escape_xml(&program.installation_source))?;
         writeln!(file, r#"        <IsVFDeployed>{}</IsVFDeployed>"#, program.is_vf_deployed)?;
         writeln!(file, r#"        <RegistryPath>{}</RegistryPath>"#, escape_xml(&program.registry_path))?;
+        if let Some(provenance) = &program.registry_provenance {
+            let last_write_time = provenance.last_write_time
+                .map(|t| format!(r#" lastWriteTime="{}""#, t.to_rfc3339()))
+                .unwrap_or_default();
+            writeln!(file, r#"        <RegistryProvenance hive="{}" view="{}"{}>"#,
+                provenance.hive.name(), provenance.view.name(), last_write_time)?;
+            writeln!(file, r#"            <KeyPath>{}</KeyPath>"#, escape_xml(&provenance.key_path))?;
+            for (name, value_type) in &provenance.value_types {
+                writeln!(file, r#"            <Value name="{}" type="{}"/>"#, escape_xml(name), value_type)?;
+            }
+            writeln!(file, r#"        </RegistryProvenance>"#)?;
+        }
         writeln!(file, r#"    </Program>"#)?;
     }
     
@@ -408,6 +668,111 @@ fn export_to_xml(programs: &[ProgramInfo], file_path: &str) -> Result<(), Box<dy
     Ok(())
 }
 
+// Value names with their REG_* types, e.g. "DisplayName=REG_SZ; EstimatedSize=REG_DWORD"
+fn format_value_types(provenance: &RegistryProvenance) -> String {
+    provenance.value_types.iter()
+        .map(|(name, value_type)| {
+            let name = if name.is_empty() { "(Default)" } else { name.as_str() };
+            format!("{}={}", name, value_type)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// "C:\App\app.exe (64-bit, Gui, .NET v4.0.30319), signed by Contoso Ltd (SHA-256, timestamped 2024-03-22)"
+fn describe_image(image: &ImageInfo) -> String {
+    let mut traits = vec![image.architecture().to_string(), format!("{:?}", image.subsystem)];
+    if let Some(dotnet) = &image.dotnet {
+        traits.push(format!(".NET {}", dotnet.runtime_version.as_deref().unwrap_or("")).trim_end().to_string());
+    }
+    let signed = match (&image.signature, &image.signature_error) {
+        (Some(signature), _) => format!(
+            ", signed by {} ({}{})",
+            signature.signer_name.as_deref().unwrap_or(&signature.signer_subject),
+            signature.digest_algorithm,
+            signature.timestamp.map(|t| format!(", timestamped {}", t.format("%Y-%m-%d"))).unwrap_or_default()
+        ),
+        (None, Some(error)) => format!(", unreadable signature: {}", error),
+        (None, None) => ", not signed".to_string(),
+    };
+    format!("{} ({}){}", image.path, traits.join(", "), signed)
+}
+
+fn write_image_xml(file: &mut File, element: &str, image: &ImageInfo) -> Result<(), Box<dyn Error>> {
+    writeln!(file, r#"            <{} path="{}" architecture="{}" machine="{:?}" subsystem="{:?}"{}>"#,
+        element, escape_xml(&image.path), image.architecture(), image.machine, image.subsystem,
+        image.dotnet.as_ref().map(|d| format!(r#" dotnetRuntime="{}""#, escape_xml(d.runtime_version.as_deref().unwrap_or("")))).unwrap_or_default())?;
+    if let Some(signature) = &image.signature {
+        writeln!(file, r#"                <Signature digestAlgorithm="{}"{}>"#, escape_xml(&signature.digest_algorithm),
+            signature.timestamp.map(|t| format!(r#" timestamp="{}""#, t.to_rfc3339())).unwrap_or_default())?;
+        writeln!(file, r#"                    <Signer>{}</Signer>"#, escape_xml(&signature.signer_subject))?;
+        writeln!(file, r#"                    <Issuer>{}</Issuer>"#, escape_xml(&signature.issuer))?;
+        writeln!(file, r#"                </Signature>"#)?;
+    }
+    writeln!(file, r#"            </{}>"#, element)?;
+    Ok(())
+}
+
+// "SHA-256 e3b0... (Emotet) on the main executable; ..."
+fn format_ioc_hits(file_hashes: &FileHashInventory) -> String {
+    file_hashes.ioc_hits.iter()
+        .map(|hit| {
+            let role = match hit.role {
+                ExecutableRole::MainExecutable => "main executable",
+                ExecutableRole::Uninstaller => "uninstaller",
+            };
+            match &hit.indicator {
+                Some(indicator) => format!("{} {} ({}) on the {}", hit.hash_type.name(), hit.hash, indicator, role),
+                None => format!("{} {} on the {}", hit.hash_type.name(), hit.hash, role),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// The listed architecture, with what the main executable turned out to be; a mismatch is highlighted
+fn format_architecture_html(listed: &str, executables: &ExecutableAnalysis) -> String {
+    match &executables.main_executable {
+        Some(image) => format!(
+            "{}<br><small{}>executable {}{}</small>",
+            escape_html(listed),
+            if executables.architecture_mismatch { " style=\"color: #DC2626;\"" } else { "" },
+            image.architecture(),
+            image.signature.as_ref()
+                .map(|s| format!(", signed by {}", escape_html(s.signer_name.as_deref().unwrap_or(&s.signer_subject))))
+                .unwrap_or_default()
+        ),
+        None => escape_html(listed),
+    }
+}
+
+// Declared size, then the measured one; a flagged discrepancy is highlighted
+fn format_size_html(size: &InstallSizeInfo) -> String {
+    let declared = size.declared_bytes.map(format_bytes).unwrap_or_else(|| "Unknown".to_string());
+    match (&size.measured, size.discrepancy) {
+        (Some(measured), discrepancy) => format!(
+            "{}<br><small{}>measured {}{}{}</small>",
+            declared,
+            if discrepancy.is_some() { " style=\"color: #DC2626;\"" } else { "" },
+            format_bytes(measured.allocated_bytes),
+            if measured.complete { "" } else { "+" },
+            discrepancy.map(|d| format!(", {:?}", d)).unwrap_or_default()
+        ),
+        (None, _) => declared,
+    }
+}
+
+// Issue kinds with the missing path, e.g. "UninstallerMissing: C:\Foo\uninst.exe; DisplayIconMissing: ..."
+fn format_entry_issues(health: &EntryHealthInfo) -> String {
+    health.issues.iter()
+        .map(|issue| match &issue.path {
+            Some(path) => format!("{:?}: {}", issue.kind, path),
+            None => format!("{:?}", issue.kind),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 // Helper function for XML escaping
 fn escape_xml(s: &str) -> String {
     s.replace("&", "&amp;")
@@ -438,4 +803,440 @@ pub async fn export_programs(
         ExportFormat::XML => export_to_xml(&programs, &file_path),
         ExportFormat::TXT => export_to_txt(&programs, &file_path),
     }.map_err(|e| e.to_string())
-} 
\ No newline at end of file
+}
+
+fn uninstall_report_to_csv(report: &UninstallReport, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let file = File::create(Path::new(file_path))?;
+    let mut writer = csv::Writer::from_writer(file);
+
+    writer.write_record(&[
+        "Position",
+        "Name",
+        "Registry Path",
+        "State",
+        "Attempts",
+        "Command",
+        "Exit Code",
+        "Outcome",
+        "Message",
+        "Started",
+        "Duration (ms)",
+    ])?;
+
+    for item in &report.items {
+        let result = item.result.as_ref();
+        writer.write_record(&[
+            &(item.position + 1).to_string(),
+            &item.name,
+            &item.registry_path,
+            &format!("{:?}", item.state),
+            &item.attempts.to_string(),
+            item.command.as_deref().unwrap_or(""),
+            &result.and_then(|r| r.exit_code).map(|code| code.to_string()).unwrap_or_default(),
+            &result.map(|r| format!("{:?}", r.outcome)).unwrap_or_default(),
+            item.message.as_deref().unwrap_or(""),
+            &result.map(|r| r.started_at.to_rfc3339()).unwrap_or_default(),
+            &result.map(|r| r.duration_ms.to_string()).unwrap_or_default(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn uninstall_report_summary(report: &UninstallReport) -> String {
+    let mut summary = format!(
+        "{} succeeded, {} failed, {} skipped, {} not run",
+        report.succeeded, report.failed, report.skipped, report.not_run
+    );
+    if report.halted {
+        summary.push_str("; halted at the first failure");
+    }
+    if report.cancelled {
+        summary.push_str("; cancelled");
+    }
+    if report.reboot_required {
+        summary.push_str("; a restart is required");
+    }
+    summary
+}
+
+fn uninstall_report_to_txt(report: &UninstallReport, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(Path::new(file_path))?;
+
+    writeln!(file, "Software Scope - Uninstall Report")?;
+    writeln!(file, "==================================")?;
+    writeln!(file, "Queue: {}", report.job_id)?;
+    writeln!(file, "Started: {}", report.started_at.format("%Y-%m-%d %H:%M:%S UTC"))?;
+    if let Some(finished_at) = report.finished_at {
+        writeln!(file, "Finished: {}", finished_at.format("%Y-%m-%d %H:%M:%S UTC"))?;
+    }
+    writeln!(file, "Failure Policy: {:?}", report.failure_policy)?;
+    writeln!(file, "Result: {}", uninstall_report_summary(report))?;
+    writeln!(file)?;
+
+    for item in &report.items {
+        writeln!(file, "{}. {} - {:?}", item.position + 1, item.name, item.state)?;
+        if let Some(command) = &item.command {
+            writeln!(file, "   Command: {}", command)?;
+        }
+        if let Some(result) = &item.result {
+            let exit_code = result.exit_code.map(|code| code.to_string()).unwrap_or_else(|| "none".to_string());
+            writeln!(file, "   Exit Code: {} ({:?}), {} attempt(s), {} ms", exit_code, result.outcome, item.attempts, result.duration_ms)?;
+        }
+        if let Some(message) = &item.message {
+            writeln!(file, "   {}", message)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn uninstall_report_to_html(report: &UninstallReport, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(Path::new(file_path))?;
+
+    write!(file, r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Software Scope - Uninstall Report</title>
+    <meta charset="UTF-8">
+    <style>
+        body {{ font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; margin: 20px; background-color: #f5f5f5; }}
+        .container {{ background-color: white; padding: 20px; border-radius: 8px; box-shadow: 0 2px 4px rgba(0,0,0,0.1); }}
+        h1 {{ color: #2c3e50; border-bottom: 3px solid #3498db; padding-bottom: 10px; }}
+        .summary {{ background-color: #ecf0f1; padding: 15px; border-radius: 5px; margin-bottom: 20px; }}
+        table {{ border-collapse: collapse; width: 100%; }}
+        th, td {{ border: 1px solid #ddd; padding: 8px; text-align: left; vertical-align: top; }}
+        th {{ background-color: #3498db; color: white; }}
+        .Succeeded {{ color: #27ae60; }}
+        .Failed {{ color: #c0392b; font-weight: bold; }}
+        .Skipped, .NotRun {{ color: #7f8c8d; }}
+        .command {{ font-family: monospace; font-size: 0.9em; }}
+    </style>
+</head>
+<body>
+<div class="container">
+    <h1>Uninstall Report</h1>
+    <div class="summary">
+        <p><strong>Queue:</strong> {}</p>
+        <p><strong>Started:</strong> {}</p>
+        <p><strong>Failure Policy:</strong> {:?}</p>
+        <p><strong>Result:</strong> {}</p>
+    </div>
+    <table>
+        <tr><th>#</th><th>Name</th><th>State</th><th>Command</th><th>Exit Code</th><th>Message</th></tr>
+"#,
+        escape_html(&report.job_id),
+        report.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        report.failure_policy,
+        escape_html(&uninstall_report_summary(report)),
+    )?;
+
+    for item in &report.items {
+        writeln!(
+            file,
+            r#"        <tr><td>{}</td><td>{}</td><td class="{:?}">{:?}</td><td class="command">{}</td><td>{}</td><td>{}</td></tr>"#,
+            item.position + 1,
+            escape_html(&item.name),
+            item.state,
+            item.state,
+            escape_html(item.command.as_deref().unwrap_or("")),
+            item.result.as_ref().and_then(|r| r.exit_code).map(|code| code.to_string()).unwrap_or_default(),
+            escape_html(item.message.as_deref().unwrap_or("")),
+        )?;
+    }
+
+    writeln!(file, "    </table>\n</div>\n</body>\n</html>")?;
+    Ok(())
+}
+
+fn uninstall_report_to_xml(report: &UninstallReport, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(Path::new(file_path))?;
+
+    writeln!(file, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        file,
+        r#"<UninstallReport jobId="{}" startedAt="{}" failurePolicy="{:?}" halted="{}" cancelled="{}" rebootRequired="{}">"#,
+        escape_xml(&report.job_id),
+        report.started_at.to_rfc3339(),
+        report.failure_policy,
+        report.halted,
+        report.cancelled,
+        report.reboot_required,
+    )?;
+    writeln!(
+        file,
+        r#"    <Summary succeeded="{}" failed="{}" skipped="{}" notRun="{}"/>"#,
+        report.succeeded, report.failed, report.skipped, report.not_run
+    )?;
+
+    for item in &report.items {
+        writeln!(file, r#"    <Item position="{}" state="{:?}" attempts="{}">"#, item.position + 1, item.state, item.attempts)?;
+        writeln!(file, r#"        <Name>{}</Name>"#, escape_xml(&item.name))?;
+        writeln!(file, r#"        <RegistryPath>{}</RegistryPath>"#, escape_xml(&item.registry_path))?;
+        if let Some(command) = &item.command {
+            writeln!(file, r#"        <Command>{}</Command>"#, escape_xml(command))?;
+        }
+        if let Some(result) = &item.result {
+            let exit_code = result.exit_code.map(|code| format!(r#" exitCode="{}""#, code)).unwrap_or_default();
+            writeln!(file, r#"        <Result outcome="{:?}"{} durationMs="{}"/>"#, result.outcome, exit_code, result.duration_ms)?;
+        }
+        if let Some(message) = &item.message {
+            writeln!(file, r#"        <Message>{}</Message>"#, escape_xml(message))?;
+        }
+        writeln!(file, r#"    </Item>"#)?;
+    }
+
+    writeln!(file, r#"</UninstallReport>"#)?;
+    Ok(())
+}
+
+/// Write a finished uninstall queue's report
+#[tauri::command]
+pub async fn export_uninstall_report(
+    report: UninstallReport,
+    format: ExportFormat,
+    file_path: String
+) -> Result<(), String> {
+    match format {
+        ExportFormat::CSV => uninstall_report_to_csv(&report, &file_path),
+        ExportFormat::HTML => uninstall_report_to_html(&report, &file_path),
+        ExportFormat::XML => uninstall_report_to_xml(&report, &file_path),
+        ExportFormat::TXT => uninstall_report_to_txt(&report, &file_path),
+    }.map_err(|e| e.to_string())
+}
+
+fn signature_status_label(entry: &SignatureTrustItem) -> String {
+    match &entry.verification {
+        Some(verification) => format!("{:?}", verification.status),
+        None => "Unreadable".to_string(),
+    }
+}
+
+fn signature_trust_summary(report: &SignatureTrustReport) -> String {
+    format!(
+        "{} executables of {} programs: {} valid, {} unsigned, {} invalid, {} expired, {} untrusted; {} publisher mismatch(es)",
+        report.executables, report.checked, report.valid, report.unsigned, report.invalid, report.expired, report.untrusted, report.publisher_mismatches
+    )
+}
+
+fn signature_trust_report_to_csv(report: &SignatureTrustReport, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let file = File::create(Path::new(file_path))?;
+    let mut writer = csv::Writer::from_writer(file);
+
+    writer.write_record(&[
+        "Name",
+        "Publisher",
+        "Registry Path",
+        "Role",
+        "Path",
+        "Status",
+        "Signed By",
+        "Signer Organization",
+        "Publisher Mismatch",
+        "Image Hash Valid",
+        "Signature Valid",
+        "Timestamp",
+        "Timestamp Valid",
+        "Trusted Root",
+        "Chain",
+        "Problems",
+    ])?;
+
+    for entry in &report.entries {
+        let verification = entry.verification.as_ref();
+        let mut problems = verification.map(|v| v.problems.clone()).unwrap_or_default();
+        problems.extend(entry.error.clone());
+        writer.write_record(&[
+            entry.name.as_str(),
+            entry.publisher.as_deref().unwrap_or(""),
+            &entry.registry_path,
+            &format!("{:?}", entry.role),
+            &entry.path,
+            &signature_status_label(entry),
+            verification.and_then(|v| v.signer_name.as_deref()).unwrap_or(""),
+            verification.and_then(|v| v.signer_organization.as_deref()).unwrap_or(""),
+            if entry.publisher_mismatch { "Yes" } else { "No" },
+            &verification.map(|v| v.image_hash_valid.to_string()).unwrap_or_default(),
+            &verification.map(|v| v.signature_valid.to_string()).unwrap_or_default(),
+            &verification.and_then(|v| v.timestamp).map(|t| t.to_rfc3339()).unwrap_or_default(),
+            &verification.and_then(|v| v.timestamp_valid).map(|valid| valid.to_string()).unwrap_or_default(),
+            verification.and_then(|v| v.trusted_root.as_deref()).unwrap_or(""),
+            &verification.map(|v| v.chain.join(" -> ")).unwrap_or_default(),
+            &problems.join("; "),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn signature_trust_report_to_txt(report: &SignatureTrustReport, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(Path::new(file_path))?;
+
+    writeln!(file, "Software Scope - Publisher Trust Report")?;
+    writeln!(file, "========================================")?;
+    writeln!(file, "Trusted Roots: {} ({} certificates)", report.trusted_roots, report.trusted_root_count)?;
+    writeln!(file, "Result: {}", signature_trust_summary(report))?;
+    writeln!(file)?;
+
+    for entry in &report.entries {
+        writeln!(file, "{} ({:?}) - {}", entry.name, entry.role, signature_status_label(entry))?;
+        writeln!(file, "   Path: {}", entry.path)?;
+        if let Some(verification) = &entry.verification {
+            if let Some(signer) = &verification.signer_name {
+                writeln!(file, "   Signed By: {}", signer)?;
+            }
+            if entry.publisher_mismatch {
+                writeln!(file, "   Registered Publisher: {} (does not match the signer)", entry.publisher.as_deref().unwrap_or(""))?;
+            }
+            if let Some(timestamp) = verification.timestamp {
+                let validity = if verification.timestamp_valid == Some(true) { "verified" } else { "not verified" };
+                writeln!(file, "   Timestamp: {} ({})", timestamp.format("%Y-%m-%d %H:%M:%S UTC"), validity)?;
+            }
+            if !verification.chain.is_empty() {
+                writeln!(file, "   Chain: {}", verification.chain.join(" -> "))?;
+            }
+            for problem in &verification.problems {
+                writeln!(file, "   {}", problem)?;
+            }
+        }
+        if let Some(error) = &entry.error {
+            writeln!(file, "   {}", error)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn signature_trust_report_to_html(report: &SignatureTrustReport, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(Path::new(file_path))?;
+
+    write!(file, r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Software Scope - Publisher Trust Report</title>
+    <meta charset="UTF-8">
+    <style>
+        body {{ font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; margin: 20px; background-color: #f5f5f5; }}
+        .container {{ background-color: white; padding: 20px; border-radius: 8px; box-shadow: 0 2px 4px rgba(0,0,0,0.1); }}
+        h1 {{ color: #2c3e50; border-bottom: 3px solid #3498db; padding-bottom: 10px; }}
+        .summary {{ background-color: #ecf0f1; padding: 15px; border-radius: 5px; margin-bottom: 20px; }}
+        table {{ border-collapse: collapse; width: 100%; }}
+        th, td {{ border: 1px solid #ddd; padding: 8px; text-align: left; vertical-align: top; }}
+        th {{ background-color: #3498db; color: white; }}
+        .Valid {{ color: #27ae60; }}
+        .Invalid, .Unreadable {{ color: #c0392b; font-weight: bold; }}
+        .Expired, .Untrusted, .mismatch {{ color: #e67e22; }}
+        .Unsigned {{ color: #7f8c8d; }}
+        .path {{ font-family: monospace; font-size: 0.9em; }}
+    </style>
+</head>
+<body>
+<div class="container">
+    <h1>Publisher Trust Report</h1>
+    <div class="summary">
+        <p><strong>Trusted Roots:</strong> {} ({} certificates)</p>
+        <p><strong>Result:</strong> {}</p>
+    </div>
+    <table>
+        <tr><th>Name</th><th>Role</th><th>Status</th><th>Signed By</th><th>Publisher</th><th>Timestamp</th><th>Path</th><th>Problems</th></tr>
+"#,
+        escape_html(&report.trusted_roots),
+        report.trusted_root_count,
+        escape_html(&signature_trust_summary(report)),
+    )?;
+
+    for entry in &report.entries {
+        let verification = entry.verification.as_ref();
+        let status = signature_status_label(entry);
+        let publisher = escape_html(entry.publisher.as_deref().unwrap_or(""));
+        let mut problems = verification.map(|v| v.problems.clone()).unwrap_or_default();
+        problems.extend(entry.error.clone());
+        writeln!(
+            file,
+            r#"        <tr><td>{}</td><td>{:?}</td><td class="{}">{}</td><td>{}</td><td>{}</td><td>{}</td><td class="path">{}</td><td>{}</td></tr>"#,
+            escape_html(&entry.name),
+            entry.role,
+            status,
+            status,
+            escape_html(verification.and_then(|v| v.signer_name.as_deref()).unwrap_or("")),
+            if entry.publisher_mismatch { format!(r#"<span class="mismatch">{} (mismatch)</span>"#, publisher) } else { publisher },
+            verification.and_then(|v| v.timestamp).map(|t| t.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default(),
+            escape_html(&entry.path),
+            problems.iter().map(|problem| escape_html(problem)).collect::<Vec<_>>().join("<br>"),
+        )?;
+    }
+
+    writeln!(file, "    </table>\n</div>\n</body>\n</html>")?;
+    Ok(())
+}
+
+fn signature_trust_report_to_xml(report: &SignatureTrustReport, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(Path::new(file_path))?;
+
+    writeln!(file, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        file,
+        r#"<SignatureTrustReport trustedRoots="{}" trustedRootCount="{}">"#,
+        escape_xml(&report.trusted_roots),
+        report.trusted_root_count,
+    )?;
+    writeln!(
+        file,
+        r#"    <Summary programs="{}" executables="{}" valid="{}" unsigned="{}" invalid="{}" expired="{}" untrusted="{}" publisherMismatches="{}"/>"#,
+        report.checked, report.executables, report.valid, report.unsigned, report.invalid, report.expired, report.untrusted, report.publisher_mismatches
+    )?;
+
+    for entry in &report.entries {
+        writeln!(file, r#"    <Executable role="{:?}" status="{}" publisherMismatch="{}">"#, entry.role, signature_status_label(entry), entry.publisher_mismatch)?;
+        writeln!(file, r#"        <Name>{}</Name>"#, escape_xml(&entry.name))?;
+        if let Some(publisher) = &entry.publisher {
+            writeln!(file, r#"        <Publisher>{}</Publisher>"#, escape_xml(publisher))?;
+        }
+        writeln!(file, r#"        <RegistryPath>{}</RegistryPath>"#, escape_xml(&entry.registry_path))?;
+        writeln!(file, r#"        <Path>{}</Path>"#, escape_xml(&entry.path))?;
+        if let Some(verification) = &entry.verification {
+            writeln!(file, r#"        <Verification imageHashValid="{}" signatureValid="{}" checkedAt="{}">"#,
+                verification.image_hash_valid, verification.signature_valid, verification.checked_at.to_rfc3339())?;
+            if let Some(signer) = &verification.signer_name {
+                writeln!(file, r#"            <Signer>{}</Signer>"#, escape_xml(signer))?;
+            }
+            if let Some(timestamp) = verification.timestamp {
+                writeln!(file, r#"            <Timestamp valid="{}">{}</Timestamp>"#, verification.timestamp_valid == Some(true), timestamp.to_rfc3339())?;
+            }
+            for subject in &verification.chain {
+                writeln!(file, r#"            <Certificate>{}</Certificate>"#, escape_xml(subject))?;
+            }
+            if let Some(root) = &verification.trusted_root {
+                writeln!(file, r#"            <TrustedRoot>{}</TrustedRoot>"#, escape_xml(root))?;
+            }
+            for problem in &verification.problems {
+                writeln!(file, r#"            <Problem>{}</Problem>"#, escape_xml(problem))?;
+            }
+            writeln!(file, r#"        </Verification>"#)?;
+        }
+        if let Some(error) = &entry.error {
+            writeln!(file, r#"        <Error>{}</Error>"#, escape_xml(error))?;
+        }
+        writeln!(file, r#"    </Executable>"#)?;
+    }
+
+    writeln!(file, r#"</SignatureTrustReport>"#)?;
+    Ok(())
+}
+
+/// Write a publisher trust report
+#[tauri::command]
+pub async fn export_signature_trust_report(
+    report: SignatureTrustReport,
+    format: ExportFormat,
+    file_path: String
+) -> Result<(), String> {
+    match format {
+        ExportFormat::CSV => signature_trust_report_to_csv(&report, &file_path),
+        ExportFormat::HTML => signature_trust_report_to_html(&report, &file_path),
+        ExportFormat::XML => signature_trust_report_to_xml(&report, &file_path),
+        ExportFormat::TXT => signature_trust_report_to_txt(&report, &file_path),
+    }.map_err(|e| e.to_string())
+}
@@ -0,0 +1,171 @@
+// Hash inventory of each program's main executable and uninstaller, matched against a local IOC list so
+// "is this hash anywhere on the machine" can be answered from a scan
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::commands::executables::{main_executable_path, uninstaller_path, ExecutableRole};
+use crate::commands::registry::{with_live_environments, ProgramEnvironments, ProgramInfo};
+use crate::services::file_hash::{HashCache, HashType, HashedFile};
+use crate::services::ioc::IocList;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IocHit {
+    pub role: ExecutableRole,
+    pub path: String,
+    pub hash_type: HashType,
+    pub hash: String,
+    pub indicator: Option<String>, // The feed's name for it
+    pub ioc_source: String,        // The IOC list it came from
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileHashInventory {
+    pub main_executable: Option<HashedFile>,
+    pub uninstaller: Option<HashedFile>,
+    pub ioc_hits: Vec<IocHit>,
+    pub ioc_checked: bool, // False when no IOC list was given, so no hits means nothing
+    pub errors: Vec<String>,
+    pub hashed_at: DateTime<Utc>,
+}
+
+/// Hash the given programs' main executables and uninstallers and fill in their `file_hashes`, matching
+/// them against the IOC list at `ioc_list` when one is given
+#[tauri::command]
+pub async fn hash_executables(programs: Vec<ProgramInfo>, ioc_list: Option<String>) -> Result<Vec<ProgramInfo>, String> {
+    tokio::task::spawn_blocking(move || {
+        let iocs = load_ioc_list(ioc_list.as_deref())?;
+        let mut programs = programs;
+        attach_file_hashes(&mut programs, iocs.as_ref(), &|| false);
+        Ok(programs)
+    })
+    .await
+    .map_err(|e| format!("Hashing did not complete: {}", e))?
+}
+
+/// The IOC list at `path`, or None when no path is configured
+pub fn load_ioc_list(path: Option<&str>) -> Result<Option<IocList>, String> {
+    match path.map(str::trim).filter(|path| !path.is_empty()) {
+        Some(path) => IocList::load(Path::new(path)).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Hash every program's executables, stopping early when `is_cancelled` says so. The hash cache is
+/// loaded once and saved at the end
+pub fn attach_file_hashes(programs: &mut [ProgramInfo], iocs: Option<&IocList>, is_cancelled: &dyn Fn() -> bool) {
+    let mut cache = HashCache::load();
+    with_live_environments(|environments| {
+        for program in programs.iter_mut() {
+            if is_cancelled() {
+                break;
+            }
+            program.file_hashes = Some(hash_program_executables(program, environments, &mut cache, iocs));
+        }
+    });
+    if let Err(e) = cache.save() {
+        println!("DEBUG: Failed to save the hash cache: {}", e);
+    }
+}
+
+pub fn hash_program_executables(program: &ProgramInfo, environments: &ProgramEnvironments, cache: &mut HashCache, iocs: Option<&IocList>) -> FileHashInventory {
+    // Executables found by an earlier analysis are the ones to hash; otherwise find them the same way
+    let (main_path, uninstaller) = match &program.executables {
+        Some(analysis) => (
+            analysis.main_executable.as_ref().map(|image| image.path.clone()),
+            analysis.uninstaller.as_ref().map(|image| image.path.clone()),
+        ),
+        None => {
+            let uninstaller = uninstaller_path(program, environments);
+            (main_executable_path(program, environments, uninstaller.as_deref()), uninstaller)
+        }
+    };
+
+    let mut errors = Vec::new();
+    let mut ioc_hits = Vec::new();
+    let mut hash = |role: ExecutableRole, path: Option<String>| -> Option<HashedFile> {
+        let hashed = match cache.hash_file(Path::new(&path?)) {
+            Ok(hashed) => hashed,
+            Err(error) => {
+                errors.push(error);
+                return None;
+            }
+        };
+        if let Some(iocs) = iocs {
+            for indicator in iocs.matches(&hashed.hashes) {
+                println!("DEBUG: IOC hit for {}: {} {} ({:?})", program.name, indicator.hash_type.name(), indicator.hash, indicator.name);
+                ioc_hits.push(IocHit {
+                    role,
+                    path: hashed.path.clone(),
+                    hash_type: indicator.hash_type,
+                    hash: indicator.hash.clone(),
+                    indicator: indicator.name.clone(),
+                    ioc_source: iocs.source.clone(),
+                });
+            }
+        }
+        Some(hashed)
+    };
+    let main_executable = hash(ExecutableRole::MainExecutable, main_path);
+    let uninstaller = hash(ExecutableRole::Uninstaller, uninstaller);
+
+    FileHashInventory {
+        main_executable,
+        uninstaller,
+        ioc_hits,
+        ioc_checked: iocs.is_some(),
+        errors,
+        hashed_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::environment::Environment;
+    use crate::services::file_hash::hash_file;
+
+    #[test]
+    fn matches_the_main_executable_and_the_uninstaller() {
+        let dir = std::env::temp_dir().join(format!("file-hashes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let main = dir.join("contoso.exe");
+        let uninstaller = dir.join("uninst.exe");
+        std::fs::write(&main, b"main executable").unwrap();
+        std::fs::write(&uninstaller, b"uninstaller").unwrap();
+        let main_hashes = hash_file(&main).unwrap();
+        let uninstaller_hashes = hash_file(&uninstaller).unwrap();
+        let feed = dir.join("feed.csv");
+        std::fs::write(&feed, format!("hash,name\n{},Contoso dropper\n{},\n", main_hashes.sha256, uninstaller_hashes.md5)).unwrap();
+
+        let program = ProgramInfo::with_fields("Contoso", serde_json::json!({
+            "install_location": dir.to_string_lossy(),
+            "uninstall_string": uninstaller.to_string_lossy(),
+        }));
+        let environment = Environment::windows_defaults();
+        let environments = ProgramEnvironments::new(None, &environment);
+        let iocs = load_ioc_list(Some(&feed.to_string_lossy())).unwrap();
+        let mut cache = HashCache::default();
+
+        let inventory = hash_program_executables(&program, &environments, &mut cache, iocs.as_ref());
+        assert!(inventory.errors.is_empty());
+        assert!(inventory.ioc_checked);
+        assert_eq!(inventory.main_executable.unwrap().hashes, main_hashes);
+        assert_eq!(inventory.uninstaller.unwrap().hashes, uninstaller_hashes);
+        let hits: Vec<(ExecutableRole, HashType, Option<&str>)> = inventory.ioc_hits.iter()
+            .map(|hit| (hit.role, hit.hash_type, hit.indicator.as_deref()))
+            .collect();
+        assert_eq!(hits, vec![
+            (ExecutableRole::MainExecutable, HashType::Sha256, Some("Contoso dropper")),
+            (ExecutableRole::Uninstaller, HashType::Md5, None),
+        ]);
+
+        // Without a list nothing is matched, and the second run takes the hashes from the cache
+        let unchecked = hash_program_executables(&program, &environments, &mut cache, None);
+        assert!(!unchecked.ioc_checked && unchecked.ioc_hits.is_empty());
+        assert!(unchecked.main_executable.unwrap().from_cache);
+
+        assert_eq!(load_ioc_list(Some("  ")).unwrap().map(|list| list.len()), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,318 @@
+// Broken and orphaned uninstall entries: an entry whose uninstaller, cached MSI package, icon or install
+// folder is gone. Checks the local disk, so it only means something for entries of this machine
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::commands::registry::{with_live_environments, ProgramEnvironments, ProgramInfo};
+use crate::services::command_line::{parse_command_line, CommandKind};
+
+// Ordered from fine to worst, so the report can sort on it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EntryHealth {
+    Healthy,
+    Degraded, // Uninstallable, but its icon or install folder is missing
+    Broken,   // Cannot be uninstalled as registered: no command, or the uninstaller or cached package is gone
+    Orphaned, // Nothing it points to is left; the program was removed and the entry stayed behind
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum EntryIssueKind {
+    NoUninstallCommand,
+    UninstallerMissing,
+    LocalPackageMissing,    // The cached .msi under C:\Windows\Installer that msiexec /x needs
+    DisplayIconMissing,
+    InstallLocationMissing,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntryIssue {
+    pub kind: EntryIssueKind,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntryHealthInfo {
+    pub status: EntryHealth,
+    pub issues: Vec<EntryIssue>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntryHealthReportItem {
+    pub name: String,
+    pub publisher: Option<String>,
+    pub version: Option<String>,
+    pub registry_path: String,
+    pub health: EntryHealthInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntryHealthReport {
+    pub checked: usize,
+    pub healthy: usize,
+    pub degraded: usize,
+    pub broken: usize,
+    pub orphaned: usize,
+    pub entries: Vec<EntryHealthReportItem>, // Everything but healthy entries, worst first
+}
+
+/// Check the given entries against this machine's disk and fill in their `health`
+#[tauri::command]
+pub fn check_entry_health(mut programs: Vec<ProgramInfo>) -> Vec<ProgramInfo> {
+    with_live_environments(|environments| attach_entry_health(&mut programs, environments));
+    programs
+}
+
+/// Keep programs whose health is one of `statuses`; entries never checked count as healthy
+#[tauri::command]
+pub fn filter_programs_by_health(programs: Vec<ProgramInfo>, statuses: Vec<EntryHealth>) -> Vec<ProgramInfo> {
+    programs.into_iter()
+        .filter(|program| statuses.contains(&program.health.as_ref().map_or(EntryHealth::Healthy, |health| health.status)))
+        .collect()
+}
+
+/// Broken, orphaned and degraded entries, checking any that have not been checked yet
+#[tauri::command]
+pub fn get_entry_health_report(programs: Vec<ProgramInfo>) -> EntryHealthReport {
+    let mut entries: Vec<EntryHealthReportItem> = with_live_environments(|environments| programs.iter()
+        .map(|program| EntryHealthReportItem {
+            name: program.name.clone(),
+            publisher: program.publisher.clone(),
+            version: program.version.clone(),
+            registry_path: program.registry_path.clone(),
+            health: program.health.clone().unwrap_or_else(|| assess_entry_health(program, environments, &|path| path.exists())),
+        })
+        .collect());
+
+    let count = |status: EntryHealth| entries.iter().filter(|entry| entry.health.status == status).count();
+    let report_counts = (count(EntryHealth::Healthy), count(EntryHealth::Degraded), count(EntryHealth::Broken), count(EntryHealth::Orphaned));
+
+    entries.retain(|entry| entry.health.status != EntryHealth::Healthy);
+    entries.sort_by(|a, b| b.health.status.cmp(&a.health.status).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+
+    EntryHealthReport {
+        checked: programs.len(),
+        healthy: report_counts.0,
+        degraded: report_counts.1,
+        broken: report_counts.2,
+        orphaned: report_counts.3,
+        entries,
+    }
+}
+
+pub fn attach_entry_health(programs: &mut [ProgramInfo], environments: &ProgramEnvironments) {
+    for program in programs.iter_mut() {
+        let health = assess_entry_health(program, environments, &|path| path.exists());
+        if health.status != EntryHealth::Healthy {
+            println!("DEBUG: {} is {:?}: {:?}", program.name, health.status, health.issues.iter().map(|issue| issue.kind).collect::<Vec<_>>());
+        }
+        program.health = Some(health);
+    }
+}
+
+/// Classify one entry. Only uninstall-key entries are judged; portable and MSIX finds have no
+/// uninstall registration to break
+pub fn assess_entry_health(program: &ProgramInfo, environments: &ProgramEnvironments, exists: &dyn Fn(&Path) -> bool) -> EntryHealthInfo {
+    let mut issues = Vec::new();
+    if program.installation_source != "System" && program.installation_source != "User" {
+        return EntryHealthInfo { status: EntryHealth::Healthy, issues };
+    }
+
+    let missing = |path: &str| is_local_absolute(path) && !exists(Path::new(path));
+    let mut checked_paths = 0;
+
+    // The quiet command is an alternative way in, so the entry is only stuck when neither works
+    let commands: Vec<String> = [&program.uninstall_string, &program.quiet_uninstall_string]
+        .iter()
+        .filter_map(|command| command.as_deref())
+        .map(|command| environments.expand_path(program, command))
+        .collect();
+    if commands.is_empty() {
+        // Hidden components and patches are removed with their parent product
+        if program.program_type == "Application" {
+            issues.push(EntryIssue { kind: EntryIssueKind::NoUninstallCommand, path: None });
+        }
+    } else {
+        let targets: Vec<String> = commands.iter()
+            .filter_map(|command| parse_command_line(command))
+            .filter_map(|command| match command.kind {
+                CommandKind::Executable => Some(command.executable),
+                CommandKind::Rundll32 => command.dll_path,
+                CommandKind::Msiexec => None, // Needs the cached package instead, checked below
+            })
+            .filter(|target| is_local_absolute(target))
+            .collect();
+        if !targets.is_empty() {
+            checked_paths += 1;
+        }
+        let missing_targets: Vec<&String> = targets.iter().filter(|target| missing(target)).collect();
+        if !targets.is_empty() && missing_targets.len() == targets.len() {
+            issues.push(EntryIssue { kind: EntryIssueKind::UninstallerMissing, path: Some(missing_targets[0].clone()) });
+        }
+    }
+
+    if let Some(local_package) = program.msi.as_ref().and_then(|msi| msi.local_package.as_deref()) {
+        checked_paths += 1;
+        if missing(local_package) {
+            issues.push(EntryIssue { kind: EntryIssueKind::LocalPackageMissing, path: Some(local_package.to_string()) });
+        }
+    }
+
+    // DisplayIcon is "<path>,<index>", sometimes quoted
+    if let Some(display_icon) = program.display_icon.as_deref() {
+        let icon = environments.expand_path(program, display_icon.split(',').next().unwrap_or_default().trim().trim_matches('"'));
+        if is_local_absolute(&icon) {
+            checked_paths += 1;
+            if missing(&icon) {
+                issues.push(EntryIssue { kind: EntryIssueKind::DisplayIconMissing, path: Some(icon) });
+            }
+        }
+    }
+
+    // The scan falls back to InstallSource for the location; that is where setup ran from, which is
+    // routinely a deleted temp folder, so only a real InstallLocation is checked
+    if let Some(location) = program.install_location.as_deref().filter(|location| Some(*location) != program.install_source.as_deref()) {
+        let location = environments.expand_path(program, location.trim().trim_matches('"'));
+        if is_local_absolute(&location) {
+            checked_paths += 1;
+            if missing(&location) {
+                issues.push(EntryIssue { kind: EntryIssueKind::InstallLocationMissing, path: Some(location) });
+            }
+        }
+    }
+
+    let has = |kind: EntryIssueKind| issues.iter().any(|issue| issue.kind == kind);
+    let cannot_uninstall = has(EntryIssueKind::NoUninstallCommand)
+        || has(EntryIssueKind::UninstallerMissing)
+        || has(EntryIssueKind::LocalPackageMissing);
+    let missing_paths = issues.iter().filter(|issue| issue.path.is_some()).count();
+    let status = if cannot_uninstall && checked_paths > 0 && missing_paths == checked_paths {
+        EntryHealth::Orphaned
+    } else if cannot_uninstall {
+        EntryHealth::Broken
+    } else if !issues.is_empty() {
+        EntryHealth::Degraded
+    } else {
+        EntryHealth::Healthy
+    };
+
+    EntryHealthInfo { status, issues }
+}
+
+// Only drive-letter paths are checked: bare file names are found on PATH, and network shares may just be offline
+fn is_local_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && (bytes[2] == b'\\' || bytes[2] == b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::environment::Environment;
+
+    const FOLDER: &str = "C:\\Program Files\\Contoso";
+    const UNINSTALLER: &str = "C:\\Program Files\\Contoso\\uninstall.exe";
+    const ICON: &str = "C:\\Program Files\\Contoso\\agent.exe";
+    const PACKAGE: &str = "C:\\Windows\\Installer\\1a2b3c.msi";
+
+    fn entry(fields: serde_json::Value) -> ProgramInfo {
+        let mut program = serde_json::json!({
+            "uninstall_string": format!("\"{}\"", UNINSTALLER),
+            "display_icon": "%ProgramFiles%\\Contoso\\agent.exe,0",
+            "install_location": FOLDER,
+        });
+        if let (Some(program), serde_json::Value::Object(fields)) = (program.as_object_mut(), fields) {
+            program.extend(fields);
+        }
+        ProgramInfo::with_fields("Contoso Agent", program)
+    }
+
+    fn msi_entry(fields: serde_json::Value) -> ProgramInfo {
+        let mut program = entry(fields);
+        program.uninstall_string = Some("MsiExec.exe /X{11111111-2222-3333-4444-555555555555}".to_string());
+        program.msi = serde_json::from_value(serde_json::json!({
+            "product_code": "{11111111-2222-3333-4444-555555555555}",
+            "packed_product_code": "",
+            "upgrade_code": null,
+            "local_package": PACKAGE,
+            "context": "Machine",
+            "user_sid": null,
+        })).unwrap();
+        program
+    }
+
+    // Health with only `present` on disk
+    fn assess(program: &ProgramInfo, present: &[&str]) -> (EntryHealth, Vec<EntryIssueKind>) {
+        let environment = Environment::windows_defaults();
+        let environments = ProgramEnvironments::new(None, &environment);
+        let health = assess_entry_health(program, &environments, &|path| present.iter().any(|present| Path::new(present) == path));
+        (health.status, health.issues.iter().map(|issue| issue.kind).collect())
+    }
+
+    #[test]
+    fn entries_with_everything_in_place_are_healthy() {
+        assert_eq!(assess(&entry(serde_json::json!({})), &[FOLDER, UNINSTALLER, ICON]), (EntryHealth::Healthy, vec![]));
+        assert_eq!(assess(&msi_entry(serde_json::json!({})), &[FOLDER, PACKAGE, ICON]), (EntryHealth::Healthy, vec![]));
+        // Network paths and bare names are not looked at
+        let remote = entry(serde_json::json!({ "uninstall_string": "\\\\server\\share\\uninstall.exe", "display_icon": "agent.ico" }));
+        assert_eq!(assess(&remote, &[FOLDER]), (EntryHealth::Healthy, vec![]));
+        // Portable finds have no registration to break
+        let portable = entry(serde_json::json!({ "installation_source": "Filesystem" }));
+        assert_eq!(assess(&portable, &[]), (EntryHealth::Healthy, vec![]));
+    }
+
+    #[test]
+    fn a_missing_icon_or_folder_degrades_an_entry() {
+        assert_eq!(
+            assess(&entry(serde_json::json!({})), &[FOLDER, UNINSTALLER]),
+            (EntryHealth::Degraded, vec![EntryIssueKind::DisplayIconMissing]),
+        );
+        assert_eq!(
+            assess(&entry(serde_json::json!({})), &[UNINSTALLER, ICON]),
+            (EntryHealth::Degraded, vec![EntryIssueKind::InstallLocationMissing]),
+        );
+    }
+
+    #[test]
+    fn entries_that_cannot_be_uninstalled_are_broken() {
+        assert_eq!(
+            assess(&entry(serde_json::json!({})), &[FOLDER, ICON]),
+            (EntryHealth::Broken, vec![EntryIssueKind::UninstallerMissing]),
+        );
+        assert_eq!(
+            assess(&msi_entry(serde_json::json!({})), &[FOLDER, ICON]),
+            (EntryHealth::Broken, vec![EntryIssueKind::LocalPackageMissing]),
+        );
+        assert_eq!(
+            assess(&entry(serde_json::json!({ "uninstall_string": null })), &[FOLDER, ICON]),
+            (EntryHealth::Broken, vec![EntryIssueKind::NoUninstallCommand]),
+        );
+        // Hidden components go with their parent product
+        let component = entry(serde_json::json!({ "uninstall_string": null, "program_type": "SystemComponent" }));
+        assert_eq!(assess(&component, &[FOLDER, ICON]), (EntryHealth::Healthy, vec![]));
+        // A working quiet command is still a way to remove it
+        let quiet = entry(serde_json::json!({ "quiet_uninstall_string": "\"C:\\Program Files\\Contoso\\remove.exe\" /S" }));
+        assert_eq!(assess(&quiet, &[FOLDER, ICON, "C:\\Program Files\\Contoso\\remove.exe"]), (EntryHealth::Healthy, vec![]));
+    }
+
+    #[test]
+    fn entries_pointing_at_nothing_are_orphaned() {
+        assert_eq!(
+            assess(&entry(serde_json::json!({})), &[]),
+            (EntryHealth::Orphaned, vec![EntryIssueKind::UninstallerMissing, EntryIssueKind::DisplayIconMissing, EntryIssueKind::InstallLocationMissing]),
+        );
+        assert_eq!(
+            assess(&msi_entry(serde_json::json!({ "display_icon": null, "install_location": null })), &[]),
+            (EntryHealth::Orphaned, vec![EntryIssueKind::LocalPackageMissing]),
+        );
+    }
+
+    #[test]
+    fn ignores_the_install_source_fallback() {
+        let temp = "C:\\Users\\alice\\AppData\\Local\\Temp\\setup-1234";
+        let program = entry(serde_json::json!({ "install_location": temp, "install_source": temp }));
+        assert_eq!(assess(&program, &[UNINSTALLER, ICON]), (EntryHealth::Healthy, vec![]));
+        // Without the fallback the same folder is checked
+        let program = entry(serde_json::json!({ "install_location": temp }));
+        assert_eq!(assess(&program, &[UNINSTALLER, ICON]), (EntryHealth::Degraded, vec![EntryIssueKind::InstallLocationMissing]));
+    }
+}
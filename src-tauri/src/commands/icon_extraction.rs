@@ -2,6 +2,7 @@ use std::sync::Mutex;
 use tauri::State;
 use serde::{Serialize, Deserialize};
 use crate::services::icon_extractor::{IconExtractor, ExtractedIcon, resolve_icon_path, resolve_icon_path_with_vf_fallback};
+use crate::services::path_resolver::with_live_resolver;
 
 // Global state for the icon extractor
 pub type IconExtractorState = Mutex<IconExtractor>;
@@ -44,7 +45,7 @@ pub fn extract_icon_from_path(
     println!("🔍 Icon extraction request for: {}", request.icon_path);
     
     // Resolve the icon path to find the actual executable or icon file
-    let resolved_path = match resolve_icon_path(&request.icon_path) {
+    let resolved_path = match with_live_resolver(|resolver| resolve_icon_path(resolver, &request.icon_path)) {
         Some(path) => {
             println!("✅ Resolved path: {}", path);
             path
@@ -170,12 +171,13 @@ pub fn extract_icon_from_path_vf(
     println!("🔍 VF Icon extraction request for: {} (VF: {})", request.program_name, request.is_vf_deployed);
     
     // Use the enhanced VF-aware path resolution
-    let resolved_path = match resolve_icon_path_with_vf_fallback(
+    let resolved_path = match with_live_resolver(|resolver| resolve_icon_path_with_vf_fallback(
+        resolver,
         &request.icon_path, 
         &request.program_name, 
         request.publisher.as_deref(), 
         request.is_vf_deployed
-    ) {
+    )) {
         Some(path) => {
             println!("✅ Resolved path: {}", path);
             path
@@ -227,5 +229,5 @@ pub fn extract_icon_from_path_vf(
 
 #[tauri::command]
 pub fn resolve_icon_path_command(icon_path: String) -> Result<Option<String>, String> {
-    Ok(resolve_icon_path(&icon_path))
+    Ok(with_live_resolver(|resolver| resolve_icon_path(resolver, &icon_path)))
 }
\ No newline at end of file
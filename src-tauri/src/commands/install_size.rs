@@ -0,0 +1,182 @@
+// Measured install size next to the declared one. EstimatedSize is whatever the installer wrote, in KB;
+// the measurement walks InstallLocation, which is what reclaiming disk space actually depends on
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::commands::leftovers::is_program_specific_folder;
+use crate::commands::registry::{with_live_environments, ProgramEnvironments, ProgramInfo};
+use crate::services::disk_usage::{measure_folder, DiskUsage};
+use crate::services::environment::expand;
+
+const DEFAULT_TIME_BUDGET_SECS: u64 = 120;
+// No single folder may use up the whole budget
+const MAX_FOLDER_TIME: Duration = Duration::from_secs(20);
+// A discrepancy is flagged when the sizes differ by this factor and by at least this much
+const DISCREPANCY_RATIO: f64 = 2.0;
+const MIN_DISCREPANCY_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SizeDiscrepancy {
+    Understated, // Takes far more than it declares
+    Overstated,  // Takes far less, e.g. data moved elsewhere or a partial removal
+    Undeclared,  // No EstimatedSize, but a sizeable folder
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InstallSizeInfo {
+    pub declared_kb: Option<u32>,       // EstimatedSize; Windows defines it in KB
+    pub declared_bytes: Option<u64>,
+    pub measured_path: Option<String>,
+    pub measured: Option<DiskUsage>,
+    pub error: Option<String>,          // Why nothing was measured
+    pub discrepancy: Option<SizeDiscrepancy>,
+    pub measured_at: DateTime<Utc>,
+}
+
+/// Measure the install folders of the given programs and fill in their `install_size`. Folders are
+/// walked until the time budget is spent; programs left over get an error saying so
+#[tauri::command]
+pub async fn measure_install_sizes(programs: Vec<ProgramInfo>, time_budget_seconds: Option<u64>) -> Result<Vec<ProgramInfo>, String> {
+    let budget = Duration::from_secs(time_budget_seconds.unwrap_or(DEFAULT_TIME_BUDGET_SECS));
+    // Walking large folders takes a while; keep it off the async runtime's worker threads
+    tokio::task::spawn_blocking(move || {
+        let mut programs = programs;
+        with_live_environments(|environments| attach_install_sizes(&mut programs, budget, environments));
+        programs
+    })
+    .await
+    .map_err(|e| format!("Size measurement did not complete: {}", e))
+}
+
+pub fn attach_install_sizes(programs: &mut [ProgramInfo], budget: Duration, environments: &ProgramEnvironments) {
+    let started = Instant::now();
+    // Suites register several entries with one InstallLocation; walk it once
+    let mut measured: HashMap<String, Result<DiskUsage, String>> = HashMap::new();
+
+    for program in programs.iter_mut() {
+        let environment = environments.of(program);
+        let path = program.install_location.as_deref()
+            .map(|location| expand(location.trim().trim_matches('"'), &environment).trim_end_matches('\\').to_string())
+            .filter(|location| !location.is_empty());
+
+        let result = match &path {
+            None => Err("No install location".to_string()),
+            Some(path) if !is_program_specific_folder(path, &environment) => Err(format!("{} is not a folder of this program alone", path)),
+            Some(path) => {
+                let remaining = budget.checked_sub(started.elapsed()).unwrap_or_default();
+                match measured.get(&path.to_lowercase()) {
+                    Some(result) => result.clone(),
+                    None if remaining.is_zero() => Err("The time budget was used up before this folder".to_string()),
+                    None => {
+                        let result = measure_folder(Path::new(path), remaining.min(MAX_FOLDER_TIME));
+                        measured.insert(path.to_lowercase(), result.clone());
+                        result
+                    }
+                }
+            }
+        };
+
+        let (measured, error) = match result {
+            Ok(usage) => (Some(usage), None),
+            Err(error) => (None, Some(error)),
+        };
+        let declared_bytes = program.estimated_size.map(|kb| kb as u64 * 1024);
+        let discrepancy = measured.as_ref().and_then(|usage| size_discrepancy(declared_bytes, usage));
+        if let Some(discrepancy) = discrepancy {
+            println!("DEBUG: {} size is {:?}: declared {:?} bytes, measured {:?}", program.name, discrepancy, declared_bytes, measured.as_ref().map(|usage| usage.allocated_bytes));
+        }
+
+        program.install_size = Some(InstallSizeInfo {
+            declared_kb: program.estimated_size,
+            declared_bytes,
+            measured_path: path,
+            measured,
+            error,
+            discrepancy,
+            measured_at: Utc::now(),
+        });
+    }
+}
+
+/// Compare the declared size with what the folder takes on disk. An incomplete walk is a lower bound,
+/// so it can only show a size to be understated
+pub fn size_discrepancy(declared_bytes: Option<u64>, usage: &DiskUsage) -> Option<SizeDiscrepancy> {
+    let measured = usage.allocated_bytes;
+    let declared = match declared_bytes {
+        Some(declared) => declared,
+        None if measured >= MIN_DISCREPANCY_BYTES => return Some(SizeDiscrepancy::Undeclared),
+        None => return None,
+    };
+    if measured.abs_diff(declared) < MIN_DISCREPANCY_BYTES {
+        None
+    } else if measured as f64 > declared as f64 * DISCREPANCY_RATIO {
+        Some(SizeDiscrepancy::Understated)
+    } else if usage.complete && declared as f64 > measured as f64 * DISCREPANCY_RATIO {
+        Some(SizeDiscrepancy::Overstated)
+    } else {
+        None
+    }
+}
+
+/// Bytes for people: "512 B", "1.5 KB", "230.4 MB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    // Move up a unit at whatever would print as 1024.0
+    while value >= 1023.95 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn usage(allocated_bytes: u64, complete: bool) -> DiskUsage {
+        DiskUsage { allocated_bytes, logical_bytes: allocated_bytes, complete, ..DiskUsage::default() }
+    }
+
+    #[test]
+    fn flags_sizes_far_from_the_declared_one() {
+        assert_eq!(size_discrepancy(Some(10 * MB), &usage(100 * MB, true)), Some(SizeDiscrepancy::Understated));
+        assert_eq!(size_discrepancy(Some(500 * MB), &usage(100 * MB, true)), Some(SizeDiscrepancy::Overstated));
+        assert_eq!(size_discrepancy(None, &usage(60 * MB, true)), Some(SizeDiscrepancy::Undeclared));
+        assert_eq!(size_discrepancy(None, &usage(10 * MB, true)), None);
+        // Small folders are never worth flagging, however far off the ratio
+        assert_eq!(size_discrepancy(Some(MB), &usage(40 * MB, true)), None);
+        assert_eq!(size_discrepancy(Some(100 * MB), &usage(180 * MB, true)), None);
+    }
+
+    #[test]
+    fn an_incomplete_walk_is_only_a_lower_bound() {
+        assert_eq!(size_discrepancy(Some(500 * MB), &usage(100 * MB, false)), None);
+        assert_eq!(size_discrepancy(Some(10 * MB), &usage(100 * MB, false)), Some(SizeDiscrepancy::Understated));
+        assert_eq!(size_discrepancy(None, &usage(60 * MB, false)), Some(SizeDiscrepancy::Undeclared));
+    }
+
+    #[test]
+    fn formats_bytes_at_unit_boundaries() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KB");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(MB - 1), "1.0 MB");
+        assert_eq!(format_bytes(MB), "1.0 MB");
+        assert_eq!(format_bytes(1023 * MB), "1023.0 MB");
+        assert_eq!(format_bytes(1024 * MB), "1.0 GB");
+        assert_eq!(format_bytes(1024 * 1024 * MB), "1.0 TB");
+        // TB is the largest unit
+        assert_eq!(format_bytes(1024 * 1024 * 1024 * MB), "1024.0 TB");
+    }
+}
@@ -0,0 +1,626 @@
+// Leftovers of an uninstall: folders, files, shortcuts and registry keys a program leaves behind. Without
+// a baseline they are found by install path, name and publisher; with a baseline taken before the
+// uninstall the comparison is exact: whatever belonged to the program then and still exists is left over
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::commands::merge::{normalize_name, normalize_publisher};
+use crate::commands::registry::{scan_programdata_paths, scan_shortcuts, ProgramEnvironments, ProgramInfo};
+use crate::services::command_line::{parse_command_line, CommandKind};
+use crate::services::environment::{expand, EnvironmentProvider, ProcessEnvironment};
+use crate::services::installer_fingerprint::{contains, Confidence};
+use crate::services::msi::{INSTALLER_USERDATA_PATH, LOCAL_SYSTEM_SID};
+use crate::services::path_resolver::{FilesystemRoots, PathResolver};
+use crate::services::registry_source::{live_registry, RegistryHive, RegistrySource};
+
+// Bump when the baseline layout changes in a way older readers cannot load
+pub const BASELINE_FORMAT_VERSION: u32 = 1;
+// Normalized names shorter than this match far too much to search by
+const MIN_NAME_LENGTH: usize = 3;
+// Shortcuts are small; anything bigger is not a .lnk worth reading
+const MAX_SHORTCUT_SIZE: u64 = 64 * 1024;
+// Start Menu folders nest a publisher and a product folder at most
+const SHORTCUT_SEARCH_DEPTH: usize = 3;
+// Software keys that hold Windows' own configuration, never a single program's
+const SHARED_SOFTWARE_KEYS: [&str; 6] = ["classes", "clients", "microsoft", "policies", "wow6432node", "registeredapplications"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LeftoverKind {
+    Folder,
+    File,
+    Shortcut,
+    RegistryKey,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LeftoverReason {
+    InstallLocation, // The entry's InstallLocation
+    Uninstaller,     // The executable of its uninstall command
+    LocalPackage,    // The cached .msi of a Windows Installer product
+    UninstallKey,    // The uninstall entry itself
+    MsiRegistration, // Windows Installer's record of the product
+    ShortcutTarget,  // A shortcut pointing into the install folder
+    NameMatch,       // Named after the program
+    PublisherMatch,  // A publisher folder or key holding nothing but the program's
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Leftover {
+    pub kind: LeftoverKind,
+    pub path: String,               // File system path, or registry key as regedit shows it
+    pub reason: LeftoverReason,
+    pub confidence: Confidence,
+    pub detail: String,
+}
+
+/// What a program had on disk and in the registry while it was still installed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LeftoverBaseline {
+    pub format_version: u32,
+    pub id: String,
+    pub name: String,
+    pub registry_path: String,
+    pub created_at: DateTime<Utc>,
+    pub items: Vec<Leftover>,
+}
+
+/// A baseline without its items, for listing
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LeftoverBaselineSummary {
+    pub id: String,
+    pub name: String,
+    pub registry_path: String,
+    pub created_at: DateTime<Utc>,
+    pub item_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LeftoverReport {
+    pub name: String,
+    pub registry_path: String,
+    pub still_registered: bool,      // The uninstall key is still there: these are the program's own files, not yet leftovers
+    pub baseline_id: Option<String>,
+    pub removed: usize,              // Baseline items that are gone; 0 without a baseline
+    pub leftovers: Vec<Leftover>,    // Most certain first
+}
+
+// Get the baselines directory path
+fn get_baselines_dir() -> PathBuf {
+    let mut path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("software-scope");
+    path.push("leftover-baselines");
+    path
+}
+
+fn get_baseline_path(id: &str) -> Result<PathBuf, String> {
+    // IDs come from the frontend; keep them to the characters we generate
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid baseline id: {}", id));
+    }
+    Ok(get_baselines_dir().join(format!("{}.json", id)))
+}
+
+/// Record what a program has before it is uninstalled, for `find_leftovers` to compare against afterwards
+#[tauri::command]
+pub fn capture_leftover_baseline(program: ProgramInfo) -> Result<LeftoverBaselineSummary, String> {
+    let registry = live_registry().ok();
+    let process_environment = ProcessEnvironment::new();
+    let environment = ProgramEnvironments::new(registry.as_deref(), &process_environment).of(&program);
+    let items = collect_candidates(&program, registry.as_deref(), &PathResolver::new(&FilesystemRoots::Live, &environment));
+    save_baseline(&program, items).map_err(|e| format!("Failed to save leftover baseline: {}", e))
+}
+
+/// Leftovers of a program, before or after its removal. With a baseline only what the program had when
+/// it was taken is reported; without one, candidates are found by install path, name and publisher
+#[tauri::command]
+pub fn find_leftovers(program: ProgramInfo, baseline_id: Option<String>) -> Result<LeftoverReport, String> {
+    let registry = live_registry().ok();
+    let registry = registry.as_deref();
+    let process_environment = ProcessEnvironment::new();
+    let environment = ProgramEnvironments::new(registry, &process_environment).of(&program);
+    let exists = |item: &Leftover| leftover_exists(item, registry);
+
+    let (mut leftovers, removed) = match &baseline_id {
+        Some(id) => {
+            let baseline = load_baseline(id).map_err(|e| format!("Failed to load leftover baseline {}: {}", id, e))?;
+            compare_with_baseline(&baseline, &exists)
+        }
+        None => (collect_candidates(&program, registry, &PathResolver::new(&FilesystemRoots::Live, &environment)), 0),
+    };
+    leftovers.sort_by(|a, b| b.confidence.cmp(&a.confidence).then_with(|| a.path.to_lowercase().cmp(&b.path.to_lowercase())));
+    println!("DEBUG: {} leftovers for {} ({} removed since the baseline)", leftovers.len(), program.name, removed);
+
+    Ok(LeftoverReport {
+        name: program.name.clone(),
+        registry_path: program.registry_path.clone(),
+        still_registered: registry.map_or(false, |registry| registry_key_exists(registry, &program.registry_path)),
+        baseline_id,
+        removed,
+        leftovers,
+    })
+}
+
+/// All saved baselines, oldest first
+#[tauri::command]
+pub fn list_leftover_baselines() -> Result<Vec<LeftoverBaselineSummary>, String> {
+    let dir = get_baselines_dir();
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut summaries = Vec::new();
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read baselines directory: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        match fs::read_to_string(&path).map(|json| serde_json::from_str::<LeftoverBaseline>(&json)) {
+            Ok(Ok(baseline)) => summaries.push(summary(&baseline)),
+            _ => println!("DEBUG: Skipping unreadable leftover baseline {}", path.display()),
+        }
+    }
+
+    summaries.sort_by_key(|summary| summary.created_at);
+    Ok(summaries)
+}
+
+#[tauri::command]
+pub fn delete_leftover_baseline(id: String) -> Result<(), String> {
+    let path = get_baseline_path(&id)?;
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete leftover baseline {}: {}", id, e))
+}
+
+pub fn save_baseline(program: &ProgramInfo, items: Vec<Leftover>) -> Result<LeftoverBaselineSummary, Box<dyn std::error::Error>> {
+    fs::create_dir_all(get_baselines_dir())?;
+
+    let created_at = Utc::now();
+    let key_part: String = program.registry_name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let id = format!("{}-{}", created_at.format("%Y%m%dT%H%M%S%3fZ"), key_part);
+
+    let baseline = LeftoverBaseline {
+        format_version: BASELINE_FORMAT_VERSION,
+        id: id.clone(),
+        name: program.name.clone(),
+        registry_path: program.registry_path.clone(),
+        created_at,
+        items,
+    };
+    fs::write(get_baseline_path(&id)?, serde_json::to_string(&baseline)?)?;
+    println!("DEBUG: Saved leftover baseline {} with {} items for {}", id, baseline.items.len(), program.name);
+
+    Ok(summary(&baseline))
+}
+
+pub fn load_baseline(id: &str) -> Result<LeftoverBaseline, Box<dyn std::error::Error>> {
+    let json = fs::read_to_string(get_baseline_path(id)?)?;
+    let baseline: LeftoverBaseline = serde_json::from_str(&json)?;
+    if baseline.format_version > BASELINE_FORMAT_VERSION {
+        return Err(format!("Baseline {} was written by a newer version (format {})", id, baseline.format_version).into());
+    }
+    Ok(baseline)
+}
+
+fn summary(baseline: &LeftoverBaseline) -> LeftoverBaselineSummary {
+    LeftoverBaselineSummary {
+        id: baseline.id.clone(),
+        name: baseline.name.clone(),
+        registry_path: baseline.registry_path.clone(),
+        created_at: baseline.created_at,
+        item_count: baseline.items.len(),
+    }
+}
+
+/// Baseline items that still exist, and how many are gone. Each keeps the confidence it was captured with:
+/// a folder that merely shares the program's name was a guess before the uninstall and still is
+pub fn compare_with_baseline(baseline: &LeftoverBaseline, exists: &dyn Fn(&Leftover) -> bool) -> (Vec<Leftover>, usize) {
+    let (remaining, removed): (Vec<&Leftover>, Vec<&Leftover>) = baseline.items.iter().partition(|item| exists(item));
+    let leftovers = remaining.into_iter()
+        .map(|item| Leftover {
+            detail: format!("{}; present before the uninstall", item.detail),
+            ..item.clone()
+        })
+        .collect();
+    (leftovers, removed.len())
+}
+
+/// Everything on the machine `resolver` looks at that seems to belong to the program, each with why.
+/// The resolver should carry the program's own environment, e.g. its owner's for a per-user install
+pub fn collect_candidates(program: &ProgramInfo, registry: Option<&dyn RegistrySource>, resolver: &PathResolver) -> Vec<Leftover> {
+    let environment = resolver.environment();
+    let is_dir = |path: &str| resolver.local_path(path).map_or(false, |path| path.is_dir());
+    let is_file = |path: &str| resolver.local_path(path).map_or(false, |path| path.is_file());
+    let mut candidates: Vec<Leftover> = Vec::new();
+    let mut add = |candidate: Leftover| {
+        if !candidates.iter().any(|existing| existing.kind == candidate.kind && existing.path.eq_ignore_ascii_case(&candidate.path)) {
+            candidates.push(candidate);
+        }
+    };
+
+    let install_location = program.install_location.as_deref()
+        .map(|location| expand(location.trim().trim_matches('"'), environment).trim_end_matches('\\').to_string())
+        .filter(|location| is_program_specific_folder(location, environment) && is_dir(location));
+    if let Some(location) = &install_location {
+        add(leftover(LeftoverKind::Folder, location, LeftoverReason::InstallLocation, Confidence::High, "InstallLocation of the entry"));
+    }
+
+    if let Some(uninstaller) = program.uninstall_string.as_deref().and_then(parse_command_line) {
+        if uninstaller.kind == CommandKind::Executable && is_file(&uninstaller.executable) {
+            add(leftover(LeftoverKind::File, &uninstaller.executable, LeftoverReason::Uninstaller, Confidence::High, "Executable of the uninstall command"));
+        }
+    }
+    if let Some(local_package) = program.msi.as_ref().and_then(|msi| msi.local_package.as_deref()) {
+        if is_file(local_package) {
+            add(leftover(LeftoverKind::File, local_package, LeftoverReason::LocalPackage, Confidence::High, "Cached Windows Installer package"));
+        }
+    }
+
+    let matcher = NameMatcher::new(&program.name, program.version.as_deref(), program.publisher.as_deref());
+    if let Some(matcher) = &matcher {
+        // The scan's own ProgramData and shortcut lookups match on raw substrings; grade what they find
+        for path in scan_programdata_paths(resolver, &program.name, program.publisher.as_deref()) {
+            for (path, reason, confidence, detail) in grade_folder(Path::new(&path), matcher) {
+                add(leftover(LeftoverKind::Folder, &path, reason, confidence, &detail));
+            }
+        }
+        for path in scan_shortcuts(resolver, &program.name, program.publisher.as_deref()) {
+            if let Some((reason, confidence, detail)) = matcher.grade(file_name(&path).trim_end_matches(".lnk")) {
+                add(leftover(LeftoverKind::Shortcut, &path, reason, confidence, &detail));
+            }
+        }
+        for root in folder_roots(environment).iter().filter_map(|root| resolver.local_path(root)) {
+            let folders = fs::read_dir(&root).map(|entries| entries.flatten().map(|entry| entry.path()).collect::<Vec<_>>()).unwrap_or_default();
+            for folder in folders {
+                for (path, reason, confidence, detail) in grade_folder(&folder, matcher) {
+                    add(leftover(LeftoverKind::Folder, &path, reason, confidence, &detail));
+                }
+            }
+        }
+    }
+
+    if let Some(location) = &install_location {
+        for root in shortcut_roots(environment).iter().filter_map(|root| resolver.local_path(root)) {
+            for path in shortcuts_into(&root.to_string_lossy(), location, SHORTCUT_SEARCH_DEPTH) {
+                add(leftover(LeftoverKind::Shortcut, &path, LeftoverReason::ShortcutTarget, Confidence::High, &format!("Points into {}", location)));
+            }
+        }
+    }
+
+    if let Some(registry) = registry {
+        for candidate in registry_candidates(program, registry, matcher.as_ref()) {
+            add(candidate);
+        }
+    }
+
+    candidates
+}
+
+fn registry_candidates(program: &ProgramInfo, registry: &dyn RegistrySource, matcher: Option<&NameMatcher>) -> Vec<Leftover> {
+    let mut candidates = Vec::new();
+
+    if registry_key_exists(registry, &program.registry_path) {
+        candidates.push(leftover(LeftoverKind::RegistryKey, &program.registry_path, LeftoverReason::UninstallKey, Confidence::High, "The uninstall entry"));
+    }
+
+    if let Some(msi) = &program.msi {
+        let sid = msi.user_sid.as_deref().unwrap_or(LOCAL_SYSTEM_SID);
+        let registrations = [
+            (RegistryHive::LocalMachine, format!("SOFTWARE\\Classes\\Installer\\Products\\{}", msi.packed_product_code)),
+            (RegistryHive::LocalMachine, format!("{}\\{}\\Products\\{}", INSTALLER_USERDATA_PATH, sid, msi.packed_product_code)),
+        ];
+        for (hive, path) in registrations {
+            if registry.open_key(hive, &path).is_some() {
+                let full_path = format!("{}\\{}", hive.name(), path);
+                candidates.push(leftover(LeftoverKind::RegistryKey, &full_path, LeftoverReason::MsiRegistration, Confidence::High, "Windows Installer product registration"));
+            }
+        }
+    }
+
+    let matcher = match matcher {
+        Some(matcher) => matcher,
+        None => return candidates,
+    };
+    // A per-user entry keeps its settings in its owner's hive
+    let user_software = match &program.user_sid {
+        Some(sid) => (RegistryHive::Users, format!("{}\\Software", sid)),
+        None => (RegistryHive::CurrentUser, "Software".to_string()),
+    };
+    let roots = [
+        (RegistryHive::LocalMachine, "SOFTWARE".to_string()),
+        (RegistryHive::LocalMachine, "SOFTWARE\\WOW6432Node".to_string()),
+        user_software,
+    ];
+    for (hive, root_path) in roots {
+        let root = match registry.open_key(hive, &root_path) {
+            Some(root) => root,
+            None => continue,
+        };
+        for name in root.subkey_names() {
+            let key_path = format!("{}\\{}\\{}", hive.name(), root_path, name);
+            if let Some((reason, confidence, detail)) = matcher.grade(&name) {
+                candidates.push(leftover(LeftoverKind::RegistryKey, &key_path, reason, confidence, &detail));
+                continue;
+            }
+            // Vendors nest products under a key of their own, Microsoft included
+            if !matcher.is_publisher(&name) {
+                continue;
+            }
+            let children = root.open_subkey(&name).map(|key| key.subkey_names()).unwrap_or_default();
+            let matches: Vec<(String, LeftoverReason, Confidence, String)> = children.iter()
+                .filter_map(|child| matcher.grade(child).map(|(reason, confidence, detail)| (child.clone(), reason, confidence, detail)))
+                .collect();
+            if children.len() == 1 && matches.len() == 1 && !SHARED_SOFTWARE_KEYS.contains(&name.to_lowercase().as_str()) {
+                candidates.push(leftover(LeftoverKind::RegistryKey, &key_path, LeftoverReason::PublisherMatch, Confidence::Low, "Publisher key holding only this program"));
+            }
+            for (child, reason, confidence, detail) in matches {
+                candidates.push(leftover(LeftoverKind::RegistryKey, &format!("{}\\{}", key_path, child), reason, confidence, &detail));
+            }
+        }
+    }
+    candidates
+}
+
+// Folders and files are checked on disk, registry keys in the live registry when there is one
+fn leftover_exists(item: &Leftover, registry: Option<&dyn RegistrySource>) -> bool {
+    match item.kind {
+        LeftoverKind::RegistryKey => registry.map_or(false, |registry| registry_key_exists(registry, &item.path)),
+        _ => Path::new(&item.path).exists(),
+    }
+}
+
+fn registry_key_exists(registry: &dyn RegistrySource, full_path: &str) -> bool {
+    let (hive_name, path) = match full_path.split_once('\\') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let hive = [RegistryHive::LocalMachine, RegistryHive::CurrentUser, RegistryHive::Users, RegistryHive::ClassesRoot]
+        .into_iter()
+        .find(|hive| hive.name().eq_ignore_ascii_case(hive_name));
+    hive.map_or(false, |hive| registry.open_key(hive, path).is_some())
+}
+
+/// Matches folder, shortcut and key names against a program's name and publisher
+pub struct NameMatcher {
+    name: String,              // Normalized DisplayName, e.g. "mozilla firefox"
+    short_name: Option<String>, // The name without the publisher's words, e.g. "firefox"
+    publisher: Option<String>,
+}
+
+impl NameMatcher {
+    /// None when the name is too short to search by
+    pub fn new(name: &str, version: Option<&str>, publisher: Option<&str>) -> Option<Self> {
+        // Folders are named after the product, not its "(x64 en-US)" or "(User)" qualifiers
+        let mut unqualified = String::new();
+        let mut depth = 0;
+        for c in name.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                _ if depth == 0 => unqualified.push(c),
+                _ => {}
+            }
+        }
+        let name = normalize_name(&unqualified, version);
+        if name.len() < MIN_NAME_LENGTH {
+            return None;
+        }
+        let publisher = publisher.map(normalize_publisher).filter(|publisher| publisher.len() >= MIN_NAME_LENGTH);
+        let short_name = publisher.as_ref()
+            .map(|publisher| {
+                let publisher_words: Vec<&str> = publisher.split(' ').collect();
+                name.split(' ').filter(|word| !publisher_words.contains(word)).collect::<Vec<_>>().join(" ")
+            })
+            .filter(|short_name| short_name.len() >= MIN_NAME_LENGTH && *short_name != name);
+        Some(NameMatcher { name, short_name, publisher })
+    }
+
+    /// Why a folder or key of this name would be the program's
+    pub fn grade(&self, entry_name: &str) -> Option<(LeftoverReason, Confidence, String)> {
+        let entry = normalize_name(entry_name, None);
+        if entry.is_empty() {
+            return None;
+        }
+        if entry == self.name {
+            return Some((LeftoverReason::NameMatch, Confidence::High, format!("Named after the program ({})", entry_name)));
+        }
+        if self.short_name.as_deref() == Some(entry.as_str()) {
+            return Some((LeftoverReason::NameMatch, Confidence::Medium, format!("Named after the program without its publisher ({})", entry_name)));
+        }
+        // Whole words only: "Code" must not match "Encoder"
+        if format!(" {} ", entry).contains(&format!(" {} ", self.name)) {
+            return Some((LeftoverReason::NameMatch, Confidence::Low, format!("Name contains the program's ({})", entry_name)));
+        }
+        None
+    }
+
+    pub fn is_publisher(&self, entry_name: &str) -> bool {
+        self.publisher.as_deref().map_or(false, |publisher| normalize_publisher(entry_name) == publisher)
+    }
+}
+
+// Where programs keep their folders; Start Menu folders hold their shortcuts
+fn folder_roots(environment: &dyn EnvironmentProvider) -> Vec<String> {
+    expand_folders(environment, &[
+        "%ProgramFiles%",
+        "%ProgramFiles(x86)%",
+        "%CommonProgramFiles%",
+        "%APPDATA%",
+        "%LOCALAPPDATA%",
+        "%LOCALAPPDATA%\\Programs",
+        "%APPDATA%\\Microsoft\\Windows\\Start Menu\\Programs",
+        "%ProgramData%\\Microsoft\\Windows\\Start Menu\\Programs",
+    ])
+}
+
+fn shortcut_roots(environment: &dyn EnvironmentProvider) -> Vec<String> {
+    expand_folders(environment, &[
+        "%APPDATA%\\Microsoft\\Windows\\Start Menu\\Programs",
+        "%ProgramData%\\Microsoft\\Windows\\Start Menu\\Programs",
+        "%USERPROFILE%\\Desktop",
+        "%PUBLIC%\\Desktop",
+    ])
+}
+
+// Each folder once; one whose variable the environment does not have is left out rather than searched as written
+fn expand_folders(environment: &dyn EnvironmentProvider, folders: &[&str]) -> Vec<String> {
+    let mut expanded: Vec<String> = Vec::new();
+    for folder in folders {
+        let path = expand(folder, environment);
+        if !path.contains('%') && !expanded.iter().any(|existing| existing.eq_ignore_ascii_case(&path)) {
+            expanded.push(path);
+        }
+    }
+    expanded
+}
+
+// A folder named after the program, or the matching folders inside a publisher folder
+fn grade_folder(folder: &Path, matcher: &NameMatcher) -> Vec<(String, LeftoverReason, Confidence, String)> {
+    let mut found = Vec::new();
+    let name = match folder.file_name() {
+        Some(name) if folder.is_dir() => name.to_string_lossy().to_string(),
+        _ => return found,
+    };
+    if let Some((reason, confidence, detail)) = matcher.grade(&name) {
+        found.push((folder.to_string_lossy().to_string(), reason, confidence, detail));
+        return found;
+    }
+    if !matcher.is_publisher(&name) {
+        return found;
+    }
+
+    let children: Vec<PathBuf> = fs::read_dir(folder)
+        .map(|children| children.flatten().map(|child| child.path()).collect())
+        .unwrap_or_default();
+    let matches: Vec<(String, LeftoverReason, Confidence, String)> = children.iter()
+        .filter(|child| child.is_dir())
+        .filter_map(|child| {
+            let (reason, confidence, detail) = matcher.grade(&child.file_name()?.to_string_lossy())?;
+            Some((child.to_string_lossy().to_string(), reason, confidence, detail))
+        })
+        .collect();
+    if children.len() == 1 && matches.len() == 1 {
+        found.push((folder.to_string_lossy().to_string(), LeftoverReason::PublisherMatch, Confidence::Low, "Publisher folder holding only this program".to_string()));
+    }
+    found.extend(matches);
+    found
+}
+
+// Shortcuts store their target's path as text, ANSI or UTF-16, so a byte search finds those into a folder
+fn shortcuts_into(folder: &str, target_folder: &str, depth: usize) -> Vec<String> {
+    let mut found = Vec::new();
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return found,
+    };
+    let target = format!("{}\\", target_folder.to_lowercase());
+    let target_utf16: Vec<u8> = target.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 1 {
+                found.extend(shortcuts_into(&path.to_string_lossy(), target_folder, depth - 1));
+            }
+            continue;
+        }
+        let is_shortcut = path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("lnk"));
+        let small_enough = entry.metadata().map_or(false, |metadata| metadata.len() <= MAX_SHORTCUT_SIZE);
+        if !is_shortcut || !small_enough {
+            continue;
+        }
+        if let Ok(bytes) = fs::read(&path) {
+            let lower = bytes.to_ascii_lowercase();
+            if contains(&lower, target.as_bytes()) || contains(&lower, &target_utf16) {
+                found.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+    found
+}
+
+// An InstallLocation of "C:\Program Files" or a profile folder is wrong, and removing it would be a disaster
+pub fn is_program_specific_folder(path: &str, environment: &dyn EnvironmentProvider) -> bool {
+    let lower = path.to_lowercase();
+    let bytes = lower.as_bytes();
+    if bytes.len() < 4 || !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' || bytes[2] != b'\\' {
+        return false;
+    }
+    let shared = ["windows", "users", "program files", "program files (x86)", "programdata"];
+    if shared.contains(&lower[3..].trim_end_matches('\\')) {
+        return false;
+    }
+    let profile = expand("%USERPROFILE%", environment);
+    !folder_roots(environment).iter().chain(shortcut_roots(environment).iter()).chain(std::iter::once(&profile)).any(|root| root.eq_ignore_ascii_case(path))
+}
+
+fn leftover(kind: LeftoverKind, path: &str, reason: LeftoverReason, confidence: Confidence, detail: &str) -> Leftover {
+    Leftover {
+        kind,
+        path: path.to_string(),
+        reason,
+        confidence,
+        detail: detail.to_string(),
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn baseline(items: Vec<Leftover>) -> LeftoverBaseline {
+        LeftoverBaseline {
+            format_version: BASELINE_FORMAT_VERSION,
+            id: "test".to_string(),
+            name: "Example App".to_string(),
+            registry_path: "HKEY_LOCAL_MACHINE\\SOFTWARE\\Example".to_string(),
+            created_at: Utc::now(),
+            items,
+        }
+    }
+
+    #[test]
+    fn surviving_baseline_items_keep_their_confidence() {
+        let baseline = baseline(vec![
+            leftover(LeftoverKind::Folder, "C:\\Program Files\\Example App", LeftoverReason::InstallLocation, Confidence::High, "InstallLocation of the entry"),
+            leftover(LeftoverKind::Folder, "C:\\ProgramData\\Example App Tools", LeftoverReason::NameMatch, Confidence::Low, "Name contains the program's"),
+            leftover(LeftoverKind::Folder, "C:\\Users\\me\\AppData\\Roaming\\App", LeftoverReason::NameMatch, Confidence::Medium, "Named after the program without its publisher"),
+            leftover(LeftoverKind::File, "C:\\Windows\\Installer\\1a2b3c.msi", LeftoverReason::LocalPackage, Confidence::High, "Cached Windows Installer package"),
+        ]);
+        let (leftovers, removed) = compare_with_baseline(&baseline, &|item| item.kind == LeftoverKind::Folder);
+
+        assert_eq!(removed, 1);
+        let confidences: Vec<Confidence> = leftovers.iter().map(|item| item.confidence).collect();
+        assert_eq!(confidences, vec![Confidence::High, Confidence::Low, Confidence::Medium]);
+        assert!(leftovers.iter().all(|item| item.detail.ends_with("; present before the uninstall")));
+    }
+
+    #[test]
+    fn shortcuts_are_found_by_their_target_in_either_encoding() {
+        let dir = std::env::temp_dir().join(format!("leftover-shortcuts-{}", std::process::id()));
+        let nested = dir.join("Example");
+        fs::create_dir_all(&nested).unwrap();
+        let target = "C:\\Program Files\\Example App\\app.exe";
+        let utf16: Vec<u8> = target.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        fs::write(dir.join("ansi.lnk"), [b"L\0\0\0".as_slice(), target.as_bytes()].concat()).unwrap();
+        fs::write(nested.join("unicode.lnk"), [b"L\0\0\0".as_slice(), &utf16].concat()).unwrap();
+        fs::write(dir.join("other.lnk"), b"L\0\0\0C:\\Program Files\\Example Application\\app.exe").unwrap();
+        fs::write(dir.join("notes.txt"), target).unwrap();
+
+        let mut found: Vec<String> = shortcuts_into(&dir.to_string_lossy(), "c:\\program files\\EXAMPLE APP", 2)
+            .iter()
+            .map(|path| file_name(path).to_string())
+            .collect();
+        found.sort();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, vec!["ansi.lnk", "unicode.lnk"]);
+    }
+}
@@ -0,0 +1,475 @@
+// Identity and merge stage: the same product shows up under HKLM 64-bit, WOW6432Node, HKCU and the
+// portable-app scan. Entries are clustered and folded into one ProgramInfo per installed product.
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+
+use crate::commands::registry::ProgramInfo;
+use crate::services::version::ProgramVersion;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MatchReason {
+    ProductCode,      // Same Windows Installer ProductCode
+    InstallLocation,  // Same or nested install folder and the same normalized name
+    NameVersion,      // Same normalized name, publisher and version, with no conflicting install folder
+}
+
+/// A raw entry that was folded into another
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergedEntry {
+    pub name: String,
+    pub registry_path: String,
+    pub installation_source: String,
+    pub architecture: String,
+    pub matched_by: MatchReason,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergeCluster {
+    pub name: String,
+    pub kept_registry_path: String,
+    pub folded: Vec<MergedEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergeReport {
+    pub input_count: usize,
+    pub output_count: usize,
+    pub clusters: Vec<MergeCluster>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MergeOutcome {
+    pub programs: Vec<ProgramInfo>,
+    pub report: MergeReport,
+}
+
+// Name words that describe the build rather than the product
+const PLATFORM_WORDS: [&str; 10] = ["x64", "x86", "64", "32", "bit", "64bit", "32bit", "amd64", "arm64", "win64"];
+const PUBLISHER_SUFFIXES: [&str; 11] = ["inc", "corp", "corporation", "ltd", "limited", "llc", "gmbh", "co", "ag", "ab", "sa"];
+
+/// Merge a combined program list, e.g. results from several imports
+#[tauri::command]
+pub fn merge_programs(programs: Vec<ProgramInfo>) -> MergeOutcome {
+    merge_duplicate_programs(programs)
+}
+
+/// Cluster entries that describe the same installed product and fold each cluster into one entry
+pub fn merge_duplicate_programs(programs: Vec<ProgramInfo>) -> MergeOutcome {
+    let input_count = programs.len();
+    let identities: Vec<Identity> = programs.iter().map(Identity::of).collect();
+
+    let mut clusters = DisjointSet::new(programs.len());
+    // Owner of each cluster's registry entries, by its root; unset while it holds only portable finds,
+    // which can match anyone's install and must not join two users' installs through themselves
+    let mut cluster_owners: Vec<Option<Option<String>>> = identities.iter()
+        .map(|identity| if identity.is_filesystem { None } else { Some(identity.owner.clone()) })
+        .collect();
+    for i in 0..programs.len() {
+        for j in i + 1..programs.len() {
+            if match_reason(&identities[i], &identities[j]).is_none() {
+                continue;
+            }
+            let (a, b) = (clusters.find(i), clusters.find(j));
+            if a == b {
+                continue;
+            }
+            let owner = match (&cluster_owners[a], &cluster_owners[b]) {
+                (Some(a), Some(b)) if a != b => continue,
+                (Some(owner), _) | (_, Some(owner)) => Some(owner.clone()),
+                (None, None) => None,
+            };
+            clusters.union(a, b);
+            cluster_owners[clusters.find(a)] = owner;
+        }
+    }
+
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..programs.len() {
+        members.entry(clusters.find(i)).or_default().push(i);
+    }
+    // Keep the order of the first entry of each cluster
+    let mut groups: Vec<Vec<usize>> = members.into_values().collect();
+    groups.sort_by_key(|group| group[0]);
+
+    let mut slots: Vec<Option<ProgramInfo>> = programs.into_iter().map(Some).collect();
+    let mut merged_programs = Vec::new();
+    let mut report_clusters = Vec::new();
+
+    for group in groups {
+        if group.len() == 1 {
+            merged_programs.extend(slots[group[0]].take());
+            continue;
+        }
+
+        let primary = *group.iter()
+            .min_by_key(|&&i| (source_rank(slots[i].as_ref().unwrap()), std::cmp::Reverse(populated_fields(slots[i].as_ref().unwrap()))))
+            .unwrap();
+        let mut merged = slots[primary].take().unwrap();
+        let mut field_sources = BTreeMap::new();
+        let mut folded = Vec::new();
+
+        for &i in group.iter().filter(|&&i| i != primary) {
+            let other = slots[i].take().unwrap();
+            let matched_by = match_reason(&identities[primary], &identities[i])
+                .or_else(|| group.iter().filter(|&&j| j != i).find_map(|&j| match_reason(&identities[j], &identities[i])))
+                .unwrap_or(MatchReason::NameVersion);
+            fill_missing_fields(&mut merged, &other, &mut field_sources);
+            folded.push(MergedEntry {
+                name: other.name.clone(),
+                registry_path: other.registry_path.clone(),
+                installation_source: other.installation_source.clone(),
+                architecture: other.architecture.clone(),
+                matched_by,
+            });
+        }
+
+        println!("DEBUG: Merged {} entries into {} ({})", folded.len() + 1, merged.name, merged.registry_path);
+        report_clusters.push(MergeCluster {
+            name: merged.name.clone(),
+            kept_registry_path: merged.registry_path.clone(),
+            folded: folded.clone(),
+        });
+        merged.merged_from = Some(folded);
+        if !field_sources.is_empty() {
+            merged.field_sources = Some(field_sources);
+        }
+        merged_programs.push(merged);
+    }
+
+    MergeOutcome {
+        report: MergeReport {
+            input_count,
+            output_count: merged_programs.len(),
+            clusters: report_clusters,
+        },
+        programs: merged_programs,
+    }
+}
+
+// The comparable parts of an entry, normalized once up front
+struct Identity {
+    product_code: Option<String>,
+    name: String,
+    publisher: String,
+    version: Option<ProgramVersion>,
+    install_location: Option<String>,
+    platform: Option<&'static str>, // Build architecture, from a platform word in the name or the entry itself
+    owner: Option<String>, // Owning user for per-user installs; None for machine-wide ones
+    is_filesystem: bool,
+}
+
+impl Identity {
+    fn of(program: &ProgramInfo) -> Self {
+        Identity {
+            product_code: program.msi.as_ref().map(|msi| msi.product_code.clone()),
+            name: normalize_name(&program.name, program.version.as_deref()),
+            publisher: program.publisher.as_deref().map(normalize_publisher).unwrap_or_default(),
+            version: program.version_info.clone(),
+            install_location: program.install_location.as_deref().and_then(normalize_path),
+            platform: name_platform(&program.name).or_else(|| entry_platform(&program.architecture)),
+            owner: if program.installation_source == "User" {
+                program.user_sid.clone().or_else(|| program.installed_for.clone())
+            } else {
+                None
+            },
+            is_filesystem: program.installation_source == "Filesystem",
+        }
+    }
+}
+
+fn match_reason(a: &Identity, b: &Identity) -> Option<MatchReason> {
+    // Installs for different users, or for one user and for everyone, are separate installs.
+    // The portable scan cannot tell who a folder belongs to
+    if a.owner != b.owner && !a.is_filesystem && !b.is_filesystem {
+        return None;
+    }
+
+    // Two Windows Installer products are only ever the same product by ProductCode
+    match (&a.product_code, &b.product_code) {
+        (Some(a), Some(b)) if a.eq_ignore_ascii_case(b) => return Some(MatchReason::ProductCode),
+        (Some(_), Some(_)) => return None,
+        _ => {}
+    }
+    if a.name.is_empty() || a.name != b.name {
+        return None;
+    }
+
+    let paths_overlap = match (&a.install_location, &b.install_location) {
+        (Some(a), Some(b)) => Some(paths_overlap(a, b)),
+        _ => None,
+    };
+    if paths_overlap == Some(true) {
+        return Some(MatchReason::InstallLocation);
+    }
+
+    // Side-by-side 32-bit and 64-bit builds share name and version but not the folder or the architecture,
+    // and often record no folder at all; 2.1 and 2.1.0 are the same version
+    let platforms_differ = matches!((a.platform, b.platform), (Some(a), Some(b)) if a != b);
+    if paths_overlap != Some(false) && !platforms_differ && a.publisher == b.publisher && a.version == b.version {
+        return Some(MatchReason::NameVersion);
+    }
+
+    None
+}
+
+// "7-Zip 23.01 (x64)" and the portable folder "7-Zip" both become "7 zip": the version and
+// platform words are dropped, as is a shortened version ("Foo 2.1" with DisplayVersion 2.1.0)
+pub fn normalize_name(name: &str, version: Option<&str>) -> String {
+    let mut lower = name.to_lowercase();
+    if let Some(version) = version.map(str::trim).filter(|version| !version.is_empty()) {
+        let parts: Vec<&str> = version.split('.').collect();
+        for length in (2..=parts.len()).rev() {
+            lower = lower.replace(&parts[..length].join(".").to_lowercase(), " ");
+        }
+        lower = lower.replace(&version.to_lowercase(), " ");
+    }
+    lower.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !PLATFORM_WORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The platform word normalize_name drops: "(x64)", "x86", "64-bit", "amd64"
+fn name_platform(name: &str) -> Option<&'static str> {
+    let lower = name.to_lowercase();
+    let words: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).collect();
+    words.iter().enumerate().find_map(|(i, word)| {
+        let followed_by_bit = words.get(i + 1) == Some(&"bit");
+        match *word {
+            "x64" | "amd64" | "win64" | "64bit" => Some("64-bit"),
+            "64" if followed_by_bit => Some("64-bit"),
+            "x86" | "win32" | "32bit" => Some("32-bit"),
+            "32" if followed_by_bit => Some("32-bit"),
+            "arm64" => Some("ARM64"),
+            _ => None,
+        }
+    })
+}
+
+// Neutral and unknown entries can be either build
+fn entry_platform(architecture: &str) -> Option<&'static str> {
+    match architecture {
+        "64-bit" => Some("64-bit"),
+        "32-bit" => Some("32-bit"),
+        "ARM64" => Some("ARM64"),
+        "ARM" => Some("ARM"),
+        _ => None,
+    }
+}
+
+pub fn normalize_publisher(publisher: &str) -> String {
+    publisher.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && !PUBLISHER_SUFFIXES.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn normalize_path(path: &str) -> Option<String> {
+    let normalized = path.trim().trim_matches('"').replace('/', "\\").trim_end_matches('\\').to_lowercase();
+    if normalized.is_empty() {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+// One folder inside the other; a portable entry records its executable, the registry its folder
+fn paths_overlap(a: &str, b: &str) -> bool {
+    let nested = |outer: &str, inner: &str| inner.len() > outer.len()
+        && inner.starts_with(outer)
+        && inner[outer.len()..].starts_with('\\');
+    a == b || nested(a, b) || nested(b, a)
+}
+
+// Which entry of a cluster is kept as the base: machine-wide registry entries first, portable finds last
+fn source_rank(program: &ProgramInfo) -> u8 {
+    match (program.installation_source.as_str(), program.architecture.as_str()) {
+        ("System", "64-bit") => 0,
+        ("System", _) => 1,
+        ("User", _) => 2,
+        ("MSIX", _) => 3,
+        _ => 4,
+    }
+}
+
+fn populated_fields(program: &ProgramInfo) -> usize {
+    match serde_json::to_value(program) {
+        Ok(serde_json::Value::Object(fields)) => fields.values().filter(|value| !value.is_null()).count(),
+        _ => 0,
+    }
+}
+
+// Take fields the kept entry lacks from a folded one, recording where each came from
+fn fill_missing_fields(merged: &mut ProgramInfo, other: &ProgramInfo, field_sources: &mut BTreeMap<String, String>) {
+    macro_rules! fill {
+        ($($field:ident),* $(,)?) => {
+            $(
+                if merged.$field.is_none() && other.$field.is_some() {
+                    merged.$field = other.$field.clone();
+                    field_sources.insert(stringify!($field).to_string(), other.registry_path.clone());
+                }
+            )*
+        };
+    }
+
+    fill!(
+        version, version_info, registry_time, install_date, install_location, install_source,
+        install_folder_created, install_folder_modified, install_folder_owner, publisher,
+        uninstall_string, change_install_string, quiet_uninstall_string, comments, about_url,
+        update_info_url, help_link, install_source_path, installer_name, release_type, icon_path, display_icon,
+        msi_filename, estimated_size, attributes, language, parent_key_name, msi,
+        installer_technology,
+    );
+
+    // The most trustworthy install date wins, not merely the first one found
+    if let Some(installed_on) = &other.installed_on {
+        let better = merged.installed_on.as_ref().map_or(true, |current| installed_on.confidence > current.confidence);
+        if better {
+            merged.installed_on = Some(installed_on.clone());
+            field_sources.insert("installed_on".to_string(), other.registry_path.clone());
+        }
+    }
+
+    for (merged_paths, other_paths) in [
+        (&mut merged.shortcuts, &other.shortcuts),
+        (&mut merged.programdata_paths, &other.programdata_paths),
+    ] {
+        if let Some(other_paths) = other_paths {
+            let paths = merged_paths.get_or_insert_with(Vec::new);
+            for path in other_paths {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+        }
+    }
+
+    merged.is_windows_installer |= other.is_windows_installer;
+    merged.is_vf_deployed |= other.is_vf_deployed;
+}
+
+struct DisjointSet {
+    parents: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        DisjointSet { parents: (0..size).collect() }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut current = i;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(name: &str, version: &str, architecture: &str, source: &str, extra: serde_json::Value) -> ProgramInfo {
+        let mut fields = serde_json::json!({
+            "version": version,
+            "version_info": ProgramVersion::parse(version),
+            "publisher": "Microsoft Corporation",
+            "architecture": architecture,
+            "installation_source": source,
+        });
+        if let (Some(fields), serde_json::Value::Object(extra)) = (fields.as_object_mut(), extra) {
+            fields.extend(extra);
+        }
+        ProgramInfo::with_fields(name, fields)
+    }
+
+    fn msi(product_code: &str) -> serde_json::Value {
+        serde_json::json!({
+            "msi": {
+                "product_code": product_code,
+                "packed_product_code": "",
+                "upgrade_code": null,
+                "local_package": null,
+                "context": "Machine",
+                "user_sid": null,
+            }
+        })
+    }
+
+    #[test]
+    fn side_by_side_redistributables_stay_separate() {
+        // Both bundles register under WOW6432Node without an InstallLocation
+        let outcome = merge_duplicate_programs(vec![
+            program("Microsoft Visual C++ 2015-2022 Redistributable (x64) - 14.38.33130", "14.38.33130.0", "32-bit", "System", serde_json::json!({})),
+            program("Microsoft Visual C++ 2015-2022 Redistributable (x86) - 14.38.33130", "14.38.33130.0", "32-bit", "System", serde_json::json!({})),
+        ]);
+        assert_eq!(outcome.programs.len(), 2);
+        assert!(outcome.report.clusters.is_empty());
+    }
+
+    #[test]
+    fn different_product_codes_are_different_products() {
+        let outcome = merge_duplicate_programs(vec![
+            program("Microsoft Visual C++ 2022 X64 Minimum Runtime - 14.38.33130", "14.38.33130", "64-bit", "System", msi("{A181A302-3F6D-4BAD-97A8-A426A6499D78}")),
+            program("Microsoft Visual C++ 2022 X86 Minimum Runtime - 14.38.33130", "14.38.33130", "32-bit", "System", msi("{59CED48F-EBFE-480C-8A38-FC079C2BEC0F}")),
+            program("Contoso Agent", "2.0", "Unknown", "System", msi("{11111111-1111-1111-1111-111111111111}")),
+            program("Contoso Agent", "2.0", "Unknown", "System", msi("{22222222-2222-2222-2222-222222222222}")),
+        ]);
+        assert_eq!(outcome.programs.len(), 4);
+    }
+
+    #[test]
+    fn one_product_seen_twice_is_merged() {
+        let outcome = merge_duplicate_programs(vec![
+            program("Contoso Agent", "2.0", "64-bit", "System", msi("{11111111-1111-1111-1111-111111111111}")),
+            program("Contoso Agent (x64)", "2.0.0", "64-bit", "System", serde_json::json!({ "install_location": "C:\\Program Files\\Contoso" })),
+            program("Contoso Agent", "2.0", "64-bit", "Filesystem", serde_json::json!({ "install_location": "C:\\Program Files\\Contoso\\agent.exe" })),
+            program("Contoso Agent", "2.0", "32-bit", "System", serde_json::json!({ "install_location": "C:\\Program Files (x86)\\Contoso" })),
+        ]);
+        assert_eq!(outcome.programs.len(), 2);
+        let merged = &outcome.programs[0];
+        assert_eq!(merged.merged_from.as_ref().unwrap().len(), 2);
+        assert!(merged.msi.is_some());
+        assert_eq!(merged.install_location.as_deref(), Some("C:\\Program Files\\Contoso"));
+        assert_eq!(outcome.programs[1].install_location.as_deref(), Some("C:\\Program Files (x86)\\Contoso"));
+    }
+
+    #[test]
+    fn a_portable_find_does_not_join_two_users_installs() {
+        let outcome = merge_duplicate_programs(vec![
+            program("Contoso Agent", "2.0", "64-bit", "User", serde_json::json!({ "user_sid": "S-1-5-21-1" })),
+            program("Contoso Agent", "2.0", "64-bit", "Filesystem", serde_json::json!({ "install_location": "C:\\Tools\\Contoso Agent" })),
+            program("Contoso Agent", "2.0", "64-bit", "User", serde_json::json!({ "user_sid": "S-1-5-21-2" })),
+        ]);
+        assert_eq!(outcome.programs.len(), 2);
+        assert_eq!(outcome.programs[0].user_sid.as_deref(), Some("S-1-5-21-1"));
+        assert_eq!(outcome.programs[0].merged_from.as_ref().unwrap()[0].installation_source, "Filesystem");
+        assert_eq!(outcome.programs[1].user_sid.as_deref(), Some("S-1-5-21-2"));
+        assert!(outcome.programs[1].merged_from.is_none());
+    }
+
+    #[test]
+    fn normalizes_names_and_platforms() {
+        assert_eq!(normalize_name("7-Zip 23.01 (x64)", Some("23.01")), "7 zip");
+        assert_eq!(name_platform("7-Zip 23.01 (x64)"), Some("64-bit"));
+        assert_eq!(name_platform("Python 3.12.1 (32-bit)"), Some("32-bit"));
+        assert_eq!(name_platform("Python 3.12.1 (64 bit)"), Some("64-bit"));
+        assert_eq!(name_platform("Windows 32 Tools"), None);
+        assert_eq!(normalize_publisher("Contoso, Inc."), "contoso");
+    }
+}
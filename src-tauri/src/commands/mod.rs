@@ -1,6 +1,17 @@
 pub mod registry;
 pub mod export;
 pub mod cli;
+pub mod merge;
+pub mod health;
+pub mod leftovers;
+pub mod install_size;
+pub mod executables;
+pub mod signature_trust;
+pub mod file_hashes;
+pub mod scan_job;
+pub mod snapshots;
+pub mod uninstall;
+pub mod uninstall_queue;
 pub mod logs;
 pub mod icon_extraction;
 pub mod custom_icons; 
\ No newline at end of file
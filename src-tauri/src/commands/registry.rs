@@ -1,10 +1,36 @@
-use winreg::enums::*;
-use winreg::RegKey;
 use serde::{Serialize, Deserialize};
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
 use std::path::Path;
 use std::fs;
 use base64::Engine;
 use reqwest;
+use crate::commands::health::{attach_entry_health, EntryHealthInfo};
+use crate::commands::executables::ExecutableAnalysis;
+use crate::commands::file_hashes::FileHashInventory;
+use crate::commands::install_size::InstallSizeInfo;
+use crate::commands::merge::{merge_duplicate_programs, MergedEntry};
+use crate::services::registry_source::{live_registry, MemoryRegistry, RegistryHive, RegistryKey, RegistryProvenance, RegistrySource, RegistryView};
+use crate::services::regf::OfflineRegistry;
+use crate::services::environment::{expand, Environment, EnvironmentProvider, ProcessEnvironment, Variable, SYSTEM_ENVIRONMENT_PATH, USER_ENVIRONMENT_PATH};
+use crate::services::path_resolver::{find_path_case_insensitive, with_live_resolver, FilesystemRoots, PathResolver};
+use crate::services::reg_file::load_reg_file;
+use crate::services::installer_fingerprint::{fingerprint_uninstall_key, is_chocolatey_path, refine_with_uninstaller, Confidence, InstallerEngine, InstallerTechnology};
+use crate::services::command_line::{parse_command_line, CommandKind};
+use crate::services::install_date::{parse_install_time, parse_registry_date, DateConfidence, InstallDateInfo, InstallDateSource};
+use crate::services::appx::{load_appx_manifest, registered_packages, resolve_logo, AppxManifest, AppxRegistration, APPX_ALL_USER_STORE_PATH, MANIFEST_FILE_NAME};
+use crate::services::pe::{read_image, PeImage};
+use crate::services::msi::{open_install_properties, pack_guid, MsiContext, MsiFamilyMember, MsiInventory, MsiProductFamily, MsiProductInfo, LOCAL_SYSTEM_SID};
+use crate::services::version::{compare_version_strings, ProgramVersion};
+use crate::services::user_profiles::{profile_list, PROFILE_LIST_PATH, VOLATILE_ENVIRONMENT_PATH, resolve_user, same_session, session_identity, sid_for_profile_folder, UserProfile};
+
+const UNINSTALL_PATH: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall";
+const UNINSTALL_PATH_WOW64: &str = "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall";
+const CURRENT_VERSION_PATH: &str = "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion";
+const ATEA_APPLICATIONS_PATH: &str = "SOFTWARE\\Atea\\Applications";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SystemInfo {
@@ -18,9 +44,12 @@ pub struct ProgramInfo {
     pub name: String,                    // DisplayName
     pub registry_name: String,           // Registry key name
     pub version: Option<String>,         // DisplayVersion
+    pub version_info: Option<ProgramVersion>, // Parsed DisplayVersion for ordering and normalized display
     pub registry_time: Option<String>,   // NEW: InstallTime
     pub install_date: Option<String>,    // InstallDate
-    pub installed_for: Option<String>,   // NEW: Based on registry path (32/64 bit)
+    pub installed_on: Option<InstallDateInfo>, // Parsed install date, with its source and confidence
+    pub installed_for: Option<String>,   // NEW: Based on registry path (32/64 bit), or the owning user's name
+    pub user_sid: Option<String>,        // SID of the owning user for per-user installs
     pub install_location: Option<String>, // InstallLocation
     pub install_source: Option<String>,  // NEW: InstallSource
     pub install_folder_created: Option<String>, // NEW
@@ -37,7 +66,8 @@ pub struct ProgramInfo {
     pub install_source_path: Option<String>, // NEW
     pub installer_name: Option<String>,  // NEW
     pub release_type: Option<String>,    // NEW
-    pub icon_path: Option<String>,       // DisplayIcon
+    pub icon_path: Option<String>,       // DisplayIcon, resolved; None when the file is not there
+    pub display_icon: Option<String>,    // DisplayIcon as written
     pub msi_filename: Option<String>,    // NEW
     pub estimated_size: Option<u32>,     // NEW: EstimatedSize
     pub attributes: Option<String>,      // NEW
@@ -46,11 +76,41 @@ pub struct ProgramInfo {
     pub shortcuts: Option<Vec<String>>,  // NEW: List of shortcut paths found
     pub programdata_paths: Option<Vec<String>>, // NEW: List of ProgramData paths found
     pub registry_path: String,
+    pub registry_provenance: Option<RegistryProvenance>, // Hive, view, exact key path and LastWriteTime
     pub program_type: String,
     pub is_windows_installer: bool,
+    pub msi: Option<MsiProductInfo>,     // ProductCode, UpgradeCode, LocalPackage and context for MSI installs
+    pub installer_technology: Option<InstallerTechnology>, // MSI, NSIS, Inno Setup, ..., with the evidence for it
+    pub health: Option<EntryHealthInfo>, // Missing uninstaller, cached package, icon or folder; live scans only
+    pub install_size: Option<InstallSizeInfo>, // Declared and measured size, once measured on request
+    pub executables: Option<ExecutableAnalysis>, // Machine, subsystem and signer of the main executable and uninstaller, on request
+    pub file_hashes: Option<FileHashInventory>, // SHA-256, SHA-1 and MD5 of those executables and any IOC hits, on request
     pub architecture: String,
-    pub installation_source: String,     // NEW: "System", "User", "Filesystem"
+    pub installation_source: String,     // NEW: "System", "User", "Filesystem", "MSIX"
     pub is_vf_deployed: bool,            // NEW: Indicates if deployed by VF company
+    pub merged_from: Option<Vec<MergedEntry>>, // Raw entries folded into this one by the merge stage
+    pub field_sources: Option<BTreeMap<String, String>>, // Field name -> registry path of the folded entry it was taken from
+}
+
+#[cfg(test)]
+impl ProgramInfo {
+    /// A machine-wide 64-bit entry with the given fields set and every other optional field empty
+    pub fn with_fields(name: &str, fields: serde_json::Value) -> Self {
+        let mut program = serde_json::json!({
+            "name": name,
+            "registry_name": name,
+            "registry_path": format!("HKEY_LOCAL_MACHINE\\{}\\{}", UNINSTALL_PATH, name),
+            "program_type": "Application",
+            "is_windows_installer": false,
+            "architecture": "64-bit",
+            "installation_source": "System",
+            "is_vf_deployed": false,
+        });
+        if let (Some(program), serde_json::Value::Object(fields)) = (program.as_object_mut(), fields) {
+            program.extend(fields);
+        }
+        serde_json::from_value(program).unwrap()
+    }
 }
 
 #[tauri::command]
@@ -76,14 +136,15 @@ pub fn open_winver() -> Result<(), String> {
 
 #[tauri::command]
 pub fn debug_windows_version() -> Result<String, String> {
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let registry = live_registry()?;
+    let version_key = registry.open_key(RegistryHive::LocalMachine, CURRENT_VERSION_PATH);
     
-    if let Ok(version_key) = hklm.open_subkey("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion") {
-        let product_name = version_key.get_value::<String, _>("ProductName").unwrap_or_default();
-        let build_number = version_key.get_value::<u32, _>("CurrentBuild").unwrap_or(0);
-        let display_version = version_key.get_value::<String, _>("DisplayVersion").unwrap_or_default();
-        let release_id = version_key.get_value::<String, _>("ReleaseId").unwrap_or_default();
-        let ubr = version_key.get_value::<u32, _>("UBR").unwrap_or(0);
+    if let Some(version_key) = version_key {
+        let product_name = version_key.get_string("ProductName").unwrap_or_default();
+        let build_number = version_key.get_dword("CurrentBuild").unwrap_or(0);
+        let display_version = version_key.get_string("DisplayVersion").unwrap_or_default();
+        let release_id = version_key.get_string("ReleaseId").unwrap_or_default();
+        let ubr = version_key.get_dword("UBR").unwrap_or(0);
         
         Ok(format!(
             "ProductName: {}\nBuild: {}\nDisplayVersion: {}\nReleaseId: {}\nUBR: {}",
@@ -129,15 +190,16 @@ fn get_windows_version() -> Result<String, String> {
     }
     
     // Fallback to registry if PowerShell fails
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let registry = live_registry()?;
+    let version_key = registry.open_key(RegistryHive::LocalMachine, CURRENT_VERSION_PATH);
     
-    if let Ok(version_key) = hklm.open_subkey("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion") {
-        let product_name = version_key.get_value::<String, _>("ProductName")
-            .unwrap_or_else(|_| "Windows".to_string());
+    if let Some(version_key) = version_key {
+        let product_name = version_key.get_string("ProductName")
+            .unwrap_or_else(|| "Windows".to_string());
         
-        let display_version = version_key.get_value::<String, _>("DisplayVersion")
-            .unwrap_or_else(|_| version_key.get_value::<String, _>("ReleaseId")
-                .unwrap_or_else(|_| "Unknown".to_string()));
+        let display_version = version_key.get_string("DisplayVersion")
+            .unwrap_or_else(|| version_key.get_string("ReleaseId")
+                .unwrap_or_else(|| "Unknown".to_string()));
         
         // Clean up the product name
         let clean_product_name = if product_name.contains("Windows 11") {
@@ -156,73 +218,565 @@ fn get_windows_version() -> Result<String, String> {
 
 #[tauri::command]
 pub fn get_installed_programs() -> Result<Vec<ProgramInfo>, String> {
+    let registry = live_registry()?;
+    Ok(scan_installed_programs(registry.as_ref()))
+}
+
+/// Run the same scan as `get_installed_programs` against a captured JSON registry fixture
+#[tauri::command]
+pub fn get_installed_programs_from_fixture(fixture_path: String) -> Result<Vec<ProgramInfo>, String> {
+    let registry = MemoryRegistry::load_from_file(&fixture_path)
+        .map_err(|e| format!("Failed to load registry fixture {}: {}", fixture_path, e))?;
+    Ok(scan_installed_programs(&registry))
+}
+
+/// Capture the keys the inventory scan reads into a JSON fixture for offline reproduction
+#[tauri::command]
+pub fn capture_registry_fixture(output_path: String) -> Result<String, String> {
+    let live = live_registry()?;
+    let mut fixture = MemoryRegistry::new();
+
+    let mut captured_keys = vec![
+        (RegistryHive::LocalMachine, UNINSTALL_PATH.to_string()),
+        (RegistryHive::LocalMachine, UNINSTALL_PATH_WOW64.to_string()),
+        (RegistryHive::CurrentUser, UNINSTALL_PATH.to_string()),
+        (RegistryHive::CurrentUser, VOLATILE_ENVIRONMENT_PATH.to_string()),
+        (RegistryHive::CurrentUser, USER_ENVIRONMENT_PATH.to_string()),
+        (RegistryHive::LocalMachine, SYSTEM_ENVIRONMENT_PATH.to_string()),
+        (RegistryHive::LocalMachine, ATEA_APPLICATIONS_PATH.to_string()),
+        (RegistryHive::LocalMachine, PROFILE_LIST_PATH.to_string()),
+        (RegistryHive::LocalMachine, APPX_ALL_USER_STORE_PATH.to_string()),
+    ];
+
+    // Loaded profiles of other users, as far as we are allowed to read them
+    for hive_name in live.user_hive_names() {
+        captured_keys.push((RegistryHive::Users, format!("{}\\{}", hive_name, UNINSTALL_PATH)));
+        captured_keys.push((RegistryHive::Users, format!("{}\\{}", hive_name, VOLATILE_ENVIRONMENT_PATH)));
+        captured_keys.push((RegistryHive::Users, format!("{}\\{}", hive_name, USER_ENVIRONMENT_PATH)));
+    }
+
+    let mut captured = 0;
+    for (hive, path) in &captured_keys {
+        if fixture.capture_from(live.as_ref(), *hive, path) {
+            captured += 1;
+        }
+    }
+
+    fixture.save_to_file(&output_path)
+        .map_err(|e| format!("Failed to write registry fixture {}: {}", output_path, e))?;
+
+    Ok(format!("Captured {} registry keys to {}", captured, output_path))
+}
+
+/// Inventory a machine from a regedit export (.reg) of its uninstall keys
+#[tauri::command]
+pub fn import_reg_file(file_path: String) -> Result<Vec<ProgramInfo>, String> {
+    let registry = load_reg_file(&file_path)
+        .map_err(|e| format!("Failed to import registry export {}: {}", file_path, e))?;
+    Ok(scan_offline_registry(&registry, &FilesystemRoots::Unavailable))
+}
+
+/// Inventory a machine from registry hive files copied off its disk. The SYSTEM hive is optional; it holds
+/// the machine's environment variables, which otherwise fall back to what SOFTWARE records
+#[tauri::command]
+pub fn scan_offline_hives(software_hive: Option<String>, system_hive: Option<String>, user_hives: Vec<String>) -> Result<Vec<ProgramInfo>, String> {
+    let mut registry = OfflineRegistry::new();
+
+    if let Some(path) = &software_hive {
+        registry.load_software_hive(path)
+            .map_err(|e| format!("Failed to load SOFTWARE hive {}: {}", path, e))?;
+    }
+    if let Some(path) = &system_hive {
+        registry.load_system_hive(path)
+            .map_err(|e| format!("Failed to load SYSTEM hive {}: {}", path, e))?;
+    }
+
+    let profiles = profile_list(&registry);
+    for path in &user_hives {
+        let folder_name = offline_user_name(Path::new(path));
+        let hive_name = sid_for_profile_folder(&profiles, &folder_name).unwrap_or(folder_name);
+        registry.load_user_hive(&hive_name, path)
+            .map_err(|e| format!("Failed to load user hive {}: {}", path, e))?;
+    }
+
+    Ok(scan_offline_registry(&registry, &FilesystemRoots::Unavailable))
+}
+
+/// Inventory a Windows installation mounted or attached as another drive (e.g. "E:\" or "/mnt/evidence")
+#[tauri::command]
+pub fn scan_external_drive(drive: String) -> Result<Vec<ProgramInfo>, String> {
+    let root = Path::new(&drive);
+    if !root.is_dir() {
+        return Err(format!("Drive or directory does not exist: {}", drive));
+    }
+
+    let software_hive = find_path_case_insensitive(root, &["Windows", "System32", "config", "SOFTWARE"])
+        .ok_or_else(|| format!("No SOFTWARE hive found under {}", drive))?;
+
+    let mut registry = OfflineRegistry::new();
+    registry.load_software_hive(&software_hive.to_string_lossy())
+        .map_err(|e| format!("Failed to load SOFTWARE hive {}: {}", software_hive.display(), e))?;
+    if let Some(system_hive) = find_path_case_insensitive(root, &["Windows", "System32", "config", "SYSTEM"]) {
+        if let Err(e) = registry.load_system_hive(&system_hive.to_string_lossy()) {
+            println!("DEBUG: Skipping SYSTEM hive {}: {}", system_hive.display(), e);
+        }
+    }
+
+    // Per-user hives live in each profile folder under Users; ProfileList maps the folders back to SIDs
+    let profiles = profile_list(&registry);
+    if let Some(users_dir) = find_path_case_insensitive(root, &["Users"]) {
+        if let Ok(entries) = fs::read_dir(&users_dir) {
+            for entry in entries.flatten() {
+                if let Some(ntuser) = find_path_case_insensitive(&entry.path(), &["NTUSER.DAT"]) {
+                    let folder_name = entry.file_name().to_string_lossy().to_string();
+                    let hive_name = sid_for_profile_folder(&profiles, &folder_name).unwrap_or(folder_name);
+                    if let Err(e) = registry.load_user_hive(&hive_name, &ntuser.to_string_lossy()) {
+                        println!("DEBUG: Skipping user hive {}: {}", ntuser.display(), e);
+                    }
+                }
+            }
+        }
+    }
+
+    // The volume is mounted at `root`, but the registry refers to it by the drive letter Windows booted from
+    let system_drive = expand("%SystemDrive%", &Environment::from_registry(&registry));
+    Ok(scan_offline_registry(&registry, &FilesystemRoots::mounted(&system_drive, root)))
+}
+
+/// Keep programs installed between `since` and `until` (inclusive, YYYY-MM-DD), optionally only
+/// counting dates at least as trustworthy as `min_confidence`
+#[tauri::command]
+pub fn filter_programs_by_install_date(
+    programs: Vec<ProgramInfo>,
+    since: Option<String>,
+    until: Option<String>,
+    min_confidence: Option<DateConfidence>,
+) -> Result<Vec<ProgramInfo>, String> {
+    let parse_bound = |bound: Option<String>| -> Result<Option<NaiveDate>, String> {
+        bound.map(|raw| NaiveDate::parse_from_str(&raw, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date {}: {}", raw, e)))
+            .transpose()
+    };
+    let since = parse_bound(since)?;
+    let until = parse_bound(until)?;
+
+    Ok(filter_by_install_date(programs, since, until, min_confidence.unwrap_or(DateConfidence::Low)))
+}
+
+pub fn filter_by_install_date(
+    programs: Vec<ProgramInfo>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    min_confidence: DateConfidence,
+) -> Vec<ProgramInfo> {
+    programs.into_iter()
+        .filter(|program| match &program.installed_on {
+            Some(installed_on) => installed_on.confidence >= min_confidence
+                && since.map_or(true, |since| installed_on.date >= since)
+                && until.map_or(true, |until| installed_on.date <= until),
+            None => false,
+        })
+        .collect()
+}
+
+/// Compare two version strings: -1 if `a` is older, 0 if equal, 1 if newer
+#[tauri::command]
+pub fn compare_versions(a: String, b: String) -> i32 {
+    match compare_version_strings(&a, &b) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+/// Keep programs whose version is at least `min_version` and below `below_version`
+#[tauri::command]
+pub fn filter_programs_by_version(
+    programs: Vec<ProgramInfo>,
+    min_version: Option<String>,
+    below_version: Option<String>,
+) -> Result<Vec<ProgramInfo>, String> {
+    let parse_bound = |bound: Option<String>| -> Result<Option<ProgramVersion>, String> {
+        bound.map(|raw| ProgramVersion::parse(&raw).ok_or_else(|| format!("Invalid version: {}", raw)))
+            .transpose()
+    };
+    let min_version = parse_bound(min_version)?;
+    let below_version = parse_bound(below_version)?;
+
+    Ok(filter_by_version(programs, min_version.as_ref(), below_version.as_ref()))
+}
+
+// MSI products are compared the way Windows Installer does, on the first three fields only
+pub fn filter_by_version(
+    programs: Vec<ProgramInfo>,
+    min_version: Option<&ProgramVersion>,
+    below_version: Option<&ProgramVersion>,
+) -> Vec<ProgramInfo> {
+    programs.into_iter()
+        .filter(|program| {
+            let version = match &program.version_info {
+                Some(version) => version,
+                None => return false,
+            };
+            let compare = |bound: &ProgramVersion| if program.is_windows_installer {
+                version.cmp_msi(bound)
+            } else {
+                version.cmp(bound)
+            };
+            min_version.map_or(true, |min| compare(min) != Ordering::Less)
+                && below_version.map_or(true, |below| compare(below) == Ordering::Less)
+        })
+        .collect()
+}
+
+/// Group MSI products that share an UpgradeCode, newest version first within each family
+#[tauri::command]
+pub fn get_msi_product_families(programs: Vec<ProgramInfo>) -> Vec<MsiProductFamily> {
+    let mut families: BTreeMap<String, Vec<&ProgramInfo>> = BTreeMap::new();
+    for program in &programs {
+        if let Some(upgrade_code) = program.msi.as_ref().and_then(|msi| msi.upgrade_code.clone()) {
+            families.entry(upgrade_code).or_default().push(program);
+        }
+    }
+
+    families.into_iter()
+        .map(|(upgrade_code, mut members)| {
+            members.sort_by(|a, b| b.version_info.cmp(&a.version_info));
+            MsiProductFamily {
+                upgrade_code,
+                members: members.into_iter()
+                    .filter_map(|program| {
+                        let msi = program.msi.as_ref()?;
+                        Some(MsiFamilyMember {
+                            name: program.name.clone(),
+                            version: program.version.clone(),
+                            product_code: msi.product_code.clone(),
+                            context: msi.context,
+                            user_sid: msi.user_sid.clone(),
+                        })
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Receives progress from a running scan and can stop it early. Work is counted in registry keys,
+/// packages and programs, and the total grows as each source discovers how much it has to read
+pub trait ScanObserver {
+    fn stage(&self, _operation: &str) {}
+    fn add_work(&self, _items: usize) {}
+    fn advance(&self) {}
+    // Raw entries from one source, before merging
+    fn found(&self, _source: &str, _programs: &[ProgramInfo]) {}
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+struct NoProgress;
+
+impl ScanObserver for NoProgress {}
+
+/// Build the full program inventory from any registry source
+pub fn scan_installed_programs(registry: &dyn RegistrySource) -> Vec<ProgramInfo> {
+    scan_installed_programs_with(registry, &NoProgress)
+}
+
+/// `scan_installed_programs`, reporting to `observer`. A cancelled scan returns what it had found so far,
+/// merged but without the enrichment stages that run after all sources have been read
+pub fn scan_installed_programs_with(registry: &dyn RegistrySource, observer: &dyn ScanObserver) -> Vec<ProgramInfo> {
     let mut programs = Vec::new();
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let environment = Environment::from_registry(registry);
+    // Portable apps, shortcuts and the like are looked for in the profile of the user running the scan
+    let current_user = registry.open_key(RegistryHive::CurrentUser, "");
+    let session_environment = environment.for_session(current_user.as_deref());
+    let roots = FilesystemRoots::Live;
+    let resolver = PathResolver::new(&roots, &session_environment);
+
+    let completed = run_scan_stage(observer, &mut programs, "System", "Scanning machine-wide uninstall keys", |programs| {
+        scan_machine_programs(registry, &resolver, programs, observer)
+    })
+    // Scan user-installed programs for every profile, not just HKEY_CURRENT_USER
+    && run_scan_stage(observer, &mut programs, "User", "Scanning per-user uninstall keys", |programs| {
+        scan_user_programs(registry, &environment, &resolver, programs, observer)
+    })
+    // Store and MSIX apps never appear under the Uninstall keys
+    && run_scan_stage(observer, &mut programs, "MSIX", "Scanning MSIX and Store packages", |programs| {
+        scan_appx_packages(registry, &resolver, programs, observer)
+    })
+    // Scan alternative installation locations
+    && run_scan_stage(observer, &mut programs, "Filesystem", "Scanning for portable applications", |programs| {
+        scan_alternative_locations(&resolver, programs, observer)
+    });
+    if !completed {
+        // What was found so far, still one entry per product
+        return merge_duplicate_programs(programs).programs;
+    }
+
+    observer.stage("Reading Windows Installer data and install dates");
+    attach_msi_metadata(registry, &mut programs);
+    reconstruct_install_dates(registry, &mut programs, true);
+    inspect_uninstallers(&mut programs);
+
+    // Fold entries for the same product found by several of the sources above
+    observer.stage("Merging duplicate entries");
+    let mut programs = merge_duplicate_programs(programs).programs;
+    attach_entry_health(&mut programs, &ProgramEnvironments::new(Some(registry), &session_environment));
+
+    // Scan VF company deployed applications
+    observer.stage("Locating install folders, shortcuts and ProgramData");
+    scan_vf_deployed_applications(&mut programs, &resolver, observer);
+
+    programs
+}
 
+// Run one source, report what it found, and tell the caller whether to go on
+fn run_scan_stage(
+    observer: &dyn ScanObserver,
+    programs: &mut Vec<ProgramInfo>,
+    source: &str,
+    operation: &str,
+    stage: impl FnOnce(&mut Vec<ProgramInfo>),
+) -> bool {
+    if observer.is_cancelled() {
+        return false;
+    }
+    observer.stage(operation);
+    let before = programs.len();
+    stage(programs);
+    observer.found(source, &programs[before..]);
+    !observer.is_cancelled()
+}
+
+// Scan the system-wide (HKLM) uninstall keys, 64-bit and 32-bit
+fn scan_machine_programs(registry: &dyn RegistrySource, resolver: &PathResolver, programs: &mut Vec<ProgramInfo>, observer: &dyn ScanObserver) {
     // Scan system-wide 64-bit programs
-    if let Ok(uninstall_key) = hklm.open_subkey("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall") {
-        scan_registry_key(&uninstall_key, &mut programs, "64-bit", "System");
+    if let Some(uninstall_key) = registry.open_key(RegistryHive::LocalMachine, UNINSTALL_PATH) {
+        let location = UninstallKeyLocation::new(RegistryHive::LocalMachine, RegistryView::Registry64, UNINSTALL_PATH);
+        scan_registry_key(uninstall_key.as_ref(), programs, "64-bit", None, resolver, &location, observer);
     }
 
     // Scan system-wide 32-bit programs
-    if let Ok(uninstall_key) = hklm.open_subkey("SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall") {
-        scan_registry_key(&uninstall_key, &mut programs, "32-bit", "System");
+    if let Some(uninstall_key) = registry.open_key(RegistryHive::LocalMachine, UNINSTALL_PATH_WOW64) {
+        let location = UninstallKeyLocation::new(RegistryHive::LocalMachine, RegistryView::Registry32, UNINSTALL_PATH_WOW64);
+        scan_registry_key(uninstall_key.as_ref(), programs, "32-bit", None, resolver, &location, observer);
     }
+}
+
+// Scan the per-user uninstall keys of every profile: hives mounted under HKEY_USERS,
+// HKEY_CURRENT_USER when it is not one of those, and the NTUSER.DAT of profiles that are not loaded.
+// Each user's paths are expanded with that user's own environment
+fn scan_user_programs(registry: &dyn RegistrySource, environment: &Environment, resolver: &PathResolver, programs: &mut Vec<ProgramInfo>, observer: &dyn ScanObserver) {
+    let profiles = profile_list(registry);
+    let loaded_hives = registry.user_hive_names();
+
+    let current_session = registry.open_key(RegistryHive::CurrentUser, "")
+        .and_then(|current_user| session_identity(current_user.as_ref()));
+    let mut current_user_scanned = false;
+
+    for hive_name in &loaded_hives {
+        let user_root = match registry.open_key(RegistryHive::Users, hive_name) {
+            Some(user_root) => user_root,
+            None => continue,
+        };
 
-    // Scan user-installed programs (HKEY_CURRENT_USER)
-    if let Ok(uninstall_key) = hkcu.open_subkey("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall") {
-        scan_registry_key(&uninstall_key, &mut programs, "User", "User");
+        if let (Some(current), Some(session)) = (&current_session, session_identity(user_root.as_ref())) {
+            if same_session(current, &session) {
+                current_user_scanned = true;
+            }
+        }
+
+        let owner = resolve_user(registry, &profiles, hive_name);
+        let uninstall_key = user_root.open_subkey(UNINSTALL_PATH);
+        if let Some(uninstall_key) = uninstall_key {
+            let location = UninstallKeyLocation::new(RegistryHive::Users, RegistryView::Registry64, &format!("{}\\{}", hive_name, UNINSTALL_PATH));
+            let user_environment = environment.for_user(&owner, Some(user_root.as_ref()));
+            scan_registry_key(uninstall_key.as_ref(), programs, "User", Some(&owner), &resolver.with_environment(&user_environment), &location, observer);
+        }
     }
 
-    // Scan alternative installation locations
-    scan_alternative_locations(&mut programs);
+    if !current_user_scanned {
+        if let Some(uninstall_key) = registry.open_key(RegistryHive::CurrentUser, UNINSTALL_PATH) {
+            let owner = UserProfile {
+                sid: None,
+                user_name: current_session
+                    .map(|(user_name, _)| user_name)
+                    .unwrap_or_else(|| "Current User".to_string()),
+                profile_path: None,
+            };
+            let location = UninstallKeyLocation::new(RegistryHive::CurrentUser, RegistryView::Registry64, UNINSTALL_PATH);
+            let current_user = registry.open_key(RegistryHive::CurrentUser, "");
+            let user_environment = environment.for_user(&owner, current_user.as_deref());
+            scan_registry_key(uninstall_key.as_ref(), programs, "User", Some(&owner), &resolver.with_environment(&user_environment), &location, observer);
+        }
+    }
 
-    // Scan VF company deployed applications
-    scan_vf_deployed_applications(&mut programs);
+    // Profiles of users who are not logged on have no hive under HKEY_USERS
+    for profile in &profiles {
+        let sid = match &profile.sid {
+            Some(sid) => sid,
+            None => continue,
+        };
+        if loaded_hives.iter().any(|hive_name| hive_name.eq_ignore_ascii_case(sid)) {
+            continue;
+        }
+        let profile_path = match profile.profile_path.as_deref().and_then(|profile_path| resolver.local_path(profile_path)) {
+            Some(profile_path) => profile_path,
+            None => continue,
+        };
+
+        let hive_path = profile_path.join("NTUSER.DAT");
+        if !hive_path.exists() {
+            continue;
+        }
+        if let Some(user_root) = registry.load_user_hive_file(&hive_path) {
+            if let Some(uninstall_key) = user_root.open_subkey(UNINSTALL_PATH) {
+                // Recorded where Windows mounts the hive once the user logs on
+                let location = UninstallKeyLocation::new(RegistryHive::Users, RegistryView::Registry64, &format!("{}\\{}", sid, UNINSTALL_PATH));
+                let user_environment = environment.for_user(profile, Some(user_root.as_ref()));
+                scan_registry_key(uninstall_key.as_ref(), programs, "User", Some(profile), &resolver.with_environment(&user_environment), &location, observer);
+            }
+        }
+    }
+}
 
-    Ok(programs)
+// Offline scans look at files only through `roots`: a mounted image's, or none for registry-only sources,
+// never the analysis workstation's. The other filesystem heuristics describe a running machine and are skipped
+fn scan_offline_registry(registry: &dyn RegistrySource, roots: &FilesystemRoots) -> Vec<ProgramInfo> {
+    let mut programs = Vec::new();
+    let environment = Environment::from_registry(registry);
+    let resolver = PathResolver::new(roots, &environment);
+
+    scan_machine_programs(registry, &resolver, &mut programs, &NoProgress);
+    scan_user_programs(registry, &environment, &resolver, &mut programs, &NoProgress);
+    attach_msi_metadata(registry, &mut programs);
+    reconstruct_install_dates(registry, &mut programs, false);
+    let mut programs = merge_duplicate_programs(programs).programs;
+    mark_vf_deployed_applications(&mut programs);
+
+    programs
 }
 
-#[tauri::command]
-pub fn get_scan_progress() -> Result<ScanProgress, String> {
-    // This would be implemented with a shared state in a real application
-    // For now, return a mock progress
-    Ok(ScanProgress {
-        total_keys: 0,
-        scanned_keys: 0,
-        is_complete: false,
-        current_operation: "Ready".to_string(),
+// MSI uninstall entries are named after their ProductCode; match them against the Installer's own records
+fn attach_msi_metadata(registry: &dyn RegistrySource, programs: &mut [ProgramInfo]) {
+    let inventory = MsiInventory::load(registry);
+
+    for program in programs.iter_mut() {
+        if program.installation_source == "Filesystem" {
+            continue;
+        }
+        let packed = match pack_guid(&program.registry_name) {
+            Some(packed) => packed,
+            None => continue,
+        };
+        let per_user = program.installation_source == "User";
+
+        program.msi = inventory.product_info(&program.registry_name, per_user, program.user_sid.as_deref())
+            .or_else(|| {
+                // Not in the Installer's records (e.g. a .reg export of the Uninstall key alone)
+                if !program.is_windows_installer {
+                    return None;
+                }
+                Some(MsiProductInfo {
+                    product_code: program.registry_name.to_ascii_uppercase(),
+                    packed_product_code: packed,
+                    upgrade_code: None,
+                    local_package: None,
+                    context: if per_user { MsiContext::UserUnmanaged } else { MsiContext::Machine },
+                    user_sid: if per_user { program.user_sid.clone() } else { None },
+                })
+            });
+    }
+}
+
+// Settle installer technologies the registry left unsure about by reading the uninstaller executable,
+// e.g. an unins000.exe that turns out to be an Inno Setup stub
+fn inspect_uninstallers(programs: &mut [ProgramInfo]) {
+    for program in programs.iter_mut() {
+        let technology = match program.installer_technology.as_mut() {
+            Some(technology) if technology.confidence < Confidence::High => technology,
+            _ => continue,
+        };
+        let uninstaller = program.uninstall_string.as_deref()
+            .and_then(parse_command_line)
+            .filter(|command| command.kind == CommandKind::Executable);
+        if let Some(uninstaller) = uninstaller {
+            refine_with_uninstaller(technology, Path::new(&uninstaller.executable));
+        }
+    }
+}
+
+// Fill in install dates the uninstall keys do not record, most trustworthy source first:
+// MSI InstallProperties, the install folder's creation time, then the uninstall key's LastWriteTime
+fn reconstruct_install_dates(registry: &dyn RegistrySource, programs: &mut [ProgramInfo], inspect_filesystem: bool) {
+    for program in programs.iter_mut().filter(|program| program.installed_on.is_none()) {
+        program.installed_on = msi_install_date(registry, program)
+            .or_else(|| if inspect_filesystem { install_folder_date(program) } else { None })
+            .or_else(|| {
+                let last_write_time = program.registry_provenance.as_ref()?.last_write_time?;
+                Some(InstallDateInfo::from_timestamp(last_write_time, InstallDateSource::KeyLastWriteTime, DateConfidence::Low))
+            });
+    }
+}
+
+fn msi_install_date(registry: &dyn RegistrySource, program: &ProgramInfo) -> Option<InstallDateInfo> {
+    let msi = program.msi.as_ref()?;
+    let sid = msi.user_sid.as_deref().unwrap_or(LOCAL_SYSTEM_SID);
+    let properties = open_install_properties(registry, sid, &msi.product_code)?;
+    let (date, confidence) = parse_registry_date(&properties.get_string("InstallDate")?)?;
+    Some(InstallDateInfo {
+        date,
+        timestamp: None,
+        source: InstallDateSource::MsiInstallProperties,
+        confidence,
     })
 }
 
+// Folder creation is close to the install time, but pre-created or reused folders make it less certain
+fn install_folder_date(program: &ProgramInfo) -> Option<InstallDateInfo> {
+    let location = program.install_location.as_deref()?.trim().trim_matches('"');
+    if location.is_empty() {
+        return None;
+    }
+    let created = fs::metadata(location).ok()?.created().ok()?;
+    Some(InstallDateInfo::from_timestamp(created.into(), InstallDateSource::InstallFolderCreated, DateConfidence::Medium))
+}
+
+// Name an offline user hive after its profile folder (C:\Users\alice\NTUSER.DAT -> "alice")
+fn offline_user_name(hive_path: &Path) -> String {
+    hive_path.parent()
+        .and_then(|parent| parent.file_name())
+        .or_else(|| hive_path.file_stem())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Offline User".to_string())
+}
+
 #[tauri::command]
 pub fn debug_icon_paths() -> Result<Vec<DebugIconInfo>, String> {
     let mut debug_info = Vec::new();
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let registry = live_registry()?;
 
-    if let Ok(uninstall_key) = hklm.open_subkey("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall") {
+    if let Some(uninstall_key) = registry.open_key(RegistryHive::LocalMachine, UNINSTALL_PATH) {
         let mut count = 0;
-        for key_result in uninstall_key.enum_keys() {
-            if let Ok(key_name) = key_result {
-                if let Ok(program_key) = uninstall_key.open_subkey(&key_name) {
-                    if let Ok(name) = program_key.get_value::<String, _>("DisplayName") {
-                        let raw_icon_path: Option<String> = program_key.get_value("DisplayIcon").ok();
-                        let processed_icon_path = raw_icon_path.as_ref().and_then(|path| extract_icon_path(path));
-                        
-                        let file_exists = processed_icon_path.as_ref().map(|p| Path::new(p).exists()).unwrap_or(false);
-                        
-                        debug_info.push(DebugIconInfo {
-                            program_name: name,
-                            raw_icon_path,
-                            processed_icon_path,
-                            file_exists,
-                        });
-                        
-                        count += 1;
-                        if count >= 10 { // Limit to first 10 for debugging
-                            break;
-                        }
+        for key_name in uninstall_key.subkey_names() {
+            if let Some(program_key) = uninstall_key.open_subkey(&key_name) {
+                if let Some(name) = program_key.get_string("DisplayName") {
+                    let raw_icon_path: Option<String> = program_key.get_string("DisplayIcon");
+                    let processed_icon_path = raw_icon_path.as_ref().and_then(|path| with_live_resolver(|resolver| resolver.resolve_icon_file(path)));
+                    
+                    let file_exists = processed_icon_path.as_ref().map(|p| Path::new(p).exists()).unwrap_or(false);
+                    
+                    debug_info.push(DebugIconInfo {
+                        program_name: name,
+                        raw_icon_path,
+                        processed_icon_path,
+                        file_exists,
+                    });
+                    
+                    count += 1;
+                    if count >= 10 { // Limit to first 10 for debugging
+                        break;
                     }
                 }
             }
@@ -235,27 +789,25 @@ pub fn debug_icon_paths() -> Result<Vec<DebugIconInfo>, String> {
 #[tauri::command]
 pub fn debug_vf_apps() -> Result<Vec<VFDebugInfo>, String> {
     let mut debug_info = Vec::new();
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-
-    if let Ok(uninstall_key) = hklm.open_subkey("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall") {
-        for key_result in uninstall_key.enum_keys() {
-            if let Ok(key_name) = key_result {
-                if let Ok(program_key) = uninstall_key.open_subkey(&key_name) {
-                    if let Ok(name) = program_key.get_value::<String, _>("DisplayName") {
-                        let comments: Option<String> = program_key.get_value("Comments").ok();
-                        let has_appid = comments.as_ref().map_or(false, |c| c.contains("APPID:"));
-                        let icon_path: Option<String> = program_key.get_value("DisplayIcon").ok();
-                        let publisher: Option<String> = program_key.get_value("Publisher").ok();
-                        
-                        debug_info.push(VFDebugInfo {
-                            program_name: name,
-                            comments,
-                            has_appid,
-                            is_vf_deployed: has_appid,
-                            icon_path,
-                            publisher,
-                        });
-                    }
+    let registry = live_registry()?;
+
+    if let Some(uninstall_key) = registry.open_key(RegistryHive::LocalMachine, UNINSTALL_PATH) {
+        for key_name in uninstall_key.subkey_names() {
+            if let Some(program_key) = uninstall_key.open_subkey(&key_name) {
+                if let Some(name) = program_key.get_string("DisplayName") {
+                    let comments: Option<String> = program_key.get_string("Comments");
+                    let has_appid = comments.as_ref().map_or(false, |c| c.contains("APPID:"));
+                    let icon_path: Option<String> = program_key.get_string("DisplayIcon");
+                    let publisher: Option<String> = program_key.get_string("Publisher");
+                    
+                    debug_info.push(VFDebugInfo {
+                        program_name: name,
+                        comments,
+                        has_appid,
+                        is_vf_deployed: has_appid,
+                        icon_path,
+                        publisher,
+                    });
                 }
             }
         }
@@ -270,28 +822,26 @@ pub fn debug_vf_icons_to_file() -> Result<String, String> {
     use std::io::Write;
     
     let mut debug_info = Vec::new();
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let registry = live_registry()?;
 
     // Get all programs and check VF status
-    if let Ok(uninstall_key) = hklm.open_subkey("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall") {
-        for key_result in uninstall_key.enum_keys() {
-            if let Ok(key_name) = key_result {
-                if let Ok(program_key) = uninstall_key.open_subkey(&key_name) {
-                    if let Ok(name) = program_key.get_value::<String, _>("DisplayName") {
-                        let comments: Option<String> = program_key.get_value("Comments").ok();
-                        let has_appid = comments.as_ref().map_or(false, |c| c.contains("APPID:"));
-                        let icon_path: Option<String> = program_key.get_value("DisplayIcon").ok();
-                        let publisher: Option<String> = program_key.get_value("Publisher").ok();
-                        
-                        debug_info.push(VFDebugInfo {
-                            program_name: name,
-                            comments,
-                            has_appid,
-                            is_vf_deployed: has_appid,
-                            icon_path,
-                            publisher,
-                        });
-                    }
+    if let Some(uninstall_key) = registry.open_key(RegistryHive::LocalMachine, UNINSTALL_PATH) {
+        for key_name in uninstall_key.subkey_names() {
+            if let Some(program_key) = uninstall_key.open_subkey(&key_name) {
+                if let Some(name) = program_key.get_string("DisplayName") {
+                    let comments: Option<String> = program_key.get_string("Comments");
+                    let has_appid = comments.as_ref().map_or(false, |c| c.contains("APPID:"));
+                    let icon_path: Option<String> = program_key.get_string("DisplayIcon");
+                    let publisher: Option<String> = program_key.get_string("Publisher");
+                    
+                    debug_info.push(VFDebugInfo {
+                        program_name: name,
+                        comments,
+                        has_appid,
+                        is_vf_deployed: has_appid,
+                        icon_path,
+                        publisher,
+                    });
                 }
             }
         }
@@ -372,14 +922,6 @@ pub fn get_icon_as_base64(icon_path: String) -> Result<String, String> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ScanProgress {
-    pub total_keys: usize,
-    pub scanned_keys: usize,
-    pub is_complete: bool,
-    pub current_operation: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DebugIconInfo {
     pub program_name: String,
@@ -414,88 +956,83 @@ pub struct AteaInformation {
     pub detection_key: Option<String>,
 }
 
-fn extract_icon_path(icon_path: &str) -> Option<String> {
-    let parts: Vec<&str> = icon_path.split(',').collect();
-    let path = parts[0].trim_matches('"');
-    
-    // Expand environment variables like %ProgramFiles%
-    let expanded_path = expand_environment_path(path);
-    
-    // Try multiple path variations
-    let paths_to_try = vec![
-        expanded_path.clone(),
-        path.to_string(),
-        // Try with and without quotes
-        path.trim_matches('"').to_string(),
-        // Try common variations
-        path.replace("%ProgramFiles%", r"C:\Program Files").to_string(),
-        path.replace("%ProgramFiles%", r"C:\Program Files (x86)").to_string(),
-    ];
-    
-    for test_path in &paths_to_try {
-        if Path::new(test_path).exists() {
-            let extension = Path::new(test_path)
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .unwrap_or("");
+/// Expand environment variables in a path with this process's environment
+pub fn expand_environment_path(path: &str) -> String {
+    expand(path, &ProcessEnvironment::new())
+}
 
-            match extension.to_lowercase().as_str() {
-                "ico" => {
-                    // Direct ICO file - return as is
-                    return Some(test_path.clone());
-                }
-                "exe" | "dll" => {
-                    // For executable files, return the path
-                    return Some(test_path.clone());
-                }
-                "lnk" => {
-                    // Shortcut file
-                    return Some(test_path.clone());
-                }
-                _ => {
-                    // Unknown extension, but file exists
-                    return Some(test_path.clone());
-                }
-            }
+/// The environments a batch of programs' paths are expanded with: the machine's for machine-wide entries,
+/// and for per-user installs their owner's, so %APPDATA% is their AppData and not ours. Each user's is
+/// read from `registry` once, the first time one of their programs asks
+pub struct ProgramEnvironments<'a> {
+    registry: Option<&'a dyn RegistrySource>,
+    machine: &'a dyn EnvironmentProvider,
+    users_base: Environment, // The registry's machine-wide environment, which each user's builds on
+    profiles: Vec<UserProfile>,
+    users: RefCell<HashMap<String, Rc<Environment>>>, // Keyed by the uppercased SID
+}
+
+impl<'a> ProgramEnvironments<'a> {
+    /// Without a registry every entry is expanded with `machine`
+    pub fn new(registry: Option<&'a dyn RegistrySource>, machine: &'a dyn EnvironmentProvider) -> Self {
+        ProgramEnvironments {
+            registry,
+            machine,
+            users_base: registry.map(Environment::from_registry).unwrap_or_else(Environment::windows_defaults),
+            profiles: registry.map(profile_list).unwrap_or_default(),
+            users: RefCell::new(HashMap::new()),
         }
     }
-    
-    None
+
+    /// The environment a program's paths were written in
+    pub fn of(&self, program: &ProgramInfo) -> ProgramEnvironment<'a> {
+        let sid = program.user_sid.as_deref().filter(|_| program.installation_source == "User");
+        let (registry, sid) = match (self.registry, sid) {
+            (Some(registry), Some(sid)) => (registry, sid),
+            _ => return ProgramEnvironment::Machine(self.machine),
+        };
+        let mut users = self.users.borrow_mut();
+        let environment = users.entry(sid.to_uppercase()).or_insert_with(|| {
+            let owner = resolve_user(registry, &self.profiles, sid);
+            let user_root = registry.open_key(RegistryHive::Users, sid);
+            Rc::new(self.users_base.for_user(&owner, user_root.as_deref()))
+        });
+        ProgramEnvironment::User(Rc::clone(environment))
+    }
+
+    /// Expand a path from a program's entry
+    pub fn expand_path(&self, program: &ProgramInfo, path: &str) -> String {
+        if !path.contains('%') {
+            return path.to_string();
+        }
+        expand(path, &self.of(program))
+    }
 }
 
-fn expand_environment_path(path: &str) -> String {
-    // Enhanced environment variable expansion for common Windows paths
-    let mut expanded = path.to_string();
-    
-    // Common Windows environment variables
-    let replacements = [
-        ("%ProgramFiles%", r"C:\Program Files"),
-        ("%ProgramFiles(x86)%", r"C:\Program Files (x86)"),
-        ("%ProgramData%", r"C:\ProgramData"),
-        ("%SystemRoot%", r"C:\Windows"),
-        ("%windir%", r"C:\Windows"),
-        ("%SystemDrive%", "C:"),
-        ("%SystemDirectory%", r"C:\Windows\System32"),
-        ("%SystemPath%", r"C:\Windows\System32"),
-        ("%CommonProgramFiles%", r"C:\Program Files\Common Files"),
-        ("%CommonProgramFiles(x86)%", r"C:\Program Files (x86)\Common Files"),
-        ("%APPDATA%", r"C:\Users\%USERNAME%\AppData\Roaming"),
-        ("%LOCALAPPDATA%", r"C:\Users\%USERNAME%\AppData\Local"),
-        ("%USERPROFILE%", r"C:\Users\%USERNAME%"),
-        ("%TEMP%", r"C:\Users\%USERNAME%\AppData\Local\Temp"),
-        ("%TMP%", r"C:\Users\%USERNAME%\AppData\Local\Temp"),
-        ("%PUBLIC%", r"C:\Users\Public"),
-        ("%ALLUSERSPROFILE%", r"C:\ProgramData"),
-    ];
-    
-    for (var, value) in &replacements {
-        expanded = expanded.replace(var, value);
+/// One program's environment, see `ProgramEnvironments::of`
+pub enum ProgramEnvironment<'a> {
+    Machine(&'a dyn EnvironmentProvider),
+    User(Rc<Environment>),
+}
+
+impl EnvironmentProvider for ProgramEnvironment<'_> {
+    fn get(&self, name: &str) -> Option<Variable> {
+        match self {
+            ProgramEnvironment::Machine(environment) => environment.get(name),
+            ProgramEnvironment::User(environment) => environment.get(name),
+        }
     }
-    
-    expanded
 }
 
-fn scan_vf_deployed_applications(programs: &mut Vec<ProgramInfo>) {
+/// Run `f` with the environments of the machine we are running on: this process's for machine-wide
+/// entries, the live registry's for other users' installs
+pub fn with_live_environments<T>(f: impl FnOnce(&ProgramEnvironments) -> T) -> T {
+    let registry = live_registry().ok();
+    let environment = ProcessEnvironment::new();
+    f(&ProgramEnvironments::new(registry.as_deref(), &environment))
+}
+
+fn mark_vf_deployed_applications(programs: &mut [ProgramInfo]) {
     // Scan all programs for APPID in Comments field to identify VF-deployed applications
     for program in programs.iter_mut() {
         if let Some(comments) = &program.comments {
@@ -503,23 +1040,35 @@ fn scan_vf_deployed_applications(programs: &mut Vec<ProgramInfo>) {
                 program.is_vf_deployed = true;
             }
         }
-        
+    }
+}
+
+fn scan_vf_deployed_applications(programs: &mut Vec<ProgramInfo>, resolver: &PathResolver, observer: &dyn ScanObserver) {
+    mark_vf_deployed_applications(programs);
+
+    observer.add_work(programs.len());
+    for program in programs.iter_mut() {
+        if observer.is_cancelled() {
+            return;
+        }
+        observer.advance();
+
         // For ALL apps, try to detect actual installation location in Program Files
         // Check if install_location is None, empty, or invalid path
         let needs_location_detection = program.install_location.is_none() || 
             program.install_location.as_ref().map_or(false, |loc| {
-                loc.is_empty() || !Path::new(loc).exists()
+                loc.is_empty() || resolver.existing(loc).is_none()
             });
             
         if needs_location_detection {
-            if let Some(detected_location) = detect_program_files_location(&program.name, program.publisher.as_deref()) {
+            if let Some(detected_location) = detect_program_files_location(resolver, &program.name, program.publisher.as_deref()) {
                 println!("DEBUG: Detected location for {}: {}", program.name, detected_location);
                 program.install_location = Some(detected_location);
             } else {
                 // For VF Managed apps, try additional detection methods
                 if program.is_vf_deployed {
                     println!("DEBUG: VF Managed app {} - trying additional detection methods", program.name);
-                    if let Some(vf_location) = detect_vf_managed_location(&program.name, program.publisher.as_deref()) {
+                    if let Some(vf_location) = detect_vf_managed_location(resolver, &program.name, program.publisher.as_deref()) {
                         println!("DEBUG: VF Managed location detected for {}: {}", program.name, vf_location);
                         program.install_location = Some(vf_location);
                     } else {
@@ -534,8 +1083,8 @@ fn scan_vf_deployed_applications(programs: &mut Vec<ProgramInfo>) {
         }
         
         // For ALL apps, scan for shortcuts and ProgramData paths
-        let shortcuts = scan_shortcuts(&program.name, program.publisher.as_deref());
-        let programdata_paths = scan_programdata_paths(&program.name, program.publisher.as_deref());
+        let shortcuts = scan_shortcuts(resolver, &program.name, program.publisher.as_deref());
+        let programdata_paths = scan_programdata_paths(resolver, &program.name, program.publisher.as_deref());
         
         if !shortcuts.is_empty() {
             program.shortcuts = Some(shortcuts);
@@ -546,37 +1095,26 @@ fn scan_vf_deployed_applications(programs: &mut Vec<ProgramInfo>) {
     }
 }
 
-fn scan_alternative_locations(programs: &mut Vec<ProgramInfo>) {
-    // Get current user's profile path
-    if let Ok(user_profile) = std::env::var("USERPROFILE") {
-        let appdata_roaming = format!("{}\\AppData\\Roaming", user_profile);
-        let appdata_local = format!("{}\\AppData\\Local", user_profile);
-        
-        // Scan AppData\Roaming for portable applications
-        scan_directory_for_programs(&appdata_roaming, programs, "AppData\\Roaming");
-        
-        // Scan AppData\Local for portable applications
-        scan_directory_for_programs(&appdata_local, programs, "AppData\\Local");
-    }
-    
-    // Scan common portable application locations
-    let common_paths = [
-        r"C:\PortableApps",
-        r"C:\Tools",
-        r"C:\Utilities",
-        r"C:\Programs",
-    ];
-    
-    for path in &common_paths {
-        if Path::new(path).exists() {
-            scan_directory_for_programs(path, programs, "Portable");
+fn scan_alternative_locations(resolver: &PathResolver, programs: &mut Vec<ProgramInfo>, observer: &dyn ScanObserver) {
+    // The user's AppData, then common portable application locations on the system drive
+    let folders = resolver.portable_app_folders();
+    observer.add_work(folders.len());
+    for (folder, source) in folders {
+        if observer.is_cancelled() {
+            return;
         }
+        scan_directory_for_programs(&folder.to_string_lossy(), programs, source, observer);
+        observer.advance();
     }
 }
 
-fn scan_directory_for_programs(base_path: &str, programs: &mut Vec<ProgramInfo>, source: &str) {
+fn scan_directory_for_programs(base_path: &str, programs: &mut Vec<ProgramInfo>, source: &str, observer: &dyn ScanObserver) {
     if let Ok(entries) = fs::read_dir(base_path) {
         for entry in entries {
+            // AppData can hold hundreds of folders, each probed for executables
+            if observer.is_cancelled() {
+                return;
+            }
             if let Ok(entry) = entry {
                 let path = entry.path();
                 if path.is_dir() {
@@ -593,26 +1131,38 @@ fn scan_directory_for_programs(base_path: &str, programs: &mut Vec<ProgramInfo>,
                     for pattern in &executable_patterns {
                         let full_path = format!("{}\\{}", base_path, pattern);
                         if Path::new(&full_path).exists() {
-                            // Try to get version info from the executable
-                            let version = get_file_version(&full_path);
-                            
+                            let bytes = read_image(Path::new(&full_path)).unwrap_or_default();
+                            let image = PeImage::parse(&bytes).ok();
+                            // The executable's version resource names the product better than its folder
+                            let version_info = image.as_ref().and_then(|image| image.version_info());
+                            // Nothing but the executable's machine type says which architecture it is
+                            let architecture = image.as_ref().map_or("Unknown", |image| image.architecture());
+                            let version = version_info.as_ref().and_then(|info| info.display_version());
+                            let comments = match version_info.as_ref().and_then(|info| info.file_description()) {
+                                Some(description) => format!("{}; portable application found in {}", description, source),
+                                None => format!("Portable application found in {}", source),
+                            };
+
                             let program = ProgramInfo {
-                                name: dir_name.clone(),
+                                name: version_info.as_ref().and_then(|info| info.product_name()).map(str::to_string).unwrap_or_else(|| dir_name.clone()),
                                 registry_name: format!("{}_{}", dir_name, source.replace("\\", "_")),
+                                version_info: version.as_deref().and_then(ProgramVersion::parse),
                                 version,
                                 registry_time: None,
                                 install_date: None,
+                                installed_on: None, // Filled in by reconstruct_install_dates
                                 installed_for: Some("Current User".to_string()),
+                                user_sid: None,
                                 install_location: Some(full_path.clone()),
                                 install_source: None,
                                 install_folder_created: None,
                                 install_folder_modified: None,
                                 install_folder_owner: None,
-                                publisher: None,
+                                publisher: version_info.as_ref().and_then(|info| info.company_name()).map(str::to_string),
                                 uninstall_string: None,
                                 change_install_string: None,
                                 quiet_uninstall_string: None,
-                                comments: Some(format!("Portable application found in {}", source)),
+                                comments: Some(comments),
                                 about_url: None,
                                 update_info_url: None,
                                 help_link: None,
@@ -620,6 +1170,7 @@ fn scan_directory_for_programs(base_path: &str, programs: &mut Vec<ProgramInfo>,
                                 installer_name: None,
                                 release_type: None,
                                 icon_path: Some(full_path.clone()),
+                                display_icon: None,
                                 msi_filename: None,
                                 estimated_size: None,
                                 attributes: None,
@@ -628,11 +1179,24 @@ fn scan_directory_for_programs(base_path: &str, programs: &mut Vec<ProgramInfo>,
                                 shortcuts: None, // Will be populated later if needed
                                 programdata_paths: None, // Will be populated later if needed
                                 registry_path: format!("Filesystem: {}", full_path),
+                                registry_provenance: None,
                                 program_type: "Portable Application".to_string(),
                                 is_windows_installer: false,
-                                architecture: "Unknown".to_string(),
+                                msi: None,
+                                installer_technology: Some(if is_chocolatey_path(&full_path) {
+                                    InstallerTechnology::new(InstallerEngine::Chocolatey, Confidence::High, &format!("Installed under {}", full_path))
+                                } else {
+                                    InstallerTechnology::new(InstallerEngine::Portable, Confidence::Medium, "Found by the portable application scan")
+                                }),
+                                health: None,
+                                install_size: None,
+                                executables: None,
+                                file_hashes: None,
+                                architecture: architecture.to_string(),
                                 installation_source: "Filesystem".to_string(),
                                 is_vf_deployed: false, // Portable apps are not VF-deployed
+                                merged_from: None,
+                                field_sources: None,
                             };
                             
                             // Check if we already have this program (avoid duplicates)
@@ -648,101 +1212,283 @@ fn scan_directory_for_programs(base_path: &str, programs: &mut Vec<ProgramInfo>,
     }
 }
 
-fn get_file_version(_file_path: &str) -> Option<String> {
-    // This is a simplified version extraction
-    // In a real implementation, you might want to use a Windows API to get the actual version
-    // For now, we'll just return None and let the UI handle missing versions
-    None
+// Installed MSIX/AppX packages: those registered under AppxAllUserStore, plus any other package
+// folders under WindowsApps
+fn scan_appx_packages(registry: &dyn RegistrySource, resolver: &PathResolver, programs: &mut Vec<ProgramInfo>, observer: &dyn ScanObserver) {
+    let mut registrations = registered_packages(registry);
+
+    let windows_apps = "%ProgramFiles%\\WindowsApps";
+    let local_windows_apps = resolver.local_path(windows_apps);
+    if let Some(entries) = local_windows_apps.as_ref().and_then(|folder| fs::read_dir(folder).ok()) {
+        for entry in entries.flatten() {
+            let folder_name = entry.file_name().to_string_lossy().to_string();
+            if !entry.path().join(MANIFEST_FILE_NAME).is_file()
+                || registrations.iter().any(|r| r.package_full_name.eq_ignore_ascii_case(&folder_name))
+            {
+                continue;
+            }
+            registrations.push(AppxRegistration {
+                install_location: Some(format!("{}\\{}", windows_apps, folder_name)),
+                package_full_name: folder_name,
+                user_sids: Vec::new(),
+            });
+        }
+    }
+
+    let profiles = profile_list(registry);
+    observer.add_work(registrations.len());
+    for registration in &registrations {
+        if observer.is_cancelled() {
+            return;
+        }
+        observer.advance();
+        let package_dir = match &registration.install_location {
+            Some(location) => resolver.local_path(location),
+            None => local_windows_apps.as_ref().map(|folder| folder.join(&registration.package_full_name)),
+        };
+        let package_dir = match package_dir {
+            Some(package_dir) => package_dir,
+            None => continue,
+        };
+        let manifest = match load_appx_manifest(&package_dir.join(MANIFEST_FILE_NAME)) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                println!("DEBUG: Skipping package {}: {}", registration.package_full_name, e);
+                continue;
+            }
+        };
+        // Resource packages only add languages or scales to their main package
+        if manifest.is_resource_package {
+            continue;
+        }
+
+        let owners: Vec<UserProfile> = registration.user_sids.iter()
+            .map(|sid| resolve_user(registry, &profiles, sid))
+            .collect();
+        programs.push(appx_program_info(&manifest, &registration.package_full_name, &package_dir, &owners));
+    }
+}
+
+fn appx_program_info(manifest: &AppxManifest, package_full_name: &str, package_dir: &Path, owners: &[UserProfile]) -> ProgramInfo {
+    let manifest_path = package_dir.join(MANIFEST_FILE_NAME);
+    let (installed_for, user_sid) = match owners {
+        [] => ("All Users".to_string(), None),
+        [owner] => (owner.user_name.clone(), owner.sid.clone()),
+        owners => (owners.iter().map(|owner| owner.user_name.as_str()).collect::<Vec<_>>().join(", "), None),
+    };
+    let architecture = match manifest.architecture.as_str() {
+        "x64" => "64-bit",
+        "x86" => "32-bit",
+        "arm64" => "ARM64",
+        "arm" => "ARM",
+        "neutral" => "Neutral",
+        _ => "Unknown",
+    };
+
+    ProgramInfo {
+        name: manifest.display_name.clone(),
+        registry_name: package_full_name.to_string(),
+        version_info: ProgramVersion::parse(&manifest.version),
+        version: Some(manifest.version.clone()),
+        registry_time: None,
+        install_date: None,
+        installed_on: None, // Filled in by reconstruct_install_dates
+        installed_for: Some(installed_for),
+        user_sid,
+        install_location: Some(package_dir.to_string_lossy().to_string()),
+        install_source: None,
+        install_folder_created: None,
+        install_folder_modified: None,
+        install_folder_owner: None,
+        publisher: manifest.publisher_display_name.clone(),
+        uninstall_string: Some(format!("powershell.exe -NoProfile -Command \"Remove-AppxPackage -Package '{}'\"", package_full_name)),
+        change_install_string: None,
+        quiet_uninstall_string: None,
+        comments: manifest.description.clone(),
+        about_url: None,
+        update_info_url: None,
+        help_link: None,
+        install_source_path: None,
+        installer_name: None,
+        release_type: None,
+        icon_path: manifest.logo.as_deref()
+            .and_then(|logo| resolve_logo(package_dir, logo))
+            .map(|logo| logo.to_string_lossy().to_string()),
+        display_icon: manifest.logo.clone(),
+        msi_filename: None,
+        estimated_size: None,
+        attributes: None,
+        language: None,
+        parent_key_name: None,
+        shortcuts: None,
+        programdata_paths: None,
+        registry_path: format!("MSIX: {}", manifest_path.display()),
+        registry_provenance: None,
+        program_type: if manifest.is_framework { "SystemComponent" } else { "Application" }.to_string(),
+        is_windows_installer: false,
+        msi: None,
+        installer_technology: Some(InstallerTechnology::new(InstallerEngine::Msix, Confidence::High, "Registered in the AppxAllUserStore")),
+        health: None,
+        install_size: None,
+        executables: None,
+        file_hashes: None,
+        architecture: architecture.to_string(),
+        installation_source: "MSIX".to_string(),
+        is_vf_deployed: false,
+        merged_from: None,
+        field_sources: None,
+    }
+}
+
+/// Read a single AppxManifest.xml the way the package scan does, e.g. to check a fixture manifest
+#[tauri::command]
+pub fn inspect_appx_manifest(manifest_path: String) -> Result<ProgramInfo, String> {
+    let manifest = load_appx_manifest(Path::new(&manifest_path))
+        .map_err(|e| format!("Failed to read package manifest {}: {}", manifest_path, e))?;
+    let package_dir = Path::new(&manifest_path).parent().unwrap_or_else(|| Path::new(""));
+    // Installed package folders are named after the package full name
+    let package_full_name = package_dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| format!("{}_{}_{}", manifest.name, manifest.version, manifest.architecture));
+    Ok(appx_program_info(&manifest, &package_full_name, package_dir, &[]))
+}
+
+// Where an uninstall key was read from; every entry under it records this as provenance
+struct UninstallKeyLocation {
+    hive: RegistryHive,
+    view: RegistryView,
+    path: String,
 }
 
-fn scan_registry_key(key: &RegKey, programs: &mut Vec<ProgramInfo>, architecture: &str, source: &str) {
-    for key_result in key.enum_keys() {
-        if let Ok(key_name) = key_result {
-            if let Ok(program_key) = key.open_subkey(&key_name) {
-                if let Ok(name) = program_key.get_value::<String, _>("DisplayName") {
-                    let icon_path = program_key.get_value("DisplayIcon")
-                        .ok()
-                        .and_then(|path: String| extract_icon_path(&path));
-
-                    let installed_for = match source {
-                        "User" => Some("Current User".to_string()),
-                        _ => Some(format!("All Users ({})", architecture)),
-                    };
-
-                    let registry_path = match source {
-                        "User" => format!("HKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{}", key_name),
-                        _ => format!("HKEY_LOCAL_MACHINE\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{}", key_name),
-                    };
-
-                    let install_location = program_key.get_value("InstallLocation").ok()
-                        .or_else(|| program_key.get_value("InstallSource").ok())
-                        .or_else(|| {
-                            // Try to extract path from uninstall string as last resort
-                            program_key.get_value::<String, _>("UninstallString").ok()
-                                .and_then(|uninstall_str| {
-                                    // Extract path from common uninstall string patterns
-                                    if uninstall_str.contains("\\") {
-                                        let path = uninstall_str.split('\\').take(3).collect::<Vec<&str>>().join("\\");
-                                        if Path::new(&path).exists() {
-                                            Some(path)
-                                        } else {
-                                            None
-                                        }
+impl UninstallKeyLocation {
+    fn new(hive: RegistryHive, view: RegistryView, path: &str) -> Self {
+        UninstallKeyLocation {
+            hive,
+            view,
+            path: path.to_string(),
+        }
+    }
+}
+
+fn scan_registry_key(key: &dyn RegistryKey, programs: &mut Vec<ProgramInfo>, architecture: &str, owner: Option<&UserProfile>, resolver: &PathResolver, location: &UninstallKeyLocation, observer: &dyn ScanObserver) {
+    let key_names = key.subkey_names();
+    observer.add_work(key_names.len());
+    for key_name in key_names {
+        if observer.is_cancelled() {
+            return;
+        }
+        observer.advance();
+        if let Some(program_key) = key.open_subkey(&key_name) {
+            if let Some(name) = program_key.get_string("DisplayName") {
+                let icon_path = program_key.get_string("DisplayIcon")
+                    .and_then(|path| resolver.resolve_icon_file(&path));
+
+                let installed_for = match owner {
+                    Some(owner) => Some(owner.user_name.clone()),
+                    None => Some(format!("All Users ({})", architecture)),
+                };
+
+                let version = program_key.get_string("DisplayVersion");
+                let install_date = program_key.get_string("InstallDate");
+                let installed_on = install_date.as_deref()
+                    .and_then(parse_registry_date)
+                    .map(|(date, confidence)| InstallDateInfo {
+                        date,
+                        timestamp: program_key.get_value("InstallTime")
+                            .and_then(|value| parse_install_time(&value, Some(date)))
+                            .filter(|timestamp| timestamp.date_naive() == date),
+                        source: InstallDateSource::InstallDate,
+                        confidence,
+                    });
+
+                let provenance = RegistryProvenance::from_key(
+                    program_key.as_ref(),
+                    location.hive,
+                    location.view,
+                    format!("{}\\{}", location.path, key_name),
+                );
+
+                let install_location = program_key.get_string("InstallLocation")
+                    .or_else(|| program_key.get_string("InstallSource"))
+                    .or_else(|| {
+                        // Try to extract path from uninstall string as last resort
+                        program_key.get_string("UninstallString")
+                            .and_then(|uninstall_str| {
+                                // Extract path from common uninstall string patterns
+                                if uninstall_str.contains("\\") {
+                                    let path = uninstall_str.split('\\').take(3).collect::<Vec<&str>>().join("\\");
+                                    if Path::new(&path).exists() {
+                                        Some(path)
                                     } else {
                                         None
                                     }
-                                })
-                        });
-                    
-                    let program = ProgramInfo {
-                        name,
-                        registry_name: key_name.clone(),
-                        version: program_key.get_value("DisplayVersion").ok(),
-                        registry_time: program_key.get_value("InstallTime").ok(),
-                        install_date: program_key.get_value("InstallDate").ok(),
-                        installed_for,
-                        install_location,
-                        install_source: program_key.get_value("InstallSource").ok(),
-                        install_folder_created: program_key.get_value("InstallFolderCreated").ok(),
-                        install_folder_modified: program_key.get_value("InstallFolderModified").ok(),
-                        install_folder_owner: program_key.get_value("InstallFolderOwner").ok(),
-                        publisher: program_key.get_value("Publisher").ok(),
-                        uninstall_string: program_key.get_value("UninstallString").ok(),
-                        change_install_string: program_key.get_value("ModifyPath").ok(),
-                        quiet_uninstall_string: program_key.get_value("QuietUninstallString").ok(),
-                        comments: program_key.get_value("Comments").ok(),
-                        about_url: program_key.get_value("URLInfoAbout").ok(),
-                        update_info_url: program_key.get_value("URLUpdateInfo").ok(),
-                        help_link: program_key.get_value("HelpLink").ok(),
-                        install_source_path: program_key.get_value("InstallSource").ok(),
-                        installer_name: program_key.get_value("InstallerName").ok(),
-                        release_type: program_key.get_value("ReleaseType").ok(),
-                        icon_path,
-                        msi_filename: program_key.get_value("MSIFilename").ok(),
-                        estimated_size: program_key.get_value("EstimatedSize").ok(),
-                        attributes: program_key.get_value("Attributes").ok(),
-                        language: program_key.get_value("Language").ok(),
-                        parent_key_name: program_key.get_value("ParentKeyName").ok(),
-                        shortcuts: None, // Will be populated later if needed
-                        programdata_paths: None, // Will be populated later if needed
-                        registry_path,
-                        program_type: determine_program_type(&program_key),
-                        is_windows_installer: program_key.get_value::<u32, _>("WindowsInstaller").unwrap_or(0) == 1,
-                        architecture: architecture.to_string(),
-                        installation_source: source.to_string(),
-                        is_vf_deployed: false, // Will be updated later by scan_vf_deployed_applications
-                    };
-                    programs.push(program);
-                }
+                                } else {
+                                    None
+                                }
+                            })
+                    });
+                
+                let program = ProgramInfo {
+                    name,
+                    registry_name: key_name.clone(),
+                    version_info: version.as_deref().and_then(ProgramVersion::parse),
+                    version,
+                    registry_time: program_key.get_string("InstallTime"),
+                    install_date,
+                    installed_on,
+                    installed_for,
+                    user_sid: owner.and_then(|owner| owner.sid.clone()),
+                    install_location,
+                    install_source: program_key.get_string("InstallSource"),
+                    install_folder_created: program_key.get_string("InstallFolderCreated"),
+                    install_folder_modified: program_key.get_string("InstallFolderModified"),
+                    install_folder_owner: program_key.get_string("InstallFolderOwner"),
+                    publisher: program_key.get_string("Publisher"),
+                    uninstall_string: program_key.get_string("UninstallString"),
+                    change_install_string: program_key.get_string("ModifyPath"),
+                    quiet_uninstall_string: program_key.get_string("QuietUninstallString"),
+                    comments: program_key.get_string("Comments"),
+                    about_url: program_key.get_string("URLInfoAbout"),
+                    update_info_url: program_key.get_string("URLUpdateInfo"),
+                    help_link: program_key.get_string("HelpLink"),
+                    install_source_path: program_key.get_string("InstallSource"),
+                    installer_name: program_key.get_string("InstallerName"),
+                    release_type: program_key.get_string("ReleaseType"),
+                    icon_path,
+                    display_icon: program_key.get_string("DisplayIcon"),
+                    msi_filename: program_key.get_string("MSIFilename"),
+                    estimated_size: program_key.get_dword("EstimatedSize"),
+                    attributes: program_key.get_string("Attributes"),
+                    language: program_key.get_string("Language"),
+                    parent_key_name: program_key.get_string("ParentKeyName"),
+                    shortcuts: None, // Will be populated later if needed
+                    programdata_paths: None, // Will be populated later if needed
+                    registry_path: provenance.full_path(),
+                    registry_provenance: Some(provenance),
+                    program_type: determine_program_type(program_key.as_ref()),
+                    is_windows_installer: program_key.get_dword("WindowsInstaller").unwrap_or(0) == 1,
+                    msi: None, // Filled in by attach_msi_metadata
+                    installer_technology: Some(fingerprint_uninstall_key(program_key.as_ref(), &key_name)),
+                    health: None, // Filled in by attach_entry_health
+                    install_size: None,
+                    executables: None,
+                    file_hashes: None,
+                    architecture: architecture.to_string(),
+                    installation_source: if owner.is_some() { "User" } else { "System" }.to_string(),
+                    is_vf_deployed: false, // Will be updated later by scan_vf_deployed_applications
+                    merged_from: None, // Filled in by merge_duplicate_programs
+                    field_sources: None,
+                };
+                programs.push(program);
             }
         }
     }
 }
 
-fn determine_program_type(key: &RegKey) -> String {
-    if key.get_value::<u32, _>("SystemComponent").unwrap_or(0) == 1 {
+fn determine_program_type(key: &dyn RegistryKey) -> String {
+    if key.get_dword("SystemComponent").unwrap_or(0) == 1 {
         "SystemComponent".to_string()
-    } else if key.get_value::<String, _>("ParentKeyName").is_ok() {
+    } else if key.get_string("ParentKeyName").is_some() {
         "Update".to_string()
     } else {
         "Application".to_string()
@@ -817,26 +1563,11 @@ pub fn test_alternative_locations() -> Result<Vec<String>, String> {
 }
 
 /// Scan for shortcuts related to a program
-fn scan_shortcuts(program_name: &str, publisher: Option<&str>) -> Vec<String> {
+pub fn scan_shortcuts(resolver: &PathResolver, program_name: &str, publisher: Option<&str>) -> Vec<String> {
     let mut shortcuts = Vec::new();
     
-    // Common shortcut locations
-    let shortcut_locations = vec![
-        // User shortcuts
-        format!("{}\\Microsoft\\Windows\\Start Menu\\Programs", 
-                std::env::var("APPDATA").unwrap_or_default()),
-        // All users shortcuts
-        format!("{}\\Microsoft\\Windows\\Start Menu\\Programs", 
-                std::env::var("ALLUSERSPROFILE").unwrap_or_default()),
-        // Desktop shortcuts
-        format!("{}\\Desktop", 
-                std::env::var("USERPROFILE").unwrap_or_default()),
-        // Public desktop shortcuts
-        format!("{}\\Desktop", 
-                std::env::var("PUBLIC").unwrap_or_default()),
-    ];
-    
-    for location in shortcut_locations {
+    // Start Menu and desktop folders, the user's and the shared ones
+    for location in resolver.shortcut_folders() {
         if let Ok(entries) = std::fs::read_dir(&location) {
             for entry in entries.flatten() {
                 if let Some(file_name) = entry.file_name().to_str() {
@@ -859,10 +1590,13 @@ fn scan_shortcuts(program_name: &str, publisher: Option<&str>) -> Vec<String> {
 }
 
 /// Scan for ProgramData folders related to a program
-fn scan_programdata_paths(program_name: &str, publisher: Option<&str>) -> Vec<String> {
+pub fn scan_programdata_paths(resolver: &PathResolver, program_name: &str, publisher: Option<&str>) -> Vec<String> {
     let mut paths = Vec::new();
     
-    let programdata = std::env::var("PROGRAMDATA").unwrap_or_else(|_| "C:\\ProgramData".to_string());
+    let programdata = match resolver.existing("%ProgramData%") {
+        Some(programdata) => programdata,
+        None => return paths,
+    };
     
     if let Ok(entries) = std::fs::read_dir(&programdata) {
         for entry in entries.flatten() {
@@ -885,13 +1619,8 @@ fn scan_programdata_paths(program_name: &str, publisher: Option<&str>) -> Vec<St
 }
 
 /// Detect actual installation location in Program Files for VF Managed apps
-fn detect_program_files_location(program_name: &str, publisher: Option<&str>) -> Option<String> {
-    let program_files_paths = vec![
-        r"C:\Program Files",
-        r"C:\Program Files (x86)",
-    ];
-    
-    for program_files in program_files_paths {
+fn detect_program_files_location(resolver: &PathResolver, program_name: &str, publisher: Option<&str>) -> Option<String> {
+    for program_files in resolver.program_files_folders() {
         if let Ok(entries) = std::fs::read_dir(program_files) {
             for entry in entries.flatten() {
                 if let Some(folder_name) = entry.file_name().to_str() {
@@ -1007,17 +1736,9 @@ fn is_likely_program_folder(folder_path: &str, program_name: &str) -> bool {
 }
 
 /// Special detection method for VF Managed applications
-fn detect_vf_managed_location(program_name: &str, publisher: Option<&str>) -> Option<String> {
+fn detect_vf_managed_location(resolver: &PathResolver, program_name: &str, publisher: Option<&str>) -> Option<String> {
     // VF Managed apps might be installed in different locations
-    let search_paths = vec![
-        r"C:\Program Files",
-        r"C:\Program Files (x86)",
-        r"C:\ProgramData",
-        r"C:\Windows\System32",
-        r"C:\Windows\SysWOW64",
-    ];
-    
-    for search_path in search_paths {
+    for search_path in resolver.managed_app_folders() {
         if let Ok(entries) = std::fs::read_dir(search_path) {
             for entry in entries.flatten() {
                 if let Some(folder_name) = entry.file_name().to_str() {
@@ -1086,33 +1807,33 @@ fn is_likely_vf_managed_folder(folder_path: &str, _program_name: &str) -> bool {
 
 #[tauri::command]
 pub fn get_atea_information(appid: String) -> Result<AteaInformation, String> {
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-    let atea_path = "SOFTWARE\\Atea\\Applications";
-    
-    if let Ok(atea_key) = hklm.open_subkey(atea_path) {
+    let registry = live_registry()?;
+    find_atea_information(registry.as_ref(), &appid)
+}
+
+pub fn find_atea_information(registry: &dyn RegistrySource, appid: &str) -> Result<AteaInformation, String> {
+    if let Some(atea_key) = registry.open_key(RegistryHive::LocalMachine, ATEA_APPLICATIONS_PATH) {
         // Find the GUID that matches the APPID
-        for key_result in atea_key.enum_keys() {
-            if let Ok(guid) = key_result {
-                if let Ok(app_key) = atea_key.open_subkey(&guid) {
-                    // Check if this GUID has the matching APPID
-                    if let Ok(stored_appid) = app_key.get_value::<String, _>("APPID") {
-                        if stored_appid == appid {
-                            // Found the matching app, extract all Atea information
-                            return Ok(AteaInformation {
-                                appid: app_key.get_value("APPID").ok(),
-                                app_reference: app_key.get_value("APPReference").ok(),
-                                app_script_author: app_key.get_value("APPScriptAuthor").ok(),
-                                app_update: app_key.get_value("AppUpdate").ok(),
-                                architecture: app_key.get_value("Architecture").ok(),
-                                date_time: app_key.get_value("DateTime").ok(),
-                                language: app_key.get_value("Language").ok(),
-                                manufacturer: app_key.get_value("Manufacturer").ok(),
-                                name: app_key.get_value("Name").ok(),
-                                revision: app_key.get_value("Revision").ok(),
-                                version: app_key.get_value("Version").ok(),
-                                detection_key: Some(guid.clone()),
-                            });
-                        }
+        for guid in atea_key.subkey_names() {
+            if let Some(app_key) = atea_key.open_subkey(&guid) {
+                // Check if this GUID has the matching APPID
+                if let Some(stored_appid) = app_key.get_string("APPID") {
+                    if stored_appid == appid {
+                        // Found the matching app, extract all Atea information
+                        return Ok(AteaInformation {
+                            appid: app_key.get_string("APPID"),
+                            app_reference: app_key.get_string("APPReference"),
+                            app_script_author: app_key.get_string("APPScriptAuthor"),
+                            app_update: app_key.get_string("AppUpdate"),
+                            architecture: app_key.get_string("Architecture"),
+                            date_time: app_key.get_string("DateTime"),
+                            language: app_key.get_string("Language"),
+                            manufacturer: app_key.get_string("Manufacturer"),
+                            name: app_key.get_string("Name"),
+                            revision: app_key.get_string("Revision"),
+                            version: app_key.get_string("Version"),
+                            detection_key: Some(guid.clone()),
+                        });
                     }
                 }
             }
@@ -1124,14 +1845,15 @@ pub fn get_atea_information(appid: String) -> Result<AteaInformation, String> {
 
 #[tauri::command]
 pub fn open_program_files_folder(architecture: String) -> Result<(), String> {
-    let folder_path = match architecture.as_str() {
-        "64-bit" => r"C:\Program Files",
-        "32-bit" => r"C:\Program Files (x86)",
+    let folder = match architecture.as_str() {
+        "64-bit" => "%ProgramFiles%",
+        "32-bit" => "%ProgramFiles(x86)%",
         _ => return Err(format!("Unsupported architecture: {}", architecture)),
     };
+    let folder_path = expand_environment_path(folder);
     
     // Check if folder exists
-    if !std::path::Path::new(folder_path).exists() {
+    if !std::path::Path::new(&folder_path).exists() {
         return Err(format!("Folder does not exist: {}", folder_path));
     }
     
@@ -1142,4 +1864,144 @@ pub fn open_program_files_folder(architecture: String) -> Result<(), String> {
         .map_err(|e| format!("Failed to open folder: {}", e))?;
     
     Ok(())
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::registry_source::RegistryValue;
+
+    fn appx_fixture(package_full_name: &str) -> String {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests").join("fixtures").join("appx").join(package_full_name).join(MANIFEST_FILE_NAME)
+            .to_string_lossy().to_string()
+    }
+
+    fn registry_fixture(name: &str) -> String {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests").join("fixtures").join("registry").join(name)
+            .to_string_lossy().to_string()
+    }
+
+    // Cancels the scan as soon as the first source has reported
+    struct CancelAfterFirstSource {
+        sources: std::cell::RefCell<Vec<String>>,
+    }
+
+    impl ScanObserver for CancelAfterFirstSource {
+        fn found(&self, source: &str, _programs: &[ProgramInfo]) {
+            self.sources.borrow_mut().push(source.to_string());
+        }
+
+        fn is_cancelled(&self) -> bool {
+            !self.sources.borrow().is_empty()
+        }
+    }
+
+    #[test]
+    fn cancelled_scans_return_merged_entries() {
+        let registry = crate::services::reg_file::parse_reg_file(br#"Windows Registry Editor Version 5.00
+
+[HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\ContosoAgent]
+"DisplayName"="Contoso Agent"
+"DisplayVersion"="2.0"
+"InstallLocation"="C:\\Contoso\\Agent"
+
+[HKEY_LOCAL_MACHINE\SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall\ContosoAgent]
+"DisplayName"="Contoso Agent"
+"DisplayVersion"="2.0"
+"InstallLocation"="C:\\Contoso\\Agent\\"
+"#).unwrap();
+        let observer = CancelAfterFirstSource { sources: std::cell::RefCell::new(Vec::new()) };
+
+        let programs = scan_installed_programs_with(&registry, &observer);
+        assert_eq!(observer.sources.borrow().as_slice(), ["System".to_string()]);
+        assert_eq!(programs.len(), 1);
+        assert_eq!(programs[0].merged_from.as_ref().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn scans_a_json_registry_fixture() {
+        let mut programs = get_installed_programs_from_fixture(registry_fixture("uninstall.json")).unwrap();
+        programs.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = programs.iter().map(|program| program.name.as_str()).collect();
+        assert_eq!(names, ["Contoso Agent", "Fabrikam Viewer", "Northwind Notes"]);
+
+        let agent = &programs[0];
+        assert_eq!(agent.version.as_deref(), Some("2.4.1"));
+        assert_eq!(agent.publisher.as_deref(), Some("Contoso Ltd."));
+        assert_eq!(agent.architecture, "64-bit");
+        assert_eq!(agent.installation_source, "System");
+        assert_eq!(agent.installed_for.as_deref(), Some("All Users (64-bit)"));
+        assert_eq!(agent.registry_path, format!("HKEY_LOCAL_MACHINE\\{}\\ContosoAgent", UNINSTALL_PATH));
+        assert_eq!(agent.estimated_size, Some(20480));
+        assert_eq!(agent.installed_on.as_ref().map(|installed_on| installed_on.date.to_string()).as_deref(), Some("2024-03-22"));
+
+        let viewer = &programs[1];
+        assert_eq!(viewer.version.as_deref(), Some("7.0"));
+        assert_eq!(viewer.architecture, "32-bit");
+        assert_eq!(viewer.installation_source, "System");
+        assert_eq!(viewer.installed_for.as_deref(), Some("All Users (32-bit)"));
+        assert_eq!(viewer.registry_path, format!("HKEY_LOCAL_MACHINE\\{}\\FabrikamViewer", UNINSTALL_PATH_WOW64));
+        assert_eq!(viewer.registry_provenance.as_ref().map(|provenance| provenance.view), Some(RegistryView::Registry32));
+
+        let notes = &programs[2];
+        assert_eq!(notes.version.as_deref(), Some("1.12.0"));
+        assert_eq!(notes.installation_source, "User");
+        assert_eq!(notes.installed_for.as_deref(), Some("alice"));
+        assert_eq!(notes.user_sid, None);
+        assert_eq!(notes.registry_path, format!("HKEY_CURRENT_USER\\{}\\NorthwindNotes", UNINSTALL_PATH));
+
+        assert!(get_installed_programs_from_fixture(registry_fixture("missing.json")).is_err());
+    }
+
+    #[test]
+    fn inspects_appx_manifests_as_programs() {
+        let terminal = inspect_appx_manifest(appx_fixture("Microsoft.WindowsTerminal_1.18.3181.0_x64__8wekyb3d8bbwe")).unwrap();
+        assert_eq!(terminal.name, "Windows Terminal");
+        assert_eq!(terminal.registry_name, "Microsoft.WindowsTerminal_1.18.3181.0_x64__8wekyb3d8bbwe");
+        assert_eq!(terminal.architecture, "64-bit");
+        assert_eq!(terminal.program_type, "Application");
+        assert!(terminal.icon_path.unwrap().ends_with("StoreLogo.scale-200.png"));
+
+        let framework = inspect_appx_manifest(appx_fixture("Microsoft.VCLibs.140.00_14.0.33519.0_x64__8wekyb3d8bbwe")).unwrap();
+        assert_eq!(framework.program_type, "SystemComponent");
+        assert_eq!(framework.icon_path, None);
+
+        let resources = inspect_appx_manifest(appx_fixture("Microsoft.WindowsTerminal_1.18.3181.0_neutral_split.scale-200_8wekyb3d8bbwe")).unwrap();
+        assert_eq!(resources.architecture, "Neutral");
+
+        assert!(inspect_appx_manifest(appx_fixture("Missing.Package_1.0.0.0_x64__abc")).is_err());
+    }
+
+    #[test]
+    fn expands_per_user_paths_with_their_owners_environment() {
+        let sid = "S-1-5-21-1-2-3-1001";
+        let mut registry = MemoryRegistry::new();
+        let machine = registry.hive_mut(RegistryHive::LocalMachine);
+        machine.create_path("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion").set_value("SystemRoot", RegistryValue::String("D:\\Windows".to_string()));
+        machine.create_path(&format!("{}\\{}", PROFILE_LIST_PATH, sid))
+            .set_value("ProfileImagePath", RegistryValue::ExpandString("%SystemDrive%\\Users\\bob".to_string()));
+        registry.hive_mut(RegistryHive::Users).create_path(&format!("{}\\{}", sid, USER_ENVIRONMENT_PATH))
+            .set_value("TOOLS", RegistryValue::ExpandString("%USERPROFILE%\\Tools".to_string()));
+        let scanning_user = Environment::from_pairs([("USERPROFILE", "C:\\Users\\scanner"), ("ProgramFiles", "C:\\Program Files")]);
+        let environments = ProgramEnvironments::new(Some(&registry), &scanning_user);
+
+        let per_user = ProgramInfo::with_fields("Bob's App", serde_json::json!({ "installation_source": "User", "user_sid": sid }));
+        assert_eq!(environments.expand_path(&per_user, "%LOCALAPPDATA%\\Programs\\App"), "D:\\Users\\bob\\AppData\\Local\\Programs\\App");
+        assert_eq!(environments.expand_path(&per_user, "%TOOLS%\\app.exe"), "D:\\Users\\bob\\Tools\\app.exe");
+        assert_eq!(environments.expand_path(&per_user, "%NOT_SET%\\app.exe"), "%NOT_SET%\\app.exe");
+        // Built once per user, however many of their paths are expanded
+        match (environments.of(&per_user), environments.of(&per_user)) {
+            (ProgramEnvironment::User(first), ProgramEnvironment::User(second)) => assert!(Rc::ptr_eq(&first, &second)),
+            _ => panic!("a per-user entry should get its owner's environment"),
+        }
+
+        let machine_wide = ProgramInfo::with_fields("Machine App", serde_json::json!({}));
+        assert_eq!(environments.expand_path(&machine_wide, "%ProgramFiles%\\App"), "C:\\Program Files\\App");
+        assert_eq!(environments.expand_path(&machine_wide, "%USERPROFILE%\\Desktop"), "C:\\Users\\scanner\\Desktop");
+
+        // Without a registry there is nobody else's environment to read
+        let no_registry = ProgramEnvironments::new(None, &scanning_user);
+        assert_eq!(no_registry.expand_path(&per_user, "%USERPROFILE%"), "C:\\Users\\scanner");
+    }
+}
@@ -0,0 +1,286 @@
+// Background inventory scans: each scan runs on its own thread under a job ID, streams what it finds
+// to the frontend as events, and can be cancelled
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use serde::{Serialize, Deserialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::file_hashes::{attach_file_hashes, load_ioc_list};
+use crate::commands::registry::{scan_installed_programs_with, ProgramInfo, ScanObserver};
+use crate::commands::snapshots::{local_machine_name, save_snapshot};
+use crate::services::registry_source::live_registry;
+
+pub const SCAN_PROGRESS_EVENT: &str = "scan-progress";
+pub const SCAN_BATCH_EVENT: &str = "scan-batch";
+pub const SCAN_FINISHED_EVENT: &str = "scan-finished";
+
+// Progress events are sent at most this often while keys are being read; stage changes are always sent
+const PROGRESS_EVENT_INTERVAL: usize = 25;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ScanState {
+    Idle,
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanProgress {
+    pub job_id: Option<String>,
+    pub state: ScanState,
+    pub total_keys: usize,
+    pub scanned_keys: usize,
+    pub programs_found: usize,   // Raw entries so far, before duplicates are merged
+    pub is_complete: bool,
+    pub current_operation: String,
+    pub error: Option<String>,
+}
+
+/// Entries one source found, sent as soon as that source finishes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanBatch {
+    pub job_id: String,
+    pub source: String,
+    pub programs: Vec<ProgramInfo>,
+}
+
+/// The final, merged inventory; partial when the scan was cancelled
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanFinished {
+    pub job_id: String,
+    pub state: ScanState,
+    pub programs: Vec<ProgramInfo>,
+    pub snapshot_id: Option<String>, // Completed scans are saved as a snapshot
+    pub error: Option<String>, // Why the scan failed, or why an optional stage could not run in full
+}
+
+/// Optional stages run after the registry has been read
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ScanOptions {
+    pub hash_executables: bool,   // Hash main executables and uninstallers
+    pub ioc_list: Option<String>, // Match those hashes against this IOC list
+}
+
+pub struct ScanJob {
+    cancelled: AtomicBool,
+    progress: Mutex<ScanProgress>,
+}
+
+#[derive(Default)]
+pub struct ScanJobs {
+    jobs: HashMap<String, Arc<ScanJob>>,
+    latest: Option<String>,
+    next_id: u64,
+}
+
+// Global state for scan jobs
+pub type ScanJobState = Mutex<ScanJobs>;
+
+/// Start a background scan and return its job ID. If a scan is already running its ID is returned instead
+#[tauri::command]
+pub fn start_scan(app: AppHandle, jobs: State<ScanJobState>, options: Option<ScanOptions>) -> Result<String, String> {
+    let mut jobs = jobs.lock().map_err(|e| format!("Failed to lock scan jobs: {}", e))?;
+
+    if let Some(running) = jobs.jobs.iter().find(|(_, job)| job.state() == ScanState::Running) {
+        return Ok(running.0.clone());
+    }
+
+    // Only the last finished job is kept for get_scan_progress
+    jobs.jobs.clear();
+    jobs.next_id += 1;
+    let job_id = format!("scan-{}", jobs.next_id);
+    let job = Arc::new(ScanJob {
+        cancelled: AtomicBool::new(false),
+        progress: Mutex::new(ScanProgress {
+            job_id: Some(job_id.clone()),
+            state: ScanState::Running,
+            total_keys: 0,
+            scanned_keys: 0,
+            programs_found: 0,
+            is_complete: false,
+            current_operation: "Starting scan".to_string(),
+            error: None,
+        }),
+    });
+    jobs.jobs.insert(job_id.clone(), job.clone());
+    jobs.latest = Some(job_id.clone());
+
+    let thread_job_id = job_id.clone();
+    let options = options.unwrap_or_default();
+    thread::spawn(move || run_scan_job(app, thread_job_id, job, options));
+
+    Ok(job_id)
+}
+
+/// Ask a running scan to stop; it finishes with the programs found so far
+#[tauri::command]
+pub fn cancel_scan(job_id: String, jobs: State<ScanJobState>) -> Result<(), String> {
+    let jobs = jobs.lock().map_err(|e| format!("Failed to lock scan jobs: {}", e))?;
+    let job = jobs.jobs.get(&job_id).ok_or_else(|| format!("No scan job {}", job_id))?;
+    job.cancelled.store(true, Ordering::SeqCst);
+    job.update(|progress| progress.current_operation = "Cancelling".to_string());
+    Ok(())
+}
+
+/// Progress of the given scan job, or of the most recent one
+#[tauri::command]
+pub fn get_scan_progress(job_id: Option<String>, jobs: State<ScanJobState>) -> Result<ScanProgress, String> {
+    let jobs = jobs.lock().map_err(|e| format!("Failed to lock scan jobs: {}", e))?;
+    let job_id = match job_id.or_else(|| jobs.latest.clone()) {
+        Some(job_id) => job_id,
+        None => {
+            return Ok(ScanProgress {
+                job_id: None,
+                state: ScanState::Idle,
+                total_keys: 0,
+                scanned_keys: 0,
+                programs_found: 0,
+                is_complete: false,
+                current_operation: "Ready".to_string(),
+                error: None,
+            })
+        }
+    };
+    let job = jobs.jobs.get(&job_id).ok_or_else(|| format!("No scan job {}", job_id))?;
+    Ok(job.current_progress())
+}
+
+fn run_scan_job(app: AppHandle, job_id: String, job: Arc<ScanJob>, options: ScanOptions) {
+    let observer = JobObserver {
+        app: app.clone(),
+        job_id: job_id.clone(),
+        job: job.clone(),
+        sources: Mutex::new(Vec::new()),
+    };
+
+    let result = live_registry().map(|registry| scan_installed_programs_with(registry.as_ref(), &observer));
+
+    // An IOC list that cannot be read does not fail the scan; the executables are still hashed
+    let mut stage_error = None;
+    let result = result.map(|mut programs| {
+        if options.hash_executables && !job.cancelled.load(Ordering::SeqCst) {
+            observer.stage("Hashing executables");
+            let iocs = load_ioc_list(options.ioc_list.as_deref()).unwrap_or_else(|e| {
+                println!("DEBUG: Scan job {} hashes without IOC matching: {}", job_id, e);
+                stage_error = Some(e);
+                None
+            });
+            attach_file_hashes(&mut programs, iocs.as_ref(), &|| job.cancelled.load(Ordering::SeqCst));
+            let hits: usize = programs.iter().filter_map(|program| program.file_hashes.as_ref()).map(|hashes| hashes.ioc_hits.len()).sum();
+            if let Some(iocs) = &iocs {
+                println!("DEBUG: Scan job {} matched {} IOC hits against {} indicators", job_id, hits, iocs.len());
+            }
+        }
+        programs
+    });
+
+    let (state, programs, error) = match result {
+        Ok(programs) if job.cancelled.load(Ordering::SeqCst) => (ScanState::Cancelled, programs, stage_error),
+        Ok(programs) => (ScanState::Completed, programs, stage_error),
+        Err(e) => (ScanState::Failed, Vec::new(), Some(e)),
+    };
+    println!("DEBUG: Scan job {} finished: {:?}, {} programs", job_id, state, programs.len());
+
+    // Cancelled scans are incomplete and would show up as mass removals in a diff
+    let snapshot_id = if state == ScanState::Completed {
+        let sources = observer.sources.lock().map(|sources| sources.clone()).unwrap_or_default();
+        match save_snapshot(&programs, &local_machine_name(), sources) {
+            Ok(summary) => Some(summary.id),
+            Err(e) => {
+                println!("DEBUG: Failed to save snapshot for scan job {}: {}", job_id, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    job.update(|progress| {
+        progress.state = state;
+        progress.is_complete = true;
+        progress.current_operation = match state {
+            ScanState::Cancelled => "Cancelled".to_string(),
+            ScanState::Failed => "Failed".to_string(),
+            _ => "Complete".to_string(),
+        };
+        progress.error = error.clone();
+    });
+    emit(&app, SCAN_PROGRESS_EVENT, job.current_progress());
+    emit(&app, SCAN_FINISHED_EVENT, ScanFinished { job_id, state, programs, snapshot_id, error });
+}
+
+fn emit<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app.emit_all(event, payload) {
+        println!("DEBUG: Failed to emit {}: {}", event, e);
+    }
+}
+
+impl ScanJob {
+    fn state(&self) -> ScanState {
+        self.current_progress().state
+    }
+
+    fn current_progress(&self) -> ScanProgress {
+        match self.progress.lock() {
+            Ok(progress) => progress.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ScanProgress)) -> ScanProgress {
+        let mut progress = match self.progress.lock() {
+            Ok(progress) => progress,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        change(&mut progress);
+        progress.clone()
+    }
+}
+
+struct JobObserver {
+    app: AppHandle,
+    job_id: String,
+    job: Arc<ScanJob>,
+    sources: Mutex<Vec<String>>, // Sources that ran to completion, recorded in the snapshot
+}
+
+impl ScanObserver for JobObserver {
+    fn stage(&self, operation: &str) {
+        let progress = self.job.update(|progress| progress.current_operation = operation.to_string());
+        emit(&self.app, SCAN_PROGRESS_EVENT, progress);
+    }
+
+    fn add_work(&self, items: usize) {
+        self.job.update(|progress| progress.total_keys += items);
+    }
+
+    fn advance(&self) {
+        let progress = self.job.update(|progress| progress.scanned_keys += 1);
+        if progress.scanned_keys % PROGRESS_EVENT_INTERVAL == 0 {
+            emit(&self.app, SCAN_PROGRESS_EVENT, progress);
+        }
+    }
+
+    fn found(&self, source: &str, programs: &[ProgramInfo]) {
+        let progress = self.job.update(|progress| progress.programs_found += programs.len());
+        if let Ok(mut sources) = self.sources.lock() {
+            sources.push(source.to_string());
+        }
+        if !programs.is_empty() {
+            emit(&self.app, SCAN_BATCH_EVENT, ScanBatch {
+                job_id: self.job_id.clone(),
+                source: source.to_string(),
+                programs: programs.to_vec(),
+            });
+        }
+        emit(&self.app, SCAN_PROGRESS_EVENT, progress);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.job.cancelled.load(Ordering::SeqCst)
+    }
+}
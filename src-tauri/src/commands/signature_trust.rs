@@ -0,0 +1,205 @@
+// Which programs can be trusted to be what they say: each main executable and uninstaller has its
+// Authenticode signature verified offline against a trusted-roots bundle, and the signer is held against
+// the Publisher the entry registers
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::commands::executables::{analyze_program_executables, ExecutableRole};
+use crate::commands::merge::normalize_publisher;
+use crate::commands::registry::{with_live_environments, ProgramEnvironments, ProgramInfo};
+use crate::services::signature_verification::{verify_file, SignatureStatus, SignatureVerification, TrustStore};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SignatureTrustItem {
+    pub name: String,
+    pub publisher: Option<String>,
+    pub registry_path: String,
+    pub role: ExecutableRole,
+    pub path: String,
+    pub verification: Option<SignatureVerification>,
+    pub error: Option<String>,      // The executable could not be read
+    pub publisher_mismatch: bool,   // Validly signed, but not by the registered publisher
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SignatureTrustReport {
+    pub checked: usize,                 // Programs with at least one executable found
+    pub executables: usize,
+    pub valid: usize,
+    pub unsigned: usize,
+    pub invalid: usize,
+    pub expired: usize,
+    pub untrusted: usize,
+    pub publisher_mismatches: usize,
+    pub trusted_roots: String,          // The bundle chains were checked against
+    pub trusted_root_count: usize,
+    pub entries: Vec<SignatureTrustItem>, // Problems first, then valid signatures
+}
+
+/// Verify the signatures on the given programs' executables. Programs whose executables have not been
+/// analyzed yet are analyzed first; `trusted_roots_path` falls back to trusted-roots.pem in the app's data folder
+#[tauri::command]
+pub async fn get_signature_trust_report(programs: Vec<ProgramInfo>, trusted_roots_path: Option<String>) -> Result<SignatureTrustReport, String> {
+    // Every executable is read and hashed whole
+    tokio::task::spawn_blocking(move || {
+        let trust = TrustStore::open(trusted_roots_path.as_deref())?;
+        Ok(with_live_environments(|environments| build_signature_trust_report(&programs, environments, &trust)))
+    })
+    .await
+    .map_err(|e| format!("Signature verification did not complete: {}", e))?
+}
+
+pub fn build_signature_trust_report(programs: &[ProgramInfo], environments: &ProgramEnvironments, trust: &TrustStore) -> SignatureTrustReport {
+    let mut entries = Vec::new();
+    let mut checked = 0;
+    for program in programs {
+        let analysis = program.executables.clone().unwrap_or_else(|| analyze_program_executables(program, environments));
+        let executables = [
+            (ExecutableRole::MainExecutable, analysis.main_executable),
+            (ExecutableRole::Uninstaller, analysis.uninstaller),
+        ];
+        let mut found = false;
+        for (role, info) in executables {
+            let path = match info {
+                Some(info) => info.path,
+                None => continue,
+            };
+            found = true;
+            entries.push(verify_program_executable(program, role, path, trust));
+        }
+        if found {
+            checked += 1;
+        }
+    }
+
+    let count = |status: SignatureStatus| entries.iter()
+        .filter(|entry| entry.verification.as_ref().map(|verification| verification.status) == Some(status))
+        .count();
+    let report_counts = (count(SignatureStatus::Valid), count(SignatureStatus::Unsigned), count(SignatureStatus::Invalid),
+        count(SignatureStatus::Expired), count(SignatureStatus::Untrusted));
+    let publisher_mismatches = entries.iter().filter(|entry| entry.publisher_mismatch).count();
+
+    entries.sort_by(|a, b| severity(b).cmp(&severity(a)).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+
+    SignatureTrustReport {
+        checked,
+        executables: entries.len(),
+        valid: report_counts.0,
+        unsigned: report_counts.1,
+        invalid: report_counts.2,
+        expired: report_counts.3,
+        untrusted: report_counts.4,
+        publisher_mismatches,
+        trusted_roots: trust.source.clone(),
+        trusted_root_count: trust.len(),
+        entries,
+    }
+}
+
+fn verify_program_executable(program: &ProgramInfo, role: ExecutableRole, path: String, trust: &TrustStore) -> SignatureTrustItem {
+    let (verification, error) = match verify_file(Path::new(&path), trust) {
+        Ok(verification) => (Some(verification), None),
+        Err(error) => (None, Some(error)),
+    };
+    // Uninstallers are often the installer framework's own stub, signed by whoever built the installer,
+    // so only the main executable is expected to carry the publisher's name
+    let publisher_mismatch = role == ExecutableRole::MainExecutable
+        && verification.as_ref().map_or(false, |verification| {
+            verification.status != SignatureStatus::Unsigned && verification.status != SignatureStatus::Invalid
+                && program.publisher.as_deref().map_or(false, |publisher| !is_signed_by(publisher, verification))
+        });
+    if publisher_mismatch {
+        println!("DEBUG: {} is registered by {:?} but signed by {:?}", program.name, program.publisher,
+            verification.as_ref().and_then(|verification| verification.signer_name.clone()));
+    }
+    SignatureTrustItem {
+        name: program.name.clone(),
+        publisher: program.publisher.clone(),
+        registry_path: program.registry_path.clone(),
+        role,
+        path,
+        verification,
+        error,
+        publisher_mismatch,
+    }
+}
+
+// The registered publisher and the signer's CN or O name the same company once legal suffixes are
+// dropped, or one's words appear whole in the other ("Adobe" and "Adobe Systems", but not "Intel" and
+// "Intelligent Systems")
+fn is_signed_by(publisher: &str, verification: &SignatureVerification) -> bool {
+    let publisher = normalize_publisher(publisher);
+    if publisher.is_empty() {
+        return true;
+    }
+    [&verification.signer_name, &verification.signer_organization]
+        .iter()
+        .filter_map(|name| name.as_deref())
+        .map(normalize_publisher)
+        .filter(|signer| !signer.is_empty())
+        .any(|signer| signer == publisher || contains_words(&signer, &publisher) || contains_words(&publisher, &signer))
+}
+
+// Normalized names are words joined by single spaces, so padding both makes the match whole words only
+fn contains_words(outer: &str, inner: &str) -> bool {
+    format!(" {} ", outer).contains(&format!(" {} ", inner))
+}
+
+// Worst first: broken signatures, then files that could not be read, missing or doubtful trust, publisher
+// mismatches, and valid signatures last
+fn severity(entry: &SignatureTrustItem) -> u8 {
+    match entry.verification.as_ref().map(|verification| verification.status) {
+        Some(SignatureStatus::Invalid) => 6,
+        None => 5,
+        Some(SignatureStatus::Unsigned) => 4,
+        Some(SignatureStatus::Untrusted) => 3,
+        Some(SignatureStatus::Expired) => 2,
+        Some(SignatureStatus::Valid) => u8::from(entry.publisher_mismatch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn signed_by(name: Option<&str>, organization: Option<&str>) -> SignatureVerification {
+        SignatureVerification {
+            status: SignatureStatus::Valid,
+            image_hash_valid: true,
+            signature_valid: true,
+            signer_name: name.map(str::to_string),
+            signer_organization: organization.map(str::to_string),
+            chain: Vec::new(),
+            trusted_root: None,
+            timestamp: None,
+            timestamp_valid: None,
+            checked_at: Utc::now(),
+            problems: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_publishers_by_whole_words() {
+        let microsoft = signed_by(Some("Microsoft Corporation"), Some("Microsoft Corporation"));
+        assert!(is_signed_by("Microsoft", &microsoft));
+        assert!(is_signed_by("Microsoft Corporation", &microsoft));
+        assert!(is_signed_by("", &microsoft));
+
+        let adobe = signed_by(Some("Adobe Inc."), Some("Adobe Inc."));
+        assert!(is_signed_by("Adobe Systems Incorporated", &adobe));
+
+        // Substrings of words are different companies
+        assert!(!is_signed_by("Intel Corporation", &signed_by(Some("Intelligent Systems Ltd"), None)));
+        assert!(!is_signed_by("Apple Inc.", &signed_by(Some("Snapple Group"), None)));
+        assert!(!is_signed_by("Code Ltd", &signed_by(Some("Encoder Works"), None)));
+    }
+
+    #[test]
+    fn matches_either_the_common_name_or_the_organization() {
+        let verification = signed_by(Some("Mozilla Corporation"), Some("Mozilla Foundation"));
+        assert!(is_signed_by("Mozilla", &verification));
+        assert!(!is_signed_by("Contoso", &verification));
+        assert!(!is_signed_by("Contoso", &signed_by(None, None)));
+    }
+}
@@ -0,0 +1,454 @@
+// Saved scans: every completed scan is kept as a snapshot in the app data directory so that any two
+// can be compared, e.g. before and after Patch Tuesday or a deployment wave
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::commands::merge::{normalize_name, normalize_publisher};
+use crate::commands::registry::ProgramInfo;
+
+// Bump when the snapshot layout changes in a way older readers cannot load
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanSnapshot {
+    pub format_version: u32,
+    pub id: String,
+    pub machine: String,
+    pub created_at: DateTime<Utc>,
+    pub sources: Vec<String>,     // Sources the scan read, e.g. "System", "User", "MSIX"
+    pub program_count: usize,
+    pub programs: Vec<ProgramInfo>,
+}
+
+/// A snapshot without its program list, for listing
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotSummary {
+    pub format_version: u32,
+    pub id: String,
+    pub machine: String,
+    pub created_at: DateTime<Utc>,
+    pub sources: Vec<String>,
+    pub program_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProgramChange {
+    pub name: String,
+    pub registry_path: String,        // As of the later scan
+    pub before_version: Option<String>,
+    pub after_version: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProgramDiff {
+    pub added: Vec<ProgramInfo>,
+    pub removed: Vec<ProgramInfo>,
+    pub upgraded: Vec<ProgramChange>,
+    pub downgraded: Vec<ProgramChange>,
+    pub changed: Vec<ProgramChange>,  // Same version (or versions that cannot be ordered), other fields differ
+    pub unchanged_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotDiff {
+    pub from: SnapshotSummary,
+    pub to: SnapshotSummary,
+    pub diff: ProgramDiff,
+}
+
+// Get the snapshots directory path
+fn get_snapshots_dir() -> PathBuf {
+    let mut path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("software-scope");
+    path.push("snapshots");
+    path
+}
+
+fn get_snapshot_path(id: &str) -> Result<PathBuf, String> {
+    // IDs come from the frontend; keep them to the characters we generate
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid snapshot id: {}", id));
+    }
+    Ok(get_snapshots_dir().join(format!("{}.json", id)))
+}
+
+/// Name of this computer, as recorded in snapshots of local scans
+pub fn local_machine_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "Unknown".to_string())
+}
+
+/// Save a program list as a new snapshot
+pub fn save_snapshot(programs: &[ProgramInfo], machine: &str, sources: Vec<String>) -> Result<SnapshotSummary, Box<dyn std::error::Error>> {
+    fs::create_dir_all(get_snapshots_dir())?;
+
+    let created_at = Utc::now();
+    let machine_part: String = machine.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let id = format!("{}-{}", created_at.format("%Y%m%dT%H%M%S%3fZ"), machine_part);
+
+    let snapshot = ScanSnapshot {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        id: id.clone(),
+        machine: machine.to_string(),
+        created_at,
+        sources,
+        program_count: programs.len(),
+        programs: programs.to_vec(),
+    };
+    fs::write(get_snapshot_path(&id)?, serde_json::to_string(&snapshot)?)?;
+    println!("DEBUG: Saved snapshot {} with {} programs", id, programs.len());
+
+    Ok(summary(&snapshot))
+}
+
+pub fn load_snapshot(id: &str) -> Result<ScanSnapshot, Box<dyn std::error::Error>> {
+    let json = fs::read_to_string(get_snapshot_path(id)?)?;
+    let snapshot: ScanSnapshot = serde_json::from_str(&json)?;
+    if snapshot.format_version > SNAPSHOT_FORMAT_VERSION {
+        return Err(format!("Snapshot {} was written by a newer version (format {})", id, snapshot.format_version).into());
+    }
+    Ok(snapshot)
+}
+
+fn summary(snapshot: &ScanSnapshot) -> SnapshotSummary {
+    SnapshotSummary {
+        format_version: snapshot.format_version,
+        id: snapshot.id.clone(),
+        machine: snapshot.machine.clone(),
+        created_at: snapshot.created_at,
+        sources: snapshot.sources.clone(),
+        program_count: snapshot.program_count,
+    }
+}
+
+/// Save programs from an import or offline scan as a snapshot; local scan jobs save their own
+#[tauri::command]
+pub fn save_scan_snapshot(programs: Vec<ProgramInfo>, machine: Option<String>, sources: Option<Vec<String>>) -> Result<SnapshotSummary, String> {
+    let sources = sources.unwrap_or_else(|| {
+        let mut sources: Vec<String> = Vec::new();
+        for program in &programs {
+            if !sources.contains(&program.installation_source) {
+                sources.push(program.installation_source.clone());
+            }
+        }
+        sources
+    });
+    let machine = machine.unwrap_or_else(local_machine_name);
+    save_snapshot(&programs, &machine, sources).map_err(|e| format!("Failed to save snapshot: {}", e))
+}
+
+/// All saved snapshots, oldest first
+#[tauri::command]
+pub fn list_snapshots() -> Result<Vec<SnapshotSummary>, String> {
+    let dir = get_snapshots_dir();
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut summaries = Vec::new();
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read snapshots directory: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        // Summary fields only; the program list is skipped while parsing
+        match fs::read_to_string(&path).map(|json| serde_json::from_str::<SnapshotSummary>(&json)) {
+            Ok(Ok(summary)) => summaries.push(summary),
+            _ => println!("DEBUG: Skipping unreadable snapshot {}", path.display()),
+        }
+    }
+
+    summaries.sort_by_key(|summary| summary.created_at);
+    Ok(summaries)
+}
+
+#[tauri::command]
+pub fn get_snapshot(id: String) -> Result<ScanSnapshot, String> {
+    load_snapshot(&id).map_err(|e| format!("Failed to load snapshot {}: {}", id, e))
+}
+
+#[tauri::command]
+pub fn delete_snapshot(id: String) -> Result<(), String> {
+    let path = get_snapshot_path(&id)?;
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete snapshot {}: {}", id, e))
+}
+
+/// What changed between two snapshots: `from` is the earlier scan, `to` the later one
+#[tauri::command]
+pub fn diff_snapshots(from_id: String, to_id: String) -> Result<SnapshotDiff, String> {
+    let from = load_snapshot(&from_id).map_err(|e| format!("Failed to load snapshot {}: {}", from_id, e))?;
+    let to = load_snapshot(&to_id).map_err(|e| format!("Failed to load snapshot {}: {}", to_id, e))?;
+    Ok(SnapshotDiff {
+        from: summary(&from),
+        to: summary(&to),
+        diff: diff_programs(&from.programs, &to.programs),
+    })
+}
+
+/// Pair up programs across two scans and classify each pair. Entries are matched by a key that
+/// survives upgrades first, then whatever is left by name and publisher
+pub fn diff_programs(before: &[ProgramInfo], after: &[ProgramInfo]) -> ProgramDiff {
+    let mut diff = ProgramDiff::default();
+    let mut unmatched_before: Vec<Option<&ProgramInfo>> = before.iter().map(Some).collect();
+    let mut unmatched_after: Vec<Option<&ProgramInfo>> = after.iter().map(Some).collect();
+    let mut pairs = Vec::new();
+
+    for match_key in [stable_key as fn(&ProgramInfo) -> String, product_key] {
+        let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, program) in unmatched_before.iter().enumerate() {
+            if let Some(program) = program {
+                by_key.entry(match_key(program)).or_default().push(i);
+            }
+        }
+        for slot in unmatched_after.iter_mut() {
+            let program = match slot {
+                Some(program) => *program,
+                None => continue,
+            };
+            let candidates = match by_key.get_mut(&match_key(program)) {
+                Some(candidates) if !candidates.is_empty() => candidates,
+                _ => continue,
+            };
+            let i = candidates.remove(0);
+            if let Some(earlier) = unmatched_before[i].take() {
+                pairs.push((earlier, program));
+                *slot = None;
+            }
+        }
+    }
+
+    diff.removed = unmatched_before.into_iter().flatten().cloned().collect();
+    diff.added = unmatched_after.into_iter().flatten().cloned().collect();
+
+    for (earlier, later) in pairs {
+        let changes = field_changes(earlier, later);
+        if changes.is_empty() {
+            diff.unchanged_count += 1;
+            continue;
+        }
+
+        let change = ProgramChange {
+            name: later.name.clone(),
+            registry_path: later.registry_path.clone(),
+            before_version: earlier.version.clone(),
+            after_version: later.version.clone(),
+            changes,
+        };
+        match (&earlier.version_info, &later.version_info) {
+            (Some(a), Some(b)) if b > a => diff.upgraded.push(change),
+            (Some(a), Some(b)) if b < a => diff.downgraded.push(change),
+            _ => diff.changed.push(change),
+        }
+    }
+
+    diff
+}
+
+// Keys that stay the same when a product is updated in place: the UpgradeCode for MSI products, the
+// package family for MSIX, otherwise the uninstall key
+fn stable_key(program: &ProgramInfo) -> String {
+    let owner = program.user_sid.as_deref().unwrap_or("");
+    if let Some(upgrade_code) = program.msi.as_ref().and_then(|msi| msi.upgrade_code.as_deref()) {
+        return format!("upgrade:{}:{}", upgrade_code, owner);
+    }
+    if program.installation_source == "MSIX" {
+        // Name_Version_Architecture_ResourceId_PublisherId -> Name_PublisherId
+        let parts: Vec<&str> = program.registry_name.split('_').collect();
+        if parts.len() == 5 {
+            return format!("msix:{}_{}:{}", parts[0], parts[4], owner).to_lowercase();
+        }
+    }
+    format!("path:{}", program.registry_path.to_lowercase())
+}
+
+// A major upgrade gets a new ProductCode and so a new uninstall key; fall back to what the user sees
+fn product_key(program: &ProgramInfo) -> String {
+    format!(
+        "product:{}|{}|{}|{}|{}",
+        normalize_name(&program.name, program.version.as_deref()),
+        program.publisher.as_deref().map(normalize_publisher).unwrap_or_default(),
+        program.installation_source,
+        program.architecture,
+        program.user_sid.as_deref().unwrap_or(""),
+    )
+}
+
+fn field_changes(before: &ProgramInfo, after: &ProgramInfo) -> Vec<FieldChange> {
+    let fields: [(&str, Option<String>, Option<String>); 14] = [
+        ("name", Some(before.name.clone()), Some(after.name.clone())),
+        ("version", before.version.clone(), after.version.clone()),
+        ("publisher", before.publisher.clone(), after.publisher.clone()),
+        ("install_location", before.install_location.clone(), after.install_location.clone()),
+        ("install_date", before.install_date.clone(), after.install_date.clone()),
+        ("uninstall_string", before.uninstall_string.clone(), after.uninstall_string.clone()),
+        ("quiet_uninstall_string", before.quiet_uninstall_string.clone(), after.quiet_uninstall_string.clone()),
+        ("estimated_size", before.estimated_size.map(|s| s.to_string()), after.estimated_size.map(|s| s.to_string())),
+        ("architecture", Some(before.architecture.clone()), Some(after.architecture.clone())),
+        ("installed_for", before.installed_for.clone(), after.installed_for.clone()),
+        ("program_type", Some(before.program_type.clone()), Some(after.program_type.clone())),
+        ("registry_path", Some(before.registry_path.clone()), Some(after.registry_path.clone())),
+        ("msi_product_code", before.msi.as_ref().map(|m| m.product_code.clone()), after.msi.as_ref().map(|m| m.product_code.clone())),
+        ("is_vf_deployed", Some(before.is_vf_deployed.to_string()), Some(after.is_vf_deployed.to_string())),
+    ];
+
+    fields.into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldChange {
+            field: field.to_string(),
+            before,
+            after,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::version::ProgramVersion;
+
+    const UNINSTALL_KEY: &str = "HKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall";
+
+    fn program(name: &str, version: &str, key: &str, extra: serde_json::Value) -> ProgramInfo {
+        let mut fields = serde_json::json!({
+            "version": version,
+            "version_info": ProgramVersion::parse(version),
+            "publisher": "Contoso Ltd.",
+            "registry_name": key,
+            "registry_path": format!("{}\\{}", UNINSTALL_KEY, key),
+        });
+        if let (Some(fields), serde_json::Value::Object(extra)) = (fields.as_object_mut(), extra) {
+            fields.extend(extra);
+        }
+        ProgramInfo::with_fields(name, fields)
+    }
+
+    fn msi(product_code: &str, upgrade_code: &str) -> serde_json::Value {
+        serde_json::json!({
+            "is_windows_installer": true,
+            "msi": {
+                "product_code": product_code,
+                "packed_product_code": "",
+                "upgrade_code": upgrade_code,
+                "local_package": null,
+                "context": "Machine",
+                "user_sid": null,
+            }
+        })
+    }
+
+    fn names(programs: &[ProgramInfo]) -> Vec<&str> {
+        programs.iter().map(|program| program.name.as_str()).collect()
+    }
+
+    fn changed_fields(change: &ProgramChange) -> Vec<&str> {
+        change.changes.iter().map(|change| change.field.as_str()).collect()
+    }
+
+    #[test]
+    fn reports_added_removed_and_unchanged_programs() {
+        let before = [
+            program("Contoso Agent", "1.0", "Contoso Agent", serde_json::json!({})),
+            program("Contoso Viewer", "2.0", "Contoso Viewer", serde_json::json!({})),
+        ];
+        let after = [
+            program("Contoso Agent", "1.0", "Contoso Agent", serde_json::json!({})),
+            program("Fabrikam Tools", "1.0", "Fabrikam Tools", serde_json::json!({})),
+        ];
+        let diff = diff_programs(&before, &after);
+        assert_eq!(names(&diff.added), vec!["Fabrikam Tools"]);
+        assert_eq!(names(&diff.removed), vec!["Contoso Viewer"]);
+        assert_eq!(diff.unchanged_count, 1);
+        assert!(diff.upgraded.is_empty() && diff.downgraded.is_empty() && diff.changed.is_empty());
+    }
+
+    #[test]
+    fn classifies_version_changes() {
+        let before = [
+            program("Contoso Agent", "1.9.2", "Agent", serde_json::json!({})),
+            program("Contoso Viewer", "2.0", "Viewer", serde_json::json!({})),
+            program("Contoso Sync", "3.0", "Sync", serde_json::json!({ "install_location": "C:\\Program Files\\Contoso Sync" })),
+        ];
+        let after = [
+            program("Contoso Agent", "1.10.0", "Agent", serde_json::json!({})),
+            program("Contoso Viewer", "1.9", "Viewer", serde_json::json!({})),
+            program("Contoso Sync", "3.0.0", "Sync", serde_json::json!({ "install_location": "D:\\Apps\\Contoso Sync" })),
+        ];
+        let diff = diff_programs(&before, &after);
+
+        assert_eq!(diff.upgraded.len(), 1);
+        assert_eq!(diff.upgraded[0].name, "Contoso Agent");
+        assert_eq!((diff.upgraded[0].before_version.as_deref(), diff.upgraded[0].after_version.as_deref()), (Some("1.9.2"), Some("1.10.0")));
+        assert_eq!(changed_fields(&diff.upgraded[0]), vec!["version"]);
+        assert_eq!(diff.downgraded.len(), 1);
+        assert_eq!(diff.downgraded[0].name, "Contoso Viewer");
+        // 3.0 and 3.0.0 are one version; only the folder moved
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(changed_fields(&diff.changed[0]), vec!["version", "install_location"]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+    }
+
+    #[test]
+    fn pairs_major_upgrades_by_upgrade_code() {
+        let upgrade_code = "{0F0E0D0C-AAAA-BBBB-CCCC-DDDDEEEEFFFF}";
+        let old_code = "{11111111-1111-1111-1111-111111111111}";
+        let new_code = "{22222222-2222-2222-2222-222222222222}";
+        let before = [program("Contoso Agent", "1.0", old_code, msi(old_code, upgrade_code))];
+        let after = [
+            program("Contoso Agent 2", "2.0", new_code, msi(new_code, upgrade_code)),
+            // The same product installed for one user is a separate install
+            program("Contoso Agent", "1.0", old_code, serde_json::json!({
+                "user_sid": "S-1-5-21-1",
+                "installation_source": "User",
+                "registry_path": format!("HKEY_USERS\\S-1-5-21-1\\{}\\{}", UNINSTALL_KEY.trim_start_matches("HKEY_LOCAL_MACHINE\\"), old_code),
+            })),
+        ];
+        let diff = diff_programs(&before, &after);
+
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].user_sid.as_deref(), Some("S-1-5-21-1"));
+        assert_eq!(diff.upgraded.len(), 1);
+        assert_eq!(changed_fields(&diff.upgraded[0]), vec!["name", "version", "registry_path", "msi_product_code"]);
+        assert_eq!(diff.upgraded[0].registry_path, format!("{}\\{}", UNINSTALL_KEY, new_code));
+    }
+
+    #[test]
+    fn pairs_renamed_keys_by_product() {
+        let before = [program("7-Zip 22.01 (x64)", "22.01", "7-Zip", serde_json::json!({ "publisher": "Igor Pavlov" }))];
+        let after = [program("7-Zip 23.01 (x64)", "23.01", "{23170F69-40C1-2702-2301-000001000000}", serde_json::json!({ "publisher": "Igor Pavlov" }))];
+        let diff = diff_programs(&before, &after);
+
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.upgraded.len(), 1);
+        assert_eq!(changed_fields(&diff.upgraded[0]), vec!["name", "version", "registry_path"]);
+
+        // Another publisher's program of the same name is not the same product
+        let other = [program("7-Zip 23.01 (x64)", "23.01", "7-Zip Fork", serde_json::json!({ "publisher": "Someone Else" }))];
+        let diff = diff_programs(&before, &other);
+        assert_eq!((diff.added.len(), diff.removed.len()), (1, 1));
+    }
+
+    #[test]
+    fn rejects_snapshot_ids_that_leave_the_folder() {
+        for id in ["../x", "..", "", "a/b", "a\\b", "x.json", "C:evil"] {
+            assert!(get_snapshot_path(id).is_err(), "{}", id);
+        }
+        let path = get_snapshot_path("20240322-103000_1").unwrap();
+        assert!(path.ends_with("snapshots/20240322-103000_1.json"));
+    }
+}
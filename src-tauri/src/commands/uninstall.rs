@@ -0,0 +1,325 @@
+// Uninstall, modify and repair of an inventory entry: its commands parsed, a silent uninstall proposed,
+// and the chosen command run under supervision
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::commands::registry::ProgramInfo;
+use crate::services::command_line::{parse_command_line, synthesize_quiet_uninstall, ParsedCommandLine, SilentCommandOrigin, SilentUninstall};
+use crate::services::installer_fingerprint::{fingerprint_command, Confidence, InstallerEngine};
+use crate::services::msi::format_guid;
+use crate::services::process_runner::{ProcessRequest, ProcessRunner, SystemProcessRunner};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UninstallAnalysis {
+    pub uninstall: Option<ParsedCommandLine>,
+    pub quiet_uninstall: Option<ParsedCommandLine>,
+    pub modify: Option<ParsedCommandLine>,
+    pub engine: InstallerEngine,
+    pub engine_confidence: Confidence,
+    pub silent_uninstall: Option<SilentUninstall>, // The entry's own QuietUninstallString when it has one
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UninstallAnalysisResult {
+    pub registry_path: String,
+    pub analysis: UninstallAnalysis,
+}
+
+#[tauri::command]
+pub fn analyze_uninstall_commands(program: ProgramInfo) -> UninstallAnalysis {
+    analyze_uninstall(&program)
+}
+
+/// Analyze several entries at once, e.g. to list which ones can be removed unattended
+#[tauri::command]
+pub fn analyze_uninstall_commands_batch(programs: Vec<ProgramInfo>) -> Vec<UninstallAnalysisResult> {
+    programs.iter()
+        .map(|program| UninstallAnalysisResult {
+            registry_path: program.registry_path.clone(),
+            analysis: analyze_uninstall(program),
+        })
+        .collect()
+}
+
+pub fn analyze_uninstall(program: &ProgramInfo) -> UninstallAnalysis {
+    let uninstall = program.uninstall_string.as_deref().and_then(parse_command_line);
+    let quiet_uninstall = program.quiet_uninstall_string.as_deref().and_then(parse_command_line);
+    let modify = program.change_install_string.as_deref().and_then(parse_command_line);
+
+    let product_code = program.msi.as_ref()
+        .map(|msi| msi.product_code.as_str())
+        .or_else(|| Some(program.registry_name.as_str()).filter(|_| program.is_windows_installer));
+    // The scan's fingerprint also saw registry markers and the uninstaller itself
+    let fingerprint = program.installer_technology.as_ref()
+        .filter(|technology| technology.engine != InstallerEngine::Unknown)
+        .map(|technology| (technology.engine, technology.confidence));
+    let (engine, engine_confidence) = match (fingerprint, uninstall.as_ref().or(quiet_uninstall.as_ref())) {
+        (Some(fingerprint), _) => fingerprint,
+        (None, Some(command)) => {
+            let technology = fingerprint_command(command, &program.registry_name, program.is_windows_installer);
+            (technology.engine, technology.confidence)
+        }
+        (None, None) if program.is_windows_installer => (InstallerEngine::WindowsInstaller, Confidence::Medium),
+        (None, None) => (InstallerEngine::Unknown, Confidence::Low),
+    };
+
+    let silent_uninstall = match (&quiet_uninstall, &uninstall) {
+        (Some(quiet), _) => Some(SilentUninstall {
+            command: quiet.raw.trim().to_string(),
+            engine,
+            confidence: Confidence::High,
+            origin: SilentCommandOrigin::QuietUninstallString,
+            note: None,
+        }),
+        (None, Some(uninstall)) => synthesize_quiet_uninstall(uninstall, engine, engine_confidence, product_code),
+        (None, None) => None,
+    };
+
+    UninstallAnalysis {
+        uninstall,
+        quiet_uninstall,
+        modify,
+        engine,
+        engine_confidence,
+        silent_uninstall,
+    }
+}
+
+// Interactive uninstallers wait on the user, so the default deadline is generous
+const DEFAULT_ACTION_TIMEOUT_SECS: u64 = 30 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramAction {
+    Uninstall,
+    QuietUninstall, // QuietUninstallString, or a proposed silent command the analysis is sure of
+    Modify,
+    Repair,         // Windows Installer products only
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ExitOutcome {
+    Success,           // 0
+    RebootRequired,    // 3010: done, takes effect after a restart
+    RebootInitiated,   // 1641: done, the installer is restarting the machine
+    NotInstalled,      // 1605: the product is not installed, e.g. removed already
+    InstallInProgress, // 1618: another installation is running; worth retrying later
+    Failed,
+    TimedOut,
+}
+
+impl ExitOutcome {
+    pub fn succeeded(self) -> bool {
+        matches!(self, ExitOutcome::Success | ExitOutcome::RebootRequired | ExitOutcome::RebootInitiated)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActionResult {
+    pub name: String,
+    pub registry_path: String,
+    pub action: ProgramAction,
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub outcome: ExitOutcome,
+    pub message: String,
+    pub stdout: String,
+    pub stderr: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+/// Run a program's uninstall, quiet uninstall, modify or repair command and report how it ended
+#[tauri::command]
+pub async fn run_program_action(program: ProgramInfo, action: ProgramAction, timeout_seconds: Option<u64>) -> Result<ActionResult, String> {
+    let timeout = Duration::from_secs(timeout_seconds.unwrap_or(DEFAULT_ACTION_TIMEOUT_SECS));
+    // Uninstallers run for minutes; keep them off the async runtime's worker threads
+    tokio::task::spawn_blocking(move || execute_program_action(&program, action, timeout, &SystemProcessRunner))
+        .await
+        .map_err(|e| format!("Action for {} did not complete: {}", action_label(action), e))?
+}
+
+pub fn execute_program_action(program: &ProgramInfo, action: ProgramAction, timeout: Duration, runner: &dyn ProcessRunner) -> Result<ActionResult, String> {
+    let command = action_command(program, action)?;
+    let request = ProcessRequest {
+        executable: command.executable.clone(),
+        arguments: command.arguments.clone(),
+        timeout,
+    };
+
+    println!("DEBUG: {} {}: {}", action_label(action), program.name, command.raw);
+    let started_at = Utc::now();
+    let output = runner.run(&request)?;
+    let (outcome, message) = interpret_exit_code(output.exit_code, output.timed_out);
+    println!("DEBUG: {} {} finished: {:?} ({:?})", action_label(action), program.name, outcome, output.exit_code);
+
+    Ok(ActionResult {
+        name: program.name.clone(),
+        registry_path: program.registry_path.clone(),
+        action,
+        command: command.raw.trim().to_string(),
+        exit_code: output.exit_code,
+        outcome,
+        message,
+        stdout: output.stdout,
+        stderr: output.stderr,
+        started_at,
+        duration_ms: output.duration.as_millis() as u64,
+    })
+}
+
+/// The command line an action runs
+pub fn action_command(program: &ProgramInfo, action: ProgramAction) -> Result<ParsedCommandLine, String> {
+    let missing = |what: &str| format!("{} has no {}", program.name, what);
+    match action {
+        ProgramAction::Uninstall => program.uninstall_string.as_deref()
+            .and_then(parse_command_line)
+            .ok_or_else(|| missing("uninstall command")),
+        ProgramAction::Modify => program.change_install_string.as_deref()
+            .and_then(parse_command_line)
+            .ok_or_else(|| missing("modify command")),
+        ProgramAction::QuietUninstall => {
+            let silent = analyze_uninstall(program).silent_uninstall.ok_or_else(|| missing("silent uninstall command"))?;
+            // A guessed switch that is wrong can leave an uninstaller waiting on a dialog nobody sees
+            if silent.origin == SilentCommandOrigin::Synthesized && silent.confidence < Confidence::High {
+                return Err(format!(
+                    "{} has no QuietUninstallString and the proposed silent command is only {:?} confidence: {}",
+                    program.name, silent.confidence, silent.command
+                ));
+            }
+            parse_command_line(&silent.command).ok_or_else(|| missing("silent uninstall command"))
+        }
+        ProgramAction::Repair => {
+            let product_code = program.msi.as_ref()
+                .map(|msi| msi.product_code.clone())
+                .or_else(|| program.uninstall_string.as_deref().and_then(parse_command_line).and_then(|command| command.product_code))
+                .or_else(|| Some(program.registry_name.clone()).filter(|_| program.is_windows_installer).and_then(|code| format_guid(&code)))
+                .ok_or_else(|| format!("Repair is only available for Windows Installer products, and {} is not one", program.name))?;
+            // Reinstall missing or older files, rewrite registry and shortcuts; show progress but never prompt
+            parse_command_line(&format!("MsiExec.exe /fomus{} /qb /norestart", product_code))
+                .ok_or_else(|| missing("repair command"))
+        }
+    }
+}
+
+/// Map an exit code to what it means for the caller. The codes are Windows Installer's, which most
+/// other engines adopt for the common cases
+pub fn interpret_exit_code(exit_code: Option<i32>, timed_out: bool) -> (ExitOutcome, String) {
+    if timed_out {
+        return (ExitOutcome::TimedOut, "The process did not finish in time and was stopped".to_string());
+    }
+    match exit_code {
+        Some(0) => (ExitOutcome::Success, "Completed successfully".to_string()),
+        Some(3010) => (ExitOutcome::RebootRequired, "Completed; a restart is required to finish".to_string()),
+        Some(1641) => (ExitOutcome::RebootInitiated, "Completed; the installer started a restart".to_string()),
+        Some(1605) => (ExitOutcome::NotInstalled, "The product is not installed".to_string()),
+        Some(1618) => (ExitOutcome::InstallInProgress, "Another installation is already in progress".to_string()),
+        Some(code) => (ExitOutcome::Failed, format!("Failed with exit code {}", code)),
+        None => (ExitOutcome::Failed, "The process was terminated without an exit code".to_string()),
+    }
+}
+
+fn action_label(action: ProgramAction) -> &'static str {
+    match action {
+        ProgramAction::Uninstall => "Uninstall",
+        ProgramAction::QuietUninstall => "Quiet uninstall",
+        ProgramAction::Modify => "Modify",
+        ProgramAction::Repair => "Repair",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::process_runner::{FakeProcessRunner, ProcessOutput};
+
+    const PRODUCT_CODE: &str = "{23170F69-40C1-2702-2301-000001000000}";
+
+    fn msi_product() -> ProgramInfo {
+        ProgramInfo::with_fields("7-Zip 23.01 (x64 edition)", serde_json::json!({
+            "registry_name": PRODUCT_CODE,
+            "is_windows_installer": true,
+            "uninstall_string": format!("MsiExec.exe /I{}", PRODUCT_CODE),
+            "change_install_string": format!("MsiExec.exe /I{}", PRODUCT_CODE),
+        }))
+    }
+
+    fn run_with(exit_codes: &[i32]) -> ActionResult {
+        let runner = FakeProcessRunner::exiting_with(exit_codes);
+        execute_program_action(&msi_product(), ProgramAction::Uninstall, Duration::from_secs(60), &runner).unwrap()
+    }
+
+    #[test]
+    fn maps_exit_codes_to_outcomes() {
+        for (exit_code, outcome, succeeded) in [
+            (0, ExitOutcome::Success, true),
+            (3010, ExitOutcome::RebootRequired, true),
+            (1641, ExitOutcome::RebootInitiated, true),
+            (1605, ExitOutcome::NotInstalled, false),
+            (1618, ExitOutcome::InstallInProgress, false),
+            (1603, ExitOutcome::Failed, false),
+        ] {
+            let result = run_with(&[exit_code]);
+            assert_eq!(result.exit_code, Some(exit_code));
+            assert_eq!(result.outcome, outcome, "exit code {}", exit_code);
+            assert_eq!(result.outcome.succeeded(), succeeded);
+        }
+        assert_eq!(interpret_exit_code(None, false).0, ExitOutcome::Failed);
+    }
+
+    #[test]
+    fn reports_timeouts() {
+        let runner = FakeProcessRunner::new(vec![Ok(ProcessOutput::timed_out())]);
+        let result = execute_program_action(&msi_product(), ProgramAction::Uninstall, Duration::from_secs(5), &runner).unwrap();
+        assert_eq!(result.outcome, ExitOutcome::TimedOut);
+        assert_eq!(result.exit_code, None);
+        assert_eq!(runner.requests.lock().unwrap()[0].timeout, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn reports_processes_that_cannot_start() {
+        let runner = FakeProcessRunner::new(vec![Err("Failed to start MsiExec.exe: not found".to_string())]);
+        let error = execute_program_action(&msi_product(), ProgramAction::Uninstall, Duration::from_secs(5), &runner).unwrap_err();
+        assert!(error.contains("Failed to start"));
+    }
+
+    #[test]
+    fn runs_the_command_each_action_names() {
+        let runner = FakeProcessRunner::exiting_with(&[0, 0, 0, 0]);
+        for action in [ProgramAction::Uninstall, ProgramAction::QuietUninstall, ProgramAction::Modify, ProgramAction::Repair] {
+            execute_program_action(&msi_product(), action, Duration::from_secs(60), &runner).unwrap();
+        }
+        assert_eq!(runner.command_lines(), vec![
+            format!("MsiExec.exe /I{}", PRODUCT_CODE),
+            format!("MsiExec.exe /X{} /qn /norestart", PRODUCT_CODE),
+            format!("MsiExec.exe /I{}", PRODUCT_CODE),
+            format!("MsiExec.exe /fomus{} /qb /norestart", PRODUCT_CODE),
+        ]);
+    }
+
+    #[test]
+    fn prefers_the_entrys_own_quiet_uninstall_string() {
+        let program = ProgramInfo::with_fields("Contoso Agent", serde_json::json!({
+            "uninstall_string": "\"C:\\Program Files\\Contoso\\uninstall.exe\"",
+            "quiet_uninstall_string": "\"C:\\Program Files\\Contoso\\uninstall.exe\" --silent",
+        }));
+        let command = action_command(&program, ProgramAction::QuietUninstall).unwrap();
+        assert_eq!(command.executable, "C:\\Program Files\\Contoso\\uninstall.exe");
+        assert_eq!(command.arguments, vec!["--silent".to_string()]);
+    }
+
+    #[test]
+    fn refuses_actions_an_entry_cannot_run() {
+        let program = ProgramInfo::with_fields("Contoso Agent", serde_json::json!({
+            "uninstall_string": "\"C:\\Program Files\\Contoso\\uninstall.exe\"",
+        }));
+        assert_eq!(action_command(&program, ProgramAction::Modify).unwrap_err(), "Contoso Agent has no modify command");
+        assert!(action_command(&program, ProgramAction::Repair).unwrap_err().starts_with("Repair is only available for Windows Installer products"));
+        // No engine is known, so a silent switch would be a guess
+        assert!(action_command(&program, ProgramAction::QuietUninstall).is_err());
+
+        let runner = FakeProcessRunner::exiting_with(&[]);
+        assert!(execute_program_action(&program, ProgramAction::Modify, Duration::from_secs(60), &runner).is_err());
+        assert!(runner.requests.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,627 @@
+// Batch uninstalls: programs are removed one after another with their silent commands on a background
+// thread, each item reported to the frontend as it changes
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::merge::normalize_name;
+use crate::commands::registry::ProgramInfo;
+use crate::commands::uninstall::{action_command, execute_program_action, ActionResult, ExitOutcome, ProgramAction};
+use crate::services::installer_fingerprint::InstallerEngine;
+use crate::services::msi::format_guid;
+use crate::services::process_runner::{ProcessRunner, SystemProcessRunner};
+
+pub const UNINSTALL_QUEUE_ITEM_EVENT: &str = "uninstall-queue-item";
+pub const UNINSTALL_QUEUE_FINISHED_EVENT: &str = "uninstall-queue-finished";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FailurePolicy {
+    Halt,     // Stop at the first failure; the rest are not run
+    Continue,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueueOptions {
+    pub failure_policy: FailurePolicy,
+    pub max_retries: u32,         // Extra attempts while another installation is in progress (1618)
+    pub retry_delay_seconds: u64,
+    pub timeout_seconds: u64,     // Per attempt
+    pub keep_order: bool,         // Run in the given order rather than updates, bundles, applications, runtimes
+}
+
+impl Default for QueueOptions {
+    fn default() -> Self {
+        QueueOptions {
+            failure_policy: FailurePolicy::Continue,
+            max_retries: 3,
+            retry_delay_seconds: 30,
+            timeout_seconds: 15 * 60,
+            keep_order: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum QueueItemState {
+    Pending,
+    Running,
+    Retrying,
+    Succeeded,
+    Failed,
+    Skipped, // No silent command to run it with
+    NotRun,  // The queue halted or was cancelled first
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueueItemStatus {
+    pub job_id: String,
+    pub position: usize,
+    pub name: String,
+    pub registry_path: String,
+    pub state: QueueItemState,
+    pub attempts: u32,
+    pub command: Option<String>,
+    pub result: Option<ActionResult>, // The last attempt
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UninstallReport {
+    pub job_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub failure_policy: FailurePolicy,
+    pub halted: bool,
+    pub cancelled: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub not_run: usize,
+    pub reboot_required: bool,
+    pub items: Vec<QueueItemStatus>,
+}
+
+/// Programs a removal list resolved to, the lines that matched nothing, and the names that matched more
+/// than one program and are left for the user to pick from
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueueResolution {
+    pub programs: Vec<ProgramInfo>,
+    pub unresolved: Vec<String>,
+    pub ambiguous: Vec<AmbiguousEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AmbiguousEntry {
+    pub entry: String,
+    pub candidates: Vec<ProgramInfo>,
+}
+
+pub trait QueueObserver {
+    fn item_changed(&self, _item: &QueueItemStatus) {}
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+    // Pause before a retry
+    fn wait(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+pub struct UninstallQueue {
+    cancelled: AtomicBool,
+    report: Mutex<UninstallReport>,
+}
+
+#[derive(Default)]
+pub struct UninstallQueues {
+    queues: HashMap<String, Arc<UninstallQueue>>,
+    latest: Option<String>,
+    next_id: u64,
+}
+
+// Global state for uninstall queues
+pub type UninstallQueueState = Mutex<UninstallQueues>;
+
+/// Match a CSV of program names or product codes against an inventory. One entry per row, in a column
+/// headed "Name"/"DisplayName" or "Product Code"/"ProductCode", or in the first column without a header
+#[tauri::command]
+pub fn resolve_uninstall_list(csv_path: String, programs: Vec<ProgramInfo>) -> Result<QueueResolution, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(&csv_path)
+        .map_err(|e| format!("Failed to open {}: {}", csv_path, e))?;
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to read {}: {}", csv_path, e))?;
+        rows.push(record.iter().map(|field| field.trim().to_string()).collect::<Vec<_>>());
+    }
+
+    let header = |wanted: &[&str]| rows.first()
+        .and_then(|row| row.iter().position(|cell| wanted.iter().any(|name| cell.eq_ignore_ascii_case(name))));
+    let column = header(&["Product Code", "ProductCode", "MSI Product Code"]).or_else(|| header(&["Name", "DisplayName"]));
+    let entries: Vec<String> = match column {
+        Some(column) => rows.iter().skip(1).filter_map(|row| row.get(column).cloned()).collect(),
+        None => rows.iter().filter_map(|row| row.first().cloned()).collect(),
+    };
+
+    Ok(resolve_entries(&entries, &programs))
+}
+
+pub fn resolve_entries(entries: &[String], programs: &[ProgramInfo]) -> QueueResolution {
+    let mut resolved: Vec<ProgramInfo> = Vec::new();
+    let mut unresolved = Vec::new();
+    let mut ambiguous = Vec::new();
+
+    for entry in entries.iter().map(|entry| entry.trim()).filter(|entry| !entry.is_empty()) {
+        let product_code = Some(entry).filter(|entry| entry.starts_with('{')).and_then(format_guid);
+        let matches: Vec<&ProgramInfo> = match &product_code {
+            Some(code) => programs.iter()
+                .filter(|program| {
+                    program.msi.as_ref().map_or(false, |msi| msi.product_code == *code)
+                        || program.registry_name.eq_ignore_ascii_case(code)
+                })
+                .collect(),
+            None => {
+                let exact: Vec<&ProgramInfo> = programs.iter().filter(|program| program.name.eq_ignore_ascii_case(entry)).collect();
+                if exact.is_empty() {
+                    // "7-Zip" in a list still finds "7-Zip 23.01 (x64)"
+                    let wanted = normalize_name(entry, None);
+                    programs.iter()
+                        .filter(|program| !wanted.is_empty() && normalize_name(&program.name, program.version.as_deref()) == wanted)
+                        .collect()
+                } else {
+                    exact
+                }
+            }
+        };
+
+        if matches.is_empty() {
+            unresolved.push(entry.to_string());
+            continue;
+        }
+        // A ProductCode names one product, however many users have it. A name can fit several products,
+        // e.g. the x86 and x64 builds, and only the user can say which of them was meant
+        if product_code.is_none() && matches.len() > 1 {
+            println!("DEBUG: Removal list entry '{}' matches {} programs, not queueing any of them", entry, matches.len());
+            ambiguous.push(AmbiguousEntry {
+                entry: entry.to_string(),
+                candidates: matches.into_iter().cloned().collect(),
+            });
+            continue;
+        }
+        for program in matches {
+            if !resolved.iter().any(|existing| existing.registry_path == program.registry_path) {
+                resolved.push(program.clone());
+            }
+        }
+    }
+
+    QueueResolution { programs: resolved, unresolved, ambiguous }
+}
+
+/// Start removing the given programs and return the queue's job ID. Only one queue runs at a time,
+/// since Windows Installer serializes installations anyway
+#[tauri::command]
+pub fn start_uninstall_queue(
+    app: AppHandle,
+    programs: Vec<ProgramInfo>,
+    options: Option<QueueOptions>,
+    queues: State<UninstallQueueState>,
+) -> Result<String, String> {
+    let mut queues = queues.lock().map_err(|e| format!("Failed to lock uninstall queues: {}", e))?;
+    if let Some((running, _)) = queues.queues.iter().find(|(_, queue)| queue.current_report().finished_at.is_none()) {
+        return Err(format!("Uninstall queue {} is still running", running));
+    }
+
+    let options = options.unwrap_or_default();
+    let programs = if options.keep_order { programs } else { order_for_uninstall(programs) };
+
+    queues.queues.clear();
+    queues.next_id += 1;
+    let job_id = format!("uninstall-{}", queues.next_id);
+    let queue = Arc::new(UninstallQueue {
+        cancelled: AtomicBool::new(false),
+        report: Mutex::new(new_report(&job_id, &programs, options.failure_policy)),
+    });
+    queues.queues.insert(job_id.clone(), queue.clone());
+    queues.latest = Some(job_id.clone());
+
+    let thread_job_id = job_id.clone();
+    thread::spawn(move || {
+        let observer = QueueJobObserver { app: app.clone(), queue: queue.clone() };
+        let report = run_uninstall_queue(&thread_job_id, &programs, &options, &SystemProcessRunner, &observer);
+        if let Ok(mut current) = queue.report.lock() {
+            *current = report.clone();
+        }
+        if let Err(e) = app.emit_all(UNINSTALL_QUEUE_FINISHED_EVENT, report) {
+            println!("DEBUG: Failed to emit {}: {}", UNINSTALL_QUEUE_FINISHED_EVENT, e);
+        }
+    });
+
+    Ok(job_id)
+}
+
+/// Stop a queue after the uninstall that is running now; an uninstaller is never killed halfway
+#[tauri::command]
+pub fn cancel_uninstall_queue(job_id: String, queues: State<UninstallQueueState>) -> Result<(), String> {
+    let queues = queues.lock().map_err(|e| format!("Failed to lock uninstall queues: {}", e))?;
+    let queue = queues.queues.get(&job_id).ok_or_else(|| format!("No uninstall queue {}", job_id))?;
+    queue.cancelled.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Report of the given queue, or of the most recent one; complete once `finished_at` is set
+#[tauri::command]
+pub fn get_uninstall_queue_report(job_id: Option<String>, queues: State<UninstallQueueState>) -> Result<UninstallReport, String> {
+    let queues = queues.lock().map_err(|e| format!("Failed to lock uninstall queues: {}", e))?;
+    let job_id = job_id.or_else(|| queues.latest.clone()).ok_or_else(|| "No uninstall queue has run".to_string())?;
+    let queue = queues.queues.get(&job_id).ok_or_else(|| format!("No uninstall queue {}", job_id))?;
+    Ok(queue.current_report())
+}
+
+// Updates and patches go before their product, bundles before the packages they chain, and shared
+// runtimes and frameworks last, after the applications that use them
+pub fn order_for_uninstall(mut programs: Vec<ProgramInfo>) -> Vec<ProgramInfo> {
+    programs.sort_by_key(|program| {
+        let engine = program.installer_technology.as_ref().map(|technology| technology.engine);
+        match (program.program_type.as_str(), engine) {
+            ("Update", _) => 0,
+            (_, Some(InstallerEngine::WixBurn)) => 1,
+            ("SystemComponent", _) => 3,
+            _ => 2,
+        }
+    });
+    programs
+}
+
+/// Run a queue to the end, or until it halts or is cancelled
+pub fn run_uninstall_queue(
+    job_id: &str,
+    programs: &[ProgramInfo],
+    options: &QueueOptions,
+    runner: &dyn ProcessRunner,
+    observer: &dyn QueueObserver,
+) -> UninstallReport {
+    let mut report = new_report(job_id, programs, options.failure_policy);
+    let timeout = Duration::from_secs(options.timeout_seconds);
+
+    for (position, program) in programs.iter().enumerate() {
+        if observer.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        let item = &mut report.items[position];
+
+        match action_command(program, ProgramAction::QuietUninstall) {
+            Ok(command) => item.command = Some(command.raw.trim().to_string()),
+            Err(e) => {
+                item.state = QueueItemState::Skipped;
+                item.message = Some(e);
+                observer.item_changed(item);
+                continue;
+            }
+        }
+
+        loop {
+            item.attempts += 1;
+            item.state = QueueItemState::Running;
+            observer.item_changed(item);
+
+            match execute_program_action(program, ProgramAction::QuietUninstall, timeout, runner) {
+                Ok(result) if result.outcome == ExitOutcome::InstallInProgress && item.attempts <= options.max_retries => {
+                    item.state = QueueItemState::Retrying;
+                    item.message = Some(format!("{}; retrying in {} s", result.message, options.retry_delay_seconds));
+                    item.result = Some(result);
+                    observer.item_changed(item);
+                    observer.wait(Duration::from_secs(options.retry_delay_seconds));
+                    if observer.is_cancelled() {
+                        item.state = QueueItemState::Failed;
+                        item.message = Some("Cancelled while waiting to retry".to_string());
+                        break;
+                    }
+                }
+                Ok(result) => {
+                    // Already gone is as good as removed
+                    let removed = result.outcome.succeeded() || result.outcome == ExitOutcome::NotInstalled;
+                    item.state = if removed { QueueItemState::Succeeded } else { QueueItemState::Failed };
+                    item.message = Some(result.message.clone());
+                    item.result = Some(result);
+                    break;
+                }
+                Err(e) => {
+                    item.state = QueueItemState::Failed;
+                    item.message = Some(e);
+                    break;
+                }
+            }
+        }
+        observer.item_changed(item);
+
+        if item.state == QueueItemState::Failed && options.failure_policy == FailurePolicy::Halt && !observer.is_cancelled() {
+            println!("DEBUG: Uninstall queue {} halted at {}", job_id, program.name);
+            report.halted = true;
+            break;
+        }
+    }
+
+    for item in report.items.iter_mut().filter(|item| item.state == QueueItemState::Pending) {
+        item.state = QueueItemState::NotRun;
+    }
+    let count = |state: QueueItemState| report.items.iter().filter(|item| item.state == state).count();
+    report.succeeded = count(QueueItemState::Succeeded);
+    report.failed = count(QueueItemState::Failed);
+    report.skipped = count(QueueItemState::Skipped);
+    report.not_run = count(QueueItemState::NotRun);
+    report.reboot_required = report.items.iter()
+        .filter_map(|item| item.result.as_ref())
+        .any(|result| matches!(result.outcome, ExitOutcome::RebootRequired | ExitOutcome::RebootInitiated));
+    report.finished_at = Some(Utc::now());
+    println!(
+        "DEBUG: Uninstall queue {} finished: {} succeeded, {} failed, {} skipped, {} not run",
+        job_id, report.succeeded, report.failed, report.skipped, report.not_run
+    );
+    report
+}
+
+fn new_report(job_id: &str, programs: &[ProgramInfo], failure_policy: FailurePolicy) -> UninstallReport {
+    UninstallReport {
+        job_id: job_id.to_string(),
+        started_at: Utc::now(),
+        finished_at: None,
+        failure_policy,
+        halted: false,
+        cancelled: false,
+        succeeded: 0,
+        failed: 0,
+        skipped: 0,
+        not_run: 0,
+        reboot_required: false,
+        items: programs.iter().enumerate()
+            .map(|(position, program)| QueueItemStatus {
+                job_id: job_id.to_string(),
+                position,
+                name: program.name.clone(),
+                registry_path: program.registry_path.clone(),
+                state: QueueItemState::Pending,
+                attempts: 0,
+                command: None,
+                result: None,
+                message: None,
+            })
+            .collect(),
+    }
+}
+
+impl UninstallQueue {
+    fn current_report(&self) -> UninstallReport {
+        match self.report.lock() {
+            Ok(report) => report.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+struct QueueJobObserver {
+    app: AppHandle,
+    queue: Arc<UninstallQueue>,
+}
+
+impl QueueObserver for QueueJobObserver {
+    fn item_changed(&self, item: &QueueItemStatus) {
+        if let Ok(mut report) = self.queue.report.lock() {
+            if let Some(current) = report.items.get_mut(item.position) {
+                *current = item.clone();
+            }
+        }
+        if let Err(e) = self.app.emit_all(UNINSTALL_QUEUE_ITEM_EVENT, item.clone()) {
+            println!("DEBUG: Failed to emit {}: {}", UNINSTALL_QUEUE_ITEM_EVENT, e);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.queue.cancelled.load(Ordering::SeqCst)
+    }
+
+    // A cancelled queue does not sit out the rest of a retry delay
+    fn wait(&self, duration: Duration) {
+        let step = Duration::from_millis(500);
+        let mut waited = Duration::ZERO;
+        while waited < duration && !self.is_cancelled() {
+            thread::sleep(step);
+            waited += step;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use crate::commands::uninstall::ExitOutcome;
+    use crate::services::installer_fingerprint::{Confidence, InstallerTechnology};
+    use crate::services::process_runner::FakeProcessRunner;
+
+    // Records what the queue reports and never sleeps; optionally cancels the queue while it waits to retry
+    #[derive(Default)]
+    struct RecordingObserver {
+        changes: RefCell<Vec<(usize, QueueItemState)>>,
+        waits: RefCell<Vec<Duration>>,
+        cancel_on_wait: bool,
+        cancelled: Cell<bool>,
+    }
+
+    impl QueueObserver for RecordingObserver {
+        fn item_changed(&self, item: &QueueItemStatus) {
+            self.changes.borrow_mut().push((item.position, item.state));
+        }
+
+        fn is_cancelled(&self) -> bool {
+            self.cancelled.get()
+        }
+
+        fn wait(&self, duration: Duration) {
+            self.waits.borrow_mut().push(duration);
+            if self.cancel_on_wait {
+                self.cancelled.set(true);
+            }
+        }
+    }
+
+    fn msi_product(name: &str, product_code: &str) -> ProgramInfo {
+        ProgramInfo::with_fields(name, serde_json::json!({
+            "registry_name": product_code,
+            "registry_path": format!("HKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{}", product_code),
+            "is_windows_installer": true,
+            "uninstall_string": format!("MsiExec.exe /I{}", product_code),
+        }))
+    }
+
+    // Its uninstaller looks like NSIS, too weakly to run a guessed silent switch unattended
+    fn interactive_only() -> ProgramInfo {
+        ProgramInfo::with_fields("Contoso Agent", serde_json::json!({
+            "uninstall_string": "\"C:\\Program Files\\Contoso\\uninstall.exe\"",
+        }))
+    }
+
+    fn options(failure_policy: FailurePolicy, max_retries: u32) -> QueueOptions {
+        QueueOptions { failure_policy, max_retries, retry_delay_seconds: 30, ..QueueOptions::default() }
+    }
+
+    fn states(report: &UninstallReport) -> Vec<QueueItemState> {
+        report.items.iter().map(|item| item.state).collect()
+    }
+
+    #[test]
+    fn retries_while_another_installation_is_running() {
+        let programs = vec![msi_product("Contoso Agent", "{11111111-2222-3333-4444-555555555555}")];
+        let runner = FakeProcessRunner::exiting_with(&[1618, 1618, 0]);
+        let observer = RecordingObserver::default();
+        let report = run_uninstall_queue("job", &programs, &options(FailurePolicy::Halt, 3), &runner, &observer);
+
+        let item = &report.items[0];
+        assert_eq!(item.state, QueueItemState::Succeeded);
+        assert_eq!(item.attempts, 3);
+        assert_eq!(item.command.as_deref(), Some("MsiExec.exe /X{11111111-2222-3333-4444-555555555555} /qn /norestart"));
+        assert_eq!(runner.command_lines().len(), 3);
+        assert_eq!(*observer.waits.borrow(), vec![Duration::from_secs(30); 2]);
+        assert_eq!(*observer.changes.borrow(), vec![
+            (0, QueueItemState::Running),
+            (0, QueueItemState::Retrying),
+            (0, QueueItemState::Running),
+            (0, QueueItemState::Retrying),
+            (0, QueueItemState::Running),
+            (0, QueueItemState::Succeeded),
+        ]);
+        assert_eq!((report.succeeded, report.failed, report.skipped, report.not_run), (1, 0, 0, 0));
+        assert!(report.finished_at.is_some() && !report.halted && !report.cancelled);
+
+        // Out of retries, the last 1618 is the result
+        let runner = FakeProcessRunner::exiting_with(&[1618, 1618]);
+        let report = run_uninstall_queue("job", &programs, &options(FailurePolicy::Continue, 1), &runner, &RecordingObserver::default());
+        assert_eq!(report.items[0].state, QueueItemState::Failed);
+        assert_eq!(report.items[0].attempts, 2);
+        assert_eq!(report.items[0].result.as_ref().map(|result| result.outcome), Some(ExitOutcome::InstallInProgress));
+    }
+
+    #[test]
+    fn halts_at_the_first_failure_only_when_asked_to() {
+        let programs = vec![
+            msi_product("Contoso Agent", "{11111111-2222-3333-4444-555555555555}"),
+            interactive_only(),
+            msi_product("Fabrikam Viewer", "{66666666-7777-8888-9999-AAAAAAAAAAAA}"),
+        ];
+
+        let runner = FakeProcessRunner::exiting_with(&[1603]);
+        let report = run_uninstall_queue("job", &programs, &options(FailurePolicy::Halt, 3), &runner, &RecordingObserver::default());
+        assert_eq!(states(&report), vec![QueueItemState::Failed, QueueItemState::NotRun, QueueItemState::NotRun]);
+        assert!(report.halted);
+        assert_eq!((report.succeeded, report.failed, report.skipped, report.not_run), (0, 1, 0, 2));
+        assert_eq!(runner.command_lines().len(), 1);
+
+        let runner = FakeProcessRunner::exiting_with(&[1603, 3010]);
+        let report = run_uninstall_queue("job", &programs, &options(FailurePolicy::Continue, 3), &runner, &RecordingObserver::default());
+        assert_eq!(states(&report), vec![QueueItemState::Failed, QueueItemState::Skipped, QueueItemState::Succeeded]);
+        assert!(!report.halted);
+        assert_eq!((report.succeeded, report.failed, report.skipped, report.not_run), (1, 1, 1, 0));
+        assert!(report.reboot_required);
+        assert_eq!(report.items[1].attempts, 0);
+        assert!(report.items[1].command.is_none());
+        assert!(report.items[1].message.as_deref().unwrap().starts_with("Contoso Agent has no QuietUninstallString"));
+    }
+
+    #[test]
+    fn stops_when_cancelled_during_a_retry_wait() {
+        let programs = vec![
+            msi_product("Contoso Agent", "{11111111-2222-3333-4444-555555555555}"),
+            msi_product("Fabrikam Viewer", "{66666666-7777-8888-9999-AAAAAAAAAAAA}"),
+        ];
+        let runner = FakeProcessRunner::exiting_with(&[1618, 0]);
+        let observer = RecordingObserver { cancel_on_wait: true, ..RecordingObserver::default() };
+        let report = run_uninstall_queue("job", &programs, &options(FailurePolicy::Halt, 3), &runner, &observer);
+
+        assert_eq!(states(&report), vec![QueueItemState::Failed, QueueItemState::NotRun]);
+        assert_eq!(report.items[0].message.as_deref(), Some("Cancelled while waiting to retry"));
+        assert_eq!(report.items[0].attempts, 1);
+        // Cancelling is not a failure that halts the queue
+        assert!(report.cancelled && !report.halted);
+        assert_eq!((report.succeeded, report.failed, report.skipped, report.not_run), (0, 1, 0, 1));
+        assert_eq!(runner.command_lines().len(), 1);
+    }
+
+    #[test]
+    fn orders_updates_and_bundles_before_applications_and_runtimes_last() {
+        let mut bundle = ProgramInfo::with_fields("Contoso Suite", serde_json::json!({}));
+        bundle.installer_technology = Some(InstallerTechnology::new(InstallerEngine::WixBurn, Confidence::High, "Package Cache"));
+        let programs = vec![
+            ProgramInfo::with_fields("Contoso Runtime", serde_json::json!({ "program_type": "SystemComponent" })),
+            ProgramInfo::with_fields("Contoso Agent", serde_json::json!({})),
+            bundle,
+            ProgramInfo::with_fields("Contoso Agent Hotfix", serde_json::json!({ "program_type": "Update" })),
+            ProgramInfo::with_fields("Fabrikam Viewer", serde_json::json!({})),
+        ];
+        assert_eq!(
+            names(&order_for_uninstall(programs)),
+            vec!["Contoso Agent Hotfix", "Contoso Suite", "Contoso Agent", "Fabrikam Viewer", "Contoso Runtime"],
+        );
+    }
+
+    fn inventory() -> Vec<ProgramInfo> {
+        vec![
+            ProgramInfo::with_fields("7-Zip 23.01 (x64)", serde_json::json!({ "version": "23.01" })),
+            ProgramInfo::with_fields("7-Zip 23.01", serde_json::json!({ "version": "23.01", "architecture": "32-bit", "registry_path": "HKEY_LOCAL_MACHINE\\SOFTWARE\\WOW6432Node\\7-Zip" })),
+            ProgramInfo::with_fields("Notepad++ (64-bit x64)", serde_json::json!({ "version": "8.6.2" })),
+            ProgramInfo::with_fields("Contoso Agent", serde_json::json!({ "registry_name": "{11111111-2222-3333-4444-555555555555}", "is_windows_installer": true })),
+        ]
+    }
+
+    fn names(programs: &[ProgramInfo]) -> Vec<&str> {
+        programs.iter().map(|program| program.name.as_str()).collect()
+    }
+
+    #[test]
+    fn resolves_names_and_product_codes() {
+        let entries = ["notepad++", "{11111111-2222-3333-4444-555555555555}", "Contoso Agent", "7-Zip 23.01", "Missing App", ""]
+            .map(str::to_string);
+        let resolution = resolve_entries(&entries, &inventory());
+        // The exact name wins over the fuzzy match with the x64 build
+        assert_eq!(names(&resolution.programs), vec!["Notepad++ (64-bit x64)", "Contoso Agent", "7-Zip 23.01"]);
+        assert_eq!(resolution.unresolved, vec!["Missing App".to_string()]);
+        assert!(resolution.ambiguous.is_empty());
+    }
+
+    #[test]
+    fn names_matching_several_programs_are_not_queued() {
+        let resolution = resolve_entries(&["7-Zip".to_string()], &inventory());
+        assert!(resolution.programs.is_empty());
+        assert!(resolution.unresolved.is_empty());
+        assert_eq!(resolution.ambiguous.len(), 1);
+        assert_eq!(resolution.ambiguous[0].entry, "7-Zip");
+        assert_eq!(names(&resolution.ambiguous[0].candidates), vec!["7-Zip 23.01 (x64)", "7-Zip 23.01"]);
+    }
+}
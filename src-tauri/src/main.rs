@@ -6,6 +6,17 @@ mod services;
 use commands::registry::*;
 use commands::export::*;
 use commands::cli::*;
+use commands::merge::*;
+use commands::health::*;
+use commands::scan_job::*;
+use commands::snapshots::*;
+use commands::uninstall::*;
+use commands::uninstall_queue::*;
+use commands::leftovers::*;
+use commands::install_size::*;
+use commands::executables::*;
+use commands::signature_trust::*;
+use commands::file_hashes::*;
 use commands::logs::*;
 use commands::icon_extraction::*;
 use commands::custom_icons::*;
@@ -14,13 +25,53 @@ use services::icon_extractor::IconExtractor;
 fn main() {
     tauri::Builder::default()
         .manage(IconExtractorState::new(IconExtractor::new()))
+        .manage(ScanJobState::default())
+        .manage(UninstallQueueState::default())
         .invoke_handler(tauri::generate_handler![
             get_system_info,
             open_winver,
             debug_windows_version,
             get_installed_programs,
+            get_installed_programs_from_fixture,
+            capture_registry_fixture,
+            import_reg_file,
+            filter_programs_by_install_date,
+            compare_versions,
+            filter_programs_by_version,
+            get_msi_product_families,
+            inspect_appx_manifest,
+            merge_programs,
+            check_entry_health,
+            filter_programs_by_health,
+            get_entry_health_report,
+            scan_offline_hives,
+            scan_external_drive,
             export_programs,
+            start_scan,
+            cancel_scan,
             get_scan_progress,
+            save_scan_snapshot,
+            list_snapshots,
+            get_snapshot,
+            delete_snapshot,
+            diff_snapshots,
+            analyze_uninstall_commands,
+            analyze_uninstall_commands_batch,
+            run_program_action,
+            resolve_uninstall_list,
+            start_uninstall_queue,
+            cancel_uninstall_queue,
+            get_uninstall_queue_report,
+            export_uninstall_report,
+            export_signature_trust_report,
+            capture_leftover_baseline,
+            find_leftovers,
+            list_leftover_baselines,
+            delete_leftover_baseline,
+            measure_install_sizes,
+            analyze_executables,
+            get_signature_trust_report,
+            hash_executables,
             debug_icon_paths,
             debug_vf_apps,
             debug_vf_icons_to_file,
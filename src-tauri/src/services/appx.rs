@@ -0,0 +1,451 @@
+// MSIX/AppX packages: where Windows records them and what their AppxManifest.xml says
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::services::registry_source::{RegistryHive, RegistrySource};
+use crate::services::user_profiles::is_sid;
+
+pub const APPX_ALL_USER_STORE_PATH: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Appx\\AppxAllUserStore";
+pub const MANIFEST_FILE_NAME: &str = "AppxManifest.xml";
+// Subkeys of AppxAllUserStore whose entries carry the manifest path
+const PACKAGE_PATH_STORES: [&str; 2] = ["Applications", "InboxApplications"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppxManifest {
+    pub name: String,                           // Identity Name, e.g. Microsoft.WindowsTerminal
+    pub publisher: String,                      // Identity Publisher, a certificate subject like CN=Microsoft Corporation, O=...
+    pub version: String,
+    pub architecture: String,                   // x64, x86, arm64, arm or neutral
+    pub resource_id: Option<String>,
+    pub display_name: String,                   // Falls back to the identity name when only an ms-resource: reference is given
+    pub publisher_display_name: Option<String>,
+    pub description: Option<String>,
+    pub logo: Option<String>,                   // Package-relative, e.g. Assets\StoreLogo.png
+    pub is_framework: bool,
+    pub is_resource_package: bool,
+}
+
+/// A package Windows has registered, with the users it is installed for
+#[derive(Debug, Clone)]
+pub struct AppxRegistration {
+    pub package_full_name: String,
+    pub install_location: Option<String>,
+    pub user_sids: Vec<String>,
+}
+
+/// Packages recorded under AppxAllUserStore: provisioned and inbox packages with their manifest path,
+/// and per-user installs listed under each user's SID
+pub fn registered_packages(registry: &dyn RegistrySource) -> Vec<AppxRegistration> {
+    let mut registrations: Vec<AppxRegistration> = Vec::new();
+    let store = match registry.open_key(RegistryHive::LocalMachine, APPX_ALL_USER_STORE_PATH) {
+        Some(store) => store,
+        None => return registrations,
+    };
+
+    for store_name in PACKAGE_PATH_STORES {
+        let packages = store.open_subkey(store_name);
+        if let Some(packages) = packages {
+            for package_full_name in packages.subkey_names() {
+                let install_location = packages.open_subkey(&package_full_name)
+                    .and_then(|package| package.get_string("Path"))
+                    .map(|path| package_folder(&path));
+                registration_entry(&mut registrations, &package_full_name).install_location = install_location;
+            }
+        }
+    }
+
+    for sid in store.subkey_names().into_iter().filter(|name| is_sid(name)) {
+        let packages = store.open_subkey(&sid);
+        if let Some(packages) = packages {
+            for package_full_name in packages.subkey_names() {
+                registration_entry(&mut registrations, &package_full_name).user_sids.push(sid.clone());
+            }
+        }
+    }
+
+    registrations
+}
+
+fn registration_entry<'a>(registrations: &'a mut Vec<AppxRegistration>, package_full_name: &str) -> &'a mut AppxRegistration {
+    let index = match registrations.iter().position(|r| r.package_full_name.eq_ignore_ascii_case(package_full_name)) {
+        Some(index) => index,
+        None => {
+            registrations.push(AppxRegistration {
+                package_full_name: package_full_name.to_string(),
+                install_location: None,
+                user_sids: Vec::new(),
+            });
+            registrations.len() - 1
+        }
+    };
+    &mut registrations[index]
+}
+
+// The Path value points at the manifest itself; the package is the folder holding it
+fn package_folder(manifest_path: &str) -> String {
+    let trimmed = manifest_path.trim().trim_matches('"');
+    if trimmed.to_ascii_lowercase().ends_with(&MANIFEST_FILE_NAME.to_ascii_lowercase()) {
+        trimmed[..trimmed.len() - MANIFEST_FILE_NAME.len()].trim_end_matches('\\').to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+pub fn load_appx_manifest(path: &Path) -> Result<AppxManifest, Box<dyn std::error::Error>> {
+    let bytes = fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(parse_appx_manifest(text.trim_start_matches('\u{feff}'))?)
+}
+
+/// Read the package identity and display properties out of an AppxManifest.xml
+pub fn parse_appx_manifest(xml: &str) -> Result<AppxManifest, String> {
+    let elements = parse_elements(xml)?;
+
+    let identity = elements.iter()
+        .find(|e| e.name == "Identity" && e.parent() == Some("Package"))
+        .ok_or_else(|| "Not a package manifest: no Package/Identity element".to_string())?;
+    let name = identity.attribute("Name")
+        .ok_or_else(|| "Package identity has no Name".to_string())?
+        .to_string();
+
+    let property = |property: &str| elements.iter()
+        .find(|e| e.name == property && e.parent() == Some("Properties"))
+        .map(|e| e.text.trim().to_string())
+        .filter(|text| !text.is_empty());
+    // The first application's tile data, used where Properties only holds a resource reference
+    let visual_elements = elements.iter().find(|e| e.name == "VisualElements");
+
+    let display_name = property("DisplayName")
+        .filter(|text| !is_resource_reference(text))
+        .or_else(|| visual_elements
+            .and_then(|e| e.attribute("DisplayName"))
+            .filter(|text| !is_resource_reference(text))
+            .map(|text| text.to_string()))
+        .unwrap_or_else(|| name.clone());
+    let publisher = identity.attribute("Publisher").unwrap_or("").to_string();
+    let publisher_display_name = property("PublisherDisplayName")
+        .filter(|text| !is_resource_reference(text))
+        .or_else(|| common_name(&publisher));
+    let description = property("Description")
+        .or_else(|| visual_elements.and_then(|e| e.attribute("Description")).map(|text| text.to_string()))
+        .filter(|text| !is_resource_reference(text));
+
+    Ok(AppxManifest {
+        name,
+        publisher,
+        version: identity.attribute("Version").unwrap_or("").to_string(),
+        architecture: identity.attribute("ProcessorArchitecture").unwrap_or("neutral").to_ascii_lowercase(),
+        resource_id: identity.attribute("ResourceId").map(|id| id.to_string()),
+        display_name,
+        publisher_display_name,
+        description,
+        logo: property("Logo")
+            .or_else(|| visual_elements.and_then(|e| e.attribute("Square44x44Logo")).map(|logo| logo.to_string())),
+        is_framework: property("Framework").map_or(false, |value| value.eq_ignore_ascii_case("true")),
+        is_resource_package: property("ResourcePackage").map_or(false, |value| value.eq_ignore_ascii_case("true")),
+    })
+}
+
+/// Find the file behind a manifest logo. Packages ship qualified variants (StoreLogo.scale-200.png,
+/// Square44x44Logo.targetsize-48.png) rather than the name the manifest uses; the largest plain variant wins
+pub fn resolve_logo(package_dir: &Path, logo: &str) -> Option<PathBuf> {
+    let relative = logo.replace('\\', "/");
+    let exact = package_dir.join(&relative);
+    if exact.is_file() {
+        return Some(exact);
+    }
+
+    let folder = exact.parent()?;
+    let stem = Path::new(&relative).file_stem()?.to_string_lossy().to_ascii_lowercase();
+    let extension = Path::new(&relative).extension()?.to_string_lossy().to_ascii_lowercase();
+
+    let mut best: Option<(u32, PathBuf)> = None;
+    for entry in fs::read_dir(folder).ok()?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+        let qualifiers = match file_name
+            .strip_prefix(&format!("{}.", stem))
+            .and_then(|rest| rest.strip_suffix(&format!(".{}", extension)))
+        {
+            Some(qualifiers) => qualifiers,
+            None => continue,
+        };
+        // High-contrast and unplated variants are meant for special backgrounds
+        if qualifiers.contains("contrast-") || qualifiers.contains("altform-") {
+            continue;
+        }
+        let size = qualifiers.split('_')
+            .filter_map(|qualifier| qualifier.strip_prefix("scale-").or_else(|| qualifier.strip_prefix("targetsize-")))
+            .filter_map(|size| size.parse::<u32>().ok())
+            .max()
+            .unwrap_or(0);
+        if best.as_ref().map_or(true, |(best_size, _)| size > *best_size) {
+            best = Some((size, entry.path()));
+        }
+    }
+
+    best.map(|(_, path)| path)
+}
+
+fn is_resource_reference(text: &str) -> bool {
+    text.trim_start().to_ascii_lowercase().starts_with("ms-resource:")
+}
+
+// "CN=Microsoft Corporation, O=Microsoft Corporation, ..." -> "Microsoft Corporation"
+fn common_name(subject: &str) -> Option<String> {
+    subject.split(',')
+        .map(str::trim)
+        .find_map(|part| part.strip_prefix("CN="))
+        .map(|name| name.trim_matches('"').to_string())
+        .filter(|name| !name.is_empty())
+}
+
+// Just enough XML for manifests: elements with their attributes and direct text, namespace prefixes dropped
+struct XmlElement {
+    name: String,
+    ancestors: Vec<String>,
+    attributes: Vec<(String, String)>,
+    text: String,
+}
+
+impl XmlElement {
+    fn parent(&self) -> Option<&str> {
+        self.ancestors.last().map(|name| name.as_str())
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn parse_elements(xml: &str) -> Result<Vec<XmlElement>, String> {
+    let mut elements: Vec<XmlElement> = Vec::new();
+    let mut open: Vec<usize> = Vec::new(); // Indexes of the elements not yet closed
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        if let Some(&current) = open.last() {
+            elements[current].text.push_str(&unescape(&rest[..start]));
+        }
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = &after[after.find("-->").ok_or("Unterminated comment")? + 3..];
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").ok_or("Unterminated CDATA section")?;
+            if let Some(&current) = open.last() {
+                elements[current].text.push_str(&after[..end]);
+            }
+            rest = &after[end + 3..];
+            continue;
+        }
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = &rest[rest.find('>').ok_or("Unterminated declaration")? + 1..];
+            continue;
+        }
+
+        let end = tag_end(rest).ok_or("Unterminated tag")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if let Some(closing) = tag.strip_prefix('/') {
+            let name = local_name(closing.trim());
+            match open.iter().rposition(|&index| elements[index].name == name) {
+                Some(position) => open.truncate(position),
+                None => return Err(format!("Unexpected closing tag </{}>", closing.trim())),
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+        elements.push(XmlElement {
+            name: local_name(&tag[..name_end]),
+            ancestors: open.iter().map(|&index| elements[index].name.clone()).collect(),
+            attributes: parse_attributes(&tag[name_end..]),
+            text: String::new(),
+        });
+        if !self_closing {
+            open.push(elements.len() - 1);
+        }
+    }
+
+    Ok(elements)
+}
+
+// The closing '>' of a tag, skipping any inside quoted attribute values
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attributes(mut text: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    loop {
+        text = text.trim_start();
+        let equals = match text.find('=') {
+            Some(equals) => equals,
+            None => break,
+        };
+        let name = local_name(text[..equals].trim());
+        let value_text = text[equals + 1..].trim_start();
+        let quote = match value_text.chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => break,
+        };
+        let value_end = match value_text[1..].find(quote) {
+            Some(value_end) => value_end + 1,
+            None => break,
+        };
+        attributes.push((name, unescape(&value_text[1..value_end])));
+        text = &value_text[value_end + 1..];
+    }
+    attributes
+}
+
+fn local_name(name: &str) -> String {
+    name.rsplit(':').next().unwrap_or(name).to_string()
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|decimal| decimal.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_dir(package_full_name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("appx").join(package_full_name)
+    }
+
+    fn fixture(package_full_name: &str) -> AppxManifest {
+        load_appx_manifest(&fixture_dir(package_full_name).join(MANIFEST_FILE_NAME)).unwrap()
+    }
+
+    #[test]
+    fn reads_an_application_package() {
+        let manifest = fixture("Microsoft.WindowsTerminal_1.18.3181.0_x64__8wekyb3d8bbwe");
+        assert_eq!(manifest.name, "Microsoft.WindowsTerminal");
+        assert_eq!(manifest.display_name, "Windows Terminal");
+        assert_eq!(manifest.version, "1.18.3181.0");
+        assert_eq!(manifest.architecture, "x64");
+        assert_eq!(manifest.publisher_display_name.as_deref(), Some("Microsoft Corporation"));
+        assert_eq!(manifest.description.as_deref(), Some("The new Windows Terminal & console host"));
+        assert_eq!(manifest.logo.as_deref(), Some("Images\\StoreLogo.png"));
+        assert_eq!(manifest.resource_id, None);
+        assert!(!manifest.is_framework);
+        assert!(!manifest.is_resource_package);
+    }
+
+    #[test]
+    fn reads_a_framework_package() {
+        let manifest = fixture("Microsoft.VCLibs.140.00_14.0.33519.0_x64__8wekyb3d8bbwe");
+        assert_eq!(manifest.display_name, "Microsoft Visual C++ 2015 UWP Runtime Package");
+        assert_eq!(manifest.publisher_display_name.as_deref(), Some("Microsoft Platform Extensions"));
+        assert!(manifest.is_framework);
+        assert!(!manifest.is_resource_package);
+    }
+
+    #[test]
+    fn reads_a_resource_package() {
+        let manifest = fixture("Microsoft.WindowsTerminal_1.18.3181.0_neutral_split.scale-200_8wekyb3d8bbwe");
+        assert!(manifest.is_resource_package);
+        assert_eq!(manifest.resource_id.as_deref(), Some("split.scale-200"));
+        // No ProcessorArchitecture attribute, and only a resource reference for a name
+        assert_eq!(manifest.architecture, "neutral");
+        assert_eq!(manifest.display_name, "Microsoft.WindowsTerminal");
+    }
+
+    #[test]
+    fn resource_references_fall_back_to_identity_names() {
+        // Saved with a byte order mark, as Visual Studio writes them
+        let manifest = fixture("Microsoft.WindowsCalculator_11.2307.4.0_x64__8wekyb3d8bbwe");
+        assert_eq!(manifest.display_name, "Microsoft.WindowsCalculator");
+        assert_eq!(manifest.publisher_display_name.as_deref(), Some("Microsoft Corporation"));
+        assert_eq!(manifest.description, None);
+        assert_eq!(manifest.logo.as_deref(), Some("Assets\\CalculatorStoreLogo.png"));
+    }
+
+    #[test]
+    fn visual_elements_name_a_package_whose_properties_do_not() {
+        let manifest = parse_appx_manifest(r#"<Package>
+            <Identity Name="Contoso.App" Publisher="CN=Contoso" Version="1.0.0.0" ProcessorArchitecture="ARM64"/>
+            <Properties><DisplayName>ms-resource:Name</DisplayName></Properties>
+            <Applications><Application><uap:VisualElements DisplayName="Contoso &#x26; Co" Square44x44Logo="Assets\App.png"/></Application></Applications>
+        </Package>"#).unwrap();
+        assert_eq!(manifest.display_name, "Contoso & Co");
+        assert_eq!(manifest.architecture, "arm64");
+        assert_eq!(manifest.logo.as_deref(), Some("Assets\\App.png"));
+    }
+
+    #[test]
+    fn rejects_documents_that_are_not_manifests() {
+        assert!(parse_appx_manifest("<Package><Properties/></Package>").is_err());
+        assert!(parse_appx_manifest("<Package><Identity Name=\"A\"/></Properties>").is_err());
+        assert!(parse_appx_manifest("<Package><Identity Name=\"A\"").is_err());
+    }
+
+    #[test]
+    fn picks_the_largest_plain_logo_variant() {
+        let package_dir = fixture_dir("Microsoft.WindowsTerminal_1.18.3181.0_x64__8wekyb3d8bbwe");
+        let logo = resolve_logo(&package_dir, "Images\\StoreLogo.png").unwrap();
+        assert_eq!(logo.file_name().unwrap(), "StoreLogo.scale-200.png");
+        assert_eq!(resolve_logo(&package_dir, "Images\\Missing.png"), None);
+    }
+
+    #[test]
+    fn package_folder_drops_the_manifest_file_name() {
+        assert_eq!(
+            package_folder("\"C:\\Program Files\\WindowsApps\\Contoso.App_1.0.0.0_x64__abc\\AppxManifest.xml\""),
+            "C:\\Program Files\\WindowsApps\\Contoso.App_1.0.0.0_x64__abc"
+        );
+        assert_eq!(package_folder("C:\\Packages\\Contoso"), "C:\\Packages\\Contoso");
+    }
+}
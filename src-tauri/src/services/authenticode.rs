@@ -0,0 +1,412 @@
+// Authenticode signatures as stored in a PE image's certificate table: a PKCS#7 SignedData whose signer
+// certificate names the publisher, with the timestamp countersignature and any nested signatures in its
+// unauthenticated attributes. Reading what a signature claims is done here; checking it against the
+// image and trusted roots is signature_verification's job
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::services::der::{self, context, Element, TAG_BIT_STRING, TAG_INTEGER, TAG_OCTET_STRING, TAG_SEQUENCE, TAG_SET};
+use crate::services::pe::PeImage;
+
+pub const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 2;
+
+const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+const OID_SIGNING_TIME: &str = "1.2.840.113549.1.9.5";
+pub const OID_MESSAGE_DIGEST: &str = "1.2.840.113549.1.9.4";
+pub const OID_COUNTERSIGNATURE: &str = "1.2.840.113549.1.9.6";
+pub const OID_TST_INFO: &str = "1.2.840.113549.1.9.16.1.4";
+pub const OID_RFC3161_TIMESTAMP: &str = "1.3.6.1.4.1.311.3.3.1";
+const OID_NESTED_SIGNATURE: &str = "1.3.6.1.4.1.311.2.4.1";
+// Dual-signed files nest one level; anything deeper is not worth following
+const MAX_NESTING: usize = 4;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampKind {
+    Rfc3161,      // Timestamp token from a RFC 3161 authority
+    Authenticode, // Legacy PKCS#9 countersignature
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SignatureInfo {
+    pub signer_subject: String,           // "CN=..., O=..., C=..."
+    pub signer_name: Option<String>,      // CN of the signer, which is what Windows shows as the publisher
+    pub issuer: String,
+    pub serial_number: String,
+    pub digest_algorithm: String,         // Algorithm of the image digest, e.g. "SHA-256"
+    pub valid_from: Option<DateTime<Utc>>, // Validity of the signer certificate
+    pub valid_to: Option<DateTime<Utc>>,
+    pub timestamp: Option<DateTime<Utc>>, // When the signature was made, as vouched for by a timestamp
+    pub timestamp_kind: Option<TimestampKind>,
+    pub timestamp_authority: Option<String>, // CN of the timestamp signer
+    pub certificate_count: usize,         // Certificates shipped with the signature, chain included
+    pub nested: Vec<SignatureInfo>,       // Further signatures, e.g. SHA-256 next to a SHA-1 one
+}
+
+/// The signature of an image, if it has one. An image can carry several certificate entries, but
+/// Windows only looks at the first PKCS#7 one; further signatures are nested inside it
+pub fn read_signature(image: &PeImage) -> Option<Result<SignatureInfo, String>> {
+    let entry = image.certificate_entries()
+        .into_iter()
+        .find(|entry| entry.certificate_type == WIN_CERT_TYPE_PKCS_SIGNED_DATA)?;
+    Some(parse_signature(entry.data))
+}
+
+/// Parse a PKCS#7 ContentInfo holding an Authenticode SignedData
+pub fn parse_signature(pkcs7: &[u8]) -> Result<SignatureInfo, String> {
+    parse_signature_at(pkcs7, 0)
+}
+
+fn parse_signature_at(pkcs7: &[u8], depth: usize) -> Result<SignatureInfo, String> {
+    let signed_data = parse_signed_data(pkcs7).ok_or("Malformed PKCS#7 signature")?;
+    let signer = signed_data.signer_infos.first().ok_or("The signature has no signer")?;
+    let certificate = signed_data.certificate_of(signer);
+
+    let mut info = SignatureInfo {
+        signer_subject: String::new(),
+        signer_name: None,
+        issuer: signer.issuer.map(|issuer| der::format_name(&der::name_attributes(&issuer))).unwrap_or_default(),
+        serial_number: signer.serial.map(|serial| serial.integer_hex()).unwrap_or_default(),
+        digest_algorithm: digest_name(&signer.digest_algorithm),
+        valid_from: None,
+        valid_to: None,
+        timestamp: None,
+        timestamp_kind: None,
+        timestamp_authority: None,
+        certificate_count: signed_data.certificates.len(),
+        nested: Vec::new(),
+    };
+    if let Some(certificate) = &certificate {
+        let subject = der::name_attributes(&certificate.subject);
+        info.signer_subject = der::format_name(&subject);
+        info.signer_name = common_name(&subject);
+        info.valid_from = certificate.not_before;
+        info.valid_to = certificate.not_after;
+    }
+
+    for (oid, values) in &signer.unauthenticated_attributes {
+        match oid.as_str() {
+            OID_COUNTERSIGNATURE if info.timestamp.is_none() => {
+                // A SignerInfo over our signature; its signing time is the timestamp
+                if let Some(countersigner) = values.first().and_then(|value| parse_signer_info(value)) {
+                    info.timestamp = countersigner.signing_time();
+                    info.timestamp_kind = Some(TimestampKind::Authenticode);
+                    info.timestamp_authority = signed_data.certificate_of(&countersigner)
+                        .and_then(|certificate| common_name(&der::name_attributes(&certificate.subject)));
+                }
+            }
+            OID_RFC3161_TIMESTAMP if info.timestamp.is_none() => {
+                if let Some((time, authority)) = values.first().and_then(|value| parse_timestamp_token(value.raw)) {
+                    info.timestamp = Some(time);
+                    info.timestamp_kind = Some(TimestampKind::Rfc3161);
+                    info.timestamp_authority = authority;
+                }
+            }
+            OID_NESTED_SIGNATURE if depth < MAX_NESTING => {
+                for value in values {
+                    match parse_signature_at(value.raw, depth + 1) {
+                        Ok(nested) => info.nested.push(nested),
+                        Err(e) => println!("DEBUG: Skipping a nested signature: {}", e),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+/// The parts of a PKCS#7 SignedData that are read here
+pub struct SignedData<'a> {
+    pub certificates: Vec<Certificate<'a>>,
+    pub signer_infos: Vec<SignerInfo<'a>>,
+    pub content_type: Option<String>,
+    pub content: Option<Element<'a>>, // The encapsulated content, [0] EXPLICIT unwrapped
+}
+
+impl<'a> SignedData<'a> {
+    /// The certificate a signer names by issuer and serial number
+    pub fn certificate_of(&self, signer: &SignerInfo) -> Option<&Certificate<'a>> {
+        let (issuer, serial) = (signer.issuer?, signer.serial?);
+        self.certificates.iter().find(|certificate| certificate.issuer.raw == issuer.raw && certificate.serial.content == serial.content)
+    }
+}
+
+pub struct Certificate<'a> {
+    pub raw: &'a [u8],
+    pub tbs: &'a [u8],                 // The signed part, as encoded
+    pub serial: Element<'a>,
+    pub issuer: Element<'a>,
+    pub subject: Element<'a>,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+    pub public_key: Element<'a>,       // SubjectPublicKeyInfo
+    pub extensions: Vec<(String, &'a [u8])>, // OID and the DER inside the extension's OCTET STRING
+    pub signature_algorithm: String,
+    pub signature: &'a [u8],
+}
+
+impl<'a> Certificate<'a> {
+    pub fn extension(&self, oid: &str) -> Option<&'a [u8]> {
+        self.extensions.iter().find(|(id, _)| id == oid).map(|(_, value)| *value)
+    }
+
+    pub fn subject_name(&self) -> String {
+        der::format_name(&der::name_attributes(&self.subject))
+    }
+
+    pub fn is_valid_at(&self, time: DateTime<Utc>) -> bool {
+        self.not_before.map_or(true, |from| time >= from) && self.not_after.map_or(true, |to| time <= to)
+    }
+}
+
+pub struct SignerInfo<'a> {
+    pub issuer: Option<Element<'a>>, // Both None when the signer is named by subject key identifier
+    pub serial: Option<Element<'a>>,
+    pub digest_algorithm: String,
+    pub authenticated_attributes_raw: Option<Element<'a>>, // As encoded, for checking the signature over them
+    pub authenticated_attributes: Vec<(String, Vec<Element<'a>>)>,
+    pub signature_algorithm: String,
+    pub signature: &'a [u8],
+    pub unauthenticated_attributes: Vec<(String, Vec<Element<'a>>)>,
+}
+
+impl<'a> SignerInfo<'a> {
+    /// The first value of an authenticated attribute
+    pub fn authenticated(&self, oid: &str) -> Option<Element<'a>> {
+        self.authenticated_attributes.iter()
+            .find(|(id, _)| id == oid)
+            .and_then(|(_, values)| values.first().copied())
+    }
+
+    pub fn signing_time(&self) -> Option<DateTime<Utc>> {
+        self.authenticated(OID_SIGNING_TIME).and_then(|value| value.time())
+    }
+}
+
+pub fn parse_signed_data(pkcs7: &[u8]) -> Option<SignedData<'_>> {
+    let content_info = der::parse(pkcs7).filter(|element| element.is(TAG_SEQUENCE))?;
+    let mut parts = content_info.children();
+    if parts.next()?.oid()? != OID_SIGNED_DATA {
+        return None;
+    }
+    let signed_data = parts.next().filter(|element| element.is(context(0)))?.inner()?;
+
+    let mut fields = signed_data.children();
+    let _version = fields.next().filter(|element| element.is(TAG_INTEGER))?;
+    let _digest_algorithms = fields.next().filter(|element| element.is(TAG_SET))?;
+    let encapsulated = fields.next().filter(|element| element.is(TAG_SEQUENCE))?;
+    let content_type = encapsulated.inner().and_then(|element| element.oid());
+    let content = encapsulated.children()
+        .nth(1)
+        .filter(|element| element.is(context(0)))
+        .and_then(|element| element.inner());
+
+    let mut certificates = Vec::new();
+    let mut signer_infos = Vec::new();
+    for field in fields {
+        match field.tag {
+            // [0] IMPLICIT certificates; attribute certificates and other choices are skipped
+            tag if tag == context(0) => certificates.extend(field.children().filter_map(|element| parse_certificate(&element))),
+            // [1] IMPLICIT CRLs, not needed
+            tag if tag == context(1) => {}
+            TAG_SET => signer_infos.extend(field.children().filter_map(|element| parse_signer_info(&element))),
+            _ => {}
+        }
+    }
+    Some(SignedData { certificates, signer_infos, content_type, content })
+}
+
+pub fn parse_certificate<'a>(element: &Element<'a>) -> Option<Certificate<'a>> {
+    let mut parts = element.children();
+    let tbs = parts.next().filter(|tbs| tbs.is(TAG_SEQUENCE))?;
+    let signature_algorithm = parts.next()?.inner()?.oid()?;
+    // BIT STRING content starts with the count of unused bits, always 0 here
+    let signature = parts.next().filter(|element| element.is(TAG_BIT_STRING))?.content.get(1..)?;
+
+    let mut fields = tbs.children().peekable();
+    // The version is optional, defaulting to v1
+    if fields.peek()?.is(context(0)) {
+        fields.next();
+    }
+    let serial = fields.next().filter(|element| element.is(TAG_INTEGER))?;
+    let _signature_algorithm = fields.next()?;
+    let issuer = fields.next().filter(|element| element.is(TAG_SEQUENCE))?;
+    let validity = fields.next().filter(|element| element.is(TAG_SEQUENCE))?;
+    let subject = fields.next().filter(|element| element.is(TAG_SEQUENCE))?;
+    let public_key = fields.next().filter(|element| element.is(TAG_SEQUENCE))?;
+    // Unique identifiers [1] and [2] may come before the [3] extensions
+    let extensions = fields.find(|element| element.is(context(3)))
+        .and_then(|element| element.inner())
+        .map(|list| list.children()
+            .filter_map(|extension| {
+                let mut parts = extension.children();
+                let oid = parts.next()?.oid()?;
+                // critical is a BOOLEAN defaulting to false, so may be left out
+                let value = parts.find(|element| element.is(TAG_OCTET_STRING))?;
+                Some((oid, value.content))
+            })
+            .collect())
+        .unwrap_or_default();
+
+    let mut times = validity.children();
+    Some(Certificate {
+        raw: element.raw,
+        tbs: tbs.raw,
+        serial,
+        issuer,
+        subject,
+        not_before: times.next().and_then(|time| time.time()),
+        not_after: times.next().and_then(|time| time.time()),
+        public_key,
+        extensions,
+        signature_algorithm,
+        signature,
+    })
+}
+
+pub fn parse_signer_info<'a>(element: &Element<'a>) -> Option<SignerInfo<'a>> {
+    let mut fields = element.children().peekable();
+    let _version = fields.next().filter(|element| element.is(TAG_INTEGER))?;
+    let identifier = fields.next()?;
+    // IssuerAndSerialNumber, or [0] SubjectKeyIdentifier in version 3
+    let (issuer, serial) = if identifier.is(TAG_SEQUENCE) {
+        let mut parts = identifier.children();
+        (parts.next(), parts.next().filter(|element| element.is(TAG_INTEGER)))
+    } else {
+        (None, None)
+    };
+    let digest_algorithm = fields.next()?.inner()?.oid()?;
+
+    let mut authenticated_attributes_raw = None;
+    let mut authenticated_attributes = Vec::new();
+    if fields.peek()?.is(context(0)) {
+        let attributes = fields.next()?;
+        authenticated_attributes = parse_attributes(&attributes);
+        authenticated_attributes_raw = Some(attributes);
+    }
+    let signature_algorithm = fields.next()?.inner()?.oid()?;
+    let signature = fields.next().filter(|element| element.is(TAG_OCTET_STRING))?.content;
+    let unauthenticated_attributes = fields.next()
+        .filter(|element| element.is(context(1)))
+        .map(|element| parse_attributes(&element))
+        .unwrap_or_default();
+
+    Some(SignerInfo {
+        issuer,
+        serial,
+        digest_algorithm,
+        authenticated_attributes_raw,
+        authenticated_attributes,
+        signature_algorithm,
+        signature,
+        unauthenticated_attributes,
+    })
+}
+
+// Attributes are a SET of SEQUENCE { type, SET OF value }
+fn parse_attributes<'a>(element: &Element<'a>) -> Vec<(String, Vec<Element<'a>>)> {
+    element.children()
+        .filter_map(|attribute| {
+            let mut parts = attribute.children();
+            let oid = parts.next()?.oid()?;
+            let values = parts.next().filter(|element| element.is(TAG_SET))?.children().collect();
+            Some((oid, values))
+        })
+        .collect()
+}
+
+/// The TSTInfo of a RFC 3161 timestamp token: the OCTET STRING holding it, as its signature covers that
+pub fn tst_info<'a>(token: &SignedData<'a>) -> Option<Element<'a>> {
+    if token.content_type.as_deref() != Some(OID_TST_INFO) {
+        return None;
+    }
+    token.content.filter(|element| element.is(TAG_OCTET_STRING))
+}
+
+// A RFC 3161 timestamp token is a SignedData of its own, whose content is the TSTInfo with the time
+fn parse_timestamp_token(token: &[u8]) -> Option<(DateTime<Utc>, Option<String>)> {
+    let signed_data = parse_signed_data(token)?;
+    let tst_info = der::parse(tst_info(&signed_data)?.content).filter(|element| element.is(TAG_SEQUENCE))?;
+    // version, policy, messageImprint, serialNumber, genTime
+    let time = tst_info.children().nth(4)?.time()?;
+    let authority = signed_data.signer_infos.first()
+        .and_then(|signer| signed_data.certificate_of(signer))
+        .and_then(|certificate| common_name(&der::name_attributes(&certificate.subject)));
+    Some((time, authority))
+}
+
+pub fn common_name(attributes: &[(String, String)]) -> Option<String> {
+    // The most specific CN comes last in encoding order
+    attributes.iter().rev().find(|(name, _)| name == "CN").map(|(_, value)| value.clone())
+}
+
+/// Display name of a digest algorithm OID
+pub fn digest_name(oid: &str) -> String {
+    match oid {
+        "1.2.840.113549.2.5" => "MD5",
+        "1.3.14.3.2.26" => "SHA-1",
+        "2.16.840.1.101.3.4.2.1" => "SHA-256",
+        "2.16.840.1.101.3.4.2.2" => "SHA-384",
+        "2.16.840.1.101.3.4.2.3" => "SHA-512",
+        other => return other.to_string(),
+    }
+    .to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/signatures").join(name)).unwrap()
+    }
+
+    fn pkcs7(bytes: &[u8]) -> Vec<u8> {
+        let image = PeImage::parse(bytes).unwrap();
+        let entries = image.certificate_entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].certificate_type, WIN_CERT_TYPE_PKCS_SIGNED_DATA);
+        entries[0].data.to_vec()
+    }
+
+    #[test]
+    fn reads_the_signed_data() {
+        let bytes = fixture("signed.exe");
+        let pkcs7 = pkcs7(&bytes);
+        let signed_data = parse_signed_data(&pkcs7).unwrap();
+        assert_eq!(signed_data.content_type.as_deref(), Some("1.3.6.1.4.1.311.2.1.4"));
+        assert!(signed_data.content.map_or(false, |content| content.is(TAG_SEQUENCE)));
+        assert_eq!(signed_data.certificates.len(), 2);
+        assert_eq!(signed_data.signer_infos.len(), 1);
+
+        let signer = &signed_data.signer_infos[0];
+        assert_eq!(digest_name(&signer.digest_algorithm), "SHA-256");
+        assert!(signer.authenticated(OID_MESSAGE_DIGEST).is_some());
+        assert!(signer.unauthenticated_attributes.is_empty());
+        let certificate = signed_data.certificate_of(signer).unwrap();
+        assert_eq!(certificate.subject_name(), "CN=Contoso Test Publisher, O=Contoso Test");
+        assert_eq!(certificate.not_before.map(|time| time.to_rfc3339()).as_deref(), Some("2020-01-01T00:00:00+00:00"));
+        assert_eq!(certificate.not_after.map(|time| time.to_rfc3339()).as_deref(), Some("2030-01-01T00:00:00+00:00"));
+        assert!(certificate.extension("2.5.29.37").is_some());
+    }
+
+    #[test]
+    fn reads_the_timestamp_token() {
+        let info = parse_signature(&pkcs7(&fixture("timestamped.exe"))).unwrap();
+        assert_eq!(info.signer_name.as_deref(), Some("Contoso Test Publisher"));
+        assert_eq!(info.timestamp_kind, Some(TimestampKind::Rfc3161));
+        assert_eq!(info.timestamp.map(|time| time.to_rfc3339()).as_deref(), Some("2024-03-22T00:00:00+00:00"));
+        assert_eq!(info.timestamp_authority.as_deref(), Some("Contoso Test Timestamps"));
+        assert!(info.nested.is_empty());
+    }
+
+    #[test]
+    fn rejects_what_is_not_signed_data() {
+        let pkcs7 = pkcs7(&fixture("signed.exe"));
+        assert!(parse_signature(&pkcs7[..pkcs7.len() / 2]).is_err());
+        assert!(parse_signature(&[0x30, 0x03, 0x06, 0x01, 0x2A]).is_err());
+        assert!(parse_signature(b"not a signature").is_err());
+    }
+}
@@ -0,0 +1,619 @@
+// Uninstall and modify command lines: splitting them the way Windows does, recognising msiexec and
+// rundll32 invocations, and proposing a silent uninstall from the installer engine that wrote them
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::services::installer_fingerprint::{is_chocolatey_path, Confidence, InstallerEngine};
+use crate::services::msi::format_guid;
+
+const EXECUTABLE_EXTENSIONS: [&str; 4] = [".exe", ".com", ".bat", ".cmd"];
+const REINSTALL_MODES: &str = "pocemsuav";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKind {
+    Executable,
+    Msiexec,
+    Rundll32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MsiAction {
+    Install,   // /I or /package; on an installed product this opens maintenance mode
+    Uninstall, // /X or /uninstall
+    Repair,    // /f with its reinstall mode letters
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParsedCommandLine {
+    pub raw: String,
+    pub executable: String,
+    pub arguments: Vec<String>,
+    pub argument_string: String,       // Arguments as written, with their original quoting
+    pub kind: CommandKind,
+    pub msi_action: Option<MsiAction>,
+    pub product_code: Option<String>,  // msiexec target when it is a product code
+    pub msi_package: Option<String>,   // msiexec target when it is a package path
+    pub dll_path: Option<String>,      // rundll32 target
+    pub entry_point: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SilentCommandOrigin {
+    QuietUninstallString, // Written by the installer itself
+    Synthesized,          // Built from the detected engine's documented switches
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SilentUninstall {
+    pub command: String,
+    pub engine: InstallerEngine,
+    pub confidence: Confidence,
+    pub origin: SilentCommandOrigin,
+    pub note: Option<String>,
+}
+
+/// Split a command line as CreateProcess would, probing the file system for unquoted paths
+/// that contain spaces and have no extension
+pub fn parse_command_line(raw: &str) -> Option<ParsedCommandLine> {
+    parse_command_line_with(raw, &|path| path.is_file())
+}
+
+/// `parse_command_line` with the file-existence check supplied, for entries from another machine
+pub fn parse_command_line_with(raw: &str, file_exists: &dyn Fn(&Path) -> bool) -> Option<ParsedCommandLine> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let (executable, argument_string) = split_executable(trimmed, file_exists);
+    let mut parsed = ParsedCommandLine {
+        raw: raw.to_string(),
+        arguments: split_arguments(argument_string),
+        argument_string: argument_string.trim().to_string(),
+        kind: CommandKind::Executable,
+        executable,
+        msi_action: None,
+        product_code: None,
+        msi_package: None,
+        dll_path: None,
+        entry_point: None,
+    };
+
+    match file_stem(&parsed.executable).as_str() {
+        "msiexec" => {
+            parsed.kind = CommandKind::Msiexec;
+            parse_msiexec_arguments(&mut parsed);
+        }
+        "rundll32" => {
+            parsed.kind = CommandKind::Rundll32;
+            parse_rundll32_arguments(&mut parsed);
+        }
+        _ => {}
+    }
+
+    Some(parsed)
+}
+
+// A quoted program name ends at the closing quote. An unquoted one is ambiguous: CreateProcess tries
+// each space-delimited prefix in turn, so "C:\Program Files\Foo\uninst.exe /S" runs the first prefix
+// that names a file. Offline the extension stands in for that test
+fn split_executable<'a>(command: &'a str, file_exists: &dyn Fn(&Path) -> bool) -> (String, &'a str) {
+    if let Some(rest) = command.strip_prefix('"') {
+        return match rest.find('"') {
+            Some(end) => (rest[..end].to_string(), &rest[end + 1..]),
+            None => (rest.to_string(), ""),
+        };
+    }
+
+    let boundaries: Vec<usize> = command.char_indices()
+        .filter(|(_, c)| c.is_whitespace())
+        .map(|(i, _)| i)
+        .chain(std::iter::once(command.len()))
+        .collect();
+
+    for &end in &boundaries {
+        if has_executable_extension(&command[..end]) {
+            return (command[..end].to_string(), &command[end..]);
+        }
+    }
+    for &end in &boundaries {
+        let candidate = &command[..end];
+        if file_exists(Path::new(candidate)) || file_exists(Path::new(&format!("{}.exe", candidate))) {
+            return (candidate.to_string(), &command[end..]);
+        }
+    }
+
+    let end = boundaries[0];
+    (command[..end].to_string(), &command[end..])
+}
+
+/// Split an argument string with the CommandLineToArgvW rules: whitespace separates arguments outside
+/// quotes, backslashes only escape when they precede a quote, and "" inside quotes is a literal quote
+pub fn split_arguments(arguments: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut in_quotes = false;
+    let mut backslashes = 0;
+    let mut chars = arguments.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                backslashes += 1;
+                in_argument = true;
+            }
+            '"' => {
+                current.extend(std::iter::repeat('\\').take(backslashes / 2));
+                if backslashes % 2 == 1 {
+                    current.push('"');
+                } else if in_quotes && chars.peek() == Some(&'"') {
+                    current.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = !in_quotes;
+                }
+                backslashes = 0;
+                in_argument = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                current.extend(std::iter::repeat('\\').take(backslashes));
+                backslashes = 0;
+                if in_argument {
+                    result.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            }
+            c => {
+                current.extend(std::iter::repeat('\\').take(backslashes));
+                backslashes = 0;
+                current.push(c);
+                in_argument = true;
+            }
+        }
+    }
+    current.extend(std::iter::repeat('\\').take(backslashes));
+    if in_argument {
+        result.push(current);
+    }
+    result
+}
+
+// msiexec takes its target either glued to the option (/X{GUID}) or as the next argument (/x "{GUID}")
+fn parse_msiexec_arguments(parsed: &mut ParsedCommandLine) {
+    let mut i = 0;
+    while i < parsed.arguments.len() {
+        let argument = &parsed.arguments[i];
+        let option = match argument.strip_prefix('/').or_else(|| argument.strip_prefix('-')) {
+            Some(option) => option,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        let lower = option.to_ascii_lowercase();
+
+        let (action, attached) = if lower.starts_with("uninstall") {
+            (MsiAction::Uninstall, &option["uninstall".len()..])
+        } else if lower.starts_with("package") {
+            (MsiAction::Install, &option["package".len()..])
+        } else if lower.starts_with('x') {
+            (MsiAction::Uninstall, &option[1..])
+        } else if lower.starts_with('i') {
+            (MsiAction::Install, &option[1..])
+        } else if lower.starts_with('f') && lower != "forcerestart" && is_reinstall_mode(&lower[1..]) {
+            // Reinstall mode letters come before the target: /fvomus{GUID}
+            let modes = option[1..].find(|c: char| !c.is_ascii_alphabetic()).map_or(option.len(), |end| end + 1);
+            (MsiAction::Repair, &option[modes..])
+        } else {
+            i += 1;
+            continue;
+        };
+
+        let target = if attached.trim().is_empty() {
+            i += 1;
+            parsed.arguments.get(i).cloned()
+        } else {
+            Some(attached.trim().to_string())
+        };
+        parsed.msi_action = Some(action);
+        if let Some(target) = target {
+            if is_guid(&target) {
+                parsed.product_code = format_guid(&target);
+            } else {
+                parsed.msi_package = Some(target);
+            }
+        }
+        i += 1;
+    }
+}
+
+// /f is only a repair when every letter after it is a reinstall mode; /forcerestart is not one
+fn is_reinstall_mode(option: &str) -> bool {
+    option.chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .all(|c| REINSTALL_MODES.contains(c))
+}
+
+// rundll32 takes "<dll>,<entry point> <arguments>"; an unquoted DLL path with spaces spans several arguments
+fn parse_rundll32_arguments(parsed: &mut ParsedCommandLine) {
+    let mut target = String::new();
+    for argument in &parsed.arguments {
+        if !target.is_empty() {
+            target.push(' ');
+        }
+        target.push_str(argument);
+        if argument.contains(',') {
+            break;
+        }
+    }
+
+    match target.split_once(',') {
+        Some((dll, entry_point)) => {
+            parsed.dll_path = Some(dll.trim().to_string()).filter(|dll| !dll.is_empty());
+            parsed.entry_point = Some(entry_point.trim().to_string()).filter(|entry_point| !entry_point.is_empty());
+        }
+        None if !target.is_empty() => parsed.dll_path = Some(target),
+        None => {}
+    }
+}
+
+/// Guess which installer engine wrote an uninstall entry from its command line and key name
+pub fn detect_installer_engine(uninstall: &ParsedCommandLine, registry_name: &str, is_windows_installer: bool) -> (InstallerEngine, Confidence) {
+    if uninstall.kind == CommandKind::Msiexec {
+        return (InstallerEngine::WindowsInstaller, Confidence::High);
+    }
+    // MSI packages wrapped in a setup.exe still uninstall cleanly through msiexec
+    if is_windows_installer && is_guid(registry_name) {
+        return (InstallerEngine::WindowsInstaller, Confidence::High);
+    }
+
+    let executable = uninstall.executable.to_ascii_lowercase();
+    let name = file_stem(&uninstall.executable);
+    let has_argument = |wanted: &str| uninstall.arguments.iter().any(|argument| argument.eq_ignore_ascii_case(wanted));
+
+    if uninstall.kind == CommandKind::Rundll32 {
+        // ClickOnce apps are removed through the deployment framework's dfshim.dll
+        let dll = uninstall.dll_path.as_deref().map(file_stem).unwrap_or_default();
+        return if dll == "dfshim" {
+            (InstallerEngine::ClickOnce, Confidence::High)
+        } else {
+            (InstallerEngine::Unknown, Confidence::Low)
+        };
+    }
+    // Squirrel installs into %LocalAppData%\<app> and uninstalls through its Update.exe
+    if name == "update" && has_argument("--uninstall") {
+        return (InstallerEngine::Squirrel, Confidence::High);
+    }
+    if executable.contains("\\windowsapps\\") || uninstall.raw.contains("Remove-AppxPackage") {
+        return (InstallerEngine::Msix, Confidence::High);
+    }
+    if is_chocolatey_path(&executable) || name == "choco" {
+        return (InstallerEngine::Chocolatey, Confidence::High);
+    }
+
+    // Inno Setup names its keys "<AppId>_is1" and its uninstallers unins000.exe, unins001.exe, ...
+    let inno_uninstaller = name.len() == 8 && name.starts_with("unins") && name[5..].chars().all(|c| c.is_ascii_digit());
+    if registry_name.to_ascii_lowercase().ends_with("_is1") {
+        return (InstallerEngine::InnoSetup, Confidence::High);
+    }
+    if inno_uninstaller {
+        return (InstallerEngine::InnoSetup, Confidence::Medium);
+    }
+
+    // Burn bundles run from the package cache with /uninstall
+    if executable.contains("\\package cache\\") {
+        let confidence = if has_argument("/uninstall") { Confidence::High } else { Confidence::Medium };
+        return (InstallerEngine::WixBurn, confidence);
+    }
+
+    if executable.contains("\\installshield installation information\\") || has_argument("-removeonly") {
+        return (InstallerEngine::InstallShield, Confidence::High);
+    }
+    if has_argument("-runfromtemp") {
+        return (InstallerEngine::InstallShield, Confidence::Medium);
+    }
+
+    // NSIS uninstallers copy themselves to a temp folder and are pointed back with _?=
+    if uninstall.arguments.iter().any(|argument| argument.starts_with("_?=")) {
+        return (InstallerEngine::Nsis, Confidence::High);
+    }
+    match name.as_str() {
+        "uninst" => return (InstallerEngine::Nsis, Confidence::Medium),
+        "uninstall" | "uninstaller" => return (InstallerEngine::Nsis, Confidence::Low),
+        _ => {}
+    }
+
+    if has_argument("/uninstall") {
+        return (InstallerEngine::WixBurn, Confidence::Low);
+    }
+
+    (InstallerEngine::Unknown, Confidence::Low)
+}
+
+/// Build a silent uninstall command from the engine's documented switches. The product code is the
+/// uninstall key's name, used for MSI products whose UninstallString does not name one
+pub fn synthesize_quiet_uninstall(uninstall: &ParsedCommandLine, engine: InstallerEngine, confidence: Confidence, product_code: Option<&str>) -> Option<SilentUninstall> {
+    let executable = quote_argument(&uninstall.executable);
+    let (command, confidence, note) = match engine {
+        InstallerEngine::WindowsInstaller => {
+            // /I in an UninstallString opens maintenance mode; a silent removal always needs /X
+            let product_code = uninstall.product_code.clone().or_else(|| product_code.and_then(format_guid))?;
+            (format!("MsiExec.exe /X{} /qn /norestart", product_code), confidence, None)
+        }
+        InstallerEngine::Nsis => {
+            // /S has to come before _?=, which takes the rest of the command line unquoted
+            let silent = if uninstall.arguments.iter().any(|argument| argument == "/S") { "" } else { "/S" };
+            (join_command(&executable, &[silent, &uninstall.argument_string]), confidence, None)
+        }
+        InstallerEngine::InnoSetup => {
+            let arguments = arguments_without(uninstall, &["/silent", "/verysilent", "/suppressmsgboxes", "/norestart"]);
+            (join_command(&executable, &[&arguments, "/VERYSILENT /SUPPRESSMSGBOXES /NORESTART"]), confidence, None)
+        }
+        InstallerEngine::WixBurn => {
+            let arguments = arguments_without(uninstall, &["/uninstall", "/passive", "/quiet", "/silent", "/norestart"]);
+            (join_command(&executable, &["/uninstall", &arguments, "/quiet /norestart"]), confidence, None)
+        }
+        InstallerEngine::InstallShield => (
+            join_command(&executable, &[&uninstall.argument_string, "-s"]),
+            Confidence::Low,
+            Some("InstallScript setups only run silently with a recorded response file (-f1\"<path>\\setup.iss\")".to_string()),
+        ),
+        InstallerEngine::Squirrel => (join_command(&executable, &["--uninstall -s"]), confidence, None),
+        InstallerEngine::Chocolatey => {
+            let package = chocolatey_package(&uninstall.executable)?;
+            (format!("choco uninstall {} -y", package), confidence, None)
+        }
+        // Remove-AppxPackage never prompts, so the uninstall command is already silent
+        InstallerEngine::Msix => (uninstall.raw.trim().to_string(), confidence, None),
+        InstallerEngine::ClickOnce | InstallerEngine::Portable | InstallerEngine::Unknown => return None,
+    };
+
+    Some(SilentUninstall {
+        command,
+        engine,
+        confidence,
+        origin: SilentCommandOrigin::Synthesized,
+        note,
+    })
+}
+
+// The remaining arguments, re-quoted, without the UI and restart switches the silent command replaces
+fn arguments_without(command: &ParsedCommandLine, switches: &[&str]) -> String {
+    command.arguments.iter()
+        .filter(|argument| !switches.contains(&argument.to_ascii_lowercase().as_str()))
+        .map(|argument| quote_argument(argument))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn join_command(executable: &str, parts: &[&str]) -> String {
+    let mut command = executable.to_string();
+    for part in parts.iter().map(|part| part.trim()).filter(|part| !part.is_empty()) {
+        command.push(' ');
+        command.push_str(part);
+    }
+    command
+}
+
+fn quote_argument(argument: &str) -> String {
+    if argument.is_empty() || argument.contains(char::is_whitespace) {
+        format!("\"{}\"", argument)
+    } else {
+        argument.to_string()
+    }
+}
+
+// Package ID from a path inside the Chocolatey lib folder: ...\chocolatey\lib\<id>\tools\foo.exe
+fn chocolatey_package(path: &str) -> Option<String> {
+    let lower = path.to_ascii_lowercase();
+    let start = lower.find("\\chocolatey\\lib\\")? + "\\chocolatey\\lib\\".len();
+    let package = path[start..].split('\\').next()?;
+    Some(package.to_string()).filter(|package| !package.is_empty())
+}
+
+fn has_executable_extension(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    EXECUTABLE_EXTENSIONS.iter().any(|extension| lower.ends_with(extension))
+}
+
+// Lower-case file name without directory or extension: "C:\Windows\System32\MsiExec.exe" -> "msiexec"
+fn file_stem(path: &str) -> String {
+    let name = path.rsplit(['\\', '/']).next().unwrap_or(path).to_ascii_lowercase();
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => name[..dot].to_string(),
+        _ => name,
+    }
+}
+
+fn is_guid(value: &str) -> bool {
+    let value = value.trim();
+    value.starts_with('{') && value.ends_with('}') && format_guid(value).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRODUCT_CODE: &str = "{1D8E6291-B0D5-35EC-8441-6616F567A0F7}";
+
+    fn parse(raw: &str) -> ParsedCommandLine {
+        parse_command_line_with(raw, &|_| false).unwrap()
+    }
+
+    fn engine_of(raw: &str, registry_name: &str) -> (InstallerEngine, Confidence) {
+        detect_installer_engine(&parse(raw), registry_name, false)
+    }
+
+    fn synthesized(raw: &str, engine: InstallerEngine) -> Option<String> {
+        synthesize_quiet_uninstall(&parse(raw), engine, Confidence::High, None).map(|silent| silent.command)
+    }
+
+    #[test]
+    fn splits_unquoted_paths_with_spaces() {
+        let parsed = parse(r"C:\Program Files\Foo\uninst.exe /S");
+        assert_eq!(parsed.executable, r"C:\Program Files\Foo\uninst.exe");
+        assert_eq!(parsed.arguments, vec!["/S"]);
+        assert_eq!(parsed.kind, CommandKind::Executable);
+
+        let parsed = parse(r#""C:\Program Files\Foo\uninst.exe" /S _?=C:\Program Files\Foo"#);
+        assert_eq!(parsed.executable, r"C:\Program Files\Foo\uninst.exe");
+        assert_eq!(parsed.argument_string, r"/S _?=C:\Program Files\Foo");
+        assert_eq!(parsed.arguments, vec!["/S", r"_?=C:\Program", r"Files\Foo"]);
+
+        // Without an extension the file system decides, as CreateProcess does
+        let on_disk = |path: &Path| path == Path::new(r"C:\Program Files\Foo Bar\uninstall.exe");
+        let parsed = parse_command_line_with(r"C:\Program Files\Foo Bar\uninstall --force", &on_disk).unwrap();
+        assert_eq!(parsed.executable, r"C:\Program Files\Foo Bar\uninstall");
+        assert_eq!(parsed.arguments, vec!["--force"]);
+
+        let parsed = parse(r"C:\Program Files\Foo\setup");
+        assert_eq!(parsed.executable, r"C:\Program");
+        assert_eq!(parsed.argument_string, r"Files\Foo\setup");
+        assert!(parse_command_line_with("  ", &|_| true).is_none());
+    }
+
+    #[test]
+    fn splits_arguments_like_windows() {
+        assert_eq!(split_arguments("  a \"b c\"\td  "), vec!["a", "b c", "d"]);
+        assert_eq!(split_arguments(r#""a\"b""#), vec![r#"a"b"#]);
+        assert_eq!(split_arguments(r#"a\\"b c""#), vec![r"a\b c"]);
+        assert_eq!(split_arguments(r#"a\\\"b"#), vec![r#"a\"b"#]);
+        assert_eq!(split_arguments(r"a\\b C:\Path\ x"), vec![r"a\\b", r"C:\Path\", "x"]);
+        assert_eq!(split_arguments(r#""a""b" """#), vec![r#"a"b"#, ""]);
+        assert!(split_arguments("").is_empty());
+    }
+
+    #[test]
+    fn reads_rundll32_targets() {
+        let parsed = parse("rundll32.exe dfshim.dll,ShArpMaintain App.application, Culture=neutral");
+        assert_eq!(parsed.kind, CommandKind::Rundll32);
+        assert_eq!(parsed.dll_path.as_deref(), Some("dfshim.dll"));
+        assert_eq!(parsed.entry_point.as_deref(), Some("ShArpMaintain"));
+
+        let parsed = parse(r"RunDll32 C:\Program Files\Common Files\Setup\setup.dll,Uninstall -x");
+        assert_eq!(parsed.kind, CommandKind::Rundll32);
+        assert_eq!(parsed.dll_path.as_deref(), Some(r"C:\Program Files\Common Files\Setup\setup.dll"));
+        assert_eq!(parsed.entry_point.as_deref(), Some("Uninstall"));
+
+        let parsed = parse(r"C:\Windows\System32\rundll32.exe");
+        assert_eq!(parsed.dll_path, None);
+        assert_eq!(parsed.entry_point, None);
+    }
+
+    #[test]
+    fn reads_msiexec_actions_and_targets() {
+        let install = parse(&format!("MsiExec.exe /I{}", PRODUCT_CODE));
+        assert_eq!(install.kind, CommandKind::Msiexec);
+        assert_eq!(install.msi_action, Some(MsiAction::Install));
+        assert_eq!(install.product_code.as_deref(), Some(PRODUCT_CODE));
+
+        let uninstall = parse(&format!("msiexec /x \"{}\" /qb", PRODUCT_CODE.to_ascii_lowercase()));
+        assert_eq!(uninstall.msi_action, Some(MsiAction::Uninstall));
+        assert_eq!(uninstall.product_code.as_deref(), Some(PRODUCT_CODE));
+
+        let uninstall = parse(&format!(r"C:\Windows\System32\msiexec.exe -uninstall {}", PRODUCT_CODE));
+        assert_eq!(uninstall.msi_action, Some(MsiAction::Uninstall));
+        assert_eq!(uninstall.product_code.as_deref(), Some(PRODUCT_CODE));
+
+        let package = parse(r#"msiexec /package "C:\Setup Files\app.msi" /qn"#);
+        assert_eq!(package.msi_action, Some(MsiAction::Install));
+        assert_eq!(package.msi_package.as_deref(), Some(r"C:\Setup Files\app.msi"));
+        assert_eq!(package.product_code, None);
+    }
+
+    #[test]
+    fn synthesizes_msiexec_removals_with_x() {
+        let install = parse(&format!("MsiExec.exe /I{}", PRODUCT_CODE));
+        let silent = synthesize_quiet_uninstall(&install, InstallerEngine::WindowsInstaller, Confidence::High, None).unwrap();
+        assert_eq!(silent.command, format!("MsiExec.exe /X{} /qn /norestart", PRODUCT_CODE));
+        assert_eq!(silent.origin, SilentCommandOrigin::Synthesized);
+
+        // A setup.exe wrapper: the product code comes from the uninstall key's name
+        let wrapper = parse(r"C:\ProgramData\Vendor\setup.exe /remove");
+        let key_name = PRODUCT_CODE.to_ascii_lowercase();
+        assert_eq!(detect_installer_engine(&wrapper, &key_name, true), (InstallerEngine::WindowsInstaller, Confidence::High));
+        let silent = synthesize_quiet_uninstall(&wrapper, InstallerEngine::WindowsInstaller, Confidence::High, Some(&key_name)).unwrap();
+        assert_eq!(silent.command, format!("MsiExec.exe /X{} /qn /norestart", PRODUCT_CODE));
+        assert!(synthesize_quiet_uninstall(&wrapper, InstallerEngine::WindowsInstaller, Confidence::High, None).is_none());
+    }
+
+    #[test]
+    fn detects_installer_engines() {
+        use InstallerEngine::*;
+        assert_eq!(engine_of(&format!("MsiExec.exe /X{}", PRODUCT_CODE), "Anything"), (WindowsInstaller, Confidence::High));
+        assert_eq!(engine_of("rundll32.exe dfshim.dll,ShArpMaintain App.application", "abc123"), (ClickOnce, Confidence::High));
+        assert_eq!(engine_of("rundll32.exe setupapi.dll,InstallHinfSection Remove", "Driver"), (Unknown, Confidence::Low));
+        assert_eq!(engine_of(r#""C:\Users\a\AppData\Local\Discord\Update.exe" --uninstall"#, "Discord"), (Squirrel, Confidence::High));
+        assert_eq!(engine_of(r"C:\Users\a\AppData\Local\Discord\Update.exe --processStart Discord.exe", "Discord"), (Unknown, Confidence::Low));
+        assert_eq!(engine_of(r#"powershell.exe -Command "Remove-AppxPackage Foo_1.0_x64__abc""#, "Foo"), (Msix, Confidence::High));
+        assert_eq!(engine_of(r"C:\ProgramData\chocolatey\lib\git\tools\uninstall.exe", "Git"), (Chocolatey, Confidence::High));
+        assert_eq!(engine_of(r#""C:\Program Files\App\unins000.exe""#, "App_is1"), (InnoSetup, Confidence::High));
+        assert_eq!(engine_of(r#""C:\Program Files\App\unins001.exe""#, "App"), (InnoSetup, Confidence::Medium));
+        assert_eq!(engine_of(r#""C:\ProgramData\Package Cache\{A1}\bundle.exe" /uninstall"#, "{A1}"), (WixBurn, Confidence::High));
+        assert_eq!(engine_of(r#""C:\ProgramData\Package Cache\{A1}\bundle.exe" /modify"#, "{A1}"), (WixBurn, Confidence::Medium));
+        assert_eq!(engine_of(r"C:\Program Files (x86)\InstallShield Installation Information\{A1}\setup.exe -runfromtemp -l0x0409", "{A1}"), (InstallShield, Confidence::High));
+        assert_eq!(engine_of(r"C:\Temp\setup.exe -runfromtemp", "App"), (InstallShield, Confidence::Medium));
+        assert_eq!(engine_of(r#""C:\Program Files\App\uninst.exe" _?=C:\Program Files\App"#, "App"), (Nsis, Confidence::High));
+        assert_eq!(engine_of(r"C:\App\uninst.exe", "App"), (Nsis, Confidence::Medium));
+        assert_eq!(engine_of(r"C:\App\Uninstall.exe", "App"), (Nsis, Confidence::Low));
+        assert_eq!(engine_of(r"C:\App\remove.exe /uninstall", "App"), (WixBurn, Confidence::Low));
+        assert_eq!(engine_of(r"C:\App\remove.exe", "App"), (Unknown, Confidence::Low));
+    }
+
+    #[test]
+    fn synthesizes_each_engines_silent_switches() {
+        use InstallerEngine::*;
+        assert_eq!(
+            synthesized(r#""C:\Program Files\App\uninst.exe" /S _?=C:\Program Files\App"#, Nsis).as_deref(),
+            Some(r#""C:\Program Files\App\uninst.exe" /S _?=C:\Program Files\App"#),
+        );
+        assert_eq!(synthesized(r"C:\App\uninst.exe _?=C:\App", Nsis).as_deref(), Some(r"C:\App\uninst.exe /S _?=C:\App"));
+        assert_eq!(
+            synthesized(r#""C:\Program Files\App\unins000.exe" /SILENT"#, InnoSetup).as_deref(),
+            Some(r#""C:\Program Files\App\unins000.exe" /VERYSILENT /SUPPRESSMSGBOXES /NORESTART"#),
+        );
+        assert_eq!(
+            synthesized(r#""C:\ProgramData\Package Cache\{A1}\bundle.exe" /uninstall /passive /log "C:\Temp\uninstall log.txt""#, WixBurn).as_deref(),
+            Some(r#""C:\ProgramData\Package Cache\{A1}\bundle.exe" /uninstall /log "C:\Temp\uninstall log.txt" /quiet /norestart"#),
+        );
+        assert_eq!(
+            synthesized(r"C:\Users\a\AppData\Local\Discord\Update.exe --uninstall", Squirrel).as_deref(),
+            Some(r"C:\Users\a\AppData\Local\Discord\Update.exe --uninstall -s"),
+        );
+        assert_eq!(
+            synthesized(r"C:\ProgramData\chocolatey\lib\git.install\tools\uninstall.exe", Chocolatey).as_deref(),
+            Some("choco uninstall git.install -y"),
+        );
+        assert_eq!(synthesized(r"C:\ProgramData\chocolatey\bin\git.exe", Chocolatey), None);
+        let msix = r#"powershell.exe -Command "Remove-AppxPackage Foo_1.0_x64__abc""#;
+        assert_eq!(synthesized(&format!("  {} ", msix), Msix).as_deref(), Some(msix));
+
+        let installshield = parse(r#""C:\Program Files (x86)\InstallShield Installation Information\{A1}\setup.exe" -runfromtemp -removeonly"#);
+        let silent = synthesize_quiet_uninstall(&installshield, InstallShield, Confidence::High, None).unwrap();
+        assert_eq!(silent.command, r#""C:\Program Files (x86)\InstallShield Installation Information\{A1}\setup.exe" -runfromtemp -removeonly -s"#);
+        assert_eq!(silent.confidence, Confidence::Low);
+        assert!(silent.note.is_some());
+
+        for engine in [ClickOnce, Portable, Unknown] {
+            assert_eq!(synthesized(r"C:\App\remove.exe", engine), None);
+        }
+    }
+
+    #[test]
+    fn tells_repairs_from_restart_options() {
+        let uninstall = parse("MsiExec.exe /X{1D8E6291-B0D5-35EC-8441-6616F567A0F7} /forcerestart");
+        assert_eq!(uninstall.msi_action, Some(MsiAction::Uninstall));
+        assert_eq!(uninstall.product_code.as_deref(), Some("{1D8E6291-B0D5-35EC-8441-6616F567A0F7}"));
+        let install = parse("msiexec /i a.msi /forcerestart");
+        assert_eq!(install.msi_action, Some(MsiAction::Install));
+        assert_eq!(install.msi_package.as_deref(), Some("a.msi"));
+
+        let repair = parse("MsiExec.exe /fvomus{1D8E6291-B0D5-35EC-8441-6616F567A0F7}");
+        assert_eq!(repair.msi_action, Some(MsiAction::Repair));
+        assert_eq!(repair.product_code.as_deref(), Some("{1D8E6291-B0D5-35EC-8441-6616F567A0F7}"));
+        let repair = parse("msiexec /fa \"C:\\Setup\\a.msi\"");
+        assert_eq!(repair.msi_action, Some(MsiAction::Repair));
+        assert_eq!(repair.msi_package.as_deref(), Some("C:\\Setup\\a.msi"));
+    }
+}
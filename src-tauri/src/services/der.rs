@@ -0,0 +1,314 @@
+// Just enough DER to walk PKCS#7 and X.509 structures: elements, object identifiers, strings, times and
+// distinguished names. Only definite lengths; Authenticode signatures are DER throughout
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// Context-specific tag `[n]`
+pub const fn context(n: u8) -> u8 {
+    0xA0 | n
+}
+
+/// One TLV element
+#[derive(Debug, Clone, Copy)]
+pub struct Element<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+    pub raw: &'a [u8], // Tag and length included, as hashed and compared
+}
+
+impl<'a> Element<'a> {
+    /// The elements inside a constructed element
+    pub fn children(&self) -> Elements<'a> {
+        Elements { bytes: self.content }
+    }
+
+    /// The first element inside, which is how EXPLICIT tags wrap their value
+    pub fn inner(&self) -> Option<Element<'a>> {
+        self.children().next()
+    }
+
+    pub fn is(&self, tag: u8) -> bool {
+        self.tag == tag
+    }
+
+    pub fn oid(&self) -> Option<String> {
+        if self.tag == TAG_OID { decode_oid(self.content) } else { None }
+    }
+
+    pub fn text(&self) -> Option<String> {
+        decode_string(self.tag, self.content)
+    }
+
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        decode_time(self.tag, self.content)
+    }
+
+    /// An INTEGER as hex, leading zero bytes dropped, the way certificate serial numbers are shown
+    pub fn integer_hex(&self) -> String {
+        let digits = self.content.iter().skip_while(|byte| **byte == 0).map(|byte| format!("{:02X}", byte)).collect::<String>();
+        if digits.is_empty() { "00".to_string() } else { digits }
+    }
+}
+
+/// The elements of a constructed element's content, one after another; stops at the first malformed one
+pub struct Elements<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Element<'a>;
+
+    fn next(&mut self) -> Option<Element<'a>> {
+        let (element, rest) = read_element(self.bytes)?;
+        self.bytes = rest;
+        Some(element)
+    }
+}
+
+/// Read the element at the start of `bytes`, returning it and what follows
+pub fn read_element(bytes: &[u8]) -> Option<(Element<'_>, &[u8])> {
+    let tag = *bytes.first()?;
+    // High tag numbers never occur in the structures read here
+    if tag & 0x1F == 0x1F {
+        return None;
+    }
+    let first = *bytes.get(1)? as usize;
+    let (length, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let count = first & 0x7F;
+        // 0x80 is BER's indefinite length; more than four length bytes is beyond anything real
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let length = bytes.get(2..2 + count)?.iter().fold(0usize, |length, byte| (length << 8) | *byte as usize);
+        (length, 2 + count)
+    };
+    let end = header.checked_add(length)?;
+    let raw = bytes.get(..end)?;
+    Some((Element { tag, content: &raw[header..], raw }, &bytes[end..]))
+}
+
+/// Parse a buffer holding exactly one element, ignoring padding after it
+pub fn parse(bytes: &[u8]) -> Option<Element<'_>> {
+    read_element(bytes).map(|(element, _)| element)
+}
+
+pub fn decode_oid(content: &[u8]) -> Option<String> {
+    let mut arcs: Vec<u64> = Vec::new();
+    let mut value: u64 = 0;
+    for byte in content {
+        if value > u64::MAX >> 7 {
+            return None;
+        }
+        value = (value << 7) | (*byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                // The first byte packs the first two arcs
+                let first = (value / 40).min(2);
+                arcs.push(first);
+                arcs.push(value - first * 40);
+            } else {
+                arcs.push(value);
+            }
+            value = 0;
+        }
+    }
+    if arcs.is_empty() {
+        return None;
+    }
+    Some(arcs.iter().map(|arc| arc.to_string()).collect::<Vec<_>>().join("."))
+}
+
+pub fn decode_string(tag: u8, content: &[u8]) -> Option<String> {
+    match tag {
+        // UTF8String, PrintableString, IA5String, VisibleString, NumericString
+        0x0C | 0x13 | 0x16 | 0x1A | 0x12 => Some(String::from_utf8_lossy(content).into_owned()),
+        // TeletexString; in practice Latin-1
+        0x14 => Some(content.iter().map(|byte| *byte as char).collect()),
+        // BMPString, UTF-16 big-endian
+        0x1E => {
+            let units: Vec<u16> = content.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+            Some(String::from_utf16_lossy(&units))
+        }
+        // UniversalString, UTF-32 big-endian
+        0x1C => Some(content.chunks_exact(4)
+            .filter_map(|quad| char::from_u32(u32::from_be_bytes([quad[0], quad[1], quad[2], quad[3]])))
+            .collect()),
+        _ => None,
+    }
+}
+
+pub fn decode_time(tag: u8, content: &[u8]) -> Option<DateTime<Utc>> {
+    let text = std::str::from_utf8(content).ok()?;
+    let text = text.strip_suffix('Z')?;
+    let full = match tag {
+        // Two-digit years: 50 and up are 19xx (RFC 5280)
+        TAG_UTC_TIME => {
+            let year: u32 = text.get(..2)?.parse().ok()?;
+            format!("{}{}", if year >= 50 { "19" } else { "20" }, text)
+        }
+        TAG_GENERALIZED_TIME => text.to_string(),
+        _ => return None,
+    };
+    // Fractional seconds are allowed in GeneralizedTime; they are dropped
+    let whole = full.split('.').next()?;
+    let parsed = match whole.len() {
+        14 => NaiveDateTime::parse_from_str(whole, "%Y%m%d%H%M%S").ok()?,
+        12 => NaiveDateTime::parse_from_str(&format!("{}00", whole), "%Y%m%d%H%M%S").ok()?,
+        _ => return None,
+    };
+    Some(Utc.from_utc_datetime(&parsed))
+}
+
+/// Short names for the attribute types seen in certificate names
+fn attribute_name(oid: &str) -> String {
+    match oid {
+        "2.5.4.3" => "CN",
+        "2.5.4.5" => "SERIALNUMBER",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "S",
+        "2.5.4.9" => "STREET",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "2.5.4.15" => "BUSINESSCATEGORY",
+        "2.5.4.17" => "PostalCode",
+        "2.5.4.97" => "OrganizationIdentifier",
+        "1.2.840.113549.1.9.1" => "E",
+        "1.3.6.1.4.1.311.60.2.1.1" => "JurisdictionL",
+        "1.3.6.1.4.1.311.60.2.1.2" => "JurisdictionS",
+        "1.3.6.1.4.1.311.60.2.1.3" => "JurisdictionC",
+        other => return format!("OID.{}", other),
+    }
+    .to_string()
+}
+
+/// A distinguished name as (attribute, value) pairs, in the order they are encoded
+pub fn name_attributes(name: &Element) -> Vec<(String, String)> {
+    name.children()
+        .filter(|rdn| rdn.is(TAG_SET))
+        .flat_map(|rdn| rdn.children().collect::<Vec<_>>())
+        .filter_map(|attribute| {
+            let mut parts = attribute.children();
+            let oid = parts.next()?.oid()?;
+            let value = parts.next()?.text()?;
+            Some((attribute_name(&oid), value))
+        })
+        .collect()
+}
+
+/// A distinguished name the way Windows shows it: most specific first, "CN=..., O=..., C=..."
+pub fn format_name(attributes: &[(String, String)]) -> String {
+    attributes.iter()
+        .rev()
+        .map(|(name, value)| {
+            if value.contains(',') || value.contains('"') {
+                format!("{}=\"{}\"", name, value.replace('"', "\"\""))
+            } else {
+                format!("{}={}", name, value)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_short_and_long_lengths() {
+        let (element, rest) = read_element(&[0x04, 0x02, 0xAB, 0xCD, 0xFF]).unwrap();
+        assert_eq!((element.tag, element.content, rest), (TAG_OCTET_STRING, [0xAB, 0xCD].as_slice(), [0xFF].as_slice()));
+
+        let mut long = vec![0x04, 0x82, 0x01, 0x00];
+        long.extend(std::iter::repeat(0x5A).take(256));
+        let element = parse(&long).unwrap();
+        assert_eq!(element.content.len(), 256);
+        assert_eq!(element.raw.len(), 260);
+    }
+
+    #[test]
+    fn rejects_malformed_elements() {
+        assert!(parse(&[]).is_none());
+        assert!(parse(&[0x30]).is_none());
+        assert!(parse(&[0x30, 0x05, 0x01]).is_none());               // Shorter than its length
+        assert!(parse(&[0x30, 0x80, 0x00, 0x00]).is_none());         // Indefinite length
+        assert!(parse(&[0x30, 0x85, 1, 0, 0, 0, 0]).is_none());      // Five length bytes
+        assert!(parse(&[0x30, 0x84, 0xFF, 0xFF, 0xFF, 0xFF]).is_none());
+        assert!(parse(&[0x1F, 0x81, 0x01, 0x00]).is_none());         // High tag number
+
+        // Children stop at the first broken one
+        let sequence = parse(&[0x30, 0x06, 0x02, 0x01, 0x07, 0x02, 0x05, 0x00]).unwrap();
+        assert_eq!(sequence.children().count(), 1);
+    }
+
+    #[test]
+    fn decodes_object_identifiers() {
+        let sha256 = [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+        assert_eq!(decode_oid(&sha256).as_deref(), Some("2.16.840.1.101.3.4.2.1"));
+        assert_eq!(decode_oid(&[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02]).as_deref(), Some("1.2.840.113549.1.7.2"));
+        assert_eq!(decode_oid(&[0x55, 0x04, 0x03]).as_deref(), Some("2.5.4.3"));
+        assert!(decode_oid(&[]).is_none());
+        assert!(decode_oid(&[0xFF; 12]).is_none());
+    }
+
+    #[test]
+    fn decodes_times() {
+        let utc = |text: &str| decode_time(TAG_UTC_TIME, text.as_bytes()).map(|time| time.to_rfc3339());
+        assert_eq!(utc("240322120000Z").as_deref(), Some("2024-03-22T12:00:00+00:00"));
+        assert_eq!(utc("991231235959Z").as_deref(), Some("1999-12-31T23:59:59+00:00"));
+        assert_eq!(utc("2403221200Z").as_deref(), Some("2024-03-22T12:00:00+00:00"));
+        assert!(utc("240322120000").is_none());
+        assert!(utc("24XX22120000Z").is_none());
+
+        let generalized = |text: &str| decode_time(TAG_GENERALIZED_TIME, text.as_bytes()).map(|time| time.to_rfc3339());
+        assert_eq!(generalized("20500101000000Z").as_deref(), Some("2050-01-01T00:00:00+00:00"));
+        assert_eq!(generalized("20240322120000.123Z").as_deref(), Some("2024-03-22T12:00:00+00:00"));
+        assert!(decode_time(TAG_OCTET_STRING, b"20240322120000Z").is_none());
+    }
+
+    #[test]
+    fn reads_strings_and_names() {
+        assert_eq!(decode_string(0x0C, "Contoso Ltd.".as_bytes()).as_deref(), Some("Contoso Ltd."));
+        assert_eq!(decode_string(0x1E, &[0x00, 0x41, 0x00, 0xE9]).as_deref(), Some("Aé"));
+        assert_eq!(decode_string(0x14, &[0x43, 0xE9]).as_deref(), Some("Cé"));
+        assert!(decode_string(TAG_INTEGER, b"1").is_none());
+
+        // SEQUENCE { SET { C=US }, SET { O="Contoso, Ltd" }, SET { CN=Contoso } }
+        let attribute = |oid: &[u8], value: &str| {
+            let mut inner = vec![TAG_OID, oid.len() as u8];
+            inner.extend_from_slice(oid);
+            inner.extend([0x0C, value.len() as u8]);
+            inner.extend_from_slice(value.as_bytes());
+            let mut sequence = vec![TAG_SEQUENCE, inner.len() as u8];
+            sequence.extend(inner);
+            let mut set = vec![TAG_SET, sequence.len() as u8];
+            set.extend(sequence);
+            set
+        };
+        let attributes: Vec<u8> = [attribute(&[0x55, 0x04, 0x06], "US"), attribute(&[0x55, 0x04, 0x0A], "Contoso, Ltd"), attribute(&[0x55, 0x04, 0x03], "Contoso")].concat();
+        let mut name = vec![TAG_SEQUENCE, attributes.len() as u8];
+        name.extend(attributes);
+
+        let attributes = name_attributes(&parse(&name).unwrap());
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(format_name(&attributes), "CN=Contoso, O=\"Contoso, Ltd\", C=US");
+    }
+
+    #[test]
+    fn shows_integers_as_hex() {
+        assert_eq!(parse(&[0x02, 0x03, 0x00, 0x8F, 0x01]).unwrap().integer_hex(), "8F01");
+        assert_eq!(parse(&[0x02, 0x01, 0x00]).unwrap().integer_hex(), "00");
+    }
+}
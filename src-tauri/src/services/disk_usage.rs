@@ -0,0 +1,284 @@
+// What a folder really takes on disk: every file counted once however many hard links it has, sparse and
+// compressed files at the clusters they occupy, and links to elsewhere not followed
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use walkdir::WalkDir;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DiskUsage {
+    pub logical_bytes: u64,        // Sum of file sizes
+    pub allocated_bytes: u64,      // Space in use on the volume; less than logical for sparse and compressed files
+    pub file_count: u64,
+    pub folder_count: u64,
+    pub hard_link_duplicates: u64, // Further links to files already counted, not counted again
+    pub sparse_files: u64,         // Sparse or compressed files
+    pub skipped_links: u64,        // Symbolic links and junctions, which point at space counted elsewhere
+    pub unreadable: u64,           // Entries that could not be read, e.g. for lack of access
+    pub complete: bool,            // False when the time budget ran out; the sizes are then a lower bound
+    pub elapsed_ms: u64,
+}
+
+// How a single file sits on the volume
+struct FileAllocation {
+    allocated: u64,
+    link_id: Option<(u64, u64)>, // Volume and file id, for files with more than one link
+    sparse: bool,
+}
+
+/// Walk a folder and add up its files, stopping when `budget` runs out
+pub fn measure_folder(path: &Path, budget: Duration) -> Result<DiskUsage, String> {
+    if !path.is_dir() {
+        return Err(format!("{} is not a folder", path.display()));
+    }
+    let started = Instant::now();
+    let cluster_size = platform::cluster_size(path);
+    let mut usage = DiskUsage { complete: true, ..DiskUsage::default() };
+    let mut seen_links: HashSet<(u64, u64)> = HashSet::new();
+
+    for entry in WalkDir::new(path).follow_links(false) {
+        if started.elapsed() >= budget {
+            usage.complete = false;
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => {
+                usage.unreadable += 1;
+                continue;
+            }
+        };
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                usage.unreadable += 1;
+                continue;
+            }
+        };
+        if entry.path_is_symlink() || platform::is_reparse_point(&metadata) {
+            usage.skipped_links += 1;
+            continue;
+        }
+        if metadata.is_dir() {
+            if entry.depth() > 0 {
+                usage.folder_count += 1;
+            }
+            continue;
+        }
+
+        let allocation = platform::file_allocation(entry.path(), &metadata);
+        if let Some(link_id) = allocation.link_id {
+            if !seen_links.insert(link_id) {
+                usage.hard_link_duplicates += 1;
+                continue;
+            }
+        }
+        usage.file_count += 1;
+        usage.logical_bytes += metadata.len();
+        usage.allocated_bytes += round_up(allocation.allocated, cluster_size);
+        if allocation.sparse {
+            usage.sparse_files += 1;
+        }
+    }
+
+    usage.elapsed_ms = started.elapsed().as_millis() as u64;
+    Ok(usage)
+}
+
+// Files take whole clusters; a cluster size of 0 means the sizes are already allocation sizes
+fn round_up(bytes: u64, cluster_size: u64) -> u64 {
+    (bytes + cluster_size.saturating_sub(1))
+        .checked_div(cluster_size)
+        .map_or(bytes, |clusters| clusters * cluster_size)
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::OsStr;
+    use std::fs::{File, Metadata};
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::MetadataExt;
+    use std::os::windows::io::AsRawHandle;
+    use std::path::{Component, Path};
+    use super::FileAllocation;
+
+    const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x200;
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x800;
+    const INVALID_FILE_SIZE: u32 = 0xFFFF_FFFF;
+
+    // BY_HANDLE_FILE_INFORMATION; only the link count and file id are read
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct ByHandleFileInformation {
+        file_attributes: u32,
+        creation_time: [u32; 2],
+        last_access_time: [u32; 2],
+        last_write_time: [u32; 2],
+        volume_serial_number: u32,
+        file_size_high: u32,
+        file_size_low: u32,
+        number_of_links: u32,
+        file_index_high: u32,
+        file_index_low: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCompressedFileSizeW(file_name: *const u16, file_size_high: *mut u32) -> u32;
+        fn GetFileInformationByHandle(file: *mut std::ffi::c_void, information: *mut ByHandleFileInformation) -> i32;
+        fn GetDiskFreeSpaceW(root: *const u16, sectors_per_cluster: *mut u32, bytes_per_sector: *mut u32, free_clusters: *mut u32, total_clusters: *mut u32) -> i32;
+    }
+
+    fn wide(text: &OsStr) -> Vec<u16> {
+        text.encode_wide().chain(std::iter::once(0)).collect()
+    }
+
+    pub fn is_reparse_point(metadata: &Metadata) -> bool {
+        metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0
+    }
+
+    pub fn cluster_size(path: &Path) -> u64 {
+        let root = match path.components().next() {
+            Some(Component::Prefix(prefix)) => format!("{}\\", prefix.as_os_str().to_string_lossy()),
+            _ => return 0,
+        };
+        let (mut sectors_per_cluster, mut bytes_per_sector, mut free, mut total) = (0u32, 0u32, 0u32, 0u32);
+        let root = wide(OsStr::new(&root));
+        let ok = unsafe { GetDiskFreeSpaceW(root.as_ptr(), &mut sectors_per_cluster, &mut bytes_per_sector, &mut free, &mut total) };
+        if ok == 0 {
+            0
+        } else {
+            sectors_per_cluster as u64 * bytes_per_sector as u64
+        }
+    }
+
+    pub fn file_allocation(path: &Path, metadata: &Metadata) -> FileAllocation {
+        let attributes = metadata.file_attributes();
+        let sparse = attributes & (FILE_ATTRIBUTE_SPARSE_FILE | FILE_ATTRIBUTE_COMPRESSED) != 0;
+
+        // Only sparse and compressed files occupy less than their length
+        let allocated = if sparse {
+            let name = wide(path.as_os_str());
+            let mut high = 0u32;
+            let low = unsafe { GetCompressedFileSizeW(name.as_ptr(), &mut high) };
+            if low == INVALID_FILE_SIZE && std::io::Error::last_os_error().raw_os_error().unwrap_or(0) != 0 {
+                metadata.len()
+            } else {
+                ((high as u64) << 32) | low as u64
+            }
+        } else {
+            metadata.len()
+        };
+
+        // The link count is only known from an open handle
+        let link_id = File::open(path).ok().and_then(|file| {
+            let mut information = ByHandleFileInformation::default();
+            let ok = unsafe { GetFileInformationByHandle(file.as_raw_handle() as *mut _, &mut information) };
+            if ok == 0 || information.number_of_links < 2 {
+                return None;
+            }
+            let file_index = ((information.file_index_high as u64) << 32) | information.file_index_low as u64;
+            Some((information.volume_serial_number as u64, file_index))
+        });
+
+        FileAllocation { allocated, link_id, sparse }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::fs::Metadata;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+    use super::FileAllocation;
+
+    pub fn is_reparse_point(_metadata: &Metadata) -> bool {
+        false
+    }
+
+    // st_blocks is already in whole allocation units
+    pub fn cluster_size(_path: &Path) -> u64 {
+        0
+    }
+
+    pub fn file_allocation(_path: &Path, metadata: &Metadata) -> FileAllocation {
+        let allocated = metadata.blocks() * 512;
+        FileAllocation {
+            allocated,
+            link_id: Some((metadata.dev(), metadata.ino())).filter(|_| metadata.nlink() > 1),
+            sparse: allocated < metadata.len(),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn counts_hard_linked_files_once_and_skips_symlinks() {
+        let dir = std::env::temp_dir().join(format!("disk-usage-{}", std::process::id()));
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::write(dir.join("app.dll"), vec![1u8; 10_000]).unwrap();
+        fs::hard_link(dir.join("app.dll"), dir.join("bin").join("app.dll")).unwrap();
+        fs::write(dir.join("bin").join("readme.txt"), b"hello").unwrap();
+        std::os::unix::fs::symlink(dir.join("app.dll"), dir.join("current.dll")).unwrap();
+        std::os::unix::fs::symlink(std::env::temp_dir(), dir.join("temp")).unwrap();
+
+        let usage = measure_folder(&dir, Duration::from_secs(60)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(usage.complete);
+        assert_eq!(usage.file_count, 2);
+        assert_eq!(usage.folder_count, 1);
+        assert_eq!(usage.hard_link_duplicates, 1);
+        assert_eq!(usage.skipped_links, 2);
+        assert_eq!(usage.logical_bytes, 10_005);
+        assert!(usage.allocated_bytes >= 10_000);
+        assert_eq!(usage.unreadable, 0);
+    }
+
+    #[test]
+    fn counts_sparse_files_at_what_they_occupy() {
+        let dir = std::env::temp_dir().join(format!("disk-usage-sparse-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut file = fs::File::create(dir.join("disk.vhd")).unwrap();
+        file.write_all(b"header").unwrap();
+        file.set_len(64 * 1024 * 1024).unwrap();
+        drop(file);
+
+        let usage = measure_folder(&dir, Duration::from_secs(60)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(usage.logical_bytes, 64 * 1024 * 1024);
+        assert!(usage.allocated_bytes < usage.logical_bytes);
+        assert_eq!(usage.sparse_files, 1);
+    }
+
+    #[test]
+    fn stops_when_the_budget_runs_out() {
+        let dir = std::env::temp_dir().join(format!("disk-usage-budget-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.exe"), b"MZ").unwrap();
+
+        let usage = measure_folder(&dir, Duration::ZERO).unwrap();
+        assert!(!usage.complete);
+        assert_eq!(usage.file_count, 0);
+        assert!(measure_folder(&dir.join("app.exe"), Duration::from_secs(60)).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rounds_up_to_whole_clusters() {
+        assert_eq!(round_up(0, 4096), 0);
+        assert_eq!(round_up(1, 4096), 4096);
+        assert_eq!(round_up(4096, 4096), 4096);
+        assert_eq!(round_up(4097, 4096), 8192);
+        assert_eq!(round_up(123, 0), 123);
+    }
+}
@@ -0,0 +1,374 @@
+// Windows environment-variable expansion (%ProgramFiles%, %APPDATA%, ...) for paths read from the registry.
+// Variables come from an environment provider: the process we run in, the Environment keys of the
+// registry being scanned (so an offline image expands to its own drive letters and profiles), or a fixture
+use std::collections::HashMap;
+
+use crate::services::registry_source::{RegistryHive, RegistryKey, RegistrySource, RegistryValue};
+use crate::services::user_profiles::{is_sid, UserProfile, PROFILE_LIST_PATH, VOLATILE_ENVIRONMENT_PATH};
+
+pub const SYSTEM_ENVIRONMENT_PATH: &str = "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment";
+pub const USER_ENVIRONMENT_PATH: &str = "Environment";
+const WINDOWS_NT_CURRENT_VERSION_PATH: &str = "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion";
+const WINDOWS_CURRENT_VERSION_PATH: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion";
+
+// Deep enough for real chains (%TEMP% -> %LOCALAPPDATA% -> %USERPROFILE% -> %SystemDrive%), and a stop
+// for variables that refer to each other
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+/// The value of one variable
+#[derive(Debug, Clone, PartialEq)]
+pub enum Variable {
+    Literal(String),    // REG_SZ, or a process environment value: used as is
+    Expandable(String), // REG_EXPAND_SZ: may refer to other variables
+}
+
+/// Where variable values come from. Names are matched case-insensitively, as Windows does
+pub trait EnvironmentProvider {
+    fn get(&self, name: &str) -> Option<Variable>;
+}
+
+/// Expand every %NAME% the environment knows. Unknown variables and lone % signs are left as written,
+/// as ExpandEnvironmentStrings does
+pub fn expand(text: &str, environment: &dyn EnvironmentProvider) -> String {
+    expand_at_depth(text, environment, 0)
+}
+
+fn expand_at_depth(text: &str, environment: &dyn EnvironmentProvider, depth: usize) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = match after.find('%') {
+            Some(end) => end,
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        };
+        let name = &after[..end];
+        let value = if name.is_empty() { None } else { environment.get(name) };
+        match value {
+            Some(Variable::Literal(value)) => expanded.push_str(&value),
+            Some(Variable::Expandable(value)) if depth < MAX_EXPANSION_DEPTH => {
+                expanded.push_str(&expand_at_depth(&value, environment, depth + 1));
+            }
+            Some(Variable::Expandable(_)) => {
+                println!("DEBUG: Not expanding %{}%, its value refers back to itself", name);
+                expanded.push('%');
+                expanded.push_str(name);
+                expanded.push('%');
+            }
+            None => {
+                // The closing % may open the next variable ("50%%TEMP%")
+                expanded.push('%');
+                expanded.push_str(name);
+                rest = &after[end..];
+                continue;
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Variables held in memory: a fixture, or what was read from a registry
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    variables: HashMap<String, Variable>, // Keyed by the uppercased name
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A fixture; every value may refer to other variables
+    pub fn from_pairs<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut environment = Environment::new();
+        for (name, value) in pairs {
+            environment.set(name, Variable::Expandable(value.to_string()));
+        }
+        environment
+    }
+
+    /// The folders of a stock Windows install on C:, for whatever a registry does not record
+    pub fn windows_defaults() -> Self {
+        Environment::from_pairs([
+            ("SystemDrive", "C:"),
+            ("SystemRoot", "%SystemDrive%\\Windows"),
+            ("windir", "%SystemRoot%"),
+            ("SystemDirectory", "%SystemRoot%\\System32"),
+            ("SystemPath", "%SystemRoot%\\System32"),
+            ("ProgramFiles", "%SystemDrive%\\Program Files"),
+            ("ProgramFiles(x86)", "%SystemDrive%\\Program Files (x86)"),
+            ("ProgramW6432", "%ProgramFiles%"),
+            ("CommonProgramFiles", "%ProgramFiles%\\Common Files"),
+            ("CommonProgramFiles(x86)", "%ProgramFiles(x86)%\\Common Files"),
+            ("CommonProgramW6432", "%CommonProgramFiles%"),
+            ("ProgramData", "%SystemDrive%\\ProgramData"),
+            ("ALLUSERSPROFILE", "%ProgramData%"),
+            ("PUBLIC", "%SystemDrive%\\Users\\Public"),
+            ("USERPROFILE", "%SystemDrive%\\Users\\%USERNAME%"),
+            ("HOMEDRIVE", "%SystemDrive%"),
+            ("HOMEPATH", "\\Users\\%USERNAME%"),
+            ("APPDATA", "%USERPROFILE%\\AppData\\Roaming"),
+            ("LOCALAPPDATA", "%USERPROFILE%\\AppData\\Local"),
+            ("TEMP", "%LOCALAPPDATA%\\Temp"),
+            ("TMP", "%LOCALAPPDATA%\\Temp"),
+        ])
+    }
+
+    /// The machine-wide environment of the Windows installation `registry` belongs to. The SOFTWARE hive
+    /// records the system drive, Program Files and ProfileList folders; the SYSTEM hive's Session Manager
+    /// Environment, when it is there, has the final say
+    pub fn from_registry(registry: &dyn RegistrySource) -> Self {
+        let mut environment = Environment::windows_defaults();
+
+        if let Some(current_version) = registry.open_key(RegistryHive::LocalMachine, WINDOWS_NT_CURRENT_VERSION_PATH) {
+            if let Some(system_root) = current_version.get_string("SystemRoot") {
+                if let Some(drive) = system_root.get(..2).filter(|drive| drive.ends_with(':')) {
+                    environment.set("SystemDrive", Variable::Literal(drive.to_string()));
+                }
+                environment.set("SystemRoot", Variable::Literal(system_root));
+            }
+        }
+
+        if let Some(current_version) = registry.open_key(RegistryHive::LocalMachine, WINDOWS_CURRENT_VERSION_PATH) {
+            for (value_name, variable) in [
+                ("ProgramFilesDir", "ProgramFiles"),
+                ("ProgramFilesDir (x86)", "ProgramFiles(x86)"),
+                ("ProgramW6432Dir", "ProgramW6432"),
+                ("CommonFilesDir", "CommonProgramFiles"),
+                ("CommonFilesDir (x86)", "CommonProgramFiles(x86)"),
+                ("CommonW6432Dir", "CommonProgramW6432"),
+            ] {
+                if let Some(value) = current_version.get_value(value_name).and_then(variable_from_value) {
+                    environment.set(variable, value);
+                }
+            }
+        }
+
+        if let Some(profile_list) = registry.open_key(RegistryHive::LocalMachine, PROFILE_LIST_PATH) {
+            if let Some(program_data) = profile_list.get_value("ProgramData").and_then(variable_from_value) {
+                environment.set("ProgramData", program_data);
+            }
+            if let Some(public) = profile_list.get_value("Public").and_then(variable_from_value) {
+                environment.set("PUBLIC", public);
+            }
+            // Profiles of users not resolved to a ProfileImagePath are assumed to sit in the profiles folder
+            if let Some(profiles_directory) = profile_list.get_string("ProfilesDirectory") {
+                environment.set("USERPROFILE", Variable::Expandable(format!("{}\\%USERNAME%", profiles_directory.trim_end_matches('\\'))));
+            }
+        }
+
+        if let Some(system_environment) = registry.open_key(RegistryHive::LocalMachine, SYSTEM_ENVIRONMENT_PATH) {
+            environment.merge_key(system_environment.as_ref());
+        }
+
+        environment
+    }
+
+    /// This environment as seen by one user: their profile folder, then their own Environment key and,
+    /// while they are logged on, the Volatile Environment Windows writes for the session
+    pub fn for_user(&self, profile: &UserProfile, user_root: Option<&dyn RegistryKey>) -> Self {
+        let mut environment = self.clone();
+
+        // Hives nobody could be resolved for are named by their SID, which is no user name
+        if !is_sid(&profile.user_name) {
+            environment.set("USERNAME", Variable::Literal(profile.user_name.clone()));
+        }
+        if let Some(profile_path) = &profile.profile_path {
+            // ProfileImagePath is REG_EXPAND_SZ, typically %SystemDrive%\Users\<name>
+            environment.set("USERPROFILE", Variable::Expandable(profile_path.trim_end_matches('\\').to_string()));
+            if let Some(home_path) = profile_path.find('\\').map(|index| &profile_path[index..]) {
+                environment.set("HOMEPATH", Variable::Expandable(home_path.to_string()));
+            }
+        }
+
+        environment.for_session(user_root)
+    }
+
+    /// This environment with a user hive's Environment and Volatile Environment keys on top, e.g. those of
+    /// HKEY_CURRENT_USER for the user running the scan
+    pub fn for_session(&self, user_root: Option<&dyn RegistryKey>) -> Self {
+        let mut environment = self.clone();
+        if let Some(user_root) = user_root {
+            for path in [USER_ENVIRONMENT_PATH, VOLATILE_ENVIRONMENT_PATH] {
+                if let Some(key) = user_root.open_subkey(path) {
+                    environment.merge_key(key.as_ref());
+                }
+            }
+        }
+        environment
+    }
+
+    pub fn set(&mut self, name: &str, value: Variable) {
+        self.variables.insert(name.to_uppercase(), value);
+    }
+
+    // Every string value of an Environment key; Path is left out, it is a search list and never a folder
+    fn merge_key(&mut self, key: &dyn RegistryKey) {
+        for name in key.value_names() {
+            if name.is_empty() || name.eq_ignore_ascii_case("Path") {
+                continue;
+            }
+            if let Some(value) = key.get_value(&name).and_then(variable_from_value) {
+                self.set(&name, value);
+            }
+        }
+    }
+}
+
+impl EnvironmentProvider for Environment {
+    fn get(&self, name: &str) -> Option<Variable> {
+        self.variables.get(&name.to_uppercase()).cloned()
+    }
+}
+
+fn variable_from_value(value: RegistryValue) -> Option<Variable> {
+    match value {
+        RegistryValue::String(value) => Some(Variable::Literal(value)),
+        RegistryValue::ExpandString(value) => Some(Variable::Expandable(value)),
+        _ => None,
+    }
+}
+
+/// The environment of this process, which on Windows is the logged-on user's. Variables it does not have
+/// (all of them, when running on another OS) fall back to the stock Windows folders
+pub struct ProcessEnvironment {
+    fallback: Environment,
+}
+
+impl ProcessEnvironment {
+    pub fn new() -> Self {
+        ProcessEnvironment { fallback: Environment::windows_defaults() }
+    }
+}
+
+impl Default for ProcessEnvironment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnvironmentProvider for ProcessEnvironment {
+    fn get(&self, name: &str) -> Option<Variable> {
+        // Windows looks names up case-insensitively already; elsewhere the variables are searched
+        let value = std::env::var(name).ok()
+            .or_else(|| std::env::vars().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value))
+            .filter(|value| !value.is_empty());
+        match value {
+            Some(value) => Some(Variable::Literal(value)),
+            None => self.fallback.get(name),
+        }
+    }
+}
+
+/// What a 32-bit process sees on 64-bit Windows: %ProgramFiles% and %CommonProgramFiles% are the (x86) folders
+pub struct Wow64Environment<'a>(pub &'a dyn EnvironmentProvider);
+
+impl EnvironmentProvider for Wow64Environment<'_> {
+    fn get(&self, name: &str) -> Option<Variable> {
+        let redirected = if name.eq_ignore_ascii_case("ProgramFiles") {
+            self.0.get("ProgramFiles(x86)")
+        } else if name.eq_ignore_ascii_case("CommonProgramFiles") {
+            self.0.get("CommonProgramFiles(x86)")
+        } else {
+            None
+        };
+        redirected.or_else(|| self.0.get(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::registry_source::MemoryRegistry;
+
+    fn alice() -> Environment {
+        let mut environment = Environment::windows_defaults();
+        environment.set("USERNAME", Variable::Literal("alice".to_string()));
+        environment
+    }
+
+    #[test]
+    fn expands_nested_variables() {
+        let environment = alice();
+        // %TEMP% -> %LOCALAPPDATA% -> %USERPROFILE% -> %SystemDrive% and %USERNAME%
+        assert_eq!(expand("%TEMP%\\setup.log", &environment), "C:\\Users\\alice\\AppData\\Local\\Temp\\setup.log");
+        assert_eq!(expand("%CommonProgramFiles(x86)%\\Vendor", &environment), "C:\\Program Files (x86)\\Common Files\\Vendor");
+        assert_eq!(expand("%windir%\\System32", &environment), "C:\\Windows\\System32");
+    }
+
+    #[test]
+    fn matches_names_ignoring_case() {
+        let environment = alice();
+        assert_eq!(expand("%programfiles%\\App", &environment), "C:\\Program Files\\App");
+        assert_eq!(expand("%PROGRAMFILES(X86)%\\App", &environment), "C:\\Program Files (x86)\\App");
+        assert_eq!(expand("%LocalAppData%", &environment), "C:\\Users\\alice\\AppData\\Local");
+    }
+
+    #[test]
+    fn leaves_unknown_variables_and_lone_percent_signs() {
+        let environment = alice();
+        assert_eq!(expand("%FOO%\\bar", &environment), "%FOO%\\bar");
+        assert_eq!(expand("50% done", &environment), "50% done");
+        assert_eq!(expand("%%", &environment), "%%");
+        assert_eq!(expand("50%%TEMP%", &environment), "50%C:\\Users\\alice\\AppData\\Local\\Temp");
+        assert_eq!(expand("%FOO%%ProgramData%", &environment), "%FOO%C:\\ProgramData");
+        assert_eq!(expand("no variables", &environment), "no variables");
+    }
+
+    #[test]
+    fn stops_at_variables_that_refer_to_each_other() {
+        let environment = Environment::from_pairs([("A", "%B%\\a"), ("B", "%A%\\b")]);
+        let expanded = expand("%A%", &environment);
+        assert!(expanded.contains('%'));
+        assert!(expanded.ends_with("\\b\\a"));
+    }
+
+    #[test]
+    fn expands_only_expandable_values() {
+        let mut environment = alice();
+        environment.set("LITERAL", Variable::Literal("%SystemDrive%\\Tools".to_string()));
+        environment.set("EXPANDABLE", Variable::Expandable("%SystemDrive%\\Tools".to_string()));
+        assert_eq!(expand("%LITERAL%", &environment), "%SystemDrive%\\Tools");
+        assert_eq!(expand("%EXPANDABLE%", &environment), "C:\\Tools");
+    }
+
+    #[test]
+    fn reads_the_environment_of_a_registry() {
+        let mut registry = MemoryRegistry::new();
+        let machine = registry.hive_mut(RegistryHive::LocalMachine);
+        machine.create_path(WINDOWS_NT_CURRENT_VERSION_PATH).set_value("SystemRoot", RegistryValue::String("D:\\WINDOWS".to_string()));
+        machine.create_path(WINDOWS_CURRENT_VERSION_PATH).set_value("ProgramFilesDir", RegistryValue::String("D:\\Program Files".to_string()));
+        machine.create_path(PROFILE_LIST_PATH).set_value("ProfilesDirectory", RegistryValue::ExpandString("%SystemDrive%\\Profiles".to_string()));
+        let session = machine.create_path(SYSTEM_ENVIRONMENT_PATH);
+        session.set_value("TEMP", RegistryValue::ExpandString("%SystemRoot%\\TEMP".to_string()));
+        session.set_value("TOOLS", RegistryValue::String("%SystemDrive%\\Tools".to_string()));
+        session.set_value("Path", RegistryValue::ExpandString("%SystemRoot%\\system32".to_string()));
+        session.set_value("BUILD", RegistryValue::Dword(1));
+        let user = registry.hive_mut(RegistryHive::Users).create_path("S-1-5-21-1-2-3-1001\\Environment");
+        user.set_value("TEMP", RegistryValue::ExpandString("%USERPROFILE%\\AppData\\Local\\Temp".to_string()));
+
+        let environment = Environment::from_registry(&registry);
+        assert_eq!(expand("%SystemDrive%|%windir%|%ProgramFiles%", &environment), "D:|D:\\WINDOWS|D:\\Program Files");
+        assert_eq!(expand("%ProgramFiles(x86)%", &environment), "D:\\Program Files (x86)");
+        assert_eq!(expand("%TEMP%", &environment), "D:\\WINDOWS\\TEMP");
+        assert_eq!(expand("%TOOLS%", &environment), "%SystemDrive%\\Tools");
+        assert_eq!(expand("%Path%|%BUILD%", &environment), "%Path%|%BUILD%");
+        assert_eq!(expand("%USERPROFILE%", &environment), "D:\\Profiles\\%USERNAME%");
+
+        let profile = UserProfile {
+            sid: Some("S-1-5-21-1-2-3-1001".to_string()),
+            user_name: "bob".to_string(),
+            profile_path: Some("%SystemDrive%\\Users\\bob".to_string()),
+        };
+        let user_root = registry.open_key(RegistryHive::Users, "S-1-5-21-1-2-3-1001");
+        let bob = environment.for_user(&profile, user_root.as_deref());
+        assert_eq!(expand("%TEMP%", &bob), "D:\\Users\\bob\\AppData\\Local\\Temp");
+        assert_eq!(expand("%APPDATA%|%HOMEPATH%", &bob), "D:\\Users\\bob\\AppData\\Roaming|\\Users\\bob");
+    }
+}
@@ -0,0 +1,229 @@
+// File hashes for inventory and IOC matching: SHA-256, plus SHA-1 and MD5 because older threat feeds
+// still publish those. All three come from one read of the file, and results are cached by path, keyed
+// on size and modification time, so unchanged executables are not read again on the next scan
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use chrono::{DateTime, TimeZone, Utc};
+use md5::Md5;
+use serde::{Serialize, Deserialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+const HASH_CACHE_FILE: &str = "hash_cache.json";
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum HashType {
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+impl HashType {
+    /// The algorithm a hex digest of this length comes from
+    pub fn from_hex_length(length: usize) -> Option<Self> {
+        match length {
+            64 => Some(HashType::Sha256),
+            40 => Some(HashType::Sha1),
+            32 => Some(HashType::Md5),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashType::Sha256 => "SHA-256",
+            HashType::Sha1 => "SHA-1",
+            HashType::Md5 => "MD5",
+        }
+    }
+}
+
+/// Lowercase hex digests of one file
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileHashes {
+    pub sha256: String,
+    pub sha1: String,
+    pub md5: String,
+}
+
+impl FileHashes {
+    pub fn get(&self, hash_type: HashType) -> &str {
+        match hash_type {
+            HashType::Sha256 => &self.sha256,
+            HashType::Sha1 => &self.sha1,
+            HashType::Md5 => &self.md5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HashedFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub hashes: FileHashes,
+    pub from_cache: bool, // Size and modification time matched an earlier hashing, so the file was not read
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct CachedHashes {
+    size: u64,
+    modified_ns: Option<u128>,
+    hashes: FileHashes,
+}
+
+/// Hashes of earlier runs, by path
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct HashCache {
+    entries: HashMap<String, CachedHashes>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl HashCache {
+    /// The cache from the app's data folder; a missing or unreadable one starts empty
+    pub fn load() -> Self {
+        let cache: HashCache = fs::read_to_string(get_hash_cache_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        println!("DEBUG: Loaded {} cached file hashes", cache.entries.len());
+        cache
+    }
+
+    /// Write the cache back if anything was added
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.changed {
+            return Ok(());
+        }
+        // Entries for files that are gone would only grow the cache
+        self.entries.retain(|path, _| Path::new(path).is_file());
+        let path = get_hash_cache_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(&self)?)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Hash a file, or take its hashes from the cache when its size and modification time are unchanged
+    pub fn hash_file(&mut self, path: &Path) -> Result<HashedFile, String> {
+        let metadata = fs::metadata(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let modified_ns = metadata.modified().ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_nanos());
+        let modified = modified_ns
+            .and_then(|ns| i64::try_from(ns / 1_000_000_000).ok())
+            .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single());
+        let key = path.to_string_lossy().to_string();
+
+        if let Some(cached) = self.entries.get(&key).filter(|cached| cached.size == metadata.len() && cached.modified_ns == modified_ns) {
+            return Ok(HashedFile {
+                path: path.to_string_lossy().to_string(),
+                size: cached.size,
+                modified,
+                hashes: cached.hashes.clone(),
+                from_cache: true,
+            });
+        }
+
+        let hashes = hash_file(path)?;
+        self.entries.insert(key, CachedHashes { size: metadata.len(), modified_ns, hashes: hashes.clone() });
+        self.changed = true;
+        Ok(HashedFile {
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified,
+            hashes,
+            from_cache: false,
+        })
+    }
+}
+
+fn get_hash_cache_path() -> PathBuf {
+    let mut path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("software-scope");
+    path.push(HASH_CACHE_FILE);
+    path
+}
+
+/// SHA-256, SHA-1 and MD5 of a file in one pass
+pub fn hash_file(path: &Path) -> Result<FileHashes, String> {
+    let mut file = File::open(path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    let mut sha256 = Sha256::new();
+    let mut sha1 = Sha1::new();
+    let mut md5 = Md5::new();
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        sha256.update(&buffer[..read]);
+        sha1.update(&buffer[..read]);
+        md5.update(&buffer[..read]);
+    }
+    Ok(FileHashes {
+        sha256: to_hex(&sha256.finalize()),
+        sha1: to_hex(&sha1.finalize()),
+        md5: to_hex(&md5.finalize()),
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    const ABC_SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+    #[test]
+    fn tells_hash_types_by_digest_length() {
+        assert_eq!(HashType::from_hex_length(ABC_SHA256.len()), Some(HashType::Sha256));
+        assert_eq!(HashType::from_hex_length(ABC_SHA1.len()), Some(HashType::Sha1));
+        assert_eq!(HashType::from_hex_length(ABC_MD5.len()), Some(HashType::Md5));
+        assert_eq!(HashType::from_hex_length(128), None);
+        assert_eq!(HashType::from_hex_length(0), None);
+    }
+
+    #[test]
+    fn reuses_cached_hashes_until_size_or_time_change() {
+        let dir = std::env::temp_dir().join(format!("hash-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.exe");
+        fs::write(&path, b"abc").unwrap();
+        let mut cache = HashCache::default();
+
+        let first = cache.hash_file(&path).unwrap();
+        assert!(!first.from_cache && cache.changed);
+        assert_eq!(first.hashes, FileHashes { sha256: ABC_SHA256.to_string(), sha1: ABC_SHA1.to_string(), md5: ABC_MD5.to_string() });
+        assert_eq!(first.size, 3);
+        let second = cache.hash_file(&path).unwrap();
+        assert!(second.from_cache);
+        assert_eq!(second.hashes, first.hashes);
+
+        // Another modification time means the file is read again
+        let key = path.to_string_lossy().to_string();
+        cache.entries.get_mut(&key).unwrap().modified_ns = Some(0);
+        assert!(!cache.hash_file(&path).unwrap().from_cache);
+
+        fs::write(&path, b"abcd").unwrap();
+        let grown = cache.hash_file(&path).unwrap();
+        assert!(!grown.from_cache);
+        assert_eq!(grown.size, 4);
+        assert_ne!(grown.hashes.sha256, ABC_SHA256);
+
+        assert!(cache.hash_file(&dir.join("missing.exe")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
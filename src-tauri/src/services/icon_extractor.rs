@@ -3,9 +3,10 @@ use std::fs;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use windows_icons::get_icon_base64_by_path;
-use walkdir::WalkDir;
 use dirs;
 
+use crate::services::path_resolver::PathResolver;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ExtractedIcon {
     pub data: String,        // Base64 encoded image data
@@ -233,7 +234,7 @@ impl IconExtractor {
 }
 
 // Helper function to find executable path from registry icon path
-pub fn resolve_icon_path(icon_path: &str) -> Option<String> {
+pub fn resolve_icon_path(resolver: &PathResolver, icon_path: &str) -> Option<String> {
     println!("🔍 resolve_icon_path called with: '{}'", icon_path);
     
     if icon_path.is_empty() {
@@ -241,64 +242,28 @@ pub fn resolve_icon_path(icon_path: &str) -> Option<String> {
         return None;
     }
 
-    // Handle different icon path formats
-    let path = if icon_path.contains(',') {
-        // Format: "C:\path\to\file.exe,0" or "C:\path\to\file.exe,1"
-        icon_path.split(',').next().unwrap_or("").trim()
-    } else {
-        icon_path.trim()
-    };
-
-    // Remove quotes if present
-    let path = path.trim_matches('"');
-    println!("🔍 Processed path: '{}'", path);
-
-    // Check if it's an absolute path
-    if Path::new(path).is_absolute() {
-        println!("🔍 Path is absolute, checking if exists: {}", path);
-        if Path::new(path).exists() {
-            println!("✅ Absolute path exists: {}", path);
-            return Some(path.to_string());
-        } else {
-            println!("❌ Absolute path does not exist: {}", path);
-        }
-    } else {
-        println!("🔍 Path is not absolute, will search in common locations");
-    }
-
-    // Try to find the file in common locations
-    let common_paths = [
-        r"C:\Program Files",
-        r"C:\Program Files (x86)",
-        r"C:\ProgramData",
-        r"C:\Users\Public",
-        r"C:\Windows\System32",
-        r"C:\Windows",
-        r"C:\Windows\SysWOW64",
-        r"C:\Program Files\Common Files",
-        r"C:\Program Files (x86)\Common Files",
-        r"C:\Program Files\Microsoft Office",
-        r"C:\Program Files (x86)\Microsoft Office",
-    ];
-
-    for base_path in &common_paths {
-        if let Some(found_path) = find_file_recursive(base_path, &Path::new(path).file_name()?.to_str()?) {
-            return Some(found_path);
-        }
+    // "C:\path\to\file.exe,0", quoted or not, with its environment variables expanded
+    if let Some(path) = resolver.resolve_icon_file(icon_path) {
+        println!("✅ Icon path exists: {}", path);
+        return Some(path);
     }
 
-    None
+    // Relative paths, or files that moved: try to find the file in common locations
+    println!("🔍 Icon path not found as written, will search in common locations");
+    let path = icon_path.split(',').next().unwrap_or("").trim().trim_matches('"');
+    let file_name = path.rsplit(['\\', '/']).next().filter(|name| !name.is_empty())?;
+    resolver.find_file(file_name)
 }
 
 // Enhanced function to resolve icon path with VF managed app fallback
-pub fn resolve_icon_path_with_vf_fallback(icon_path: &str, program_name: &str, publisher: Option<&str>, is_vf_deployed: bool) -> Option<String> {
+pub fn resolve_icon_path_with_vf_fallback(resolver: &PathResolver, icon_path: &str, program_name: &str, publisher: Option<&str>, is_vf_deployed: bool) -> Option<String> {
     println!("🔍 resolve_icon_path_with_vf_fallback called for: '{}', VF: {}, icon_path: '{}'", program_name, is_vf_deployed, icon_path);
     
     // If this is a VF managed app, try Program Files scanning first (even if no registry icon path)
     if is_vf_deployed {
         println!("🔍 VF managed app '{}', scanning Program Files...", program_name);
         
-        if let Some(program_files_path) = find_vf_app_executable(program_name, publisher) {
+        if let Some(program_files_path) = find_vf_app_executable(resolver, program_name, publisher) {
             println!("✅ Found VF app executable in Program Files: {}", program_files_path);
             return Some(program_files_path);
         } else {
@@ -308,7 +273,7 @@ pub fn resolve_icon_path_with_vf_fallback(icon_path: &str, program_name: &str, p
     
     // If we have an icon path, try standard resolution
     if !icon_path.is_empty() {
-        if let Some(resolved_path) = resolve_icon_path(icon_path) {
+        if let Some(resolved_path) = resolve_icon_path(resolver, icon_path) {
             println!("✅ Standard resolution succeeded: {}", resolved_path);
             return Some(resolved_path);
         } else {
@@ -322,7 +287,7 @@ pub fn resolve_icon_path_with_vf_fallback(icon_path: &str, program_name: &str, p
     if is_vf_deployed {
         println!("🔍 Registry icon path failed for VF managed app '{}', scanning Program Files...", program_name);
         
-        if let Some(program_files_path) = find_vf_app_executable(program_name, publisher) {
+        if let Some(program_files_path) = find_vf_app_executable(resolver, program_name, publisher) {
             println!("✅ Found VF app executable in Program Files: {}", program_files_path);
             return Some(program_files_path);
         } else {
@@ -336,7 +301,7 @@ pub fn resolve_icon_path_with_vf_fallback(icon_path: &str, program_name: &str, p
 }
 
 // Find VF managed app executable in Program Files
-fn find_vf_app_executable(program_name: &str, publisher: Option<&str>) -> Option<String> {
+fn find_vf_app_executable(resolver: &PathResolver, program_name: &str, publisher: Option<&str>) -> Option<String> {
     println!("🔍 find_vf_app_executable called for: '{}', publisher: {:?}", program_name, publisher);
     
     // Special debugging for known problematic applications
@@ -347,14 +312,9 @@ fn find_vf_app_executable(program_name: &str, publisher: Option<&str>) -> Option
         }
     }
     
-    let program_files_paths = vec![
-        r"C:\Program Files",
-        r"C:\Program Files (x86)",
-    ];
-    
-    for program_files in program_files_paths {
-        println!("🔍 Scanning directory: {}", program_files);
-        if let Ok(entries) = std::fs::read_dir(program_files) {
+    for program_files in resolver.program_files_folders() {
+        println!("🔍 Scanning directory: {}", program_files.display());
+        if let Ok(entries) = std::fs::read_dir(&program_files) {
             for entry in entries.flatten() {
                 if let Some(folder_name) = entry.file_name().to_str() {
                     let folder_name_lower = folder_name.to_lowercase();
@@ -405,7 +365,7 @@ fn find_vf_app_executable(program_name: &str, publisher: Option<&str>) -> Option
                 }
             }
         } else {
-            println!("❌ Failed to read directory: {}", program_files);
+            println!("❌ Failed to read directory: {}", program_files.display());
         }
     }
     
@@ -688,19 +648,3 @@ fn find_alternative_executable_with_icon(folder_path: &str, original_exe_path: &
     println!("❌ No alternative executables or icon files found in: {}", folder_path);
     None
 }
-
-
-fn find_file_recursive(base_path: &str, filename: &str) -> Option<String> {
-    println!("🔍 Recursively searching for '{}' in '{}'", filename, base_path);
-    for entry in WalkDir::new(base_path).max_depth(5) {  // Increased from 3 to 5
-        if let Ok(entry) = entry {
-            if entry.file_name().to_str() == Some(filename) {
-                let found_path = entry.path().to_string_lossy().to_string();
-                println!("✅ Found file: {}", found_path);
-                return Some(found_path);
-            }
-        }
-    }
-    println!("❌ File '{}' not found in '{}'", filename, base_path);
-    None
-}
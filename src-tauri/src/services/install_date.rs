@@ -0,0 +1,192 @@
+// Typed install dates: parsing the many formats installers write, and recording how much to trust the result
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::services::registry_source::{filetime_to_datetime, RegistryValue};
+
+// Ordered so that `>=` means "at least this trustworthy"
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DateConfidence {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum InstallDateSource {
+    InstallDate,          // The uninstall key's own InstallDate value
+    MsiInstallProperties, // Windows Installer's InstallProperties for the product
+    InstallFolderCreated, // Creation time of the install location
+    KeyLastWriteTime,     // Last write of the uninstall key; also moves on repair or update
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InstallDateInfo {
+    pub date: NaiveDate,
+    pub timestamp: Option<DateTime<Utc>>, // Only when the source has a time of day
+    pub source: InstallDateSource,
+    pub confidence: DateConfidence,
+}
+
+impl InstallDateInfo {
+    pub fn from_timestamp(timestamp: DateTime<Utc>, source: InstallDateSource, confidence: DateConfidence) -> Self {
+        InstallDateInfo {
+            date: timestamp.date_naive(),
+            timestamp: Some(timestamp),
+            source,
+            confidence,
+        }
+    }
+}
+
+/// Parse an InstallDate string. YYYYMMDD and ISO forms are trusted; locale dates only as far as
+/// day and month can be told apart
+pub fn parse_registry_date(raw: &str) -> Option<(NaiveDate, DateConfidence)> {
+    let raw = raw.trim();
+
+    if raw.len() == 8 && raw.chars().all(|c| c.is_ascii_digit()) {
+        let date = NaiveDate::parse_from_str(raw, "%Y%m%d").ok()?;
+        return plausible(date).map(|date| (date, DateConfidence::High));
+    }
+
+    // ISO dates, possibly followed by a time. %Y takes any number of digits, so "22.03.24" is kept
+    // from being read as the year 22 by asking for four
+    let prefix = raw.get(..10).unwrap_or(raw);
+    if prefix.get(..4).map_or(false, |year| year.chars().all(|c| c.is_ascii_digit())) {
+        for format in ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"] {
+            if let Ok(date) = NaiveDate::parse_from_str(prefix, format) {
+                return plausible(date).map(|date| (date, DateConfidence::High));
+            }
+        }
+    }
+
+    parse_locale_date(raw)
+}
+
+// dd.mm.yyyy, mm/dd/yyyy, dd/mm/yyyy and dd-mm-yyyy, optionally followed by a time
+fn parse_locale_date(raw: &str) -> Option<(NaiveDate, DateConfidence)> {
+    let date_part = raw.split_whitespace().next()?;
+    let separator = date_part.chars().find(|c| matches!(c, '.' | '/' | '-'))?;
+    let parts: Vec<u32> = date_part
+        .split(separator)
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let year = match parts[2] {
+        year @ 1000..=9999 => year as i32,
+        year @ 0..=99 => 2000 + year as i32,
+        _ => return None,
+    };
+
+    let (day, month, confidence) = if parts[0] > 12 {
+        (parts[0], parts[1], DateConfidence::Medium)
+    } else if parts[1] > 12 {
+        (parts[1], parts[0], DateConfidence::Medium)
+    } else if separator == '/' {
+        // Ambiguous: slashes are most often the US month/day order
+        (parts[1], parts[0], DateConfidence::Low)
+    } else {
+        (parts[0], parts[1], DateConfidence::Low)
+    };
+
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    plausible(date).map(|date| (date, confidence))
+}
+
+/// Interpret an InstallTime value: a FILETIME, Unix seconds, or a time of day to combine with the date
+pub fn parse_install_time(value: &RegistryValue, date: Option<NaiveDate>) -> Option<DateTime<Utc>> {
+    let timestamp = match value {
+        RegistryValue::Qword(filetime) => filetime_to_datetime(*filetime)?,
+        RegistryValue::Dword(seconds) => Utc.timestamp_opt(*seconds as i64, 0).single()?,
+        RegistryValue::String(raw) | RegistryValue::ExpandString(raw) => {
+            let raw = raw.trim();
+            if let Ok(datetime) = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S") {
+                Utc.from_utc_datetime(&datetime)
+            } else {
+                let time = NaiveTime::parse_from_str(raw, "%H:%M:%S")
+                    .or_else(|_| NaiveTime::parse_from_str(raw, "%H:%M"))
+                    .ok()?;
+                Utc.from_utc_datetime(&date?.and_time(time))
+            }
+        }
+        _ => return None,
+    };
+
+    plausible(timestamp.date_naive()).map(|_| timestamp)
+}
+
+// Reject placeholder dates (1601, 1970, 2099...) that some installers write
+fn plausible(date: NaiveDate) -> Option<NaiveDate> {
+    let latest = Utc::now().date_naive().year() + 1;
+    if date.year() >= 1990 && date.year() <= latest {
+        Some(date)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn trusts_compact_and_iso_dates() {
+        let expected = Some((date(2024, 3, 22), DateConfidence::High));
+        for raw in ["20240322", " 20240322 ", "2024-03-22", "2024-03-22T10:30:00", "2024/03/22 10:30", "2024.03.22"] {
+            assert_eq!(parse_registry_date(raw), expected, "{}", raw);
+        }
+        assert_eq!(parse_registry_date("20241332"), None);
+    }
+
+    #[test]
+    fn reads_locale_dates_as_far_as_they_can_be_told_apart() {
+        use DateConfidence::*;
+        for (raw, expected) in [
+            ("22.03.2024", (date(2024, 3, 22), Medium)),
+            ("03/22/2024", (date(2024, 3, 22), Medium)),
+            ("22/03/2024", (date(2024, 3, 22), Medium)),
+            ("22-03-2024", (date(2024, 3, 22), Medium)),
+            // Day and month both fit: slashes are read month first, dots and dashes day first
+            ("03/04/2024", (date(2024, 3, 4), Low)),
+            ("3/4/2024 10:30:00 AM", (date(2024, 3, 4), Low)),
+            ("03.04.2024", (date(2024, 4, 3), Low)),
+            ("22.03.24", (date(2024, 3, 22), Medium)),
+            ("1/2/05", (date(2005, 1, 2), Low)),
+        ] {
+            assert_eq!(parse_registry_date(raw), Some(expected), "{}", raw);
+        }
+        for raw in ["", "unknown", "2024-13-45", "31.02.2024", "1.2", "1/2/123"] {
+            assert_eq!(parse_registry_date(raw), None, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn rejects_placeholder_dates() {
+        for raw in ["16010101", "19700101", "20990101", "1970-01-01", "01.01.1601", "12/31/2099", "01/01/99"] {
+            assert_eq!(parse_registry_date(raw), None, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn reads_install_times() {
+        let expected = Some(Utc.with_ymd_and_hms(2024, 3, 22, 10, 30, 0).unwrap());
+        assert_eq!(parse_install_time(&RegistryValue::Qword(133_555_770_000_000_000), None), expected);
+        assert_eq!(parse_install_time(&RegistryValue::Dword(1_711_103_400), None), expected);
+        assert_eq!(parse_install_time(&RegistryValue::String("2024-03-22 10:30:00".to_string()), None), expected);
+        assert_eq!(parse_install_time(&RegistryValue::String("10:30".to_string()), Some(date(2024, 3, 22))), expected);
+        assert_eq!(parse_install_time(&RegistryValue::ExpandString(" 10:30:00 ".to_string()), Some(date(2024, 3, 22))), expected);
+
+        // A time of day means nothing without the date
+        assert_eq!(parse_install_time(&RegistryValue::String("10:30:00".to_string()), None), None);
+        assert_eq!(parse_install_time(&RegistryValue::Qword(0), None), None);
+        assert_eq!(parse_install_time(&RegistryValue::Dword(0), None), None);
+        assert_eq!(parse_install_time(&RegistryValue::Binary(vec![1, 2, 3, 4]), None), None);
+    }
+}
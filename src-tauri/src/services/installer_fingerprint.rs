@@ -0,0 +1,163 @@
+// Which installer technology produced an entry: registry markers first, then the uninstall command,
+// then the uninstaller executable's own resources
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::services::command_line::{detect_installer_engine, parse_command_line_with, CommandKind, ParsedCommandLine};
+use crate::services::registry_source::RegistryKey;
+
+// Uninstallers larger than this are not read; the engines' own uninstallers are a few MB at most
+const MAX_UNINSTALLER_SIZE: u64 = 16 * 1024 * 1024;
+
+// Strings the engines leave in their uninstallers: manifest identities, version resources, section names
+const UNINSTALLER_MARKERS: [(&str, InstallerEngine); 6] = [
+    ("Nullsoft.NSIS.exehead", InstallerEngine::Nsis),
+    ("NullsoftInst", InstallerEngine::Nsis),
+    ("JR.Inno.Setup", InstallerEngine::InnoSetup),
+    ("Inno Setup", InstallerEngine::InnoSetup),
+    (".wixburn", InstallerEngine::WixBurn),
+    ("InstallShield", InstallerEngine::InstallShield),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum InstallerEngine {
+    WindowsInstaller,
+    Nsis,
+    InnoSetup,
+    InstallShield,
+    WixBurn,
+    Squirrel,
+    ClickOnce,
+    Chocolatey,
+    Msix,
+    Portable,
+    Unknown,
+}
+
+// Ordered so that `>=` means "at least this sure"
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InstallerTechnology {
+    pub engine: InstallerEngine,
+    pub confidence: Confidence,
+    pub evidence: Vec<String>, // What the verdict rests on, strongest first
+}
+
+impl InstallerTechnology {
+    pub fn new(engine: InstallerEngine, confidence: Confidence, evidence: &str) -> Self {
+        InstallerTechnology {
+            engine,
+            confidence,
+            evidence: vec![evidence.to_string()],
+        }
+    }
+}
+
+/// Fingerprint an uninstall key from its values alone, without touching the file system
+pub fn fingerprint_uninstall_key(key: &dyn RegistryKey, registry_name: &str) -> InstallerTechnology {
+    let has_value = |name: &str| key.get_value(name).is_some();
+
+    // Burn bundles register themselves with their bundle's upgrade code and cache path
+    for marker in ["BundleUpgradeCode", "BundleCachePath", "BundleProviderKey"] {
+        if has_value(marker) {
+            return InstallerTechnology::new(InstallerEngine::WixBurn, Confidence::High, &format!("Registry value {}", marker));
+        }
+    }
+    for marker in ["Inno Setup: App Path", "Inno Setup: Setup Version"] {
+        if has_value(marker) {
+            return InstallerTechnology::new(InstallerEngine::InnoSetup, Confidence::High, &format!("Registry value '{}'", marker));
+        }
+    }
+    if key.get_dword("WindowsInstaller").unwrap_or(0) == 1 {
+        return InstallerTechnology::new(InstallerEngine::WindowsInstaller, Confidence::High, "Registry value WindowsInstaller = 1");
+    }
+
+    let install_location = key.get_string("InstallLocation");
+    if let Some(location) = install_location.as_deref().filter(|location| is_chocolatey_path(location)) {
+        return InstallerTechnology::new(InstallerEngine::Chocolatey, Confidence::High, &format!("Installed under {}", location));
+    }
+
+    // Registry entries come from any machine, so the command line is parsed without probing the disk
+    let uninstall = key.get_string("UninstallString")
+        .or_else(|| key.get_string("QuietUninstallString"))
+        .and_then(|command| parse_command_line_with(&command, &|_| false));
+    match uninstall {
+        Some(uninstall) => fingerprint_command(&uninstall, registry_name, false),
+        None => InstallerTechnology::new(InstallerEngine::Unknown, Confidence::Low, "No registry markers or uninstall command"),
+    }
+}
+
+/// Fingerprint from an uninstall command line, for entries whose key is no longer at hand
+pub fn fingerprint_command(uninstall: &ParsedCommandLine, registry_name: &str, is_windows_installer: bool) -> InstallerTechnology {
+    let (engine, confidence) = detect_installer_engine(uninstall, registry_name, is_windows_installer);
+    let evidence = match uninstall.kind {
+        CommandKind::Msiexec => "Uninstall command runs msiexec".to_string(),
+        CommandKind::Rundll32 => format!("Uninstall command runs rundll32 {}", uninstall.dll_path.as_deref().unwrap_or_default()),
+        CommandKind::Executable if engine == InstallerEngine::Unknown => "No registry markers; uninstaller not recognised".to_string(),
+        CommandKind::Executable => format!("Uninstall command {}", uninstall.executable),
+    };
+    InstallerTechnology {
+        engine,
+        confidence,
+        evidence: vec![evidence],
+    }
+}
+
+/// Confirm or correct a registry verdict with the uninstaller's own resources. Registry markers
+/// that are already certain are never overruled
+pub fn refine_with_uninstaller(technology: &mut InstallerTechnology, uninstaller: &Path) {
+    let (engine, marker) = match scan_uninstaller(uninstaller) {
+        Some(found) => found,
+        None => return,
+    };
+    let evidence = format!("Uninstaller {} contains \"{}\"", uninstaller.display(), marker);
+
+    if engine == technology.engine {
+        technology.confidence = Confidence::High;
+        technology.evidence.push(evidence);
+    } else if technology.confidence < Confidence::High {
+        *technology = InstallerTechnology::new(engine, Confidence::High, &evidence);
+    }
+}
+
+/// Look for an installer engine's marker strings, ASCII or UTF-16, in an executable
+pub fn scan_uninstaller(path: &Path) -> Option<(InstallerEngine, &'static str)> {
+    let size = fs::metadata(path).ok()?.len();
+    if size > MAX_UNINSTALLER_SIZE {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if !bytes.starts_with(b"MZ") {
+        return None;
+    }
+    scan_uninstaller_bytes(&bytes)
+}
+
+pub fn scan_uninstaller_bytes(bytes: &[u8]) -> Option<(InstallerEngine, &'static str)> {
+    UNINSTALLER_MARKERS.iter().find_map(|(marker, engine)| {
+        let utf16: Vec<u8> = marker.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        if contains(bytes, marker.as_bytes()) || contains(bytes, &utf16) {
+            Some((*engine, *marker))
+        } else {
+            None
+        }
+    })
+}
+
+// Chocolatey's own portable packages and shims
+pub fn is_chocolatey_path(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.contains("\\chocolatey\\lib\\") || lower.contains("\\chocolatey\\bin\\")
+}
+
+/// Whether `needle` occurs anywhere in `haystack`; never for an empty needle
+pub fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|window| window == needle)
+}
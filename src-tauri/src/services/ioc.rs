@@ -0,0 +1,255 @@
+// Locally supplied indicators of compromise: file hashes from a CSV export or a STIX 2 style JSON file.
+// Only hash indicators are read; anything else in the feed is skipped
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+
+use crate::services::file_hash::{FileHashes, HashType};
+
+#[derive(Debug, Clone)]
+pub struct Indicator {
+    pub hash_type: HashType,
+    pub hash: String,         // Lowercase hex
+    pub name: Option<String>, // What the feed calls it: malware family, campaign, description
+}
+
+pub struct IocList {
+    pub source: String,
+    indicators: HashMap<String, Indicator>,
+}
+
+impl IocList {
+    /// Read a CSV or JSON IOC list, told apart by extension or, failing that, by content
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Could not read the IOC list {}: {}", path.display(), e))?;
+        let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let is_json = extension == "json" || (extension != "csv" && text.trim_start().starts_with(['{', '[']));
+        let indicators = if is_json { parse_json(&text)? } else { parse_csv(&text)? };
+
+        let mut list = IocList { source: path.to_string_lossy().to_string(), indicators: HashMap::new() };
+        for indicator in indicators {
+            // The first entry for a hash wins; later ones tend to be the same indicator from another feed
+            list.indicators.entry(indicator.hash.clone()).or_insert(indicator);
+        }
+        if list.is_empty() {
+            return Err(format!("No file hashes in the IOC list {}", path.display()));
+        }
+        println!("DEBUG: Loaded {} hash indicators from {}", list.len(), path.display());
+        Ok(list)
+    }
+
+    pub fn len(&self) -> usize {
+        self.indicators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indicators.is_empty()
+    }
+
+    /// Indicators matching any of a file's hashes
+    pub fn matches(&self, hashes: &FileHashes) -> Vec<&Indicator> {
+        [HashType::Sha256, HashType::Sha1, HashType::Md5]
+            .iter()
+            .filter_map(|hash_type| self.indicators.get(hashes.get(*hash_type)))
+            .collect()
+    }
+}
+
+/// A hex digest of one of the supported lengths, lowercased
+pub fn normalize_hash(text: &str) -> Option<(HashType, String)> {
+    let hash = text.trim().trim_matches('"').trim_matches('\'');
+    let hash_type = HashType::from_hex_length(hash.len())?;
+    if !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((hash_type, hash.to_lowercase()))
+}
+
+// Any cell that is a hash is an indicator, named by the first other cell in its row that is neither a
+// hash nor a hash type; header rows have no hashes and fall out on their own. Lines starting with # are comments
+fn parse_csv(text: &str) -> Result<Vec<Indicator>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .comment(Some(b'#'))
+        .from_reader(text.as_bytes());
+
+    let mut indicators = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Unreadable IOC list: {}", e))?;
+        let hashes: Vec<(HashType, String)> = record.iter().filter_map(normalize_hash).collect();
+        if hashes.is_empty() {
+            continue;
+        }
+        let name = record.iter()
+            .map(str::trim)
+            .find(|cell| !cell.is_empty() && normalize_hash(cell).is_none() && !is_hash_type_label(cell))
+            .map(str::to_string);
+        indicators.extend(hashes.into_iter().map(|(hash_type, hash)| Indicator { hash_type, hash, name: name.clone() }));
+    }
+    Ok(indicators)
+}
+
+fn is_hash_type_label(cell: &str) -> bool {
+    let label: String = cell.to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect();
+    matches!(label.as_str(), "sha256" | "sha1" | "md5" | "filehash" | "hash" | "filehashsha256" | "filehashsha1" | "filehashmd5")
+}
+
+// A STIX 2 bundle ({"objects": [...]}) or a bare array of objects. Indicators carry their hashes in a
+// pattern such as [file:hashes.'SHA-256' = '...'], file objects in a "hashes" map; plain
+// {"sha256": ..., "name": ...} objects are taken too
+fn parse_json(text: &str) -> Result<Vec<Indicator>, String> {
+    let json: Value = serde_json::from_str(text).map_err(|e| format!("Unreadable IOC list: {}", e))?;
+    let objects = match &json {
+        Value::Array(objects) => objects.as_slice(),
+        Value::Object(bundle) => match bundle.get("objects").or_else(|| bundle.get("indicators")) {
+            Some(Value::Array(objects)) => objects.as_slice(),
+            _ => std::slice::from_ref(&json),
+        },
+        _ => return Err("The IOC list is not a JSON object or array".to_string()),
+    };
+
+    let mut indicators = Vec::new();
+    for object in objects {
+        let name = ["name", "description", "malware", "id"].iter()
+            .find_map(|key| object.get(*key).and_then(Value::as_str))
+            .map(str::to_string);
+        let mut add = |value: &str| {
+            if let Some((hash_type, hash)) = normalize_hash(value) {
+                indicators.push(Indicator { hash_type, hash, name: name.clone() });
+            }
+        };
+
+        if let Some(pattern) = object.get("pattern").and_then(Value::as_str) {
+            for value in pattern_hashes(pattern) {
+                add(value);
+            }
+        }
+        if let Some(Value::Object(hashes)) = object.get("hashes") {
+            for value in hashes.values().filter_map(Value::as_str) {
+                add(value);
+            }
+        }
+        for key in ["sha256", "sha1", "md5", "hash", "value"] {
+            if let Some(value) = object.get(key).and_then(Value::as_str) {
+                add(value);
+            }
+        }
+    }
+    Ok(indicators)
+}
+
+// The quoted values compared with file:hashes.* in a STIX pattern; comparisons may be joined with OR/AND
+fn pattern_hashes(pattern: &str) -> Vec<&str> {
+    let mut values = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find("hashes.") {
+        rest = &rest[start + "hashes.".len()..];
+        // Skip the algorithm name, which may itself be quoted: hashes.'SHA-256' = '...'
+        let after_name = rest.trim_start_matches('\'');
+        let equals = match after_name.find('=') {
+            Some(equals) => equals,
+            None => break,
+        };
+        let value = after_name[equals + 1..].trim_start();
+        let value = match value.strip_prefix('\'') {
+            Some(value) => value,
+            None => {
+                rest = &after_name[equals + 1..];
+                continue;
+            }
+        };
+        let end = value.find('\'').unwrap_or(value.len());
+        values.push(&value[..end]);
+        rest = &value[end..];
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    const SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+    fn hashes_and_names(indicators: &[Indicator]) -> Vec<(HashType, &str, Option<&str>)> {
+        indicators.iter().map(|indicator| (indicator.hash_type, indicator.hash.as_str(), indicator.name.as_deref())).collect()
+    }
+
+    #[test]
+    fn normalizes_hashes() {
+        assert_eq!(normalize_hash(&format!(" '{}' ", SHA256.to_uppercase())), Some((HashType::Sha256, SHA256.to_string())));
+        assert_eq!(normalize_hash(&format!("\"{}\"", MD5)), Some((HashType::Md5, MD5.to_string())));
+        assert_eq!(normalize_hash(&SHA1.replace('a', "g")), None);
+        assert_eq!(normalize_hash("Emotet"), None);
+    }
+
+    #[test]
+    fn reads_hashes_from_csv_rows() {
+        let csv = format!(
+            "# exported 2024-03-22\nsha256,md5,family\n{},{},Emotet\nsha1,{}\n\"{}\",\"Cobalt Strike, beacon\"\nno hash here,,\n",
+            SHA256, MD5, SHA1, MD5.to_uppercase(),
+        );
+        let indicators = parse_csv(&csv).unwrap();
+        assert_eq!(hashes_and_names(&indicators), vec![
+            (HashType::Sha256, SHA256, Some("Emotet")),
+            (HashType::Md5, MD5, Some("Emotet")),
+            (HashType::Sha1, SHA1, None),
+            (HashType::Md5, MD5, Some("Cobalt Strike, beacon")),
+        ]);
+    }
+
+    #[test]
+    fn reads_hashes_from_stix_and_plain_json() {
+        let bundle = serde_json::json!({
+            "type": "bundle",
+            "objects": [
+                {
+                    "type": "indicator",
+                    "name": "Emotet loader",
+                    "pattern": format!("[file:hashes.'SHA-256' = '{}' OR file:hashes.MD5 = '{}']", SHA256, MD5),
+                },
+                { "type": "file", "id": "file--1", "hashes": { "SHA-1": SHA1 } },
+                { "type": "malware", "name": "Not a hash" },
+            ],
+        });
+        let indicators = parse_json(&bundle.to_string()).unwrap();
+        assert_eq!(hashes_and_names(&indicators), vec![
+            (HashType::Sha256, SHA256, Some("Emotet loader")),
+            (HashType::Md5, MD5, Some("Emotet loader")),
+            (HashType::Sha1, SHA1, Some("file--1")),
+        ]);
+
+        let plain = serde_json::json!([{ "sha256": SHA256.to_uppercase(), "description": "Dropper" }]);
+        assert_eq!(hashes_and_names(&parse_json(&plain.to_string()).unwrap()), vec![(HashType::Sha256, SHA256, Some("Dropper"))]);
+        assert!(parse_json("\"text\"").is_err());
+        assert!(parse_json("{").is_err());
+    }
+
+    #[test]
+    fn loads_and_matches_a_list() {
+        let dir = std::env::temp_dir().join(format!("ioc-list-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // No extension: told apart by content
+        let path = dir.join("feed");
+        fs::write(&path, serde_json::json!({ "indicators": [{ "md5": MD5, "name": "first" }, { "md5": MD5, "name": "second" }] }).to_string()).unwrap();
+        let empty = dir.join("empty.csv");
+        fs::write(&empty, "sha256,name\n").unwrap();
+
+        let list = IocList::load(&path).unwrap();
+        assert_eq!(list.len(), 1);
+        assert!(!list.is_empty());
+        let file = FileHashes { sha256: "0".repeat(64), sha1: SHA1.to_string(), md5: MD5.to_string() };
+        let matches = list.matches(&file);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].name.as_deref(), Some("first"));
+        assert!(list.matches(&FileHashes { md5: "0".repeat(32), ..file }).is_empty());
+
+        assert!(IocList::load(&empty).is_err());
+        assert!(IocList::load(&dir.join("missing.csv")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1 +1,21 @@
 pub mod icon_extractor;
+pub mod registry_source;
+pub mod regf;
+pub mod reg_file;
+pub mod user_profiles;
+pub mod environment;
+pub mod path_resolver;
+pub mod install_date;
+pub mod msi;
+pub mod version;
+pub mod appx;
+pub mod command_line;
+pub mod installer_fingerprint;
+pub mod process_runner;
+pub mod disk_usage;
+pub mod pe;
+pub mod der;
+pub mod authenticode;
+pub mod signature_verification;
+pub mod file_hash;
+pub mod ioc;
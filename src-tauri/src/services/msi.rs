@@ -0,0 +1,305 @@
+// Windows Installer bookkeeping in the registry, keyed by packed product codes
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+
+use crate::services::registry_source::{RegistryHive, RegistryKey, RegistrySource};
+
+pub const INSTALLER_USERDATA_PATH: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Installer\\UserData";
+const INSTALLER_MANAGED_PATH: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Installer\\Managed";
+const MACHINE_UPGRADE_CODES_PATH: &str = "SOFTWARE\\Classes\\Installer\\UpgradeCodes";
+const USER_UPGRADE_CODES_PATH: &str = "Software\\Microsoft\\Installer\\UpgradeCodes";
+// Per-machine installs are recorded under the LocalSystem SID
+pub const LOCAL_SYSTEM_SID: &str = "S-1-5-18";
+
+// Installation context, as MSI reports it: per-machine, or per-user with or without policy
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MsiContext {
+    Machine,
+    UserManaged,
+    UserUnmanaged,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MsiProductInfo {
+    pub product_code: String,
+    pub packed_product_code: String,
+    pub upgrade_code: Option<String>,
+    pub local_package: Option<String>, // Cached .msi under C:\Windows\Installer
+    pub context: MsiContext,
+    pub user_sid: Option<String>,      // UserData SID the product is registered under, for per-user installs
+}
+
+/// Products sharing an UpgradeCode: successive versions of one product, as supersedence rules see them
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MsiProductFamily {
+    pub upgrade_code: String,
+    pub members: Vec<MsiFamilyMember>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MsiFamilyMember {
+    pub name: String,
+    pub version: Option<String>,
+    pub product_code: String,
+    pub context: MsiContext,
+    pub user_sid: Option<String>,
+}
+
+struct MsiInstallation {
+    sid: String,
+    local_package: Option<String>,
+}
+
+/// Index of the Installer's registry data, built once per scan
+#[derive(Default)]
+pub struct MsiInventory {
+    installations: HashMap<String, Vec<MsiInstallation>>, // packed product code -> where it is registered
+    upgrade_codes: HashMap<String, String>,               // packed product code -> upgrade code
+    managed: HashSet<(String, String)>,                   // (SID, packed product code) with per-user managed installs
+}
+
+impl MsiInventory {
+    pub fn load(registry: &dyn RegistrySource) -> Self {
+        let mut inventory = MsiInventory::default();
+
+        let sids = registry.open_key(RegistryHive::LocalMachine, INSTALLER_USERDATA_PATH)
+            .map(|userdata| userdata.subkey_names())
+            .unwrap_or_default();
+        for sid in &sids {
+            let products_path = format!("{}\\{}\\Products", INSTALLER_USERDATA_PATH, sid);
+            let packed_codes = registry.open_key(RegistryHive::LocalMachine, &products_path)
+                .map(|products| products.subkey_names())
+                .unwrap_or_default();
+            for packed in packed_codes {
+                let local_package = registry.open_key(RegistryHive::LocalMachine, &format!("{}\\{}\\InstallProperties", products_path, packed))
+                    .and_then(|properties| properties.get_string("LocalPackage"));
+                inventory.installations
+                    .entry(packed.to_ascii_uppercase())
+                    .or_default()
+                    .push(MsiInstallation { sid: sid.clone(), local_package });
+            }
+
+            let managed_path = format!("{}\\{}\\Installer\\Products", INSTALLER_MANAGED_PATH, sid);
+            if let Some(managed) = registry.open_key(RegistryHive::LocalMachine, &managed_path) {
+                for packed in managed.subkey_names() {
+                    inventory.managed.insert((sid.to_ascii_uppercase(), packed.to_ascii_uppercase()));
+                }
+            }
+        }
+
+        // Each UpgradeCodes key lists its member products as value names
+        if let Some(upgrade_codes) = registry.open_key(RegistryHive::LocalMachine, MACHINE_UPGRADE_CODES_PATH) {
+            inventory.add_upgrade_codes(upgrade_codes.as_ref());
+        }
+        for hive_name in registry.user_hive_names() {
+            if let Some(upgrade_codes) = registry.open_key(RegistryHive::Users, &format!("{}\\{}", hive_name, USER_UPGRADE_CODES_PATH)) {
+                inventory.add_upgrade_codes(upgrade_codes.as_ref());
+            }
+        }
+        if let Some(upgrade_codes) = registry.open_key(RegistryHive::CurrentUser, USER_UPGRADE_CODES_PATH) {
+            inventory.add_upgrade_codes(upgrade_codes.as_ref());
+        }
+
+        inventory
+    }
+
+    fn add_upgrade_codes(&mut self, upgrade_codes: &dyn RegistryKey) {
+        for packed_upgrade_code in upgrade_codes.subkey_names() {
+            let upgrade_code = match unpack_guid(&packed_upgrade_code) {
+                Some(upgrade_code) => upgrade_code,
+                None => continue,
+            };
+            if let Some(family) = upgrade_codes.open_subkey(&packed_upgrade_code) {
+                for packed_product in family.value_names() {
+                    self.upgrade_codes.insert(packed_product.to_ascii_uppercase(), upgrade_code.clone());
+                }
+            }
+        }
+    }
+
+    /// Installer metadata for an uninstall entry named after its product code. Per-user entries match
+    /// the owner's SID; when the owner is unknown any per-user registration is taken
+    pub fn product_info(&self, product_code: &str, per_user: bool, user_sid: Option<&str>) -> Option<MsiProductInfo> {
+        let packed = pack_guid(product_code)?;
+        let installation = self.installations.get(&packed).and_then(|installations| {
+            installations.iter().find(|installation| match (per_user, user_sid) {
+                (false, _) => installation.sid == LOCAL_SYSTEM_SID,
+                (true, Some(sid)) => installation.sid.eq_ignore_ascii_case(sid),
+                (true, None) => installation.sid != LOCAL_SYSTEM_SID,
+            })
+        });
+        let upgrade_code = self.upgrade_codes.get(&packed).cloned();
+        if installation.is_none() && upgrade_code.is_none() {
+            return None;
+        }
+
+        let registered_sid = installation.map(|installation| installation.sid.clone())
+            .or_else(|| user_sid.map(|sid| sid.to_string()));
+        let context = match &registered_sid {
+            _ if !per_user => MsiContext::Machine,
+            Some(sid) if self.managed.contains(&(sid.to_ascii_uppercase(), packed.clone())) => MsiContext::UserManaged,
+            _ => MsiContext::UserUnmanaged,
+        };
+
+        Some(MsiProductInfo {
+            product_code: format_guid(product_code)?,
+            packed_product_code: packed,
+            upgrade_code,
+            local_package: installation.and_then(|installation| installation.local_package.clone()),
+            context,
+            user_sid: if per_user { registered_sid } else { None },
+        })
+    }
+}
+
+/// Convert a product code like {12345678-ABCD-EF01-2345-6789ABCDEF01} to the packed ("squished") form
+/// used by the Installer keys: the first three groups reversed, the remaining bytes nibble-swapped
+pub fn pack_guid(guid: &str) -> Option<String> {
+    let hex: Vec<char> = guid
+        .trim()
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .chars()
+        .filter(|c| *c != '-')
+        .collect();
+    if hex.len() != 32 || !hex.iter().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(swap_guid_order(&hex).to_ascii_uppercase())
+}
+
+/// Inverse of `pack_guid`: 87654321DCBA10FE32547698BADCFE10 -> {12345678-ABCD-EF01-2345-6789ABCDEF01}
+pub fn unpack_guid(packed: &str) -> Option<String> {
+    let hex: Vec<char> = packed.trim().chars().collect();
+    if hex.len() != 32 || !hex.iter().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    format_guid(&swap_guid_order(&hex))
+}
+
+// The packing is its own inverse
+fn swap_guid_order(hex: &[char]) -> String {
+    let mut swapped = String::with_capacity(32);
+    for (start, end) in [(0, 8), (8, 12), (12, 16)] {
+        swapped.extend(hex[start..end].iter().rev());
+    }
+    for pair in hex[16..].chunks(2) {
+        swapped.push(pair[1]);
+        swapped.push(pair[0]);
+    }
+    swapped
+}
+
+/// Canonical registry form of a GUID: braces, dashes, upper case
+pub fn format_guid(guid: &str) -> Option<String> {
+    let hex: String = guid.chars().filter(|c| c.is_ascii_hexdigit()).collect::<String>().to_ascii_uppercase();
+    if hex.len() != 32 {
+        return None;
+    }
+    Some(format!("{{{}-{}-{}-{}-{}}}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32]))
+}
+
+/// Open UserData\<SID>\Products\<packed code>\InstallProperties for a product
+pub fn open_install_properties<'a>(registry: &'a dyn RegistrySource, sid: &str, product_code: &str) -> Option<Box<dyn RegistryKey + 'a>> {
+    let packed = pack_guid(product_code)?;
+    registry.open_key(
+        RegistryHive::LocalMachine,
+        &format!("{}\\{}\\Products\\{}\\InstallProperties", INSTALLER_USERDATA_PATH, sid, packed),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::registry_source::{MemoryRegistry, RegistryValue};
+
+    const SEVEN_ZIP: &str = "{23170F69-40C1-2702-2301-000001000000}";
+    const SEVEN_ZIP_OLD: &str = "{23170F69-40C1-2702-2201-000001000000}";
+    const SEVEN_ZIP_UPGRADE: &str = "{23170F69-40C1-2702-0000-000004000000}";
+    const CONTOSO: &str = "{A1B2C3D4-1111-2222-3333-444455556666}";
+    const CONTOSO_UPGRADE: &str = "{0F0E0D0C-AAAA-BBBB-CCCC-DDDDEEEEFFFF}";
+
+    #[test]
+    fn packs_and_unpacks_product_codes() {
+        assert_eq!(pack_guid(SEVEN_ZIP).as_deref(), Some("96F071321C0420723210000010000000"));
+        assert_eq!(unpack_guid("96F071321C0420723210000010000000").as_deref(), Some(SEVEN_ZIP));
+        assert_eq!(pack_guid(" {12345678-abcd-ef01-2345-6789abcdef01} ").as_deref(), Some("87654321DCBA10FE32547698BADCFE10"));
+        assert_eq!(unpack_guid("87654321dcba10fe32547698badcfe10").as_deref(), Some("{12345678-ABCD-EF01-2345-6789ABCDEF01}"));
+        for guid in [SEVEN_ZIP_OLD, SEVEN_ZIP_UPGRADE, CONTOSO, CONTOSO_UPGRADE] {
+            assert_eq!(unpack_guid(&pack_guid(guid).unwrap()).as_deref(), Some(guid));
+        }
+
+        assert_eq!(pack_guid("{23170F69-40C1-2702-2301-00000100000}"), None);
+        assert_eq!(pack_guid("{G3170F69-40C1-2702-2301-000001000000}"), None);
+        assert_eq!(unpack_guid("96F071321C042072321000001000000X"), None);
+        assert_eq!(format_guid("23170f6940c1270223010000010000 00").as_deref(), Some(SEVEN_ZIP));
+        assert_eq!(format_guid("not a guid"), None);
+    }
+
+    fn installer_registry() -> MemoryRegistry {
+        let packed = |guid: &str| pack_guid(guid).unwrap();
+        let mut registry = MemoryRegistry::new();
+        let machine = registry.hive_mut(RegistryHive::LocalMachine);
+        machine.create_path(&format!("{}\\{}\\Products\\{}\\InstallProperties", INSTALLER_USERDATA_PATH, LOCAL_SYSTEM_SID, packed(SEVEN_ZIP)))
+            .set_value("LocalPackage", RegistryValue::String("C:\\Windows\\Installer\\1a2b3c.msi".to_string()));
+        for sid in ["S-1-5-21-1", "S-1-5-21-2"] {
+            machine.create_path(&format!("{}\\{}\\Products\\{}\\InstallProperties", INSTALLER_USERDATA_PATH, sid, packed(CONTOSO)))
+                .set_value("LocalPackage", RegistryValue::String(format!("C:\\Windows\\Installer\\{}.msi", sid)));
+        }
+        // Only the first user's install was deployed by policy
+        machine.create_path(&format!("{}\\S-1-5-21-1\\Installer\\Products\\{}", INSTALLER_MANAGED_PATH, packed(CONTOSO)));
+
+        let family = machine.create_path(&format!("{}\\{}", MACHINE_UPGRADE_CODES_PATH, packed(SEVEN_ZIP_UPGRADE)));
+        family.set_value(&packed(SEVEN_ZIP), RegistryValue::String(String::new()));
+        family.set_value(&packed(SEVEN_ZIP_OLD), RegistryValue::String(String::new()));
+        registry.hive_mut(RegistryHive::Users)
+            .create_path(&format!("S-1-5-21-2\\{}\\{}", USER_UPGRADE_CODES_PATH, packed(CONTOSO_UPGRADE)))
+            .set_value(&packed(CONTOSO), RegistryValue::String(String::new()));
+        registry
+    }
+
+    #[test]
+    fn finds_machine_installs_and_their_family() {
+        let inventory = MsiInventory::load(&installer_registry());
+
+        let product = inventory.product_info(SEVEN_ZIP, false, None).unwrap();
+        assert_eq!(product.product_code, SEVEN_ZIP);
+        assert_eq!(product.packed_product_code, "96F071321C0420723210000010000000");
+        assert_eq!(product.context, MsiContext::Machine);
+        assert_eq!(product.local_package.as_deref(), Some("C:\\Windows\\Installer\\1a2b3c.msi"));
+        assert_eq!(product.upgrade_code.as_deref(), Some(SEVEN_ZIP_UPGRADE));
+        assert_eq!(product.user_sid, None);
+
+        // The older version is only known from its UpgradeCode membership
+        let older = inventory.product_info(&SEVEN_ZIP_OLD.to_ascii_lowercase(), false, None).unwrap();
+        assert_eq!(older.product_code, SEVEN_ZIP_OLD);
+        assert_eq!(older.upgrade_code.as_deref(), Some(SEVEN_ZIP_UPGRADE));
+        assert_eq!(older.local_package, None);
+
+        assert!(inventory.product_info("{00000000-0000-0000-0000-000000000000}", false, None).is_none());
+        assert!(inventory.product_info("Contoso", false, None).is_none());
+    }
+
+    #[test]
+    fn tells_per_user_installs_apart_by_sid() {
+        let inventory = MsiInventory::load(&installer_registry());
+
+        let managed = inventory.product_info(CONTOSO, true, Some("S-1-5-21-1")).unwrap();
+        assert_eq!(managed.context, MsiContext::UserManaged);
+        assert_eq!(managed.user_sid.as_deref(), Some("S-1-5-21-1"));
+        assert_eq!(managed.local_package.as_deref(), Some("C:\\Windows\\Installer\\S-1-5-21-1.msi"));
+        assert_eq!(managed.upgrade_code.as_deref(), Some(CONTOSO_UPGRADE));
+
+        let unmanaged = inventory.product_info(CONTOSO, true, Some("s-1-5-21-2")).unwrap();
+        assert_eq!(unmanaged.context, MsiContext::UserUnmanaged);
+        assert_eq!(unmanaged.user_sid.as_deref(), Some("S-1-5-21-2"));
+        assert_eq!(unmanaged.local_package.as_deref(), Some("C:\\Windows\\Installer\\S-1-5-21-2.msi"));
+
+        // Owner unknown: any per-user registration, never the machine's
+        let anyone = inventory.product_info(CONTOSO, true, None).unwrap();
+        assert!(anyone.user_sid.as_deref().unwrap().starts_with("S-1-5-21-"));
+        let no_machine_install = inventory.product_info(CONTOSO, false, None).unwrap();
+        assert_eq!(no_machine_install.local_package, None);
+        assert_eq!(inventory.product_info(SEVEN_ZIP, true, Some("S-1-5-21-1")).unwrap().local_package, None);
+    }
+}
@@ -0,0 +1,325 @@
+// Where a Windows path from the registry can be found on the filesystem we can read: the live machine, a
+// Windows volume mounted on an analysis box (e.g. /mnt/evidence), or a test directory laid out like one.
+// The folders the scan guesses in (Program Files, ProgramData, System32, ...) all come from here, expanded
+// with the scanned machine's environment, so the same heuristics work on any of them
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::services::environment::{expand, EnvironmentProvider, ProcessEnvironment, Wow64Environment};
+
+// How deep the search for an icon file by name goes below each common folder
+const FILE_SEARCH_DEPTH: usize = 5;
+
+/// How Windows drive letters map onto local folders
+#[derive(Debug, Clone)]
+pub enum FilesystemRoots {
+    Live,                            // Running on the machine itself: paths are used as written
+    Mounted(Vec<(String, PathBuf)>), // Drive ("C:") -> the folder its volume is mounted at
+    Unavailable,                     // Registry data only (.reg exports, loose hive files): no files to look at
+}
+
+impl FilesystemRoots {
+    /// A Windows volume mounted at `root`, holding what the machine called `drive`
+    pub fn mounted(drive: &str, root: impl Into<PathBuf>) -> Self {
+        FilesystemRoots::Mounted(vec![(normalize_drive(drive), root.into())])
+    }
+
+    /// The local path of a Windows path; None for drives that are not mapped. The path may not exist
+    pub fn to_local(&self, windows_path: &str) -> Option<PathBuf> {
+        match self {
+            FilesystemRoots::Live => Some(PathBuf::from(windows_path)),
+            FilesystemRoots::Unavailable => None,
+            FilesystemRoots::Mounted(drives) => {
+                let drive = windows_path.get(..2).filter(|drive| drive.ends_with(':'))?;
+                let (_, root) = drives.iter().find(|(mapped, _)| mapped.eq_ignore_ascii_case(drive))?;
+                let components: Vec<&str> = windows_path[2..]
+                    .split(['\\', '/'])
+                    .filter(|component| !component.is_empty() && *component != ".")
+                    .collect();
+                Some(join_case_insensitive(root, &components))
+            }
+        }
+    }
+}
+
+fn normalize_drive(drive: &str) -> String {
+    format!("{}:", drive.trim_end_matches(['\\', '/']).trim_end_matches(':'))
+}
+
+/// Resolve a relative path component by component, ignoring case (mounted NTFS images are often
+/// case-sensitive). None when any component is missing
+pub fn find_path_case_insensitive(base: &Path, components: &[&str]) -> Option<PathBuf> {
+    let mut current = base.to_path_buf();
+    for component in components {
+        current = find_entry(&current, component)?;
+    }
+    Some(current)
+}
+
+// Like `find_path_case_insensitive`, but missing components are appended as written
+fn join_case_insensitive(base: &Path, components: &[&str]) -> PathBuf {
+    let mut current = base.to_path_buf();
+    for component in components {
+        current = find_entry(&current, component).unwrap_or_else(|| current.join(component));
+    }
+    current
+}
+
+fn find_entry(folder: &Path, name: &str) -> Option<PathBuf> {
+    let exact = folder.join(name);
+    if exact.exists() {
+        return Some(exact);
+    }
+    fs::read_dir(folder).ok()?
+        .flatten()
+        .find(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(name))
+        .map(|entry| entry.path())
+}
+
+/// Windows paths, with their environment variables, resolved against one set of filesystem roots
+#[derive(Clone, Copy)]
+pub struct PathResolver<'a> {
+    roots: &'a FilesystemRoots,
+    environment: &'a dyn EnvironmentProvider,
+}
+
+impl<'a> PathResolver<'a> {
+    pub fn new(roots: &'a FilesystemRoots, environment: &'a dyn EnvironmentProvider) -> Self {
+        PathResolver { roots, environment }
+    }
+
+    /// The same roots seen with another environment, e.g. a user's own
+    pub fn with_environment<'b>(&self, environment: &'b dyn EnvironmentProvider) -> PathResolver<'b> where 'a: 'b {
+        PathResolver { roots: self.roots, environment }
+    }
+
+    /// The environment paths are expanded with
+    pub fn environment(&self) -> &'a dyn EnvironmentProvider {
+        self.environment
+    }
+
+    /// Local path of a Windows path that may contain environment variables; it may not exist
+    pub fn local_path(&self, windows_path: &str) -> Option<PathBuf> {
+        self.roots.to_local(&expand(windows_path, self.environment))
+    }
+
+    /// Local path of a file or folder, when it is there
+    pub fn existing(&self, windows_path: &str) -> Option<PathBuf> {
+        self.local_path(windows_path).filter(|path| path.exists())
+    }
+
+    /// The folders programs are installed to: Program Files, and Program Files (x86) on 64-bit Windows
+    pub fn program_files_folders(&self) -> Vec<PathBuf> {
+        self.existing_folders(&["%ProgramFiles%", "%ProgramFiles(x86)%"])
+    }
+
+    /// Folders worth searching when only an icon's file name is known
+    pub fn common_folders(&self) -> Vec<PathBuf> {
+        self.existing_folders(&[
+            "%ProgramFiles%",
+            "%ProgramFiles(x86)%",
+            "%ProgramData%",
+            "%PUBLIC%",
+            "%SystemRoot%\\System32",
+            "%SystemRoot%",
+            "%SystemRoot%\\SysWOW64",
+            "%CommonProgramFiles%",
+            "%CommonProgramFiles(x86)%",
+            "%ProgramFiles%\\Microsoft Office",
+            "%ProgramFiles(x86)%\\Microsoft Office",
+        ])
+    }
+
+    /// Where managed deployments put applications that do not register an InstallLocation
+    pub fn managed_app_folders(&self) -> Vec<PathBuf> {
+        self.existing_folders(&[
+            "%ProgramFiles%",
+            "%ProgramFiles(x86)%",
+            "%ProgramData%",
+            "%SystemRoot%\\System32",
+            "%SystemRoot%\\SysWOW64",
+        ])
+    }
+
+    /// Folders portable applications are commonly unpacked to
+    pub fn portable_app_folders(&self) -> Vec<(PathBuf, &'static str)> {
+        [
+            ("%APPDATA%", "AppData\\Roaming"),
+            ("%LOCALAPPDATA%", "AppData\\Local"),
+            ("%SystemDrive%\\PortableApps", "Portable"),
+            ("%SystemDrive%\\Tools", "Portable"),
+            ("%SystemDrive%\\Utilities", "Portable"),
+            ("%SystemDrive%\\Programs", "Portable"),
+        ]
+        .iter()
+        .filter_map(|(folder, source)| self.existing(folder).filter(|path| path.is_dir()).map(|path| (path, *source)))
+        .collect()
+    }
+
+    /// Start Menu and desktop folders of the current user and of all users
+    pub fn shortcut_folders(&self) -> Vec<PathBuf> {
+        self.existing_folders(&[
+            "%APPDATA%\\Microsoft\\Windows\\Start Menu\\Programs",
+            "%ALLUSERSPROFILE%\\Microsoft\\Windows\\Start Menu\\Programs",
+            "%USERPROFILE%\\Desktop",
+            "%PUBLIC%\\Desktop",
+        ])
+    }
+
+    /// The file a DisplayIcon value ("<path>,<index>", sometimes quoted) points at, when it is there.
+    /// 32-bit setups sometimes write %ProgramFiles% for their Program Files (x86) folder, so that is tried too
+    pub fn resolve_icon_file(&self, display_icon: &str) -> Option<String> {
+        let path = display_icon.split(',').next().unwrap_or_default().trim().trim_matches('"');
+        if path.is_empty() {
+            return None;
+        }
+        let wow64 = Wow64Environment(self.environment);
+        [self.local_path(path), self.roots.to_local(&expand(path, &wow64))]
+            .into_iter()
+            .flatten()
+            .find(|candidate| candidate.is_file())
+            .map(|found| found.to_string_lossy().to_string())
+    }
+
+    /// Search the common folders for a file by name, when a DisplayIcon is relative or points nowhere
+    pub fn find_file(&self, file_name: &str) -> Option<String> {
+        for folder in self.common_folders() {
+            println!("🔍 Recursively searching for '{}' in '{}'", file_name, folder.display());
+            let found = WalkDir::new(&folder)
+                .max_depth(FILE_SEARCH_DEPTH)
+                .into_iter()
+                .flatten()
+                .find(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(file_name));
+            if let Some(found) = found {
+                println!("✅ Found file: {}", found.path().display());
+                return Some(found.path().to_string_lossy().to_string());
+            }
+        }
+        println!("❌ File '{}' not found in the common folders", file_name);
+        None
+    }
+
+    // Each folder once, in order: %ProgramFiles(x86)% is %ProgramFiles% on 32-bit Windows
+    fn existing_folders(&self, folders: &[&str]) -> Vec<PathBuf> {
+        let mut existing: Vec<PathBuf> = Vec::new();
+        for folder in folders {
+            if let Some(path) = self.existing(folder).filter(|path| path.is_dir()) {
+                if !existing.contains(&path) {
+                    existing.push(path);
+                }
+            }
+        }
+        existing
+    }
+}
+
+/// Run `f` with a resolver for the machine we are running on, in this process's environment
+pub fn with_live_resolver<T>(f: impl FnOnce(&PathResolver) -> T) -> T {
+    let environment = ProcessEnvironment::new();
+    f(&PathResolver::new(&FilesystemRoots::Live, &environment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::environment::{Environment, Variable};
+
+    // A directory laid out like a Windows system volume, removed when dropped
+    struct Volume(PathBuf);
+
+    impl Volume {
+        fn new(name: &str, folders: &[&str]) -> Self {
+            let root = std::env::temp_dir().join(format!("path-resolver-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            for folder in folders {
+                fs::create_dir_all(root.join(folder)).unwrap();
+            }
+            Volume(root)
+        }
+
+        fn file(&self, path: &str) -> PathBuf {
+            let path = self.0.join(path);
+            fs::write(&path, b"").unwrap();
+            path
+        }
+    }
+
+    impl Drop for Volume {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn windows_volume(name: &str) -> Volume {
+        Volume::new(name, &[
+            "Windows/System32",
+            "Program Files/Vendor",
+            "Program Files (x86)/Vendor/App",
+            "ProgramData",
+        ])
+    }
+
+    #[test]
+    fn maps_windows_paths_onto_the_volume_ignoring_case() {
+        let volume = windows_volume("case");
+        let roots = FilesystemRoots::mounted("c:\\", &volume.0);
+
+        assert_eq!(roots.to_local("C:\\WINDOWS\\system32"), Some(volume.0.join("Windows/System32")));
+        assert_eq!(roots.to_local("c:/program files (X86)/VENDOR/app"), Some(volume.0.join("Program Files (x86)/Vendor/App")));
+        // Missing components are kept as written, below the part that was found
+        assert_eq!(roots.to_local("C:\\program files\\Vendor\\Missing\\app.exe"), Some(volume.0.join("Program Files/Vendor/Missing/app.exe")));
+        assert_eq!(find_path_case_insensitive(&volume.0, &["programdata", "missing"]), None);
+    }
+
+    #[test]
+    fn leaves_unmapped_drives_alone() {
+        let volume = windows_volume("unmapped");
+        let roots = FilesystemRoots::mounted("C:", &volume.0);
+
+        assert_eq!(roots.to_local("D:\\Program Files\\App"), None);
+        assert_eq!(roots.to_local("\\\\server\\share\\App"), None);
+        assert_eq!(roots.to_local("app.exe"), None);
+        assert_eq!(FilesystemRoots::Unavailable.to_local("C:\\Windows"), None);
+        let environment = Environment::windows_defaults();
+        assert_eq!(PathResolver::new(&roots, &environment).existing("D:\\"), None);
+    }
+
+    #[test]
+    fn finds_icons_written_with_the_64_bit_program_files_folder() {
+        let volume = windows_volume("icons");
+        let icon = volume.file("Program Files (x86)/Vendor/App/app.ico");
+        let native = volume.file("Program Files/Vendor/tool.exe");
+        let roots = FilesystemRoots::mounted("C:", &volume.0);
+        let environment = Environment::windows_defaults();
+        let resolver = PathResolver::new(&roots, &environment);
+
+        let found = |path: &PathBuf| Some(path.to_string_lossy().to_string());
+        assert_eq!(resolver.resolve_icon_file("\"%ProgramFiles%\\Vendor\\App\\app.ico\",0"), found(&icon));
+        assert_eq!(resolver.resolve_icon_file("C:\\Program Files\\Vendor\\App\\APP.ICO"), None);
+        assert_eq!(resolver.resolve_icon_file("%programfiles%\\vendor\\TOOL.exe,-101"), found(&native));
+        assert_eq!(resolver.resolve_icon_file("%ProgramFiles%\\Vendor\\App"), None);
+        assert_eq!(resolver.resolve_icon_file(",0"), None);
+    }
+
+    #[test]
+    fn lists_each_program_files_folder_once() {
+        let volume = windows_volume("program-files");
+        let roots = FilesystemRoots::mounted("C:", &volume.0);
+
+        let environment = Environment::windows_defaults();
+        assert_eq!(PathResolver::new(&roots, &environment).program_files_folders(), vec![
+            volume.0.join("Program Files"),
+            volume.0.join("Program Files (x86)"),
+        ]);
+
+        // 32-bit Windows: both variables name the one folder, written differently
+        let mut environment = Environment::windows_defaults();
+        environment.set("ProgramFiles(x86)", Variable::Literal("c:\\PROGRAM FILES".to_string()));
+        assert_eq!(PathResolver::new(&roots, &environment).program_files_folders(), vec![volume.0.join("Program Files")]);
+
+        let mut environment = Environment::windows_defaults();
+        environment.set("ProgramFiles", Variable::Literal("D:\\Program Files".to_string()));
+        assert_eq!(PathResolver::new(&roots, &environment).program_files_folders(), vec![volume.0.join("Program Files (x86)")]);
+    }
+}
@@ -0,0 +1,880 @@
+// Portable Executable images read as plain bytes: headers, sections, the version resource, the .NET
+// header and the certificate table. No Windows API involved, so executables from any machine, or a
+// mounted image, can be read on any platform
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::services::authenticode::{read_signature, SignatureInfo};
+
+// Images larger than this are not read; installers can be huge, the programs we inspect are not
+const MAX_IMAGE_SIZE: u64 = 512 * 1024 * 1024;
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+const RESOURCE_DIRECTORY: usize = 2;
+pub const CERTIFICATE_DIRECTORY: usize = 4;
+const CLR_DIRECTORY: usize = 14;
+const RT_VERSION: u32 = 16;
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF_04BD;
+// Resource directories nest type, name and language; deeper is malformed
+const MAX_RESOURCE_DEPTH: usize = 3;
+const IMAGE_FILE_DLL: u16 = 0x2000;
+const COMIMAGE_FLAGS_ILONLY: u32 = 0x1;
+const COMIMAGE_FLAGS_32BITREQUIRED: u32 = 0x2;
+const COMIMAGE_FLAGS_32BITPREFERRED: u32 = 0x2_0000;
+const METADATA_SIGNATURE: u32 = 0x424A_5342; // "BSJB"
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Machine {
+    X86,
+    X64,
+    Arm,
+    Arm64,
+    Itanium,
+    Other,
+}
+
+impl Machine {
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            0x014C => Machine::X86,
+            0x8664 => Machine::X64,
+            0x01C0 | 0x01C2 | 0x01C4 => Machine::Arm,
+            0xAA64 => Machine::Arm64,
+            0x0200 => Machine::Itanium,
+            _ => Machine::Other,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    Gui,
+    Console,
+    Native, // Drivers and native processes
+    Efi,
+    Other,
+}
+
+impl Subsystem {
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            2 => Subsystem::Gui,
+            3 | 7 => Subsystem::Console, // Windows and POSIX console
+            1 => Subsystem::Native,
+            10..=13 => Subsystem::Efi,
+            _ => Subsystem::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    pub raw_offset: u32,
+    pub raw_size: u32,
+}
+
+/// The headers of a PE image, with the bytes they describe
+pub struct PeImage<'a> {
+    pub bytes: &'a [u8],
+    pub machine: u16,
+    pub characteristics: u16,
+    pub is_pe32_plus: bool,
+    pub subsystem: u16,
+    pub checksum_offset: usize,                      // Left out of the Authenticode image hash, as signing
+    pub certificate_directory_offset: Option<usize>, // changes it, and so does this entry
+    pub data_directories: Vec<(u32, u32)>, // RVA and size; the certificate table's "RVA" is a file offset
+    pub sections: Vec<Section>,
+}
+
+impl<'a> PeImage<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, String> {
+        if read_u16(bytes, 0) != Some(0x5A4D) {
+            return Err("Not an executable: no MZ header".to_string());
+        }
+        let pe_offset = read_u32(bytes, 0x3C).ok_or("Truncated DOS header")? as usize;
+        if bytes.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0".as_slice()) {
+            return Err("No PE signature".to_string());
+        }
+        let coff = pe_offset + 4;
+        let machine = read_u16(bytes, coff).ok_or("Truncated COFF header")?;
+        let characteristics = read_u16(bytes, coff + 18).ok_or("Truncated COFF header")?;
+        let section_count = read_u16(bytes, coff + 2).ok_or("Truncated COFF header")? as usize;
+        let optional_header_size = read_u16(bytes, coff + 16).ok_or("Truncated COFF header")? as usize;
+
+        let optional = coff + 20;
+        let is_pe32_plus = match read_u16(bytes, optional) {
+            Some(PE32_MAGIC) => false,
+            Some(PE32_PLUS_MAGIC) => true,
+            Some(magic) => return Err(format!("Unknown optional header magic {:#x}", magic)),
+            None => return Err("No optional header".to_string()),
+        };
+        let subsystem = read_u16(bytes, optional + 68).ok_or("Truncated optional header")?;
+        // The data directories follow the fixed fields, which are 16 bytes longer in PE32+
+        let (count_offset, directories_offset) = if is_pe32_plus { (108, 112) } else { (92, 96) };
+        let directory_count = read_u32(bytes, optional + count_offset).ok_or("Truncated optional header")? as usize;
+        let certificate_directory_offset = Some(optional + directories_offset + CERTIFICATE_DIRECTORY * 8)
+            .filter(|_| directory_count > CERTIFICATE_DIRECTORY);
+        let data_directories = (0..directory_count.min(16))
+            .map_while(|index| {
+                let offset = optional + directories_offset + index * 8;
+                Some((read_u32(bytes, offset)?, read_u32(bytes, offset + 4)?))
+            })
+            .collect();
+
+        let section_table = optional + optional_header_size;
+        let sections = (0..section_count)
+            .map_while(|index| {
+                let offset = section_table + index * 40;
+                Some(Section {
+                    virtual_size: read_u32(bytes, offset + 8)?,
+                    virtual_address: read_u32(bytes, offset + 12)?,
+                    raw_size: read_u32(bytes, offset + 16)?,
+                    raw_offset: read_u32(bytes, offset + 20)?,
+                })
+            })
+            .collect();
+
+        Ok(PeImage {
+            bytes,
+            machine,
+            characteristics,
+            is_pe32_plus,
+            subsystem,
+            checksum_offset: optional + 64,
+            certificate_directory_offset,
+            data_directories,
+            sections,
+        })
+    }
+
+    pub fn data_directory(&self, index: usize) -> Option<(u32, u32)> {
+        self.data_directories.get(index).copied().filter(|(address, size)| *address != 0 && *size != 0)
+    }
+
+    pub fn is_dll(&self) -> bool {
+        self.characteristics & IMAGE_FILE_DLL != 0
+    }
+
+    /// The .NET runtime header, present in managed images
+    pub fn clr_header(&self) -> Option<ClrHeader> {
+        let (rva, _) = self.data_directory(CLR_DIRECTORY)?;
+        let offset = self.rva_to_offset(rva)?;
+        let metadata_rva = read_u32(self.bytes, offset + 8)?;
+        let flags = read_u32(self.bytes, offset + 16)?;
+
+        // The metadata root names the runtime the image was built against, e.g. "v4.0.30319"
+        let runtime_version = self.rva_to_offset(metadata_rva)
+            .filter(|metadata| read_u32(self.bytes, *metadata) == Some(METADATA_SIGNATURE))
+            .and_then(|metadata| {
+                let length = read_u32(self.bytes, metadata + 12)? as usize;
+                let text = self.bytes.get(metadata + 16..(metadata + 16).checked_add(length.min(256))?)?;
+                let text = text.split(|byte| *byte == 0).next()?;
+                Some(String::from_utf8_lossy(text).into_owned()).filter(|text| !text.is_empty())
+            });
+
+        Some(ClrHeader {
+            runtime_version,
+            il_only: flags & COMIMAGE_FLAGS_ILONLY != 0,
+            requires_32_bit: flags & COMIMAGE_FLAGS_32BITREQUIRED != 0 && flags & COMIMAGE_FLAGS_32BITPREFERRED == 0,
+            prefers_32_bit: flags & COMIMAGE_FLAGS_32BITREQUIRED != 0 && flags & COMIMAGE_FLAGS_32BITPREFERRED != 0,
+        })
+    }
+
+    /// WIN_CERTIFICATE entries of the certificate table. Its directory entry holds a file offset, not an
+    /// RVA, as the table is not mapped into memory
+    pub fn certificate_entries(&self) -> Vec<CertificateEntry<'a>> {
+        let mut entries = Vec::new();
+        let (offset, size) = match self.data_directory(CERTIFICATE_DIRECTORY) {
+            Some(directory) => directory,
+            None => return entries,
+        };
+        let table = match self.bytes.get(offset as usize..(offset as usize).saturating_add(size as usize)) {
+            Some(table) => table,
+            None => return entries,
+        };
+
+        let mut cursor = 0;
+        // WIN_CERTIFICATE: length, revision, type, then the certificate itself
+        while let (Some(length), Some(certificate_type)) = (read_u32(table, cursor), read_u16(table, cursor + 6)) {
+            let length = length as usize;
+            let data = match table.get(cursor + 8..cursor + length.max(8)) {
+                Some(data) if length > 8 => data,
+                _ => break,
+            };
+            entries.push(CertificateEntry { certificate_type, data });
+            // Entries are aligned to eight bytes
+            cursor = (cursor + length + 7) & !7;
+        }
+        entries
+    }
+
+    /// File offset of a relative virtual address
+    pub fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+        self.sections.iter()
+            .find(|section| rva >= section.virtual_address && rva < section.virtual_address.saturating_add(section.virtual_size.max(section.raw_size)))
+            .and_then(|section| (rva - section.virtual_address).checked_add(section.raw_offset))
+            .map(|offset| offset as usize)
+            .filter(|offset| *offset < self.bytes.len())
+    }
+
+    /// A 32-bit IL-only image without the 32-bit flags is "Any CPU"
+    pub fn is_any_cpu(&self, dotnet: Option<&ClrHeader>) -> bool {
+        dotnet.map_or(false, |clr| clr.il_only && !clr.requires_32_bit && !clr.prefers_32_bit)
+            && Machine::from_code(self.machine) == Machine::X86
+    }
+
+    /// The architecture the way ProgramInfo names it
+    pub fn architecture(&self) -> &'static str {
+        architecture_name(Machine::from_code(self.machine), self.is_any_cpu(self.clr_header().as_ref()))
+    }
+
+    pub fn version_info(&self) -> Option<VersionInfo> {
+        parse_version_resource(self.find_resource(RT_VERSION)?)
+    }
+
+    /// The bytes of the first resource of a type, whatever its name and language
+    pub fn find_resource(&self, resource_type: u32) -> Option<&'a [u8]> {
+        let (rva, _) = self.data_directory(RESOURCE_DIRECTORY)?;
+        let root = self.rva_to_offset(rva)?;
+        let type_entry = self.resource_entries(root, root)
+            .into_iter()
+            .find(|(id, _)| *id == Some(resource_type))?;
+        let data_entry = self.first_leaf(root, type_entry.1, 1)?;
+
+        let data_rva = read_u32(self.bytes, data_entry)?;
+        let size = read_u32(self.bytes, data_entry + 4)? as usize;
+        let offset = self.rva_to_offset(data_rva)?;
+        self.bytes.get(offset..offset.checked_add(size)?)
+    }
+
+    // Entries of a resource directory: the numeric id (None for named ones) and what it points at
+    fn resource_entries(&self, root: usize, directory: usize) -> Vec<(Option<u32>, u32)> {
+        let named = read_u16(self.bytes, directory + 12).unwrap_or(0) as usize;
+        let numbered = read_u16(self.bytes, directory + 14).unwrap_or(0) as usize;
+        (0..named + numbered)
+            .map_while(|index| {
+                let entry = directory + 16 + index * 8;
+                let name = read_u32(self.bytes, entry)?;
+                let target = read_u32(self.bytes, entry + 4)?;
+                let id = if name & 0x8000_0000 == 0 { Some(name) } else { None };
+                Some((id, target))
+            })
+            .filter(|(_, target)| root + ((target & 0x7FFF_FFFF) as usize) < self.bytes.len())
+            .collect()
+    }
+
+    // Follow the first entry of each level down to a data entry
+    fn first_leaf(&self, root: usize, target: u32, depth: usize) -> Option<usize> {
+        let offset = root + (target & 0x7FFF_FFFF) as usize;
+        if target & 0x8000_0000 == 0 {
+            return Some(offset);
+        }
+        if depth >= MAX_RESOURCE_DEPTH {
+            return None;
+        }
+        let (_, next) = *self.resource_entries(root, offset).first()?;
+        self.first_leaf(root, next, depth + 1)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClrHeader {
+    pub runtime_version: Option<String>,
+    pub il_only: bool,         // No native code, so the machine type does not bind it to one architecture
+    pub requires_32_bit: bool,
+    pub prefers_32_bit: bool,  // "Prefer 32-bit": runs as 32-bit where it can
+}
+
+/// One entry of the certificate table
+pub struct CertificateEntry<'a> {
+    pub certificate_type: u16, // WIN_CERT_TYPE_PKCS_SIGNED_DATA for Authenticode
+    pub data: &'a [u8],
+}
+
+/// What the headers of an executable say about it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImageInfo {
+    pub path: String,
+    pub machine: Machine,
+    pub machine_code: u16,
+    pub is_64_bit: bool,                    // PE32+
+    pub subsystem: Subsystem,
+    pub is_dll: bool,
+    pub dotnet: Option<ClrHeader>,
+    pub any_cpu: bool,                      // IL-only .NET image that runs natively on 64-bit Windows
+    pub signature: Option<SignatureInfo>,
+    pub signature_error: Option<String>,    // The certificate table is there but could not be read
+}
+
+impl ImageInfo {
+    /// The architecture the way ProgramInfo names it
+    pub fn architecture(&self) -> &'static str {
+        architecture_name(self.machine, self.any_cpu)
+    }
+}
+
+fn architecture_name(machine: Machine, any_cpu: bool) -> &'static str {
+    if any_cpu {
+        return "Neutral";
+    }
+    match machine {
+        Machine::X86 => "32-bit",
+        Machine::X64 => "64-bit",
+        Machine::Arm64 => "ARM64",
+        Machine::Arm => "ARM",
+        Machine::Itanium => "IA-64",
+        Machine::Other => "Unknown",
+    }
+}
+
+/// Read the headers, .NET header and signature of an executable
+pub fn read_image_info(path: &Path) -> Result<ImageInfo, String> {
+    let bytes = read_image(path).ok_or_else(|| format!("Could not read {}", path.display()))?;
+    let image = PeImage::parse(&bytes)?;
+    Ok(image_info(path, &image))
+}
+
+/// What the headers, .NET header and signature of an already parsed image say
+pub fn image_info(path: &Path, image: &PeImage) -> ImageInfo {
+    let dotnet = image.clr_header();
+    let any_cpu = image.is_any_cpu(dotnet.as_ref());
+    let (signature, signature_error) = match read_signature(image) {
+        Some(Ok(signature)) => (Some(signature), None),
+        Some(Err(error)) => (None, Some(error)),
+        None => (None, None),
+    };
+
+    ImageInfo {
+        path: path.to_string_lossy().to_string(),
+        machine: Machine::from_code(image.machine),
+        machine_code: image.machine,
+        is_64_bit: image.is_pe32_plus,
+        subsystem: Subsystem::from_code(image.subsystem),
+        is_dll: image.is_dll(),
+        dotnet,
+        any_cpu,
+        signature,
+        signature_error,
+    }
+}
+
+/// What an executable says about itself in its version resource
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VersionInfo {
+    pub file_version: Option<String>,     // From the fixed part, always four numbers
+    pub product_version: Option<String>,
+    pub strings: BTreeMap<String, String>, // StringFileInfo of the preferred language
+}
+
+impl VersionInfo {
+    pub fn string(&self, name: &str) -> Option<&str> {
+        self.strings.get(name).map(|value| value.trim()).filter(|value| !value.is_empty())
+    }
+
+    pub fn company_name(&self) -> Option<&str> {
+        self.string("CompanyName")
+    }
+
+    pub fn product_name(&self) -> Option<&str> {
+        self.string("ProductName")
+    }
+
+    pub fn file_description(&self) -> Option<&str> {
+        self.string("FileDescription")
+    }
+
+    /// The version as the product presents it; "1, 2, 3, 4" style strings are tidied to "1.2.3.4",
+    /// and the fixed product version stands in when there is no string
+    pub fn display_version(&self) -> Option<String> {
+        self.string("ProductVersion")
+            .map(|version| version.replace(", ", ".").replace(',', "."))
+            .or_else(|| self.product_version.clone())
+            .or_else(|| self.file_version.clone())
+    }
+}
+
+pub fn read_image(path: &Path) -> Option<Vec<u8>> {
+    let size = fs::metadata(path).ok()?.len();
+    if size > MAX_IMAGE_SIZE {
+        return None;
+    }
+    fs::read(path).ok()
+}
+
+/// Parse a VS_VERSIONINFO block
+pub fn parse_version_resource(data: &[u8]) -> Option<VersionInfo> {
+    let root = read_block(data, 0)?;
+    if root.key != "VS_VERSION_INFO" {
+        return None;
+    }
+
+    let mut info = VersionInfo::default();
+    if let Some(fixed) = data.get(root.value_offset..root.value_offset + root.value_length) {
+        if read_u32(fixed, 0) == Some(FIXED_FILE_INFO_SIGNATURE) {
+            let version = |offset: usize| -> Option<String> {
+                let high = read_u32(fixed, offset)?;
+                let low = read_u32(fixed, offset + 4)?;
+                Some(format!("{}.{}.{}.{}", high >> 16, high & 0xFFFF, low >> 16, low & 0xFFFF))
+            };
+            info.file_version = version(8);
+            info.product_version = version(16);
+        }
+    }
+
+    let mut translations: Vec<String> = Vec::new();
+    let mut tables: Vec<(String, BTreeMap<String, String>)> = Vec::new();
+    for child in children(data, &root) {
+        match child.key.as_str() {
+            "StringFileInfo" => {
+                for table in children(data, &child) {
+                    let strings = children(data, &table)
+                        .into_iter()
+                        .map(|string| (string.key.clone(), utf16_value(data, &string)))
+                        .collect();
+                    tables.push((table.key.to_lowercase(), strings));
+                }
+            }
+            "VarFileInfo" => {
+                for var in children(data, &child).into_iter().filter(|var| var.key == "Translation") {
+                    let value = data.get(var.value_offset..var.value_offset + var.value_length).unwrap_or_default();
+                    for pair in value.chunks_exact(4) {
+                        let language = u16::from_le_bytes([pair[0], pair[1]]);
+                        let codepage = u16::from_le_bytes([pair[2], pair[3]]);
+                        translations.push(format!("{:04x}{:04x}", language, codepage));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The declared translation, then US English, then whatever table there is
+    let preferred = translations.iter()
+        .find_map(|translation| tables.iter().position(|(key, _)| key == translation))
+        .or_else(|| tables.iter().position(|(key, _)| key.starts_with("0409")))
+        .or(if tables.is_empty() { None } else { Some(0) });
+    if let Some(index) = preferred {
+        info.strings = tables.swap_remove(index).1;
+    }
+    Some(info)
+}
+
+// One node of the version resource tree
+struct VersionBlock {
+    key: String,
+    end: usize,            // Offset just past the block, children included
+    value_offset: usize,
+    value_length: usize,   // In bytes
+    children_offset: usize,
+}
+
+fn read_block(data: &[u8], offset: usize) -> Option<VersionBlock> {
+    let length = read_u16(data, offset)? as usize;
+    let value_length = read_u16(data, offset + 2)? as usize;
+    let is_text = read_u16(data, offset + 4)? == 1;
+    let end = (offset + length).min(data.len());
+    if length < 6 {
+        return None;
+    }
+
+    let mut key = Vec::new();
+    let mut cursor = offset + 6;
+    while cursor + 1 < end {
+        let unit = read_u16(data, cursor)?;
+        cursor += 2;
+        if unit == 0 {
+            break;
+        }
+        key.push(unit);
+    }
+    let value_offset = align4(cursor);
+    // Text values count UTF-16 units, binary ones bytes
+    let value_length = if is_text { value_length * 2 } else { value_length };
+    let value_length = value_length.min(end.saturating_sub(value_offset));
+
+    Some(VersionBlock {
+        key: String::from_utf16_lossy(&key),
+        end,
+        value_offset,
+        value_length,
+        children_offset: align4(value_offset + value_length),
+    })
+}
+
+fn children(data: &[u8], parent: &VersionBlock) -> Vec<VersionBlock> {
+    let mut blocks = Vec::new();
+    let mut offset = parent.children_offset;
+    while offset < parent.end {
+        match read_block(data, offset) {
+            Some(block) if block.end > offset => {
+                offset = align4(block.end);
+                blocks.push(block);
+            }
+            _ => break,
+        }
+    }
+    blocks
+}
+
+// String values are null-terminated; their declared length is not always right, so read to the null
+fn utf16_value(data: &[u8], block: &VersionBlock) -> String {
+    let units: Vec<u16> = data.get(block.value_offset..block.end)
+        .unwrap_or_default()
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+pub fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+pub fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const SECTION_RVA: u32 = 0x1000;
+    const SECTION_OFFSET: usize = 0x200;
+
+    /// A PE image with a single section, laid out the way linkers do it
+    pub struct PeBuilder {
+        pub machine: u16,
+        pub pe32_plus: bool,
+        pub subsystem: u16,
+        pub characteristics: u16,
+        pub directories: [(u32, u32); 16],
+        pub section: Vec<u8>,
+        pub trailer: Vec<u8>, // After the section, where the certificate table goes
+    }
+
+    impl PeBuilder {
+        pub fn new(machine: u16, pe32_plus: bool) -> Self {
+            PeBuilder {
+                machine,
+                pe32_plus,
+                subsystem: 2,
+                characteristics: 0x0102,
+                directories: [(0, 0); 16],
+                section: Vec::new(),
+                trailer: Vec::new(),
+            }
+        }
+
+        /// Add bytes to the section and return their RVA
+        pub fn append(&mut self, data: &[u8]) -> u32 {
+            while self.section.len() % 4 != 0 {
+                self.section.push(0);
+            }
+            let rva = SECTION_RVA + self.section.len() as u32;
+            self.section.extend_from_slice(data);
+            rva
+        }
+
+        /// A resource tree holding one RT_VERSION resource
+        pub fn with_version_resource(mut self, resource: &[u8]) -> Self {
+            let base = SECTION_RVA + ((self.section.len() as u32 + 3) & !3);
+            let directory = |entry_id: u32, target: u32| -> Vec<u8> {
+                let mut bytes = vec![0; 12];
+                bytes.extend_from_slice(&0u16.to_le_bytes());
+                bytes.extend_from_slice(&1u16.to_le_bytes());
+                bytes.extend_from_slice(&entry_id.to_le_bytes());
+                bytes.extend_from_slice(&target.to_le_bytes());
+                bytes
+            };
+            // Type, name and language directories, then the data entry and the data
+            let mut tree = directory(RT_VERSION, 0x8000_0000 | 24);
+            tree.extend(directory(1, 0x8000_0000 | 48));
+            tree.extend(directory(0x409, 72));
+            tree.extend_from_slice(&(base + 88).to_le_bytes());
+            tree.extend_from_slice(&(resource.len() as u32).to_le_bytes());
+            tree.extend_from_slice(&[0; 8]);
+            tree.extend_from_slice(resource);
+            let rva = self.append(&tree);
+            self.directories[RESOURCE_DIRECTORY] = (rva, tree.len() as u32);
+            self
+        }
+
+        /// A .NET runtime header with its metadata root
+        pub fn with_clr_header(mut self, flags: u32, runtime_version: &str) -> Self {
+            let mut version = runtime_version.as_bytes().to_vec();
+            version.push(0);
+            while version.len() % 4 != 0 {
+                version.push(0);
+            }
+            let mut metadata = METADATA_SIGNATURE.to_le_bytes().to_vec();
+            metadata.extend_from_slice(&[1, 0, 1, 0, 0, 0, 0, 0]);
+            metadata.extend_from_slice(&(version.len() as u32).to_le_bytes());
+            metadata.extend_from_slice(&version);
+            let metadata_rva = self.append(&metadata);
+
+            let mut header = 72u32.to_le_bytes().to_vec();
+            header.extend_from_slice(&[2, 0, 5, 0]);
+            header.extend_from_slice(&metadata_rva.to_le_bytes());
+            header.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+            header.extend_from_slice(&flags.to_le_bytes());
+            header.resize(72, 0);
+            let rva = self.append(&header);
+            self.directories[CLR_DIRECTORY] = (rva, 72);
+            self
+        }
+
+        pub fn build(&self) -> Vec<u8> {
+            let mut bytes = vec![0; 0x40];
+            bytes[0..2].copy_from_slice(b"MZ");
+            bytes[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+            bytes.extend_from_slice(b"PE\0\0");
+
+            let optional_size: u16 = if self.pe32_plus { 240 } else { 224 };
+            bytes.extend_from_slice(&self.machine.to_le_bytes());
+            bytes.extend_from_slice(&1u16.to_le_bytes());
+            bytes.extend_from_slice(&[0; 12]);
+            bytes.extend_from_slice(&optional_size.to_le_bytes());
+            bytes.extend_from_slice(&self.characteristics.to_le_bytes());
+
+            let mut optional = vec![0; optional_size as usize];
+            let magic = if self.pe32_plus { PE32_PLUS_MAGIC } else { PE32_MAGIC };
+            optional[0..2].copy_from_slice(&magic.to_le_bytes());
+            optional[68..70].copy_from_slice(&self.subsystem.to_le_bytes());
+            let (count_offset, directories_offset) = if self.pe32_plus { (108, 112) } else { (92, 96) };
+            optional[count_offset..count_offset + 4].copy_from_slice(&16u32.to_le_bytes());
+            let mut directories = self.directories;
+            let section_size = (self.section.len() + 0x1FF) & !0x1FF;
+            if !self.trailer.is_empty() {
+                directories[CERTIFICATE_DIRECTORY] = ((SECTION_OFFSET + section_size) as u32, self.trailer.len() as u32);
+            }
+            for (index, (address, size)) in directories.iter().enumerate() {
+                let offset = directories_offset + index * 8;
+                optional[offset..offset + 4].copy_from_slice(&address.to_le_bytes());
+                optional[offset + 4..offset + 8].copy_from_slice(&size.to_le_bytes());
+            }
+            bytes.extend_from_slice(&optional);
+
+            let mut section_header = b".rsrc\0\0\0".to_vec();
+            for value in [self.section.len() as u32, SECTION_RVA, section_size as u32, SECTION_OFFSET as u32] {
+                section_header.extend_from_slice(&value.to_le_bytes());
+            }
+            section_header.resize(40, 0);
+            bytes.extend_from_slice(&section_header);
+
+            bytes.resize(SECTION_OFFSET, 0);
+            bytes.extend_from_slice(&self.section);
+            bytes.resize(SECTION_OFFSET + section_size, 0);
+            bytes.extend_from_slice(&self.trailer);
+            bytes
+        }
+    }
+
+    // A VS_VERSIONINFO style block: header, key, value and children, each aligned to four bytes
+    pub fn version_block(key: &str, value: &[u8], is_text: bool, children: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = vec![0; 6];
+        bytes.extend(key.encode_utf16().chain(std::iter::once(0)).flat_map(|unit| unit.to_le_bytes()));
+        while bytes.len() % 4 != 0 {
+            bytes.push(0);
+        }
+        bytes.extend_from_slice(value);
+        for child in children {
+            while bytes.len() % 4 != 0 {
+                bytes.push(0);
+            }
+            bytes.extend_from_slice(child);
+        }
+        let value_length = if is_text { value.len() / 2 } else { value.len() };
+        let length = bytes.len() as u16;
+        bytes[0..2].copy_from_slice(&length.to_le_bytes());
+        bytes[2..4].copy_from_slice(&(value_length as u16).to_le_bytes());
+        bytes[4..6].copy_from_slice(&(is_text as u16).to_le_bytes());
+        bytes
+    }
+
+    fn text(value: &str) -> Vec<u8> {
+        value.encode_utf16().chain(std::iter::once(0)).flat_map(|unit| unit.to_le_bytes()).collect()
+    }
+
+    pub fn version_resource(strings: &[(&str, &str)]) -> Vec<u8> {
+        let mut fixed = vec![0; 52];
+        fixed[0..4].copy_from_slice(&FIXED_FILE_INFO_SIGNATURE.to_le_bytes());
+        for (offset, value) in [(8, 0x0001_0002u32), (12, 0x0003_0004), (16, 0x0001_0002), (20, 0x0003_0000)] {
+            fixed[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        let strings: Vec<Vec<u8>> = strings.iter().map(|(name, value)| version_block(name, &text(value), true, &[])).collect();
+        let table = version_block("040904b0", &[], true, &strings);
+        let string_file_info = version_block("StringFileInfo", &[], true, &[table]);
+        let translation = version_block("Translation", &[0x09, 0x04, 0xB0, 0x04], false, &[]);
+        let var_file_info = version_block("VarFileInfo", &[], true, &[translation]);
+        version_block("VS_VERSION_INFO", &fixed, false, &[string_file_info, var_file_info])
+    }
+
+    #[test]
+    fn reads_the_version_resource() {
+        let resource = version_resource(&[("CompanyName", "Example Corp"), ("ProductName", "Example App"), ("ProductVersion", "1, 2, 3, 4")]);
+        let bytes = PeBuilder::new(0x8664, true).with_version_resource(&resource).build();
+        let info = PeImage::parse(&bytes).unwrap().version_info().unwrap();
+
+        assert_eq!(info.company_name(), Some("Example Corp"));
+        assert_eq!(info.product_name(), Some("Example App"));
+        assert_eq!(info.file_version.as_deref(), Some("1.2.3.4"));
+        assert_eq!(info.product_version.as_deref(), Some("1.2.3.0"));
+        assert_eq!(info.display_version().as_deref(), Some("1.2.3.4"));
+    }
+
+    #[test]
+    fn maps_rvas_inside_sections_only() {
+        let mut builder = PeBuilder::new(0x014C, false);
+        builder.append(&[0xAA; 0x40]);
+        let bytes = builder.build();
+        let image = PeImage::parse(&bytes).unwrap();
+
+        assert_eq!(image.rva_to_offset(SECTION_RVA), Some(SECTION_OFFSET));
+        assert_eq!(image.rva_to_offset(SECTION_RVA + 0x10), Some(SECTION_OFFSET + 0x10));
+        assert_eq!(image.rva_to_offset(SECTION_RVA - 1), None);
+        assert_eq!(image.rva_to_offset(SECTION_RVA + 0x200), None);
+    }
+
+    #[test]
+    fn rejects_section_offsets_that_overflow() {
+        let bytes = PeBuilder::new(0x014C, false).build();
+        let mut image = PeImage::parse(&bytes).unwrap();
+        image.sections[0].raw_offset = u32::MAX - 4;
+
+        assert_eq!(image.rva_to_offset(SECTION_RVA + 0x10), None);
+        assert!(image.find_resource(RT_VERSION).is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_images() {
+        let resource = version_resource(&[("ProductName", "Example App")]);
+        let bytes = PeBuilder::new(0x8664, true).with_version_resource(&resource).build();
+
+        assert!(PeImage::parse(&bytes[..0x30]).is_err());  // Inside the DOS header
+        assert!(PeImage::parse(&bytes[..0x4C]).is_err());  // Inside the COFF header
+        assert!(PeImage::parse(&bytes[..0x60]).is_err());  // Inside the optional header
+        // Cut inside the section: headers still parse, the resource is simply not there
+        let image = PeImage::parse(&bytes[..SECTION_OFFSET + 0x20]).unwrap();
+        assert!(image.version_info().is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_images() {
+        let valid = PeBuilder::new(0x8664, true).build();
+
+        let mut no_signature = valid.clone();
+        no_signature[0x40] = b'X';
+        assert_eq!(PeImage::parse(&no_signature).err().as_deref(), Some("No PE signature"));
+
+        let mut bad_magic = valid.clone();
+        bad_magic[0x58..0x5A].copy_from_slice(&0x30bu16.to_le_bytes());
+        assert!(PeImage::parse(&bad_magic).err().unwrap().contains("Unknown optional header magic"));
+
+        let mut far_header = valid;
+        far_header[0x3C..0x40].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(PeImage::parse(&far_header).is_err());
+
+        assert!(PeImage::parse(b"").is_err());
+        assert!(PeImage::parse(b"#!/bin/sh\necho hello\n").is_err());
+    }
+
+    #[test]
+    fn stops_at_resource_directories_that_loop() {
+        let mut builder = PeBuilder::new(0x8664, true);
+        // A type directory whose only entry points back at itself
+        let mut directory = vec![0; 14];
+        directory.extend_from_slice(&1u16.to_le_bytes());
+        directory.extend_from_slice(&RT_VERSION.to_le_bytes());
+        directory.extend_from_slice(&0x8000_0000u32.to_le_bytes());
+        let rva = builder.append(&directory);
+        builder.directories[RESOURCE_DIRECTORY] = (rva, directory.len() as u32);
+        let bytes = builder.build();
+
+        assert!(PeImage::parse(&bytes).unwrap().find_resource(RT_VERSION).is_none());
+    }
+
+    #[test]
+    fn reads_machine_type_and_subsystem() {
+        let mut console_dll = PeBuilder::new(0x014C, false);
+        console_dll.subsystem = 3;
+        console_dll.characteristics |= IMAGE_FILE_DLL;
+        let info = image_info(Path::new("console.dll"), &PeImage::parse(&console_dll.build()).unwrap());
+        assert_eq!((info.machine, info.subsystem, info.is_dll, info.is_64_bit), (Machine::X86, Subsystem::Console, true, false));
+        assert_eq!(info.architecture(), "32-bit");
+        assert!(info.dotnet.is_none());
+
+        let cases = [
+            (0x8664, true, 2, Machine::X64, Subsystem::Gui, "64-bit"),
+            (0xAA64, true, 2, Machine::Arm64, Subsystem::Gui, "ARM64"),
+            (0x01C4, false, 2, Machine::Arm, Subsystem::Gui, "ARM"),
+            (0x8664, true, 1, Machine::X64, Subsystem::Native, "64-bit"),
+            (0x8664, true, 10, Machine::X64, Subsystem::Efi, "64-bit"),
+            (0x5064, true, 3, Machine::Other, Subsystem::Console, "Unknown"),
+        ];
+        for (code, pe32_plus, subsystem, machine, expected_subsystem, architecture) in cases {
+            let mut builder = PeBuilder::new(code, pe32_plus);
+            builder.subsystem = subsystem;
+            let info = image_info(Path::new("app.exe"), &PeImage::parse(&builder.build()).unwrap());
+            assert_eq!((info.machine, info.machine_code, info.subsystem, info.is_64_bit, info.is_dll), (machine, code, expected_subsystem, pe32_plus, false));
+            assert_eq!(info.architecture(), architecture);
+        }
+    }
+
+    #[test]
+    fn reads_the_dotnet_header() {
+        let any_cpu = PeBuilder::new(0x014C, false).with_clr_header(COMIMAGE_FLAGS_ILONLY, "v4.0.30319").build();
+        let image = PeImage::parse(&any_cpu).unwrap();
+        let info = image_info(Path::new("managed.exe"), &image);
+        let dotnet = info.dotnet.as_ref().unwrap();
+        assert_eq!(dotnet.runtime_version.as_deref(), Some("v4.0.30319"));
+        assert!(dotnet.il_only && !dotnet.requires_32_bit && !dotnet.prefers_32_bit);
+        assert!(info.any_cpu);
+        assert_eq!(info.architecture(), "Neutral");
+        assert_eq!(image.architecture(), "Neutral");
+
+        let prefers_32_bit = COMIMAGE_FLAGS_ILONLY | COMIMAGE_FLAGS_32BITREQUIRED | COMIMAGE_FLAGS_32BITPREFERRED;
+        for (machine, flags, architecture) in [
+            (0x014C, COMIMAGE_FLAGS_ILONLY | COMIMAGE_FLAGS_32BITREQUIRED, "32-bit"),
+            (0x014C, prefers_32_bit, "32-bit"),
+            (0x014C, 0, "32-bit"),                  // Mixed-mode C++/CLI carries native x86 code
+            (0x8664, COMIMAGE_FLAGS_ILONLY, "64-bit"), // Built for x64 only
+        ] {
+            let bytes = PeBuilder::new(machine, machine == 0x8664).with_clr_header(flags, "v4.0.30319").build();
+            let info = image_info(Path::new("managed.exe"), &PeImage::parse(&bytes).unwrap());
+            assert!(!info.any_cpu);
+            assert_eq!(info.architecture(), architecture);
+        }
+        let prefers = PeBuilder::new(0x014C, false).with_clr_header(prefers_32_bit, "v2.0.50727").build();
+        let dotnet = PeImage::parse(&prefers).unwrap().clr_header().unwrap();
+        assert!(dotnet.prefers_32_bit && !dotnet.requires_32_bit);
+        assert_eq!(dotnet.runtime_version.as_deref(), Some("v2.0.50727"));
+    }
+
+    #[test]
+    fn reads_the_signer() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/signatures/signed.exe");
+        let info = read_image_info(&path).unwrap();
+        assert_eq!((info.machine, info.subsystem), (Machine::X64, Subsystem::Gui));
+        assert!(info.signature_error.is_none());
+
+        let signature = info.signature.unwrap();
+        assert_eq!(signature.signer_name.as_deref(), Some("Contoso Test Publisher"));
+        assert_eq!(signature.signer_subject, "CN=Contoso Test Publisher, O=Contoso Test");
+        assert_eq!(signature.issuer, "CN=Contoso Test Code Signing CA, O=Contoso Test");
+        assert_eq!(signature.digest_algorithm, "SHA-256");
+        assert_eq!(signature.certificate_count, 2);
+        assert!(signature.timestamp.is_none());
+
+        let unsigned = image_info(Path::new("unsigned.exe"), &PeImage::parse(&PeBuilder::new(0x8664, true).build()).unwrap());
+        assert!(unsigned.signature.is_none() && unsigned.signature_error.is_none());
+    }
+}
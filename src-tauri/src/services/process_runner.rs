@@ -0,0 +1,277 @@
+// Running external processes under supervision: output captured, a deadline enforced. Callers take a
+// `&dyn ProcessRunner` so the logic around them can run against a fake
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often a running process is checked for exit or for passing its deadline
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// Output is collected for at most this long after exit: an uninstaller that hands off to a copy of itself
+// leaves that copy holding the pipes
+const OUTPUT_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct ProcessRequest {
+    pub executable: String,
+    pub arguments: Vec<String>,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessOutput {
+    pub exit_code: Option<i32>, // None when the process was killed
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    pub duration: Duration,
+}
+
+pub trait ProcessRunner: Send + Sync {
+    /// Run a process to completion or until its timeout. Err only when it could not be started
+    fn run(&self, request: &ProcessRequest) -> Result<ProcessOutput, String>;
+}
+
+/// Spawns real processes
+pub struct SystemProcessRunner;
+
+impl ProcessRunner for SystemProcessRunner {
+    fn run(&self, request: &ProcessRequest) -> Result<ProcessOutput, String> {
+        println!("DEBUG: Running {} {:?} (timeout {:?})", request.executable, request.arguments, request.timeout);
+        let started = Instant::now();
+        let child = Command::new(&request.executable)
+            .args(&request.arguments)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", request.executable, e))?;
+        let mut process = SupervisedProcess { tree: platform::ProcessTree::attach(&child), child };
+
+        // Drain both pipes while waiting, or a chatty process blocks on a full pipe and never exits
+        let stdout = process.child.stdout.take().map(read_in_background);
+        let stderr = process.child.stderr.take().map(read_in_background);
+
+        let (exit_code, timed_out) = loop {
+            match process.child.try_wait() {
+                Ok(Some(status)) => break (status.code(), false),
+                Ok(None) if started.elapsed() >= request.timeout => {
+                    println!("DEBUG: {} passed its timeout, killing it", request.executable);
+                    process.stop();
+                    break (None, true);
+                }
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                // Dropping the process stops it
+                Err(e) => return Err(format!("Failed to wait for {}: {}", request.executable, e)),
+            }
+        };
+
+        Ok(ProcessOutput {
+            exit_code,
+            stdout: stdout.map(collect_output).unwrap_or_default(),
+            stderr: stderr.map(collect_output).unwrap_or_default(),
+            timed_out,
+            duration: started.elapsed(),
+        })
+    }
+}
+
+// A started process that is stopped, with everything it started, unless it has exited by the time it is
+// dropped. Once it has exited, processes it handed off to are left alone: uninstallers commonly copy
+// themselves to %TEMP% and let that copy do the work
+struct SupervisedProcess {
+    child: Child,
+    tree: Option<platform::ProcessTree>,
+}
+
+impl SupervisedProcess {
+    fn stop(&mut self) {
+        if let Some(tree) = &self.tree {
+            tree.terminate();
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for SupervisedProcess {
+    fn drop(&mut self) {
+        if !matches!(self.child.try_wait(), Ok(Some(_))) {
+            self.stop();
+        }
+    }
+}
+
+fn read_in_background<R: Read + Send + 'static>(mut pipe: R) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = pipe.read_to_end(&mut buffer);
+        let _ = sender.send(buffer);
+    });
+    receiver
+}
+
+fn collect_output(reader: Receiver<Vec<u8>>) -> String {
+    reader.recv_timeout(OUTPUT_GRACE)
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .unwrap_or_default()
+}
+
+// msiexec and setup stubs start processes of their own, which killing the direct child leaves running.
+// On Windows the child is put in a Job Object, which its descendants join, so the whole tree can be
+// terminated. A descendant started before the child is assigned escapes it; uninstallers start theirs
+// well after that
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+    use std::ptr;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateJobObjectW(attributes: *const c_void, name: *const u16) -> *mut c_void;
+        fn AssignProcessToJobObject(job: *mut c_void, process: *mut c_void) -> i32;
+        fn TerminateJobObject(job: *mut c_void, exit_code: u32) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    pub struct ProcessTree {
+        job: *mut c_void,
+    }
+
+    // The job handle is only used by the thread supervising the process
+    unsafe impl Send for ProcessTree {}
+
+    impl ProcessTree {
+        pub fn attach(child: &Child) -> Option<Self> {
+            let job = unsafe { CreateJobObjectW(ptr::null(), ptr::null()) };
+            if job.is_null() {
+                println!("DEBUG: Could not create a job object: {}", std::io::Error::last_os_error());
+                return None;
+            }
+            let tree = ProcessTree { job };
+            if unsafe { AssignProcessToJobObject(job, child.as_raw_handle() as *mut c_void) } == 0 {
+                println!("DEBUG: Could not add process {} to a job object: {}", child.id(), std::io::Error::last_os_error());
+                return None;
+            }
+            Some(tree)
+        }
+
+        pub fn terminate(&self) {
+            if unsafe { TerminateJobObject(self.job, 1) } == 0 {
+                println!("DEBUG: Could not terminate the job object: {}", std::io::Error::last_os_error());
+            }
+        }
+    }
+
+    impl Drop for ProcessTree {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.job) };
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::process::Child;
+
+    // Only the direct child is stopped elsewhere
+    pub struct ProcessTree;
+
+    impl ProcessTree {
+        pub fn attach(_child: &Child) -> Option<Self> {
+            None
+        }
+
+        pub fn terminate(&self) {}
+    }
+}
+
+/// Answers requests from a script instead of starting processes, and records what it was asked to run
+#[cfg(test)]
+pub struct FakeProcessRunner {
+    responses: std::sync::Mutex<std::collections::VecDeque<Result<ProcessOutput, String>>>,
+    pub requests: std::sync::Mutex<Vec<ProcessRequest>>,
+}
+
+#[cfg(test)]
+impl FakeProcessRunner {
+    pub fn new(responses: Vec<Result<ProcessOutput, String>>) -> Self {
+        FakeProcessRunner {
+            responses: std::sync::Mutex::new(responses.into()),
+            requests: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn exiting_with(exit_codes: &[i32]) -> Self {
+        Self::new(exit_codes.iter().map(|&code| Ok(ProcessOutput::exited(code))).collect())
+    }
+
+    pub fn command_lines(&self) -> Vec<String> {
+        self.requests.lock().unwrap().iter()
+            .map(|request| std::iter::once(request.executable.clone()).chain(request.arguments.iter().cloned()).collect::<Vec<_>>().join(" "))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl ProcessOutput {
+    pub fn exited(exit_code: i32) -> Self {
+        ProcessOutput { exit_code: Some(exit_code), stdout: String::new(), stderr: String::new(), timed_out: false, duration: Duration::from_millis(10) }
+    }
+
+    pub fn timed_out() -> Self {
+        ProcessOutput { exit_code: None, timed_out: true, ..ProcessOutput::exited(0) }
+    }
+}
+
+#[cfg(test)]
+impl ProcessRunner for FakeProcessRunner {
+    fn run(&self, request: &ProcessRequest) -> Result<ProcessOutput, String> {
+        self.requests.lock().unwrap().push(request.clone());
+        self.responses.lock().unwrap().pop_front().unwrap_or_else(|| Err(format!("No scripted response for {}", request.executable)))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str, timeout: Duration) -> Result<ProcessOutput, String> {
+        SystemProcessRunner.run(&ProcessRequest {
+            executable: "sh".to_string(),
+            arguments: vec!["-c".to_string(), script.to_string()],
+            timeout,
+        })
+    }
+
+    #[test]
+    fn captures_exit_code_and_output() {
+        let output = shell("echo installed; echo warning >&2; exit 3", Duration::from_secs(10)).unwrap();
+        assert_eq!(output.exit_code, Some(3));
+        assert_eq!(output.stdout.trim(), "installed");
+        assert_eq!(output.stderr.trim(), "warning");
+        assert!(!output.timed_out);
+    }
+
+    #[test]
+    fn stops_processes_that_pass_their_timeout() {
+        let output = shell("exec sleep 30", Duration::from_millis(300)).unwrap();
+        assert!(output.timed_out);
+        assert_eq!(output.exit_code, None);
+        assert!(output.duration < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn reports_processes_that_cannot_start() {
+        let error = SystemProcessRunner.run(&ProcessRequest {
+            executable: "/nonexistent/uninstall.exe".to_string(),
+            arguments: Vec::new(),
+            timeout: Duration::from_secs(1),
+        }).unwrap_err();
+        assert!(error.starts_with("Failed to start /nonexistent/uninstall.exe"));
+    }
+}
@@ -0,0 +1,330 @@
+// Parser for regedit's text export format (.reg files), loaded into a MemoryRegistry.
+// Handles both "REGEDIT4" (ANSI) and "Windows Registry Editor Version 5.00" (usually UTF-16LE) exports.
+use std::fs;
+use std::path::Path;
+
+use crate::services::registry_source::{
+    decode_multi_string_bytes, decode_utf16_bytes, utf16_units, MemoryRegistry, RegistryHive, RegistryValue,
+};
+
+const HEADER_V5: &str = "Windows Registry Editor Version 5.00";
+const HEADER_V4: &str = "REGEDIT4";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RegFileVersion {
+    Regedit4,
+    Version5,
+}
+
+pub fn load_reg_file(path: &str) -> Result<MemoryRegistry, Box<dyn std::error::Error>> {
+    let bytes = fs::read(Path::new(path))?;
+    Ok(parse_reg_file(&bytes)?)
+}
+
+/// Parse the raw bytes of a .reg file into an in-memory registry
+pub fn parse_reg_file(bytes: &[u8]) -> Result<MemoryRegistry, String> {
+    let text = decode_text(bytes);
+    let mut lines = logical_lines(&text).into_iter();
+
+    let version = match lines.next().as_deref().map(str::trim) {
+        Some(HEADER_V5) => RegFileVersion::Version5,
+        Some(HEADER_V4) => RegFileVersion::Regedit4,
+        _ => return Err("Not a registry export: missing REGEDIT4 or Version 5.00 header".to_string()),
+    };
+
+    let mut registry = MemoryRegistry::new();
+    // None while outside a key, or inside a key we cannot represent (e.g. HKEY_CURRENT_CONFIG or a deletion)
+    let mut current: Option<(RegistryHive, String)> = None;
+
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            current = parse_key_line(line);
+            if let Some((hive, path)) = &current {
+                registry.hive_mut(*hive).create_path(path);
+            }
+            continue;
+        }
+
+        let (hive, path) = match &current {
+            Some(current) => current,
+            None => continue,
+        };
+
+        match parse_value_line(line, version) {
+            Some((name, Some(value))) => {
+                registry.hive_mut(*hive).create_path(path).set_value(&name, value);
+            }
+            Some((name, None)) => {
+                registry.hive_mut(*hive).create_path(path).remove_value(&name);
+            }
+            None => println!("DEBUG: Skipping unparseable .reg line: {}", line),
+        }
+    }
+
+    Ok(registry)
+}
+
+// UTF-16LE with BOM is what regedit writes for Version 5.00; REGEDIT4 is ANSI
+fn decode_text(bytes: &[u8]) -> String {
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return String::from_utf16_lossy(&utf16_units(&bytes[2..]));
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        // Not UTF-8, so treat it as a Latin-1 / Windows-1252 code page export
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+// Join lines ending in a backslash continuation (long hex values are wrapped at 80 columns)
+fn logical_lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pending = String::new();
+
+    for raw_line in text.lines() {
+        let line = if pending.is_empty() { raw_line } else { raw_line.trim_start() };
+        let trimmed = line.trim_end();
+        if trimmed.ends_with('\\') && is_hex_data(&pending, trimmed) {
+            pending.push_str(&trimmed[..trimmed.len() - 1]);
+            continue;
+        }
+        pending.push_str(line);
+        lines.push(std::mem::take(&mut pending));
+    }
+    if !pending.is_empty() {
+        lines.push(pending);
+    }
+
+    lines
+}
+
+// Only hex data wraps; a quoted string may legitimately end with an escaped backslash
+fn is_hex_data(pending: &str, line: &str) -> bool {
+    if !pending.is_empty() {
+        return true;
+    }
+    match line.find("=hex") {
+        Some(pos) => !line[pos..].contains('"'),
+        None => false,
+    }
+}
+
+fn parse_key_line(line: &str) -> Option<(RegistryHive, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    if inner.starts_with('-') {
+        return None;
+    }
+
+    let (root, path) = match inner.find('\\') {
+        Some(pos) => (&inner[..pos], &inner[pos + 1..]),
+        None => (inner, ""),
+    };
+
+    let hive = match root.to_ascii_uppercase().as_str() {
+        "HKEY_LOCAL_MACHINE" | "HKLM" => RegistryHive::LocalMachine,
+        "HKEY_CURRENT_USER" | "HKCU" => RegistryHive::CurrentUser,
+        "HKEY_USERS" | "HKU" => RegistryHive::Users,
+        "HKEY_CLASSES_ROOT" | "HKCR" => RegistryHive::ClassesRoot,
+        _ => {
+            println!("DEBUG: Skipping unsupported registry root in .reg file: {}", root);
+            return None;
+        }
+    };
+
+    Some((hive, path.to_string()))
+}
+
+// Returns the value name and its data, or None for the data when the line deletes the value ("name"=-)
+fn parse_value_line(line: &str, version: RegFileVersion) -> Option<(String, Option<RegistryValue>)> {
+    let (name, rest) = if let Some(rest) = line.strip_prefix('@') {
+        (String::new(), rest)
+    } else {
+        parse_quoted(line)?
+    };
+
+    let data = rest.trim_start().strip_prefix('=')?.trim();
+    if data == "-" {
+        return Some((name, None));
+    }
+
+    let value = if data.starts_with('"') {
+        let (text, _) = parse_quoted(data)?;
+        RegistryValue::String(text)
+    } else if let Some(hex) = data.strip_prefix("dword:") {
+        RegistryValue::Dword(u32::from_str_radix(hex.trim(), 16).ok()?)
+    } else if let Some(hex) = data.strip_prefix("hex:") {
+        RegistryValue::Binary(parse_hex_bytes(hex)?)
+    } else if let Some(typed) = data.strip_prefix("hex(") {
+        let close = typed.find("):")?;
+        let value_type = u32::from_str_radix(&typed[..close], 16).ok()?;
+        let bytes = parse_hex_bytes(&typed[close + 2..])?;
+        typed_value(value_type, bytes, version)
+    } else {
+        return None;
+    };
+
+    Some((name, Some(value)))
+}
+
+// hex(n): carries the REG_* type number; string types are UTF-16 in 5.00 exports and ANSI in REGEDIT4
+fn typed_value(value_type: u32, bytes: Vec<u8>, version: RegFileVersion) -> RegistryValue {
+    let decode_string = |bytes: &[u8]| match version {
+        RegFileVersion::Version5 => decode_utf16_bytes(bytes),
+        RegFileVersion::Regedit4 => bytes.iter().map(|&b| b as char).collect::<String>().trim_end_matches('\0').to_string(),
+    };
+
+    match value_type {
+        0 if bytes.is_empty() => RegistryValue::None,
+        1 => RegistryValue::String(decode_string(&bytes)),
+        2 => RegistryValue::ExpandString(decode_string(&bytes)),
+        4 if bytes.len() == 4 => RegistryValue::Dword(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        5 if bytes.len() == 4 => RegistryValue::Dword(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        7 => RegistryValue::MultiString(match version {
+            RegFileVersion::Version5 => decode_multi_string_bytes(&bytes),
+            RegFileVersion::Regedit4 => decode_string(&bytes)
+                .split('\0')
+                .filter(|part| !part.is_empty())
+                .map(|part| part.to_string())
+                .collect(),
+        }),
+        0xb if bytes.len() == 8 => {
+            let mut qword = [0u8; 8];
+            qword.copy_from_slice(&bytes);
+            RegistryValue::Qword(u64::from_le_bytes(qword))
+        }
+        _ => RegistryValue::Binary(bytes),
+    }
+}
+
+fn parse_hex_bytes(data: &str) -> Option<Vec<u8>> {
+    data.split(',')
+        .map(str::trim)
+        .filter(|byte| !byte.is_empty())
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect()
+}
+
+// Parse a leading "quoted string" with regedit's \\ and \" escapes, returning it and the remainder
+fn parse_quoted(input: &str) -> Option<(String, &str)> {
+    let mut chars = input.strip_prefix('"')?.char_indices();
+    let mut result = String::new();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped)) => result.push(escaped),
+                None => return None,
+            },
+            '"' => return Some((result, &input[i + 2..])),
+            _ => result.push(c),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::registry_source::RegistrySource;
+
+    const UNINSTALL_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\Contoso";
+
+    // Bytes as regedit writes them: comma-separated hex, wrapped with \ continuations
+    fn hex_lines(bytes: &[u8]) -> String {
+        bytes.chunks(12)
+            .map(|chunk| chunk.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(","))
+            .collect::<Vec<_>>()
+            .join(",\\\r\n  ")
+    }
+
+    fn utf16_bytes(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()
+    }
+
+    fn value(registry: &MemoryRegistry, hive: RegistryHive, path: &str, name: &str) -> Option<RegistryValue> {
+        registry.open_key(hive, path)?.get_value(name)
+    }
+
+    #[test]
+    fn reads_utf16_version5_exports() {
+        let text = format!(
+            "Windows Registry Editor Version 5.00\r\n\r\n\
+             ; exported from a test machine\r\n\
+             [HKEY_LOCAL_MACHINE\\{}]\r\n\
+             @=\"Default \\\"value\\\"\"\r\n\
+             \"DisplayName\"=\"Contoso \\\"Agent\\\"\"\r\n\
+             \"Say \\\"hi\\\"\"=\"yes\"\r\n\
+             \"InstallLocation\"=\"C:\\\\Program Files\\\\Contoso\\\\\"\r\n\
+             \"EstimatedSize\"=dword:00001f40\r\n\
+             \"UninstallPath\"=hex(2):{}\r\n\
+             \"Components\"=hex(7):{}\r\n\
+             \"Removed\"=\"soon\"\r\n\
+             \"Removed\"=-\r\n",
+            UNINSTALL_KEY,
+            hex_lines(&utf16_bytes("%ProgramFiles%\\Contoso\\uninstall.exe\0")),
+            hex_lines(&utf16_bytes("agent\0updater\0\0")),
+        );
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16_bytes(&text));
+        let registry = parse_reg_file(&bytes).unwrap();
+        let get = |name: &str| value(&registry, RegistryHive::LocalMachine, UNINSTALL_KEY, name);
+
+        assert_eq!(get(""), Some(RegistryValue::String("Default \"value\"".to_string())));
+        assert_eq!(get("DisplayName"), Some(RegistryValue::String("Contoso \"Agent\"".to_string())));
+        assert_eq!(get("Say \"hi\""), Some(RegistryValue::String("yes".to_string())));
+        // A string ending in an escaped backslash is not a continuation
+        assert_eq!(get("InstallLocation"), Some(RegistryValue::String("C:\\Program Files\\Contoso\\".to_string())));
+        assert_eq!(get("EstimatedSize"), Some(RegistryValue::Dword(8000)));
+        assert_eq!(get("UninstallPath"), Some(RegistryValue::ExpandString("%ProgramFiles%\\Contoso\\uninstall.exe".to_string())));
+        assert_eq!(get("Components"), Some(RegistryValue::MultiString(vec!["agent".to_string(), "updater".to_string()])));
+        assert_eq!(get("Removed"), None);
+    }
+
+    #[test]
+    fn reads_ansi_regedit4_exports() {
+        let mut bytes = b"REGEDIT4\n\n[HKEY_CURRENT_USER\\Software\\Contoso]\n\"Name\"=\"Caf".to_vec();
+        bytes.push(0xE9); // Windows-1252
+        bytes.extend_from_slice(b"\"\n@=\"default\"\n");
+        bytes.extend_from_slice(format!("\"Path\"=hex(2):{}\n", hex_lines(b"%TEMP%\\contoso\0")).as_bytes());
+        bytes.extend_from_slice(b"\"List\"=hex(7):61,00,62,63,00,00\n\"Big\"=hex(b):00,00,00,00,01,00,00,00\n");
+        bytes.extend_from_slice(b"\"Flags\"=dword:FFFFFFFF\n\"Blob\"=hex:de,ad,\\\n  be,ef\n\"Broken\"=dword:xyz\n");
+        let registry = parse_reg_file(&bytes).unwrap();
+        let get = |name: &str| value(&registry, RegistryHive::CurrentUser, "Software\\Contoso", name);
+
+        assert_eq!(get("Name"), Some(RegistryValue::String("Café".to_string())));
+        assert_eq!(get(""), Some(RegistryValue::String("default".to_string())));
+        assert_eq!(get("Path"), Some(RegistryValue::ExpandString("%TEMP%\\contoso".to_string())));
+        assert_eq!(get("List"), Some(RegistryValue::MultiString(vec!["a".to_string(), "bc".to_string()])));
+        assert_eq!(get("Big"), Some(RegistryValue::Qword(1 << 32)));
+        assert_eq!(get("Flags"), Some(RegistryValue::Dword(u32::MAX)));
+        assert_eq!(get("Blob"), Some(RegistryValue::Binary(vec![0xde, 0xad, 0xbe, 0xef])));
+        assert_eq!(get("Broken"), None);
+    }
+
+    #[test]
+    fn skips_keys_it_cannot_represent() {
+        let text = "Windows Registry Editor Version 5.00\n\n\
+                    [-HKEY_LOCAL_MACHINE\\SOFTWARE\\Gone]\n\"A\"=\"1\"\n\n\
+                    [HKEY_CURRENT_CONFIG\\Software]\n\"B\"=\"2\"\n\n\
+                    [HKLM\\SOFTWARE\\Kept]\n\"C\"=\"3\"\n";
+        let registry = parse_reg_file(text.as_bytes()).unwrap();
+        assert!(registry.open_key(RegistryHive::LocalMachine, "SOFTWARE\\Gone").is_none());
+        assert_eq!(value(&registry, RegistryHive::LocalMachine, "SOFTWARE\\Kept", "C"), Some(RegistryValue::String("3".to_string())));
+        assert!(value(&registry, RegistryHive::LocalMachine, "SOFTWARE\\Kept", "B").is_none());
+    }
+
+    #[test]
+    fn requires_a_header() {
+        assert!(parse_reg_file(b"[HKEY_LOCAL_MACHINE\\SOFTWARE]\n").is_err());
+        assert!(parse_reg_file(b"").is_err());
+        // A UTF-8 BOM is tolerated
+        assert!(parse_reg_file(b"\xEF\xBB\xBFREGEDIT4\r\n").is_ok());
+    }
+}
@@ -0,0 +1,604 @@
+// Pure-Rust reader for Windows registry hive files (the "regf" format used by
+// SOFTWARE, SYSTEM and NTUSER.DAT). Only what the inventory scan needs is
+// implemented: key/value enumeration and typed value data. Transaction logs
+// (.LOG1/.LOG2) are not replayed, so a hive copied from a machine that was not
+// shut down cleanly may miss its most recent writes.
+
+use std::fs;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use crate::services::registry_source::{
+    decode_multi_string_bytes, decode_utf16_bytes, filetime_to_datetime, RegistryHive, RegistryKey, RegistrySource,
+    RegistryValue,
+};
+
+const BASE_BLOCK_SIZE: usize = 4096;
+const NO_OFFSET: u32 = 0xFFFF_FFFF;
+const KEY_COMP_NAME: u16 = 0x0020;
+const VALUE_COMP_NAME: u16 = 0x0001;
+const DATA_IS_RESIDENT: u32 = 0x8000_0000;
+const BIG_DATA_SEGMENT_SIZE: usize = 16344;
+const MAX_LIST_DEPTH: usize = 8;
+
+const REG_NONE: u32 = 0;
+const REG_SZ: u32 = 1;
+const REG_EXPAND_SZ: u32 = 2;
+const REG_DWORD: u32 = 4;
+const REG_DWORD_BIG_ENDIAN: u32 = 5;
+const REG_MULTI_SZ: u32 = 7;
+const REG_QWORD: u32 = 11;
+
+/// A registry hive file loaded into memory
+pub struct RegfHive {
+    data: Vec<u8>,
+    root_offset: u32,
+    minor_version: u32,
+}
+
+impl RegfHive {
+    pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let data = fs::read(Path::new(path))
+            .map_err(|e| format!("Failed to read hive file {}: {}", path, e))?;
+        Self::from_bytes(data)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Box<dyn std::error::Error>> {
+        if data.len() < BASE_BLOCK_SIZE || &data[0..4] != b"regf" {
+            return Err("Not a registry hive file (missing regf signature)".into());
+        }
+
+        let major_version = read_u32(&data, 20).unwrap_or(0);
+        let minor_version = read_u32(&data, 24).unwrap_or(0);
+        if major_version != 1 {
+            return Err(format!("Unsupported hive format version {}.{}", major_version, minor_version).into());
+        }
+
+        let root_offset = read_u32(&data, 36).ok_or("Truncated hive base block")?;
+
+        let hive = RegfHive {
+            data,
+            root_offset,
+            minor_version,
+        };
+
+        if hive.key_at(root_offset).is_none() {
+            return Err("Hive root cell is not a valid key node".into());
+        }
+
+        Ok(hive)
+    }
+
+    /// The hive's root key (e.g. HKLM\SOFTWARE for a SOFTWARE hive)
+    pub fn root(&self) -> RegfKey<'_> {
+        RegfKey {
+            hive: self,
+            offset: self.root_offset,
+        }
+    }
+
+    /// Open a key by backslash-separated path relative to the hive root
+    pub fn open_key(&self, path: &str) -> Option<RegfKey<'_>> {
+        let mut current = self.root();
+        for part in path.split('\\').filter(|p| !p.is_empty()) {
+            current = current.child(part)?;
+        }
+        Some(current)
+    }
+
+    // Cell offsets are relative to the first hbin, which follows the base block.
+    // The cell size is negative for allocated cells; the returned slice excludes the size field.
+    fn cell(&self, offset: u32) -> Option<&[u8]> {
+        if offset == NO_OFFSET {
+            return None;
+        }
+        let start = BASE_BLOCK_SIZE.checked_add(offset as usize)?;
+        let raw_size = read_u32(&self.data, start)? as i32;
+        let size = raw_size.unsigned_abs() as usize;
+        if size < 4 {
+            return None;
+        }
+        self.data.get(start + 4..start + size)
+    }
+
+    fn key_at(&self, offset: u32) -> Option<&[u8]> {
+        let cell = self.cell(offset)?;
+        if cell.len() >= 76 && &cell[0..2] == b"nk" {
+            Some(cell)
+        } else {
+            None
+        }
+    }
+
+    // Walk lf/lh/li/ri subkey index cells and collect the nk offsets they reference
+    fn collect_subkey_offsets(&self, list_offset: u32, depth: usize, offsets: &mut Vec<u32>) {
+        if depth > MAX_LIST_DEPTH {
+            return;
+        }
+        let cell = match self.cell(list_offset) {
+            Some(cell) if cell.len() >= 4 => cell,
+            _ => return,
+        };
+        let count = read_u16(cell, 2).unwrap_or(0) as usize;
+
+        match &cell[0..2] {
+            b"lf" | b"lh" => {
+                for i in 0..count {
+                    if let Some(offset) = read_u32(cell, 4 + i * 8) {
+                        offsets.push(offset);
+                    }
+                }
+            }
+            b"li" => {
+                for i in 0..count {
+                    if let Some(offset) = read_u32(cell, 4 + i * 4) {
+                        offsets.push(offset);
+                    }
+                }
+            }
+            b"ri" => {
+                for i in 0..count {
+                    if let Some(sublist) = read_u32(cell, 4 + i * 4) {
+                        self.collect_subkey_offsets(sublist, depth + 1, offsets);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn value_data(&self, vk: &[u8]) -> Option<Vec<u8>> {
+        let raw_size = read_u32(vk, 4)?;
+        let data_offset = read_u32(vk, 8)?;
+
+        // Small values (up to 4 bytes) live directly in the offset field
+        if raw_size & DATA_IS_RESIDENT != 0 {
+            let size = ((raw_size & !DATA_IS_RESIDENT) as usize).min(4);
+            return Some(data_offset.to_le_bytes()[..size].to_vec());
+        }
+
+        let size = raw_size as usize;
+        if size == 0 {
+            return Some(Vec::new());
+        }
+
+        let cell = self.cell(data_offset)?;
+        if size > BIG_DATA_SEGMENT_SIZE && self.minor_version > 3 && cell.len() >= 8 && &cell[0..2] == b"db" {
+            return self.big_data(cell, size);
+        }
+
+        cell.get(..size).map(|bytes| bytes.to_vec())
+    }
+
+    // "db" cells split large values into segments listed in a separate cell
+    fn big_data(&self, db: &[u8], size: usize) -> Option<Vec<u8>> {
+        let segment_count = read_u16(db, 2)? as usize;
+        let list = self.cell(read_u32(db, 4)?)?;
+
+        let mut data = Vec::with_capacity(size);
+        for i in 0..segment_count {
+            let segment = self.cell(read_u32(list, i * 4)?)?;
+            let remaining = size - data.len();
+            let take = remaining.min(BIG_DATA_SEGMENT_SIZE).min(segment.len());
+            data.extend_from_slice(&segment[..take]);
+            if data.len() >= size {
+                break;
+            }
+        }
+        Some(data)
+    }
+}
+
+/// A key node inside a loaded hive
+#[derive(Clone, Copy)]
+pub struct RegfKey<'a> {
+    hive: &'a RegfHive,
+    offset: u32,
+}
+
+impl<'a> RegfKey<'a> {
+    fn node(&self) -> &'a [u8] {
+        // Offsets only ever come from validated nk cells, so this cannot fail for a well-formed key
+        self.hive.key_at(self.offset).unwrap_or(&[])
+    }
+
+    pub fn name(&self) -> String {
+        let node = self.node();
+        let flags = read_u16(node, 2).unwrap_or(0);
+        let name_length = read_u16(node, 72).unwrap_or(0) as usize;
+        let raw = node.get(76..76 + name_length).unwrap_or(&[]);
+        decode_name(raw, flags & KEY_COMP_NAME != 0)
+    }
+
+    fn subkeys(&self) -> Vec<RegfKey<'a>> {
+        let node = self.node();
+        let count = read_u32(node, 20).unwrap_or(0);
+        let list_offset = read_u32(node, 28).unwrap_or(NO_OFFSET);
+        if count == 0 || list_offset == NO_OFFSET {
+            return Vec::new();
+        }
+
+        let mut offsets = Vec::with_capacity(count as usize);
+        self.hive.collect_subkey_offsets(list_offset, 0, &mut offsets);
+
+        offsets
+            .into_iter()
+            .filter(|offset| self.hive.key_at(*offset).is_some())
+            .map(|offset| RegfKey { hive: self.hive, offset })
+            .collect()
+    }
+
+    pub fn child(&self, name: &str) -> Option<RegfKey<'a>> {
+        self.subkeys()
+            .into_iter()
+            .find(|key| key.name().eq_ignore_ascii_case(name))
+    }
+
+    fn value_cells(&self) -> Vec<&'a [u8]> {
+        let node = self.node();
+        let count = read_u32(node, 36).unwrap_or(0) as usize;
+        let list_offset = read_u32(node, 40).unwrap_or(NO_OFFSET);
+        let list = match self.hive.cell(list_offset) {
+            Some(list) => list,
+            None => return Vec::new(),
+        };
+
+        // The count comes from the file; a corrupt one must not outrun the list it describes
+        (0..count.min(list.len() / 4))
+            .filter_map(|i| read_u32(list, i * 4))
+            .filter_map(|offset| self.hive.cell(offset))
+            .filter(|cell| cell.len() >= 20 && &cell[0..2] == b"vk")
+            .collect()
+    }
+
+    fn find_value(&self, name: &str) -> Option<&'a [u8]> {
+        self.value_cells()
+            .into_iter()
+            .find(|vk| value_name(vk).eq_ignore_ascii_case(name))
+    }
+}
+
+impl<'a> RegistryKey for RegfKey<'a> {
+    fn subkey_names(&self) -> Vec<String> {
+        self.subkeys().iter().map(|key| key.name()).collect()
+    }
+
+    fn open_subkey(&self, path: &str) -> Option<Box<dyn RegistryKey + '_>> {
+        let mut current = *self;
+        for part in path.split('\\').filter(|p| !p.is_empty()) {
+            current = current.child(part)?;
+        }
+        Some(Box::new(current))
+    }
+
+    fn value_names(&self) -> Vec<String> {
+        self.value_cells().into_iter().map(value_name).collect()
+    }
+
+    fn get_value(&self, name: &str) -> Option<RegistryValue> {
+        let vk = self.find_value(name)?;
+        let data_type = read_u32(vk, 12)?;
+        let data = self.hive.value_data(vk)?;
+
+        let value = match data_type {
+            REG_NONE => RegistryValue::None,
+            REG_SZ => RegistryValue::String(decode_utf16_bytes(&data)),
+            REG_EXPAND_SZ => RegistryValue::ExpandString(decode_utf16_bytes(&data)),
+            REG_MULTI_SZ => RegistryValue::MultiString(decode_multi_string_bytes(&data)),
+            REG_DWORD if data.len() >= 4 => RegistryValue::Dword(u32::from_le_bytes([data[0], data[1], data[2], data[3]])),
+            REG_DWORD_BIG_ENDIAN if data.len() >= 4 => RegistryValue::Dword(u32::from_be_bytes([data[0], data[1], data[2], data[3]])),
+            REG_QWORD if data.len() >= 8 => RegistryValue::Qword(read_u64(&data, 0)?),
+            _ => RegistryValue::Binary(data),
+        };
+        Some(value)
+    }
+
+    fn last_write_time(&self) -> Option<DateTime<Utc>> {
+        read_u64(self.node(), 4).and_then(filetime_to_datetime)
+    }
+}
+
+/// Hive files copied off another machine, presented as that machine's registry.
+/// The SOFTWARE hive answers HKLM\SOFTWARE, the SYSTEM hive HKLM\SYSTEM, and each NTUSER.DAT is mounted
+/// as HKEY_USERS\<name>.
+#[derive(Default)]
+pub struct OfflineRegistry {
+    software: Option<RegfHive>,
+    system: Option<RegfHive>,
+    users: Vec<(String, RegfHive)>,
+}
+
+impl OfflineRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load_software_hive(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.software = Some(RegfHive::open(path)?);
+        Ok(())
+    }
+
+    pub fn load_system_hive(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.system = Some(RegfHive::open(path)?);
+        Ok(())
+    }
+
+    pub fn load_user_hive(&mut self, name: &str, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let hive = RegfHive::open(path)?;
+        self.users.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        self.users.push((name.to_string(), hive));
+        Ok(())
+    }
+}
+
+impl RegistrySource for OfflineRegistry {
+    fn open_key(&self, hive: RegistryHive, path: &str) -> Option<Box<dyn RegistryKey + '_>> {
+        let trimmed = path.trim_start_matches('\\');
+        let (first, rest) = match trimmed.find('\\') {
+            Some(index) => (&trimmed[..index], &trimmed[index + 1..]),
+            None => (trimmed, ""),
+        };
+
+        let key = match hive {
+            RegistryHive::LocalMachine if first.eq_ignore_ascii_case("SOFTWARE") => {
+                self.software.as_ref()?.open_key(rest)?
+            }
+            RegistryHive::LocalMachine if first.eq_ignore_ascii_case("SYSTEM") => {
+                let system = self.system.as_ref()?;
+                system.open_key(&resolve_current_control_set(system, rest)?)?
+            }
+            RegistryHive::Users => {
+                let (_, user_hive) = self.users.iter().find(|(name, _)| name.eq_ignore_ascii_case(first))?;
+                user_hive.open_key(rest)?
+            }
+            _ => return None,
+        };
+        Some(Box::new(key))
+    }
+
+    fn user_hive_names(&self) -> Vec<String> {
+        self.users.iter().map(|(name, _)| name.clone()).collect()
+    }
+}
+
+// CurrentControlSet is a link Windows creates at boot; in the hive file it is whichever
+// ControlSet00N Select\Current names
+fn resolve_current_control_set(system: &RegfHive, path: &str) -> Option<String> {
+    let (first, rest) = match path.find('\\') {
+        Some(index) => (&path[..index], &path[index..]),
+        None => (path, ""),
+    };
+    if !first.eq_ignore_ascii_case("CurrentControlSet") {
+        return Some(path.to_string());
+    }
+    let current = system.open_key("Select")?.get_dword("Current")?;
+    Some(format!("ControlSet{:03}{}", current, rest))
+}
+
+fn value_name(vk: &[u8]) -> String {
+    let name_length = read_u16(vk, 2).unwrap_or(0) as usize;
+    let flags = read_u16(vk, 16).unwrap_or(0);
+    let raw = vk.get(20..20 + name_length).unwrap_or(&[]);
+    decode_name(raw, flags & VALUE_COMP_NAME != 0)
+}
+
+// Compressed names are stored as Latin-1, everything else as UTF-16LE
+fn decode_name(raw: &[u8], compressed: bool) -> String {
+    if compressed {
+        raw.iter().map(|&b| b as char).collect()
+    } else {
+        decode_utf16_bytes(raw)
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8).map(|b| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(b);
+        u64::from_le_bytes(bytes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds a minimal hive in memory: a base block, then one hbin holding the cells
+    struct HiveBuilder {
+        cells: Vec<u8>,
+    }
+
+    impl HiveBuilder {
+        fn new() -> Self {
+            HiveBuilder { cells: vec![0; 32] } // Room for the hbin header
+        }
+
+        // Append an allocated cell and return its offset
+        fn cell(&mut self, body: &[u8]) -> u32 {
+            let offset = self.cells.len() as u32;
+            let size = (body.len() + 4 + 7) / 8 * 8;
+            self.cells.extend_from_slice(&(-(size as i32)).to_le_bytes());
+            self.cells.extend_from_slice(body);
+            self.cells.resize(offset as usize + size, 0);
+            offset
+        }
+
+        fn key(&mut self, name: &str, subkeys: &[u32], values: &[u32]) -> u32 {
+            let subkey_list = if subkeys.is_empty() {
+                NO_OFFSET
+            } else {
+                let mut lf = b"lf".to_vec();
+                lf.extend_from_slice(&(subkeys.len() as u16).to_le_bytes());
+                for offset in subkeys {
+                    lf.extend_from_slice(&offset.to_le_bytes());
+                    lf.extend_from_slice(&[0; 4]); // Name hint, unused by the reader
+                }
+                self.cell(&lf)
+            };
+            let value_list = self.value_list(values);
+            self.key_with_value_count(name, subkeys.len() as u32, subkey_list, values.len() as u32, value_list)
+        }
+
+        fn key_with_value_count(&mut self, name: &str, subkey_count: u32, subkey_list: u32, value_count: u32, value_list: u32) -> u32 {
+            let mut nk = vec![0u8; 76];
+            nk[0..2].copy_from_slice(b"nk");
+            nk[2..4].copy_from_slice(&KEY_COMP_NAME.to_le_bytes());
+            nk[4..12].copy_from_slice(&132_000_000_000_000_000u64.to_le_bytes());
+            nk[20..24].copy_from_slice(&subkey_count.to_le_bytes());
+            nk[28..32].copy_from_slice(&subkey_list.to_le_bytes());
+            nk[36..40].copy_from_slice(&value_count.to_le_bytes());
+            nk[40..44].copy_from_slice(&value_list.to_le_bytes());
+            nk[72..74].copy_from_slice(&(name.len() as u16).to_le_bytes());
+            nk.extend_from_slice(name.as_bytes());
+            self.cell(&nk)
+        }
+
+        fn value_list(&mut self, values: &[u32]) -> u32 {
+            if values.is_empty() {
+                return NO_OFFSET;
+            }
+            let list: Vec<u8> = values.iter().flat_map(|offset| offset.to_le_bytes()).collect();
+            self.cell(&list)
+        }
+
+        fn value(&mut self, name: &str, data_type: u32, data: &[u8]) -> u32 {
+            let (size, data_offset) = if data.len() <= 4 {
+                let mut inline = [0u8; 4];
+                inline[..data.len()].copy_from_slice(data);
+                (data.len() as u32 | DATA_IS_RESIDENT, u32::from_le_bytes(inline))
+            } else {
+                (data.len() as u32, self.cell(data))
+            };
+            let mut vk = vec![0u8; 20];
+            vk[0..2].copy_from_slice(b"vk");
+            vk[2..4].copy_from_slice(&(name.len() as u16).to_le_bytes());
+            vk[4..8].copy_from_slice(&size.to_le_bytes());
+            vk[8..12].copy_from_slice(&data_offset.to_le_bytes());
+            vk[12..16].copy_from_slice(&data_type.to_le_bytes());
+            vk[16..18].copy_from_slice(&VALUE_COMP_NAME.to_le_bytes());
+            vk.extend_from_slice(name.as_bytes());
+            self.cell(&vk)
+        }
+
+        fn build(mut self, root: u32) -> Vec<u8> {
+            let hbin_size = (self.cells.len() + 4095) / 4096 * 4096;
+            self.cells.resize(hbin_size, 0);
+            self.cells[0..4].copy_from_slice(b"hbin");
+            self.cells[8..12].copy_from_slice(&(hbin_size as u32).to_le_bytes());
+
+            let mut data = vec![0u8; BASE_BLOCK_SIZE];
+            data[0..4].copy_from_slice(b"regf");
+            data[20..24].copy_from_slice(&1u32.to_le_bytes());
+            data[24..28].copy_from_slice(&5u32.to_le_bytes());
+            data[36..40].copy_from_slice(&root.to_le_bytes());
+            data[40..44].copy_from_slice(&(hbin_size as u32).to_le_bytes());
+            data.extend_from_slice(&self.cells);
+            data
+        }
+    }
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()
+    }
+
+    fn software_hive() -> RegfHive {
+        let mut builder = HiveBuilder::new();
+        let mut padded_name = utf16("Contoso App\0");
+        padded_name.extend_from_slice(&utf16("stale"));
+        padded_name.extend_from_slice(&[0; 6]);
+        let values = [
+            builder.value("DisplayName", REG_SZ, &padded_name),
+            builder.value("InstallLocation", REG_EXPAND_SZ, &utf16("%ProgramFiles%\\Contoso\0")),
+            builder.value("EstimatedSize", REG_DWORD, &2048u32.to_le_bytes()),
+            builder.value("Languages", REG_MULTI_SZ, &utf16("en-US\0sv-SE\0\0")),
+        ];
+        let app = builder.key("{11111111-2222-3333-4444-555555555555}", &[], &values);
+        let uninstall = builder.key("Uninstall", &[app], &[]);
+        let current_version = builder.key("CurrentVersion", &[uninstall], &[]);
+        let windows = builder.key("Windows", &[current_version], &[]);
+        let microsoft = builder.key("Microsoft", &[windows], &[]);
+        let root = builder.key("ROOT", &[microsoft], &[]);
+        RegfHive::from_bytes(builder.build(root)).unwrap()
+    }
+
+    #[test]
+    fn reads_keys_and_typed_values() {
+        let hive = software_hive();
+        let uninstall = hive.open_key("Microsoft\\Windows\\CurrentVersion\\Uninstall").unwrap();
+        assert_eq!(uninstall.subkey_names(), vec!["{11111111-2222-3333-4444-555555555555}".to_string()]);
+
+        let app = hive.open_key("microsoft\\WINDOWS\\CurrentVersion\\Uninstall\\{11111111-2222-3333-4444-555555555555}").unwrap();
+        assert_eq!(app.value_names().len(), 4);
+        assert_eq!(app.get_value("InstallLocation"), Some(RegistryValue::ExpandString("%ProgramFiles%\\Contoso".to_string())));
+        assert_eq!(app.get_value("estimatedsize"), Some(RegistryValue::Dword(2048)));
+        assert_eq!(app.get_value("Languages"), Some(RegistryValue::MultiString(vec!["en-US".to_string(), "sv-SE".to_string()])));
+        assert!(app.last_write_time().is_some());
+        assert!(app.get_value("Missing").is_none());
+    }
+
+    #[test]
+    fn string_values_end_at_the_first_nul() {
+        let hive = software_hive();
+        let app = hive.open_key("Microsoft\\Windows\\CurrentVersion\\Uninstall\\{11111111-2222-3333-4444-555555555555}").unwrap();
+        assert_eq!(app.get_string("DisplayName"), Some("Contoso App".to_string()));
+    }
+
+    #[test]
+    fn corrupt_value_count_is_bounded_by_the_value_list() {
+        let mut builder = HiveBuilder::new();
+        let value = builder.value("Version", REG_SZ, &utf16("1.0\0"));
+        let value_list = builder.value_list(&[value]);
+        let root = builder.key_with_value_count("ROOT", 0, NO_OFFSET, u32::MAX, value_list);
+        let hive = RegfHive::from_bytes(builder.build(root)).unwrap();
+
+        assert_eq!(hive.root().value_names(), vec!["Version".to_string()]);
+        assert_eq!(hive.root().get_string("Version"), Some("1.0".to_string()));
+    }
+
+    #[test]
+    fn offline_registry_follows_current_control_set() {
+        let mut builder = HiveBuilder::new();
+        let system_drive = builder.value("SystemDrive", REG_SZ, &utf16("D:\0"));
+        let environment = builder.key("Environment", &[], &[system_drive]);
+        let session_manager = builder.key("Session Manager", &[environment], &[]);
+        let control = builder.key("Control", &[session_manager], &[]);
+        let control_set = builder.key("ControlSet002", &[control], &[]);
+        let current = builder.value("Current", REG_DWORD, &2u32.to_le_bytes());
+        let select = builder.key("Select", &[], &[current]);
+        let root = builder.key("ROOT", &[select, control_set], &[]);
+
+        let registry = OfflineRegistry { system: Some(RegfHive::from_bytes(builder.build(root)).unwrap()), ..OfflineRegistry::new() };
+        let environment = registry
+            .open_key(RegistryHive::LocalMachine, "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment")
+            .unwrap();
+        assert_eq!(environment.get_string("SystemDrive"), Some("D:".to_string()));
+        assert!(registry.open_key(RegistryHive::LocalMachine, "SOFTWARE\\Microsoft").is_none());
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_hives() {
+        assert!(RegfHive::from_bytes(b"regf".to_vec()).is_err());
+        assert!(RegfHive::from_bytes(vec![0; BASE_BLOCK_SIZE * 2]).is_err());
+
+        // A valid base block whose root offset points past the end of the file
+        let mut builder = HiveBuilder::new();
+        let root = builder.key("ROOT", &[], &[]);
+        let mut data = builder.build(root);
+        data[36..40].copy_from_slice(&0x00FF_FFF0u32.to_le_bytes());
+        assert!(RegfHive::from_bytes(data).is_err());
+
+        // Truncated in the middle of the root cell
+        let mut builder = HiveBuilder::new();
+        let root = builder.key("ROOT", &[], &[]);
+        let mut data = builder.build(root);
+        data.truncate(BASE_BLOCK_SIZE + root as usize + 20);
+        assert!(RegfHive::from_bytes(data).is_err());
+    }
+}
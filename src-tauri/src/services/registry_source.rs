@@ -0,0 +1,451 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, TimeZone, Utc};
+
+// Root hives that the inventory scan reads from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RegistryHive {
+    #[serde(rename = "HKEY_LOCAL_MACHINE")]
+    LocalMachine,
+    #[serde(rename = "HKEY_CURRENT_USER")]
+    CurrentUser,
+    #[serde(rename = "HKEY_USERS")]
+    Users,
+    #[serde(rename = "HKEY_CLASSES_ROOT")]
+    ClassesRoot,
+}
+
+impl RegistryHive {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegistryHive::LocalMachine => "HKEY_LOCAL_MACHINE",
+            RegistryHive::CurrentUser => "HKEY_CURRENT_USER",
+            RegistryHive::Users => "HKEY_USERS",
+            RegistryHive::ClassesRoot => "HKEY_CLASSES_ROOT",
+        }
+    }
+}
+
+// Which registry view a key was read through; 32-bit keys live under WOW6432Node on 64-bit Windows
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryView {
+    #[serde(rename = "64-bit")]
+    Registry64,
+    #[serde(rename = "32-bit")]
+    Registry32,
+}
+
+impl RegistryView {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegistryView::Registry64 => "64-bit",
+            RegistryView::Registry32 => "32-bit",
+        }
+    }
+}
+
+/// Exactly where a registry-sourced entry was read from
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistryProvenance {
+    pub hive: RegistryHive,
+    pub view: RegistryView,
+    pub key_path: String,                        // Path below the hive, including WOW6432Node for 32-bit keys
+    pub last_write_time: Option<DateTime<Utc>>,  // Key LastWriteTime, when the source records it
+    pub value_types: BTreeMap<String, String>,   // Value name -> REG_* type
+}
+
+impl RegistryProvenance {
+    /// Capture the provenance of an open key
+    pub fn from_key(key: &dyn RegistryKey, hive: RegistryHive, view: RegistryView, key_path: String) -> Self {
+        let value_types = key.value_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value_type = key.get_value(&name)?.type_name().to_string();
+                Some((name, value_type))
+            })
+            .collect();
+
+        RegistryProvenance {
+            hive,
+            view,
+            key_path,
+            last_write_time: key.last_write_time(),
+            value_types,
+        }
+    }
+
+    /// Full path as regedit shows it, e.g. HKEY_LOCAL_MACHINE\SOFTWARE\WOW6432Node\...
+    pub fn full_path(&self) -> String {
+        format!("{}\\{}", self.hive.name(), self.key_path)
+    }
+}
+
+// Typed registry value, mirroring the REG_* types we care about
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", content = "data")]
+pub enum RegistryValue {
+    #[serde(rename = "REG_SZ")]
+    String(String),
+    #[serde(rename = "REG_EXPAND_SZ")]
+    ExpandString(String),
+    #[serde(rename = "REG_MULTI_SZ")]
+    MultiString(Vec<String>),
+    #[serde(rename = "REG_DWORD")]
+    Dword(u32),
+    #[serde(rename = "REG_QWORD")]
+    Qword(u64),
+    #[serde(rename = "REG_BINARY")]
+    Binary(Vec<u8>),
+    #[serde(rename = "REG_NONE")]
+    None,
+}
+
+impl RegistryValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RegistryValue::String(_) => "REG_SZ",
+            RegistryValue::ExpandString(_) => "REG_EXPAND_SZ",
+            RegistryValue::MultiString(_) => "REG_MULTI_SZ",
+            RegistryValue::Dword(_) => "REG_DWORD",
+            RegistryValue::Qword(_) => "REG_QWORD",
+            RegistryValue::Binary(_) => "REG_BINARY",
+            RegistryValue::None => "REG_NONE",
+        }
+    }
+}
+
+/// An open registry key, independent of where the data comes from
+pub trait RegistryKey {
+    fn subkey_names(&self) -> Vec<String>;
+    fn open_subkey(&self, path: &str) -> Option<Box<dyn RegistryKey + '_>>;
+    fn value_names(&self) -> Vec<String>;
+    fn get_value(&self, name: &str) -> Option<RegistryValue>;
+    fn last_write_time(&self) -> Option<DateTime<Utc>>;
+
+    // Same coercions as winreg's `get_value::<String, _>`: any string type is accepted
+    fn get_string(&self, name: &str) -> Option<String> {
+        match self.get_value(name)? {
+            RegistryValue::String(s) | RegistryValue::ExpandString(s) => Some(s),
+            RegistryValue::MultiString(parts) => Some(parts.join("\n")),
+            _ => None,
+        }
+    }
+
+    fn get_dword(&self, name: &str) -> Option<u32> {
+        match self.get_value(name)? {
+            RegistryValue::Dword(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// A registry to scan: the live machine, a captured fixture, or anything else that can open keys
+pub trait RegistrySource {
+    fn open_key(&self, hive: RegistryHive, path: &str) -> Option<Box<dyn RegistryKey + '_>>;
+
+    /// User hives mounted under HKEY_USERS (SIDs on a live machine), without the *_Classes companions
+    fn user_hive_names(&self) -> Vec<String> {
+        self.open_key(RegistryHive::Users, "")
+            .map(|users| users.subkey_names())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| !name.ends_with("_Classes") && !name.eq_ignore_ascii_case(".DEFAULT"))
+            .collect()
+    }
+
+    /// Load a profile's NTUSER.DAT that is not mounted under HKEY_USERS; only the live registry can do this
+    fn load_user_hive_file(&self, _path: &Path) -> Option<Box<dyn RegistryKey>> {
+        None
+    }
+}
+
+// Convert a Windows FILETIME (100ns intervals since 1601-01-01) to UTC
+pub fn filetime_to_datetime(filetime: u64) -> Option<DateTime<Utc>> {
+    const EPOCH_DIFFERENCE_SECS: i64 = 11_644_473_600;
+    if filetime == 0 {
+        return None;
+    }
+    let secs = (filetime / 10_000_000) as i64 - EPOCH_DIFFERENCE_SECS;
+    let nanos = ((filetime % 10_000_000) * 100) as u32;
+    Utc.timestamp_opt(secs, nanos).single()
+}
+
+// Decode little-endian UTF-16 string data as stored in REG_SZ values. The string ends at the first NUL:
+// whatever follows the terminator is padding or stale data from an earlier, longer value
+pub fn decode_utf16_bytes(bytes: &[u8]) -> String {
+    let units: Vec<u16> = utf16_units(bytes).into_iter().take_while(|unit| *unit != 0).collect();
+    String::from_utf16_lossy(&units)
+}
+
+pub fn decode_multi_string_bytes(bytes: &[u8]) -> Vec<String> {
+    String::from_utf16_lossy(&utf16_units(bytes))
+        .split('\0')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_string())
+        .collect()
+}
+
+pub fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect()
+}
+
+// In-memory registry tree, loadable from and savable to a JSON fixture
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MemoryKey {
+    #[serde(default)]
+    pub last_write_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub values: BTreeMap<String, RegistryValue>,
+    #[serde(default)]
+    pub subkeys: BTreeMap<String, MemoryKey>,
+}
+
+impl MemoryKey {
+    // Registry names are case-insensitive, so lookups are too
+    fn child(&self, name: &str) -> Option<&MemoryKey> {
+        self.subkeys
+            .iter()
+            .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+            .map(|(_, key)| key)
+    }
+
+    fn descend(&self, path: &str) -> Option<&MemoryKey> {
+        let mut current = self;
+        for part in path.split('\\').filter(|p| !p.is_empty()) {
+            current = current.child(part)?;
+        }
+        Some(current)
+    }
+
+    /// Get or create the key at `path`, creating intermediate keys as needed
+    pub fn create_path(&mut self, path: &str) -> &mut MemoryKey {
+        let mut current = self;
+        for part in path.split('\\').filter(|p| !p.is_empty()) {
+            let existing = current
+                .subkeys
+                .keys()
+                .find(|key_name| key_name.eq_ignore_ascii_case(part))
+                .cloned()
+                .unwrap_or_else(|| part.to_string());
+            current = current.subkeys.entry(existing).or_default();
+        }
+        current
+    }
+
+    // Setting a value replaces any existing value whose name differs only in case
+    pub fn set_value(&mut self, name: &str, value: RegistryValue) {
+        self.remove_value(name);
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn remove_value(&mut self, name: &str) {
+        self.values.retain(|value_name, _| !value_name.eq_ignore_ascii_case(name));
+    }
+
+    /// Deep-copy an arbitrary registry key into memory
+    pub fn capture(key: &dyn RegistryKey) -> MemoryKey {
+        let mut captured = MemoryKey {
+            last_write_time: key.last_write_time(),
+            ..MemoryKey::default()
+        };
+        for name in key.value_names() {
+            if let Some(value) = key.get_value(&name) {
+                captured.values.insert(name, value);
+            }
+        }
+        for name in key.subkey_names() {
+            if let Some(subkey) = key.open_subkey(&name) {
+                captured.subkeys.insert(name, MemoryKey::capture(subkey.as_ref()));
+            }
+        }
+        captured
+    }
+}
+
+impl RegistryKey for MemoryKey {
+    fn subkey_names(&self) -> Vec<String> {
+        self.subkeys.keys().cloned().collect()
+    }
+
+    fn open_subkey(&self, path: &str) -> Option<Box<dyn RegistryKey + '_>> {
+        self.descend(path).map(|key| Box::new(key) as Box<dyn RegistryKey + '_>)
+    }
+
+    fn value_names(&self) -> Vec<String> {
+        self.values.keys().cloned().collect()
+    }
+
+    fn get_value(&self, name: &str) -> Option<RegistryValue> {
+        self.values
+            .iter()
+            .find(|(value_name, _)| value_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    }
+
+    fn last_write_time(&self) -> Option<DateTime<Utc>> {
+        self.last_write_time
+    }
+}
+
+impl RegistryKey for &MemoryKey {
+    fn subkey_names(&self) -> Vec<String> {
+        (*self).subkey_names()
+    }
+
+    fn open_subkey(&self, path: &str) -> Option<Box<dyn RegistryKey + '_>> {
+        (*self).open_subkey(path)
+    }
+
+    fn value_names(&self) -> Vec<String> {
+        (*self).value_names()
+    }
+
+    fn get_value(&self, name: &str) -> Option<RegistryValue> {
+        (*self).get_value(name)
+    }
+
+    fn last_write_time(&self) -> Option<DateTime<Utc>> {
+        self.last_write_time
+    }
+}
+
+/// Registry backed by an in-memory tree; used for JSON fixtures and captured customer data
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MemoryRegistry {
+    pub hives: BTreeMap<RegistryHive, MemoryKey>,
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn hive_mut(&mut self, hive: RegistryHive) -> &mut MemoryKey {
+        self.hives.entry(hive).or_default()
+    }
+
+    /// Copy a subtree from another source (typically the live registry) into this one
+    pub fn capture_from(&mut self, source: &dyn RegistrySource, hive: RegistryHive, path: &str) -> bool {
+        match source.open_key(hive, path) {
+            Some(key) => {
+                let captured = MemoryKey::capture(key.as_ref());
+                *self.hive_mut(hive).create_path(path) = captured;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(Path::new(path))?;
+        let registry: MemoryRegistry = serde_json::from_str(&content)?;
+        Ok(registry)
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+impl RegistrySource for MemoryRegistry {
+    fn open_key(&self, hive: RegistryHive, path: &str) -> Option<Box<dyn RegistryKey + '_>> {
+        self.hives.get(&hive)?.open_subkey(path)
+    }
+}
+
+#[cfg(windows)]
+mod live {
+    use super::*;
+    use winreg::enums::*;
+    use winreg::types::FromRegValue;
+    use winreg::RegKey;
+
+    pub struct WinRegKey(pub RegKey);
+
+    impl RegistryKey for WinRegKey {
+        fn subkey_names(&self) -> Vec<String> {
+            self.0.enum_keys().flatten().collect()
+        }
+
+        fn open_subkey(&self, path: &str) -> Option<Box<dyn RegistryKey + '_>> {
+            self.0
+                .open_subkey(path)
+                .ok()
+                .map(|key| Box::new(WinRegKey(key)) as Box<dyn RegistryKey + '_>)
+        }
+
+        fn value_names(&self) -> Vec<String> {
+            self.0.enum_values().flatten().map(|(name, _)| name).collect()
+        }
+
+        fn get_value(&self, name: &str) -> Option<RegistryValue> {
+            let raw = self.0.get_raw_value(name).ok()?;
+            let value = match raw.vtype {
+                REG_SZ => RegistryValue::String(String::from_reg_value(&raw).ok()?),
+                REG_EXPAND_SZ => RegistryValue::ExpandString(String::from_reg_value(&raw).ok()?),
+                REG_MULTI_SZ => RegistryValue::MultiString(Vec::<String>::from_reg_value(&raw).ok()?),
+                REG_DWORD => RegistryValue::Dword(u32::from_reg_value(&raw).ok()?),
+                REG_QWORD => RegistryValue::Qword(u64::from_reg_value(&raw).ok()?),
+                REG_NONE => RegistryValue::None,
+                _ => RegistryValue::Binary(raw.bytes),
+            };
+            Some(value)
+        }
+
+        fn last_write_time(&self) -> Option<DateTime<Utc>> {
+            let info = self.0.query_info().ok()?;
+            let filetime = ((info.last_write_time.dwHighDateTime as u64) << 32)
+                | info.last_write_time.dwLowDateTime as u64;
+            filetime_to_datetime(filetime)
+        }
+    }
+
+    /// The registry of the machine we are running on
+    pub struct WinRegSource;
+
+    impl RegistrySource for WinRegSource {
+        fn open_key(&self, hive: RegistryHive, path: &str) -> Option<Box<dyn RegistryKey + '_>> {
+            let root = RegKey::predef(match hive {
+                RegistryHive::LocalMachine => HKEY_LOCAL_MACHINE,
+                RegistryHive::CurrentUser => HKEY_CURRENT_USER,
+                RegistryHive::Users => HKEY_USERS,
+                RegistryHive::ClassesRoot => HKEY_CLASSES_ROOT,
+            });
+            let key = if path.is_empty() {
+                root
+            } else {
+                root.open_subkey(path).ok()?
+            };
+            Some(Box::new(WinRegKey(key)))
+        }
+
+        // RegLoadAppKey mounts the hive privately and unloads it when the handle is dropped
+        fn load_user_hive_file(&self, path: &Path) -> Option<Box<dyn RegistryKey>> {
+            match RegKey::load_app_key(path, false) {
+                Ok(key) => Some(Box::new(WinRegKey(key))),
+                Err(e) => {
+                    println!("DEBUG: Failed to load user hive {}: {}", path.display(), e);
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+pub use live::WinRegSource;
+
+/// Open the registry of the running machine
+#[cfg(windows)]
+pub fn live_registry() -> Result<Box<dyn RegistrySource>, String> {
+    Ok(Box::new(WinRegSource))
+}
+
+#[cfg(not(windows))]
+pub fn live_registry() -> Result<Box<dyn RegistrySource>, String> {
+    Err("Live registry scanning is only available on Windows".to_string())
+}
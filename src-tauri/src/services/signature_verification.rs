@@ -0,0 +1,669 @@
+// Offline Authenticode verification: the image hash is recomputed and compared with the signed one, the
+// signer's signature is checked, its certificate chained up to a root from a trusted-roots bundle, and the
+// timestamp checked the same way so an expired certificate can still vouch for what it signed in time.
+// Revocation needs the network and is not checked
+use std::fs;
+use std::path::{Path, PathBuf};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use rsa::{BigUint, Pkcs1v15Sign, RsaPublicKey};
+use serde::{Serialize, Deserialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::services::authenticode::{self, parse_certificate, parse_signed_data, parse_signer_info, tst_info, Certificate, SignedData, SignerInfo,
+    OID_COUNTERSIGNATURE, OID_MESSAGE_DIGEST, OID_RFC3161_TIMESTAMP, WIN_CERT_TYPE_PKCS_SIGNED_DATA};
+use crate::services::der::{self, Element, TAG_BOOLEAN, TAG_SEQUENCE};
+use crate::services::pe::{PeImage, CERTIFICATE_DIRECTORY};
+
+const TRUSTED_ROOTS_FILE: &str = "trusted-roots.pem";
+// Deeper chains than this are not issued in practice
+const MAX_CHAIN_LENGTH: usize = 8;
+// Larger RSA keys than any CA issues are refused rather than worked through
+const MAX_RSA_BITS: usize = 16384;
+
+const OID_SHA1: &str = "1.3.14.3.2.26";
+const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+const OID_SHA384: &str = "2.16.840.1.101.3.4.2.2";
+const OID_SHA512: &str = "2.16.840.1.101.3.4.2.3";
+const OID_RSA: &str = "1.2.840.113549.1.1.1";
+const OID_EC_PUBLIC_KEY: &str = "1.2.840.10045.2.1";
+const OID_P256: &str = "1.2.840.10045.3.1.7";
+const OID_P384: &str = "1.3.132.0.34";
+const OID_BASIC_CONSTRAINTS: &str = "2.5.29.19";
+const OID_EXTENDED_KEY_USAGE: &str = "2.5.29.37";
+const OID_ANY_EXTENDED_KEY_USAGE: &str = "2.5.29.37.0";
+pub const OID_CODE_SIGNING: &str = "1.3.6.1.5.5.7.3.3";
+pub const OID_TIME_STAMPING: &str = "1.3.6.1.5.5.7.3.8";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SignatureStatus {
+    Valid,
+    Expired,   // Intact and chained to a trusted root, but signed outside the certificate's validity
+    Untrusted, // Intact, but not chained to a root in the bundle
+    Invalid,   // The image was changed after signing, or the signature does not check out
+    Unsigned,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SignatureVerification {
+    pub status: SignatureStatus,
+    pub image_hash_valid: bool,       // The signed digest matches the image as it is now
+    pub signature_valid: bool,        // The signer's signature over that digest checks out
+    pub signer_name: Option<String>,  // CN and O of the signer certificate, to hold against the registered publisher
+    pub signer_organization: Option<String>,
+    pub chain: Vec<String>,           // Subjects from the signer up to the root
+    pub trusted_root: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub timestamp_valid: Option<bool>, // None without a timestamp
+    pub checked_at: DateTime<Utc>,    // The time certificate validity was judged at: the timestamp if valid, else now
+    pub problems: Vec<String>,
+}
+
+/// Roots a chain has to end at, from a PEM bundle or a single DER certificate
+pub struct TrustStore {
+    pub source: String,
+    roots: Vec<Vec<u8>>,
+}
+
+impl TrustStore {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Could not read the trusted roots bundle {}: {}", path.display(), e))?;
+        let store = TrustStore { source: path.to_string_lossy().to_string(), roots: parse_bundle(&bytes) };
+        if store.is_empty() {
+            return Err(format!("No certificates in the trusted roots bundle {}", path.display()));
+        }
+        println!("DEBUG: Loaded {} trusted roots from {}", store.len(), path.display());
+        Ok(store)
+    }
+
+    /// The configured bundle, or trusted-roots.pem in the app's data folder
+    pub fn open(path: Option<&str>) -> Result<Self, String> {
+        match path.map(str::trim).filter(|path| !path.is_empty()) {
+            Some(path) => Self::load(Path::new(path)),
+            None => {
+                let path = default_bundle_path().ok_or("No data folder for the trusted roots bundle")?;
+                Self::load(&path)
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    fn certificates(&self) -> Vec<Certificate<'_>> {
+        self.roots.iter()
+            .filter_map(|root| der::parse(root).and_then(|element| parse_certificate(&element)))
+            .collect()
+    }
+}
+
+pub fn default_bundle_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("software-scope").join(TRUSTED_ROOTS_FILE))
+}
+
+// Certificates between BEGIN/END CERTIFICATE lines; a file without any is taken as one DER certificate
+fn parse_bundle(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut roots = Vec::new();
+    let mut current: Option<String> = None;
+    for line in text.lines().map(str::trim) {
+        if line == "-----BEGIN CERTIFICATE-----" || line == "-----BEGIN TRUSTED CERTIFICATE-----" {
+            current = Some(String::new());
+        } else if line.starts_with("-----END ") {
+            if let Some(encoded) = current.take() {
+                match base64::engine::general_purpose::STANDARD.decode(encoded) {
+                    Ok(certificate) => roots.push(certificate),
+                    Err(e) => println!("DEBUG: Skipping an unreadable certificate in the bundle: {}", e),
+                }
+            }
+        } else if let Some(encoded) = current.as_mut() {
+            encoded.push_str(line);
+        }
+    }
+    if roots.is_empty() && der::parse(bytes).map_or(false, |element| element.is(TAG_SEQUENCE)) {
+        roots.push(bytes.to_vec());
+    }
+    roots
+}
+
+/// Verify the Authenticode signature of a PE image as of `now`. Only the primary signature is judged,
+/// as Windows does; nested ones are for older systems
+pub fn verify_image(bytes: &[u8], trust: &TrustStore, now: DateTime<Utc>) -> Result<SignatureVerification, String> {
+    let image = PeImage::parse(bytes)?;
+    let mut verification = SignatureVerification {
+        status: SignatureStatus::Unsigned,
+        image_hash_valid: false,
+        signature_valid: false,
+        signer_name: None,
+        signer_organization: None,
+        chain: Vec::new(),
+        trusted_root: None,
+        timestamp: None,
+        timestamp_valid: None,
+        checked_at: now,
+        problems: Vec::new(),
+    };
+    let entry = match image.certificate_entries().into_iter().find(|entry| entry.certificate_type == WIN_CERT_TYPE_PKCS_SIGNED_DATA) {
+        Some(entry) => entry,
+        None => return Ok(verification),
+    };
+
+    verification.status = SignatureStatus::Invalid;
+    let signed_data = match parse_signed_data(entry.data) {
+        Some(signed_data) => signed_data,
+        None => {
+            verification.problems.push("The signature is not a readable PKCS#7 structure".to_string());
+            return Ok(verification);
+        }
+    };
+    let signer = match signed_data.signer_infos.first() {
+        Some(signer) => signer,
+        None => {
+            verification.problems.push("The signature has no signer".to_string());
+            return Ok(verification);
+        }
+    };
+
+    // SpcIndirectDataContent: { data, DigestInfo { algorithm, digest } }
+    let indirect_data = signed_data.content;
+    let digest_info = indirect_data.and_then(|content| content.children().nth(1));
+    let signed_digest = digest_info.and_then(|info| {
+        let mut parts = info.children();
+        let algorithm = parts.next()?.inner()?.oid()?;
+        Some((algorithm, parts.next()?.content))
+    });
+    match signed_digest {
+        Some((algorithm, digest)) => match image_digest(&image, &algorithm) {
+            Some(computed) => {
+                verification.image_hash_valid = computed == digest;
+                if !verification.image_hash_valid {
+                    verification.problems.push("The file was changed after it was signed: its hash does not match the signed one".to_string());
+                }
+            }
+            None => verification.problems.push(format!("Cannot compute a {} image hash", authenticode::digest_name(&algorithm))),
+        },
+        None => verification.problems.push("The signature holds no image digest".to_string()),
+    }
+
+    // The signer signs the content octets of SpcIndirectDataContent, without its tag and length
+    let certificate = signed_data.certificate_of(signer);
+    if let Some(certificate) = certificate {
+        let subject = der::name_attributes(&certificate.subject);
+        verification.signer_name = authenticode::common_name(&subject);
+        verification.signer_organization = subject.iter().rev().find(|(name, _)| name == "O").map(|(_, value)| value.clone());
+    }
+    match (certificate, indirect_data) {
+        (Some(certificate), Some(content)) => match verify_signer(signer, certificate, content.content) {
+            Ok(()) => verification.signature_valid = true,
+            Err(problem) => verification.problems.push(problem),
+        },
+        (None, _) => verification.problems.push("The signer's certificate is not in the signature".to_string()),
+        (Some(_), None) => verification.problems.push("The signature has no signed content".to_string()),
+    }
+
+    match verify_timestamp(&signed_data, signer, trust) {
+        Some(Ok((time, valid, problems))) => {
+            verification.timestamp = Some(time);
+            verification.timestamp_valid = Some(valid);
+            verification.problems.extend(problems);
+            if valid {
+                verification.checked_at = time;
+            }
+        }
+        Some(Err(problem)) => {
+            verification.timestamp_valid = Some(false);
+            verification.problems.push(problem);
+        }
+        None => {}
+    }
+
+    let certificate = match certificate {
+        Some(certificate) => certificate,
+        None => return Ok(verification),
+    };
+    let chain = build_chain(certificate, &signed_data.certificates, trust, verification.checked_at, OID_CODE_SIGNING);
+    verification.chain = chain.subjects;
+    verification.trusted_root = chain.trusted_root;
+    verification.problems.extend(chain.problems);
+
+    verification.status = if !verification.image_hash_valid || !verification.signature_valid || chain.misused {
+        SignatureStatus::Invalid
+    } else if verification.trusted_root.is_none() {
+        SignatureStatus::Untrusted
+    } else if chain.expired {
+        SignatureStatus::Expired
+    } else {
+        SignatureStatus::Valid
+    };
+    Ok(verification)
+}
+
+/// The Authenticode image hash: the whole file but for CheckSum, the certificate table's directory entry
+/// and the certificate table itself
+pub fn image_digest(image: &PeImage, algorithm: &str) -> Option<Vec<u8>> {
+    let bytes = image.bytes;
+    let directory = image.certificate_directory_offset?;
+    let (table_start, table_end) = match image.data_directory(CERTIFICATE_DIRECTORY) {
+        Some((offset, size)) => (offset as usize, (offset as usize).checked_add(size as usize)?),
+        None => (bytes.len(), bytes.len()),
+    };
+    if image.checksum_offset + 4 > directory || directory + 8 > table_start || table_end > bytes.len() {
+        return None;
+    }
+    digest(algorithm, &[
+        &bytes[..image.checksum_offset],
+        &bytes[image.checksum_offset + 4..directory],
+        &bytes[directory + 8..table_start],
+        &bytes[table_end..],
+    ])
+}
+
+pub fn digest(algorithm: &str, parts: &[&[u8]]) -> Option<Vec<u8>> {
+    fn run<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+        let mut hasher = D::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().to_vec()
+    }
+    match algorithm {
+        OID_SHA1 => Some(run::<Sha1>(parts)),
+        OID_SHA256 => Some(run::<Sha256>(parts)),
+        OID_SHA384 => Some(run::<Sha384>(parts)),
+        OID_SHA512 => Some(run::<Sha512>(parts)),
+        _ => None,
+    }
+}
+
+// A SignerInfo signs its authenticated attributes, one of which is the digest of the content; without
+// attributes it signs the content directly
+fn verify_signer(signer: &SignerInfo, certificate: &Certificate, content: &[u8]) -> Result<(), String> {
+    let algorithm = authenticode::digest_name(&signer.digest_algorithm);
+    match signer.authenticated_attributes_raw {
+        Some(attributes) => {
+            let expected = signer.authenticated(OID_MESSAGE_DIGEST).map(|value| value.content);
+            let computed = digest(&signer.digest_algorithm, &[content]).ok_or_else(|| format!("Unsupported digest algorithm {}", algorithm))?;
+            if expected != Some(computed.as_slice()) {
+                return Err("The signed message digest does not match the signed content".to_string());
+            }
+            // The attributes are signed as a SET, not with the [0] tag they are stored under
+            let mut signed = attributes.raw.to_vec();
+            signed[0] = der::TAG_SET;
+            verify_signature(&certificate.public_key, &signer.signature_algorithm, &signer.digest_algorithm, &signed, signer.signature)
+        }
+        None => verify_signature(&certificate.public_key, &signer.signature_algorithm, &signer.digest_algorithm, content, signer.signature),
+    }
+    .map_err(|e| format!("The signature by {} does not check out: {}", certificate.subject_name(), e))
+}
+
+/// Check `signature` over `message` with a SubjectPublicKeyInfo. The hash comes from the signature
+/// algorithm, or from `digest_algorithm` when that only names the key type
+pub fn verify_signature(public_key: &Element, signature_algorithm: &str, digest_algorithm: &str, message: &[u8], signature: &[u8]) -> Result<(), String> {
+    let hash_algorithm = match signature_algorithm {
+        "1.2.840.113549.1.1.5" | "1.2.840.10045.4.1" => OID_SHA1,
+        "1.2.840.113549.1.1.11" | "1.2.840.10045.4.3.2" => OID_SHA256,
+        "1.2.840.113549.1.1.12" | "1.2.840.10045.4.3.3" => OID_SHA384,
+        "1.2.840.113549.1.1.13" | "1.2.840.10045.4.3.4" => OID_SHA512,
+        OID_RSA | OID_EC_PUBLIC_KEY => digest_algorithm,
+        other => return Err(format!("unsupported signature algorithm {}", other)),
+    };
+    let hashed = digest(hash_algorithm, &[message]).ok_or_else(|| format!("unsupported digest algorithm {}", authenticode::digest_name(hash_algorithm)))?;
+
+    let mut parts = public_key.children();
+    let algorithm = parts.next().ok_or("malformed public key")?;
+    let mut algorithm_parts = algorithm.children();
+    let key_type = algorithm_parts.next().and_then(|oid| oid.oid()).ok_or("malformed public key")?;
+    let key = parts.next().and_then(|bits| bits.content.get(1..)).ok_or("malformed public key")?;
+
+    match key_type.as_str() {
+        OID_RSA => {
+            let key = der::parse(key).ok_or("malformed RSA key")?;
+            let mut numbers = key.children();
+            let modulus = numbers.next().ok_or("malformed RSA key")?;
+            let exponent = numbers.next().ok_or("malformed RSA key")?;
+            let key = RsaPublicKey::new_with_max_size(BigUint::from_bytes_be(modulus.content), BigUint::from_bytes_be(exponent.content), MAX_RSA_BITS)
+                .map_err(|e| e.to_string())?;
+            let scheme = match hash_algorithm {
+                OID_SHA1 => Pkcs1v15Sign::new::<Sha1>(),
+                OID_SHA256 => Pkcs1v15Sign::new::<Sha256>(),
+                OID_SHA384 => Pkcs1v15Sign::new::<Sha384>(),
+                _ => Pkcs1v15Sign::new::<Sha512>(),
+            };
+            key.verify(scheme, &hashed, signature).map_err(|e| e.to_string())
+        }
+        OID_EC_PUBLIC_KEY => {
+            let curve = algorithm_parts.next().and_then(|oid| oid.oid()).unwrap_or_default();
+            match curve.as_str() {
+                OID_P256 => {
+                    let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(key).map_err(|e| e.to_string())?;
+                    let signature = p256::ecdsa::Signature::from_der(signature).map_err(|e| e.to_string())?;
+                    key.verify_prehash(&hashed, &signature).map_err(|e| e.to_string())
+                }
+                OID_P384 => {
+                    let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(key).map_err(|e| e.to_string())?;
+                    let signature = p384::ecdsa::Signature::from_der(signature).map_err(|e| e.to_string())?;
+                    key.verify_prehash(&hashed, &signature).map_err(|e| e.to_string())
+                }
+                other => Err(format!("unsupported elliptic curve {}", other)),
+            }
+        }
+        other => Err(format!("unsupported key type {}", other)),
+    }
+}
+
+// When a timestamp says the signature was made, whether that checks out, and why not
+type TimestampCheck = (DateTime<Utc>, bool, Vec<String>);
+
+// The timestamp on a signature, None without one; a timestamp that cannot be read is an error, not a missing timestamp
+fn verify_timestamp(signed_data: &SignedData, signer: &SignerInfo, trust: &TrustStore) -> Option<Result<TimestampCheck, String>> {
+    for (oid, values) in &signer.unauthenticated_attributes {
+        match oid.as_str() {
+            OID_RFC3161_TIMESTAMP => return Some(verify_rfc3161_timestamp(values.first(), signed_data, signer, trust)),
+            OID_COUNTERSIGNATURE => return Some(verify_countersignature(values.first(), signed_data, signer, trust)),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn verify_rfc3161_timestamp(value: Option<&Element>, signed_data: &SignedData, signer: &SignerInfo, trust: &TrustStore) -> Result<TimestampCheck, String> {
+    let malformed = || "The timestamp is not a readable RFC 3161 token".to_string();
+    let token = value.and_then(|value| parse_signed_data(value.raw)).ok_or_else(malformed)?;
+    let tst_octets = tst_info(&token).ok_or_else(malformed)?;
+    let tst = der::parse(tst_octets.content).ok_or_else(malformed)?;
+    // version, policy, messageImprint { algorithm, digest }, serialNumber, genTime
+    let mut fields = tst.children();
+    let imprint = fields.nth(2).ok_or_else(malformed)?;
+    let time = fields.nth(1).and_then(|time| time.time()).ok_or("The timestamp has no readable time")?;
+    let mut problems = Vec::new();
+
+    let mut imprint_parts = imprint.children();
+    let imprint_algorithm = imprint_parts.next().and_then(|algorithm| algorithm.inner()).and_then(|oid| oid.oid()).unwrap_or_default();
+    let imprint_digest = imprint_parts.next().map(|digest| digest.content);
+    // The authority timestamps the signature value itself
+    if digest(&imprint_algorithm, &[signer.signature]).as_deref() != imprint_digest {
+        problems.push("The timestamp is for a different signature".to_string());
+    }
+    // The authority's certificate may be in the token or next to the signer's
+    let mut pool: Vec<&Certificate> = token.certificates.iter().collect();
+    pool.extend(signed_data.certificates.iter());
+    check_timestamp_signer(token.signer_infos.first(), &pool, tst_octets.content, trust, time, &mut problems);
+    Ok((time, problems.is_empty(), problems))
+}
+
+fn verify_countersignature(value: Option<&Element>, signed_data: &SignedData, signer: &SignerInfo, trust: &TrustStore) -> Result<TimestampCheck, String> {
+    let countersigner = value.and_then(parse_signer_info).ok_or("The timestamp countersignature is not readable")?;
+    let time = countersigner.signing_time().ok_or("The timestamp countersignature has no readable signing time")?;
+    let mut problems = Vec::new();
+    let pool: Vec<&Certificate> = signed_data.certificates.iter().collect();
+    check_timestamp_signer(Some(&countersigner), &pool, signer.signature, trust, time, &mut problems);
+    Ok((time, problems.is_empty(), problems))
+}
+
+fn check_timestamp_signer(timestamp_signer: Option<&SignerInfo>, pool: &[&Certificate], content: &[u8], trust: &TrustStore, time: DateTime<Utc>, problems: &mut Vec<String>) {
+    let timestamp_signer = match timestamp_signer {
+        Some(timestamp_signer) => timestamp_signer,
+        None => {
+            problems.push("The timestamp has no signer".to_string());
+            return;
+        }
+    };
+    let certificate = pool.iter().copied().find(|certificate| {
+        timestamp_signer.issuer.map_or(false, |issuer| certificate.issuer.raw == issuer.raw)
+            && timestamp_signer.serial.map_or(false, |serial| certificate.serial.content == serial.content)
+    });
+    let certificate = match certificate {
+        Some(certificate) => certificate,
+        None => {
+            problems.push("The timestamp authority's certificate is not in the signature".to_string());
+            return;
+        }
+    };
+    if let Err(problem) = verify_signer(timestamp_signer, certificate, content) {
+        problems.push(format!("Timestamp: {}", problem));
+        return;
+    }
+    let chain = build_chain_from(certificate, pool, trust, time, OID_TIME_STAMPING);
+    if chain.trusted_root.is_none() || chain.expired || chain.misused {
+        problems.extend(chain.problems.into_iter().map(|problem| format!("Timestamp: {}", problem)));
+        if problems.is_empty() {
+            problems.push("The timestamp authority is not trusted".to_string());
+        }
+    }
+}
+
+struct ChainResult {
+    subjects: Vec<String>,
+    trusted_root: Option<String>,
+    expired: bool, // Some certificate was not valid at the time checked
+    misused: bool, // A certificate issued others without being a CA, or signs for a purpose it may not
+    problems: Vec<String>,
+}
+
+fn build_chain(leaf: &Certificate, pool: &[Certificate], trust: &TrustStore, at: DateTime<Utc>, purpose: &str) -> ChainResult {
+    let pool: Vec<&Certificate> = pool.iter().collect();
+    build_chain_from(leaf, &pool, trust, at, purpose)
+}
+
+// Walk from the leaf to a trusted root, checking each link's signature and validity
+fn build_chain_from(leaf: &Certificate, pool: &[&Certificate], trust: &TrustStore, at: DateTime<Utc>, purpose: &str) -> ChainResult {
+    let roots = trust.certificates();
+    let mut result = ChainResult { subjects: Vec::new(), trusted_root: None, expired: false, misused: false, problems: Vec::new() };
+    if !allows_purpose(leaf, purpose) {
+        result.misused = true;
+        result.problems.push(format!("{} is not meant for {}", leaf.subject_name(), if purpose == OID_CODE_SIGNING { "code signing" } else { "timestamping" }));
+    }
+
+    let mut current = leaf;
+    for _ in 0..MAX_CHAIN_LENGTH {
+        result.subjects.push(current.subject_name());
+        if !current.is_valid_at(at) {
+            result.expired = true;
+            result.problems.push(format!("{} was not valid on {} (valid {} to {})", current.subject_name(), at.format("%Y-%m-%d"),
+                current.not_before.map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+                current.not_after.map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_default()));
+        }
+        // The certificate may be a trusted root itself, or be issued by one
+        if roots.iter().any(|root| root.raw == current.raw) {
+            result.trusted_root = Some(current.subject_name());
+            return result;
+        }
+        let signed_by = |issuer: &Certificate| issuer.subject.raw == current.issuer.raw
+            && verify_signature(&issuer.public_key, &current.signature_algorithm, "", current.tbs, current.signature).is_ok();
+        if let Some(root) = roots.iter().find(|root| signed_by(root)) {
+            result.subjects.push(root.subject_name());
+            result.trusted_root = Some(root.subject_name());
+            return result;
+        }
+        match pool.iter().copied().find(|issuer| issuer.raw != current.raw && signed_by(issuer)) {
+            Some(issuer) => {
+                if !is_ca(issuer) {
+                    result.misused = true;
+                    result.problems.push(format!("{} issued a certificate without being a certificate authority", issuer.subject_name()));
+                }
+                current = issuer;
+            }
+            None => {
+                let issuer = der::format_name(&der::name_attributes(&current.issuer));
+                result.problems.push(if current.issuer.raw == current.subject.raw {
+                    format!("The chain ends at {}, which is not a trusted root", issuer)
+                } else {
+                    format!("No trusted certificate found for {}", issuer)
+                });
+                return result;
+            }
+        }
+    }
+    result.problems.push("The certificate chain is too long".to_string());
+    result
+}
+
+// basicConstraints { cA BOOLEAN DEFAULT FALSE, ... }. A certificate without it may not issue others; old v1
+// roots have no extensions at all, but trusted roots are matched before this is asked
+fn is_ca(certificate: &Certificate) -> bool {
+    certificate.extension(OID_BASIC_CONSTRAINTS)
+        .and_then(der::parse)
+        .and_then(|constraints| constraints.inner())
+        .map_or(false, |first| first.is(TAG_BOOLEAN) && first.content.first().map_or(false, |value| *value != 0))
+}
+
+// Without an extended key usage extension a certificate may be used for anything
+fn allows_purpose(certificate: &Certificate, purpose: &str) -> bool {
+    match certificate.extension(OID_EXTENDED_KEY_USAGE).and_then(der::parse) {
+        Some(usages) => usages.children()
+            .filter_map(|usage| usage.oid())
+            .any(|usage| usage == purpose || usage == OID_ANY_EXTENDED_KEY_USAGE),
+        None => true,
+    }
+}
+
+/// Read and verify one executable
+pub fn verify_file(path: &Path, trust: &TrustStore) -> Result<SignatureVerification, String> {
+    let bytes = crate::services::pe::read_image(path).ok_or_else(|| format!("Could not read {}", path.display()))?;
+    verify_image(&bytes, trust, Utc::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::services::pe::tests::PeBuilder;
+
+    fn fixture_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/signatures").join(name)
+    }
+
+    fn fixture(name: &str) -> Vec<u8> {
+        fs::read(fixture_path(name)).unwrap()
+    }
+
+    fn roots(name: &str) -> TrustStore {
+        TrustStore::load(&fixture_path(name)).unwrap()
+    }
+
+    fn on(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn accepts_a_valid_signature() {
+        let verification = verify_image(&fixture("signed.exe"), &roots("root.pem"), on(2025, 6, 1)).unwrap();
+        assert_eq!(verification.status, SignatureStatus::Valid, "{:?}", verification.problems);
+        assert!(verification.image_hash_valid && verification.signature_valid);
+        assert_eq!(verification.signer_name.as_deref(), Some("Contoso Test Publisher"));
+        assert_eq!(verification.signer_organization.as_deref(), Some("Contoso Test"));
+        assert_eq!(verification.chain, vec![
+            "CN=Contoso Test Publisher, O=Contoso Test",
+            "CN=Contoso Test Code Signing CA, O=Contoso Test",
+            "CN=Contoso Test Root, O=Contoso Test",
+        ]);
+        assert_eq!(verification.trusted_root.as_deref(), Some("CN=Contoso Test Root, O=Contoso Test"));
+        assert_eq!(verification.timestamp_valid, None);
+        assert!(verification.problems.is_empty());
+    }
+
+    #[test]
+    fn rejects_a_changed_image() {
+        let mut bytes = fixture("signed.exe");
+        bytes[0x200] ^= 0xFF;
+        let verification = verify_image(&bytes, &roots("root.pem"), on(2025, 6, 1)).unwrap();
+        assert_eq!(verification.status, SignatureStatus::Invalid);
+        assert!(!verification.image_hash_valid);
+        assert!(verification.signature_valid); // The signature itself is intact, it is just not for this file
+        assert!(verification.problems[0].contains("changed after it was signed"));
+    }
+
+    #[test]
+    fn rejects_a_changed_signature() {
+        let mut bytes = fixture("signed.exe");
+        // The last bytes of the entry are the signer's signature value
+        let image = PeImage::parse(&bytes).unwrap();
+        let (offset, size) = image.data_directory(CERTIFICATE_DIRECTORY).unwrap();
+        let length = u32::from_le_bytes(bytes[offset as usize..offset as usize + 4].try_into().unwrap()) as usize;
+        assert!(length <= size as usize);
+        bytes[offset as usize + length - 1] ^= 0x01;
+
+        let verification = verify_image(&bytes, &roots("root.pem"), on(2025, 6, 1)).unwrap();
+        assert_eq!(verification.status, SignatureStatus::Invalid);
+        assert!(verification.image_hash_valid && !verification.signature_valid);
+    }
+
+    #[test]
+    fn reports_an_expired_signer() {
+        let verification = verify_image(&fixture("signed.exe"), &roots("root.pem"), on(2035, 1, 1)).unwrap();
+        assert_eq!(verification.status, SignatureStatus::Expired);
+        assert_eq!(verification.checked_at, on(2035, 1, 1));
+        assert!(verification.problems.iter().any(|problem| problem.starts_with("CN=Contoso Test Publisher, O=Contoso Test was not valid on 2035-01-01")));
+    }
+
+    #[test]
+    fn reports_an_untrusted_root() {
+        let verification = verify_image(&fixture("signed.exe"), &roots("other-root.pem"), on(2025, 6, 1)).unwrap();
+        assert_eq!(verification.status, SignatureStatus::Untrusted);
+        assert!(verification.image_hash_valid && verification.signature_valid);
+        assert!(verification.trusted_root.is_none());
+        assert_eq!(verification.problems, vec!["No trusted certificate found for CN=Contoso Test Root, O=Contoso Test"]);
+    }
+
+    #[test]
+    fn judges_an_expired_signer_at_its_timestamp() {
+        let verification = verify_image(&fixture("timestamped.exe"), &roots("root.pem"), on(2035, 1, 1)).unwrap();
+        assert_eq!(verification.status, SignatureStatus::Valid, "{:?}", verification.problems);
+        assert_eq!(verification.timestamp, Some(on(2024, 3, 22)));
+        assert_eq!(verification.timestamp_valid, Some(true));
+        assert_eq!(verification.checked_at, on(2024, 3, 22));
+
+        // A timestamp from an authority outside the bundle vouches for nothing
+        let verification = verify_image(&fixture("timestamped.exe"), &roots("other-root.pem"), on(2035, 1, 1)).unwrap();
+        assert_eq!(verification.timestamp_valid, Some(false));
+        assert_eq!(verification.checked_at, on(2035, 1, 1));
+        assert_eq!(verification.status, SignatureStatus::Untrusted);
+    }
+
+    #[test]
+    fn reports_an_unreadable_timestamp_as_invalid() {
+        let mut bytes = fixture("timestamped.exe");
+        let position = bytes.windows(15).position(|window| window == b"20240322000000Z").unwrap();
+        bytes[position + 4..position + 6].copy_from_slice(b"XX");
+
+        let verification = verify_image(&bytes, &roots("root.pem"), on(2035, 1, 1)).unwrap();
+        assert_eq!(verification.timestamp, None);
+        assert_eq!(verification.timestamp_valid, Some(false));
+        assert!(verification.problems.iter().any(|problem| problem == "The timestamp has no readable time"));
+        assert_eq!(verification.status, SignatureStatus::Expired);
+    }
+
+    #[test]
+    fn rejects_certificates_issued_by_a_non_ca() {
+        let verification = verify_image(&fixture("issued-by-non-ca.exe"), &roots("root.pem"), on(2025, 6, 1)).unwrap();
+        assert_eq!(verification.status, SignatureStatus::Invalid);
+        assert!(verification.image_hash_valid && verification.signature_valid);
+        assert!(verification.problems.iter().any(|problem| problem == "CN=Contoso Test Plain Certificate, O=Contoso Test issued a certificate without being a certificate authority"));
+    }
+
+    #[test]
+    fn reports_unsigned_images() {
+        let verification = verify_image(&PeBuilder::new(0x8664, true).build(), &roots("root.pem"), on(2025, 6, 1)).unwrap();
+        assert_eq!(verification.status, SignatureStatus::Unsigned);
+        assert!(verification.problems.is_empty());
+        assert!(verify_image(b"MZ", &roots("root.pem"), on(2025, 6, 1)).is_err());
+    }
+
+    #[test]
+    fn loads_pem_bundles_and_der_certificates() {
+        let pem = fixture("root.pem");
+        assert_eq!(parse_bundle(&pem).len(), 1);
+        let both = [pem.clone(), fixture("other-root.pem")].concat();
+        assert_eq!(parse_bundle(&both).len(), 2);
+        let der = parse_bundle(&pem).remove(0);
+        assert_eq!(parse_bundle(&der), vec![der.clone()]);
+        assert!(parse_bundle(b"no certificates here").is_empty());
+    }
+}
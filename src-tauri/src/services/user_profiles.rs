@@ -0,0 +1,105 @@
+// Discovery of the user profiles on a machine, so per-user installs can be attributed to their owner
+use serde::{Serialize, Deserialize};
+
+use crate::services::registry_source::{RegistryHive, RegistryKey, RegistrySource};
+
+pub const PROFILE_LIST_PATH: &str = "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\ProfileList";
+pub const VOLATILE_ENVIRONMENT_PATH: &str = "Volatile Environment";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserProfile {
+    pub sid: Option<String>,
+    pub user_name: String,
+    pub profile_path: Option<String>, // ProfileImagePath as stored, e.g. %SystemDrive%\Users\alice
+}
+
+/// Every profile registered under ProfileList
+pub fn profile_list(registry: &dyn RegistrySource) -> Vec<UserProfile> {
+    let mut profiles = Vec::new();
+
+    if let Some(profile_list) = registry.open_key(RegistryHive::LocalMachine, PROFILE_LIST_PATH) {
+        for sid in profile_list.subkey_names() {
+            // Temporary (.bak) copies are left behind by corrupted profiles
+            if !is_sid(&sid) {
+                continue;
+            }
+            let profile_path = profile_list.open_subkey(&sid)
+                .and_then(|profile| profile.get_string("ProfileImagePath"));
+            let user_name = profile_path.as_deref()
+                .and_then(profile_folder_name)
+                .unwrap_or_else(|| sid.clone());
+            profiles.push(UserProfile {
+                sid: Some(sid),
+                user_name,
+                profile_path,
+            });
+        }
+    }
+
+    profiles
+}
+
+/// Work out who owns a hive mounted under HKEY_USERS as `hive_name` (a SID, or a profile folder name offline)
+pub fn resolve_user(registry: &dyn RegistrySource, profiles: &[UserProfile], hive_name: &str) -> UserProfile {
+    if let Some(profile) = profiles.iter().find(|profile| {
+        profile.sid.as_deref().map_or(false, |sid| sid.eq_ignore_ascii_case(hive_name))
+    }) {
+        return profile.clone();
+    }
+
+    if !is_sid(hive_name) {
+        return UserProfile {
+            sid: None,
+            user_name: hive_name.to_string(),
+            profile_path: None,
+        };
+    }
+
+    let user_name = registry.open_key(RegistryHive::Users, &format!("{}\\{}", hive_name, VOLATILE_ENVIRONMENT_PATH))
+        .and_then(|environment| environment.get_string("USERNAME"))
+        .unwrap_or_else(|| hive_name.to_string());
+
+    UserProfile {
+        sid: Some(hive_name.to_string()),
+        user_name,
+        profile_path: None,
+    }
+}
+
+/// SID of the ProfileList entry whose profile folder has the given name (C:\Users\alice -> "alice")
+pub fn sid_for_profile_folder(profiles: &[UserProfile], folder_name: &str) -> Option<String> {
+    profiles.iter()
+        .find(|profile| {
+            profile.profile_path.as_deref()
+                .and_then(profile_folder_name)
+                .map_or(false, |name| name.eq_ignore_ascii_case(folder_name))
+        })
+        .and_then(|profile| profile.sid.clone())
+}
+
+/// USERNAME and USERDOMAIN of an interactive session, used to tell which HKEY_USERS hive is HKEY_CURRENT_USER
+pub fn session_identity(user_root: &dyn RegistryKey) -> Option<(String, Option<String>)> {
+    let environment = user_root.open_subkey(VOLATILE_ENVIRONMENT_PATH)?;
+    let user_name = environment.get_string("USERNAME")?;
+    Some((user_name, environment.get_string("USERDOMAIN")))
+}
+
+pub fn same_session(a: &(String, Option<String>), b: &(String, Option<String>)) -> bool {
+    let same_domain = match (&a.1, &b.1) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => true,
+    };
+    a.0.eq_ignore_ascii_case(&b.0) && same_domain
+}
+
+pub fn is_sid(name: &str) -> bool {
+    name.len() > 4 && name[..4].eq_ignore_ascii_case("S-1-") && name[4..].chars().all(|c| c.is_ascii_digit() || c == '-')
+}
+
+fn profile_folder_name(profile_path: &str) -> Option<String> {
+    profile_path.trim_end_matches('\\')
+        .rsplit('\\')
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+}
@@ -0,0 +1,314 @@
+// Version model for DisplayVersion strings: Windows 4-part versions, MSI's 3-field comparison,
+// leading "v", pre-release and build suffixes, and vendor formats like "2023 R2" or "8 Update 391"
+use std::cmp::Ordering;
+use serde::{Serialize, Deserialize};
+
+// Sort keys show at least a Windows-style 4-part version; longer versions keep all their components
+const SORT_KEY_MIN_COMPONENTS: usize = 4;
+
+const PRERELEASE_TAGS: [&str; 8] = ["alpha", "beta", "rc", "preview", "pre", "dev", "insider", "nightly"];
+// Words that introduce another numeric component rather than a suffix
+const COMPONENT_WORDS: [&str; 6] = ["update", "r", "sp", "servicepack", "release", "rev"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProgramVersion {
+    pub raw: String,
+    pub components: Vec<u64>,
+    pub suffix: Option<String>,     // Anything after the numbers that affects ordering, e.g. "beta2"
+    pub build: Option<String>,      // Build metadata that does not affect ordering, e.g. "+sha.5114f85" or "(build 19045)"
+    pub is_prerelease: bool,
+    pub normalized: String,         // Display form: "v01.2.0003-Beta2" -> "1.2.3-beta2"
+    pub sort_key: String,           // Compares correctly as a plain string, for clients that cannot call back
+}
+
+impl ProgramVersion {
+    pub fn parse(raw: &str) -> Option<ProgramVersion> {
+        let trimmed = raw.trim();
+        let mut rest = strip_version_prefix(trimmed);
+
+        // Build metadata: "+..." (semver) or a parenthesised note
+        let mut build = None;
+        if let Some(pos) = rest.find(['+', '(']) {
+            let note = rest[pos..].trim_matches(|c: char| c == '+' || c == '(' || c == ')' || c.is_whitespace());
+            if !note.is_empty() {
+                build = Some(note.to_string());
+            }
+            rest = rest[..pos].trim_end();
+        }
+
+        let mut components = Vec::new();
+        let mut chars = rest.char_indices().peekable();
+        let mut suffix_start = rest.len();
+        while let Some(&(start, c)) = chars.peek() {
+            if !c.is_ascii_digit() {
+                suffix_start = start;
+                break;
+            }
+            let mut end = start;
+            while let Some(&(i, d)) = chars.peek() {
+                if !d.is_ascii_digit() {
+                    break;
+                }
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            // Values too large for a u64 are not versions worth comparing numerically
+            components.push(rest[start..end].parse::<u64>().ok()?);
+
+            // Dots are the norm; commas come from VS_FIXEDFILEINFO-style strings
+            match chars.peek() {
+                Some(&(_, '.')) | Some(&(_, ',')) => {
+                    let (separator_at, _) = chars.next().unwrap();
+                    if !matches!(chars.peek(), Some(&(_, d)) if d.is_ascii_digit()) {
+                        suffix_start = separator_at + 1;
+                        break;
+                    }
+                }
+                Some(&(i, _)) => {
+                    suffix_start = i;
+                    break;
+                }
+                None => suffix_start = rest.len(),
+            }
+        }
+
+        if components.is_empty() {
+            return None;
+        }
+
+        let suffix = parse_suffix(&rest[suffix_start..], &mut components);
+        let is_prerelease = suffix.as_deref().map_or(false, is_prerelease_tag);
+
+        let mut version = ProgramVersion {
+            raw: raw.to_string(),
+            components,
+            suffix,
+            build,
+            is_prerelease,
+            normalized: String::new(),
+            sort_key: String::new(),
+        };
+        version.normalized = version.normalize();
+        version.sort_key = version.build_sort_key();
+        Some(version)
+    }
+
+    /// The n-th component, with missing trailing components read as zero
+    pub fn component(&self, index: usize) -> u64 {
+        self.components.get(index).copied().unwrap_or(0)
+    }
+
+    /// Windows Installer only compares major.minor.build; the fourth field is ignored
+    pub fn cmp_msi(&self, other: &ProgramVersion) -> Ordering {
+        (0..3)
+            .map(|i| self.component(i).cmp(&other.component(i)))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+
+    fn normalize(&self) -> String {
+        let mut normalized = self.components.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(".");
+        if let Some(suffix) = &self.suffix {
+            normalized.push('-');
+            normalized.push_str(suffix);
+        }
+        normalized
+    }
+
+    // Every component zero-padded to the width of a u64, without trailing zeros so 1.2 and 1.2.0.0 get the
+    // same key as they compare equal. '!' ends the components and sorts before the '.' of a longer version,
+    // then a rank puts pre-releases before and other suffixes after the release
+    fn build_sort_key(&self) -> String {
+        let significant = self.components.iter().rposition(|component| *component != 0).map_or(0, |last| last + 1);
+        let mut key = (0..significant.max(SORT_KEY_MIN_COMPONENTS))
+            .map(|i| format!("{:020}", self.component(i)))
+            .collect::<Vec<_>>()
+            .join(".");
+        key.push('!');
+        key.push_str(&self.release_rank().to_string());
+        if let Some(suffix) = &self.suffix {
+            key.push_str(&pad_digit_runs(suffix));
+        }
+        key
+    }
+
+    fn release_rank(&self) -> u8 {
+        match &self.suffix {
+            Some(_) if self.is_prerelease => 0,
+            None => 1,
+            Some(_) => 2,
+        }
+    }
+}
+
+impl PartialEq for ProgramVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ProgramVersion {}
+
+impl PartialOrd for ProgramVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// 1.2 == 1.2.0.0; 1.2.0-beta < 1.2.0 < 1.2.0a; build metadata is ignored
+impl Ord for ProgramVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let length = self.components.len().max(other.components.len());
+        (0..length)
+            .map(|i| self.component(i).cmp(&other.component(i)))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.release_rank().cmp(&other.release_rank()))
+            .then_with(|| natural_cmp(
+                self.suffix.as_deref().unwrap_or(""),
+                other.suffix.as_deref().unwrap_or(""),
+            ))
+    }
+}
+
+/// Compare two raw version strings; unparseable versions sort before parseable ones
+pub fn compare_version_strings(a: &str, b: &str) -> Ordering {
+    match (ProgramVersion::parse(a), ProgramVersion::parse(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => a.cmp(b),
+    }
+}
+
+fn strip_version_prefix(raw: &str) -> &str {
+    let lower = raw.to_ascii_lowercase();
+    for prefix in ["version", "ver.", "ver", "v.", "v"] {
+        if lower.starts_with(prefix) {
+            let rest = raw[prefix.len()..].trim_start();
+            if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                return rest;
+            }
+        }
+    }
+    raw
+}
+
+// Turn the text after the numbers into either more components ("R2", "Update 391") or a suffix
+fn parse_suffix(text: &str, components: &mut Vec<u64>) -> Option<String> {
+    let mut words: Vec<String> = text
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_' || c == '.')
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+
+    // "23.01 (x64)" style platform tags say nothing about the version
+    words.retain(|word| !matches!(word.as_str(), "x64" | "x86" | "amd64" | "arm64" | "win32" | "win64"));
+
+    let mut suffix_words = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let word = &words[i];
+        let (letters, digits) = split_trailing_digits(word);
+        if COMPONENT_WORDS.contains(&letters) && suffix_words.is_empty() {
+            if !digits.is_empty() {
+                components.push(digits.parse().unwrap_or(0));
+                i += 1;
+                continue;
+            }
+            if let Some(number) = words.get(i + 1).and_then(|next| next.parse::<u64>().ok()) {
+                components.push(number);
+                i += 2;
+                continue;
+            }
+        }
+        if word.chars().all(|c| c.is_ascii_digit()) && suffix_words.is_empty() {
+            // A dash revision like 1.2.3-4 is just another component
+            components.push(word.parse().unwrap_or(0));
+            i += 1;
+            continue;
+        }
+        suffix_words.push(word.clone());
+        i += 1;
+    }
+
+    if suffix_words.is_empty() {
+        None
+    } else {
+        Some(suffix_words.join("."))
+    }
+}
+
+fn split_trailing_digits(word: &str) -> (&str, &str) {
+    let split = word.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    (&word[..split], &word[split..])
+}
+
+fn is_prerelease_tag(suffix: &str) -> bool {
+    PRERELEASE_TAGS.iter().any(|tag| suffix.starts_with(tag))
+}
+
+// Compare text with embedded numbers numerically: beta2 < beta10
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    pad_digit_runs(a).cmp(&pad_digit_runs(b))
+}
+
+fn pad_digit_runs(text: &str) -> String {
+    let mut padded = String::new();
+    let mut digits = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        if !digits.is_empty() {
+            padded.push_str(&format!("{:0>10}", digits));
+            digits.clear();
+        }
+        padded.push(c);
+    }
+    if !digits.is_empty() {
+        padded.push_str(&format!("{:0>10}", digits));
+    }
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(raw: &str) -> ProgramVersion {
+        ProgramVersion::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn sort_key_agrees_with_ord() {
+        let versions = [
+            "1.2", "1.2.0.0", "1.2.0.0.0.0.0", "1.2.0.0.0.0.0.1", "1.2.0.0.0.0.0.2", "1.2.0.0.0.0.1", "1.2.0.1",
+            "1.2.3-beta2", "1.2.3-beta10", "1.2.3", "1.2.3a", "1.10", "1.9.5", "2023 R2", "18446744073709551615",
+            "12345678901", "12345678902",
+        ];
+        for a in versions {
+            for b in versions {
+                let (a, b) = (version(a), version(b));
+                assert_eq!(a.sort_key.cmp(&b.sort_key), a.cmp(&b), "{} vs {}", a.raw, b.raw);
+            }
+        }
+    }
+
+    #[test]
+    fn parses_windows_and_vendor_formats() {
+        assert_eq!(version("v01.2.0003-Beta2").normalized, "1.2.3-beta2");
+        assert!(version("v01.2.0003-Beta2").is_prerelease);
+        assert_eq!(version("2023 R2").components, vec![2023, 2]);
+        assert_eq!(version("8 Update 391").components, vec![8, 391]);
+        assert_eq!(version("23.01 (x64)").build.as_deref(), Some("x64"));
+        assert!(ProgramVersion::parse("latest").is_none());
+    }
+
+    #[test]
+    fn msi_comparison_ignores_the_fourth_field() {
+        assert_eq!(version("1.2.3.4").cmp_msi(&version("1.2.3.9")), Ordering::Equal);
+        assert_eq!(version("1.2.3.4").cmp(&version("1.2.3.9")), Ordering::Less);
+    }
+}
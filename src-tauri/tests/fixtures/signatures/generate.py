@@ -0,0 +1,186 @@
+# Writes the Authenticode test images next to this script: a small x64 PE image signed by a test CA, with
+# and without a RFC 3161 timestamp, one whose issuer is not a CA, and the roots to trust them by.
+# Needs the `cryptography` package; keys are thrown away, rerun to replace every file at once.
+import datetime
+import hashlib
+import os
+import struct
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import padding, rsa
+from cryptography.x509.oid import ExtendedKeyUsageOID, NameOID
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+OID_SHA256 = "2.16.840.1.101.3.4.2.1"
+OID_RSA = "1.2.840.113549.1.1.1"
+OID_SIGNED_DATA = "1.2.840.113549.1.7.2"
+OID_CONTENT_TYPE = "1.2.840.113549.1.9.3"
+OID_MESSAGE_DIGEST = "1.2.840.113549.1.9.4"
+OID_TST_INFO = "1.2.840.113549.1.9.16.1.4"
+OID_SPC_INDIRECT_DATA = "1.3.6.1.4.1.311.2.1.4"
+OID_SPC_PE_IMAGE_DATA = "1.3.6.1.4.1.311.2.1.15"
+OID_RFC3161_TIMESTAMP = "1.3.6.1.4.1.311.3.3.1"
+
+
+def tlv(tag, content):
+    length = len(content)
+    if length < 0x80:
+        header = bytes([length])
+    else:
+        encoded = length.to_bytes((length.bit_length() + 7) // 8, "big")
+        header = bytes([0x80 | len(encoded)]) + encoded
+    return bytes([tag]) + header + content
+
+
+def seq(*parts):
+    return tlv(0x30, b"".join(parts))
+
+
+def set_of(*parts):
+    return tlv(0x31, b"".join(parts))
+
+
+def explicit(n, content):
+    return tlv(0xA0 | n, content)
+
+
+def integer(value):
+    return tlv(0x02, value.to_bytes(value.bit_length() // 8 + 1, "big"))
+
+
+def octets(content):
+    return tlv(0x04, content)
+
+
+def oid(dotted):
+    numbers = [int(part) for part in dotted.split(".")]
+    body = bytes([numbers[0] * 40 + numbers[1]])
+    for number in numbers[2:]:
+        chunk = [number & 0x7F]
+        number >>= 7
+        while number:
+            chunk.insert(0, 0x80 | (number & 0x7F))
+            number >>= 7
+        body += bytes(chunk)
+    return tlv(0x06, body)
+
+
+def algorithm(dotted):
+    return seq(oid(dotted), b"\x05\x00")
+
+
+def date(year, month, day):
+    return datetime.datetime(year, month, day, tzinfo=datetime.timezone.utc)
+
+
+def certificate(common_name, key, issuer=None, issuer_key=None, valid=(date(2015, 1, 1), date(2045, 1, 1)), ca=False, usage=None, extensions=True):
+    subject = x509.Name([x509.NameAttribute(NameOID.ORGANIZATION_NAME, common_name.split()[0] + " Test"), x509.NameAttribute(NameOID.COMMON_NAME, common_name)])
+    builder = (x509.CertificateBuilder()
+               .subject_name(subject)
+               .issuer_name(issuer.subject if issuer else subject)
+               .public_key(key.public_key())
+               .serial_number(x509.random_serial_number())
+               .not_valid_before(valid[0])
+               .not_valid_after(valid[1]))
+    if extensions:
+        builder = builder.add_extension(x509.BasicConstraints(ca=ca, path_length=None), critical=True)
+        if usage:
+            builder = builder.add_extension(x509.ExtendedKeyUsage([usage]), critical=False)
+    return builder.sign(issuer_key or key, hashes.SHA256())
+
+
+def key():
+    return rsa.generate_private_key(public_exponent=65537, key_size=2048)
+
+
+def der(cert):
+    return cert.public_bytes(serialization.Encoding.DER)
+
+
+def signer_info(cert, key, content_type, content, unauthenticated=b""):
+    attributes = [
+        seq(oid(OID_CONTENT_TYPE), set_of(oid(content_type))),
+        seq(oid(OID_MESSAGE_DIGEST), set_of(octets(hashlib.sha256(content).digest()))),
+    ]
+    signature = key.sign(set_of(*attributes), padding.PKCS1v15(), hashes.SHA256())
+    issuer_and_serial = seq(cert.issuer.public_bytes(), integer(cert.serial_number))
+    return seq(integer(1), issuer_and_serial, algorithm(OID_SHA256), tlv(0xA0, b"".join(attributes)),
+               algorithm(OID_RSA), octets(signature), tlv(0xA1, unauthenticated) if unauthenticated else b""), signature
+
+
+def signed_data(content_type, content, certificates, signer):
+    body = seq(integer(1), set_of(algorithm(OID_SHA256)), seq(oid(content_type), explicit(0, content)),
+               tlv(0xA0, b"".join(der(cert) for cert in certificates)), set_of(signer))
+    return seq(oid(OID_SIGNED_DATA), explicit(0, body))
+
+
+def image():
+    # DOS header, PE signature, COFF header, PE32+ optional header with 16 directories, one section
+    section = b"\xC3" + b"\x00" * 0x1FF
+    optional = bytearray(240)
+    struct.pack_into("<H", optional, 0, 0x20B)
+    struct.pack_into("<H", optional, 68, 2)
+    struct.pack_into("<I", optional, 108, 16)
+    header = bytearray(0x40)
+    header[0:2] = b"MZ"
+    struct.pack_into("<I", header, 0x3C, 0x40)
+    header += b"PE\0\0" + struct.pack("<HHIIIHH", 0x8664, 1, 0, 0, 0, len(optional), 0x0022) + optional
+    header += b".text\0\0\0" + struct.pack("<IIIIIIHHI", 0x1000, 0x1000, len(section), 0x200, 0, 0, 0, 0, 0x60000020)
+    return bytearray(header.ljust(0x200, b"\0") + section)
+
+
+def sign(signer_cert, signer_key, chain, timestamp=None):
+    pe = image()
+    directory = 0x40 + 4 + 20 + 112 + 4 * 8
+    image_hash = hashlib.sha256(pe[:0x58 + 64] + pe[0x58 + 68:directory] + pe[directory + 8:]).digest()
+
+    spc_data = seq(oid(OID_SPC_PE_IMAGE_DATA), seq(tlv(0x03, b"\x00"), explicit(0, explicit(2, tlv(0x80, b"")))))
+    indirect = seq(spc_data, seq(algorithm(OID_SHA256), octets(image_hash)))
+    content = indirect[2:] if indirect[1] < 0x80 else indirect[2 + (indirect[1] & 0x7F):]
+    signer, signature = signer_info(signer_cert, signer_key, OID_SPC_INDIRECT_DATA, content)
+
+    if timestamp:
+        tsa_cert, tsa_key, time = timestamp
+        tst = seq(integer(1), oid("1.2.3.4"), seq(algorithm(OID_SHA256), octets(hashlib.sha256(signature).digest())),
+                  integer(7), tlv(0x18, time.strftime("%Y%m%d%H%M%SZ").encode()))
+        tsa_signer, _ = signer_info(tsa_cert, tsa_key, OID_TST_INFO, tst)
+        token = signed_data(OID_TST_INFO, octets(tst), [tsa_cert], tsa_signer)
+        signer, _ = signer_info(signer_cert, signer_key, OID_SPC_INDIRECT_DATA, content,
+                                seq(oid(OID_RFC3161_TIMESTAMP), set_of(token)))
+
+    pkcs7 = signed_data(OID_SPC_INDIRECT_DATA, indirect, chain, signer)
+    entry = struct.pack("<IHH", len(pkcs7) + 8, 0x0200, 2) + pkcs7
+    entry += b"\0" * (-len(entry) % 8)
+    struct.pack_into("<II", pe, directory, len(pe), len(entry))
+    return bytes(pe + entry)
+
+
+def write(name, data):
+    with open(os.path.join(HERE, name), "wb") as file:
+        file.write(data)
+
+
+def main():
+    root_key, intermediate_key, signer_key, tsa_key, other_key = key(), key(), key(), key(), key()
+    root = certificate("Contoso Test Root", root_key, ca=True)
+    other_root = certificate("Fabrikam Test Root", other_key, ca=True)
+    intermediate = certificate("Contoso Test Code Signing CA", intermediate_key, root, root_key, ca=True)
+    signer = certificate("Contoso Test Publisher", signer_key, intermediate, intermediate_key,
+                         valid=(date(2020, 1, 1), date(2030, 1, 1)), usage=ExtendedKeyUsageOID.CODE_SIGNING)
+    tsa = certificate("Contoso Test Timestamps", tsa_key, root, root_key, usage=ExtendedKeyUsageOID.TIME_STAMPING)
+    # An "intermediate" without basicConstraints, which may not issue certificates
+    plain = certificate("Contoso Test Plain Certificate", intermediate_key, root, root_key, extensions=False)
+    plain_signer = certificate("Contoso Test Publisher", signer_key, plain, intermediate_key,
+                               valid=(date(2020, 1, 1), date(2030, 1, 1)), usage=ExtendedKeyUsageOID.CODE_SIGNING)
+
+    write("root.pem", root.public_bytes(serialization.Encoding.PEM))
+    write("other-root.pem", other_root.public_bytes(serialization.Encoding.PEM))
+    write("signed.exe", sign(signer, signer_key, [signer, intermediate]))
+    write("timestamped.exe", sign(signer, signer_key, [signer, intermediate], (tsa, tsa_key, date(2024, 3, 22))))
+    write("issued-by-non-ca.exe", sign(plain_signer, signer_key, [plain_signer, plain]))
+
+
+if __name__ == "__main__":
+    main()
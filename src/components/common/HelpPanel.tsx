@@ -182,7 +182,7 @@ export const HelpPanel: React.FC = () => {
                     <ListItem><strong>Publisher:</strong> Filter by software publisher/company</ListItem>
                     <ListItem><strong>Installation Date:</strong> Last 7/30/90 days or custom range</ListItem>
                     <ListItem><strong>Program Type:</strong> Applications, System Components, or Updates</ListItem>
-                    <ListItem><strong>Architecture:</strong> 32-bit or 64-bit programs</ListItem>
+                    <ListItem><strong>Architecture:</strong> 32-bit, 64-bit or ARM programs, and Neutral ones that run as either (Any CPU .NET programs and neutral app packages)</ListItem>
                   </List>
                 </Box>
 
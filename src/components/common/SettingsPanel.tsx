@@ -512,6 +512,37 @@ export const SettingsPanel: React.FC = () => {
 
               <Divider />
 
+              <FormControl>
+                <FormLabel>Trusted Roots Bundle</FormLabel>
+                <Input
+                  value={settings.trustedRootsBundle}
+                  onChange={(e) => handleSettingChange('trustedRootsBundle', e.target.value)}
+                  placeholder="trusted-roots.pem in the app data directory"
+                />
+                <FormHelperText>PEM bundle or DER certificate of the root CAs signatures are verified against. Verification is offline, so revocation is not checked</FormHelperText>
+              </FormControl>
+
+              <FormControl>
+                <FormLabel>Hash Executables During Scan</FormLabel>
+                <Switch
+                  isChecked={settings.hashExecutablesDuringScan}
+                  onChange={(e) => handleSettingChange('hashExecutablesDuringScan', e.target.checked)}
+                />
+                <FormHelperText>Compute SHA-256, SHA-1 and MD5 of each program's main executable and uninstaller. Unchanged files are taken from a cache</FormHelperText>
+              </FormControl>
+
+              <FormControl>
+                <FormLabel>IOC List</FormLabel>
+                <Input
+                  value={settings.iocListPath}
+                  onChange={(e) => handleSettingChange('iocListPath', e.target.value)}
+                  placeholder="C:\IR\iocs.csv"
+                />
+                <FormHelperText>CSV or STIX JSON file of known-bad hashes to match executables against. Takes effect on the next scan</FormHelperText>
+              </FormControl>
+
+              <Divider />
+
               <HStack spacing={3}>
                 <Button onClick={resetToDefaults} colorScheme="orange" size="sm">
                   Reset to Defaults
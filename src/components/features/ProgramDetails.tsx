@@ -32,7 +32,7 @@ import {
   useDisclosure,
   Spinner,
 } from '@chakra-ui/react';
-import { ProgramInfo, AteaInformation } from '../../types/ProgramInfo';
+import { ProgramInfo, AteaInformation, UninstallAnalysis, ProgramAction, ActionResult, LeftoverBaselineSummary, LeftoverReport, InstallSizeInfo, ExecutableAnalysis, ImageInfo, SignatureTrustItem, SignatureTrustReport, SignatureStatus, FileHashInventory, HashType } from '../../types/ProgramInfo';
 import { ExternalLinkIcon, ChevronDownIcon, ChevronUpIcon, ViewIcon, DeleteIcon, EditIcon } from '@chakra-ui/icons';
 import { ProgramIcon } from '../common/ProgramIcon';
 import { LogViewer } from '../common/LogViewer';
@@ -54,6 +54,17 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
   const [ateaInfo, setAteaInfo] = useState<AteaInformation | null>(null);
   const [isLoadingAtea, setIsLoadingAtea] = useState(false);
   const [ateaError, setAteaError] = useState<string | null>(null);
+  const [uninstallAnalysis, setUninstallAnalysis] = useState<UninstallAnalysis | null>(null);
+  const [leftoverReport, setLeftoverReport] = useState<LeftoverReport | null>(null);
+  const [isFindingLeftovers, setIsFindingLeftovers] = useState(false);
+  const [installSize, setInstallSize] = useState<InstallSizeInfo | undefined>(program.install_size);
+  const [isMeasuring, setIsMeasuring] = useState(false);
+  const [executables, setExecutables] = useState<ExecutableAnalysis | undefined>(program.executables);
+  const [isAnalyzing, setIsAnalyzing] = useState(false);
+  const [signatureChecks, setSignatureChecks] = useState<SignatureTrustItem[] | null>(null);
+  const [isVerifying, setIsVerifying] = useState(false);
+  const [fileHashes, setFileHashes] = useState<FileHashInventory | undefined>(program.file_hashes);
+  const [isHashing, setIsHashing] = useState(false);
   const { isOpen: isUninstallOpen, onOpen: onUninstallOpen, onClose: onUninstallClose } = useDisclosure();
   const { isOpen: isModifyOpen, onOpen: onModifyOpen, onClose: onModifyClose } = useDisclosure();
   const cancelRef = React.useRef<HTMLButtonElement>(null);
@@ -90,6 +101,26 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
     }
   }, [program.is_vf_deployed, program.comments]);
 
+  // Parse the uninstall commands and propose a silent uninstall when the entry has none
+  useEffect(() => {
+    if (program.uninstall_string || program.quiet_uninstall_string || program.change_install_string) {
+      invoke<UninstallAnalysis>('analyze_uninstall_commands', { program })
+        .then(setUninstallAnalysis)
+        .catch(() => setUninstallAnalysis(null));
+    } else {
+      setUninstallAnalysis(null);
+    }
+  }, [program]);
+
+  // Measurements and leftover searches belong to the program they were made for
+  useEffect(() => {
+    setInstallSize(program.install_size);
+    setExecutables(program.executables);
+    setSignatureChecks(null);
+    setFileHashes(program.file_hashes);
+    setLeftoverReport(null);
+  }, [program]);
+
   // Drag event handlers
   const handleMouseDown = useCallback((e: React.MouseEvent) => {
     if (modalRef.current) {
@@ -182,31 +213,161 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
     }
   };
 
-  const handleUninstall = () => {
-    if (program.uninstall_string) {
-      // In a real implementation, you would execute the uninstall command
-      // For now, we'll just show a toast
+  // Runs the command on the backend and reports how it ended
+  const runAction = async (action: ProgramAction, label: string): Promise<ActionResult | null> => {
+    try {
+      const result = await invoke<ActionResult>('run_program_action', { program, action });
+      const succeeded = ['Success', 'RebootRequired', 'RebootInitiated'].includes(result.outcome);
       toast({
-        title: 'Uninstall Command',
-        description: `Would execute: ${program.uninstall_string}`,
-        status: 'info',
+        title: `${label} ${succeeded ? 'finished' : 'did not complete'}`,
+        description: result.message,
+        status: succeeded ? 'success' : result.outcome === 'NotInstalled' ? 'info' : 'error',
         duration: 5000,
       });
-      onUninstallClose();
+      return result;
+    } catch (error) {
+      toast({
+        title: `${label} failed`,
+        description: `${error}`,
+        status: 'error',
+        duration: 5000,
+      });
+      return null;
     }
   };
 
-  const handleModify = () => {
-    if (program.change_install_string) {
-      // In a real implementation, you would execute the modify command
-      // For now, we'll just show a toast
+  const measureInstallSize = async () => {
+    setIsMeasuring(true);
+    try {
+      const [measured] = await invoke<ProgramInfo[]>('measure_install_sizes', { programs: [program] });
+      setInstallSize(measured?.install_size);
+    } catch (error) {
+      toast({
+        title: 'Size measurement failed',
+        description: `${error}`,
+        status: 'error',
+        duration: 5000,
+      });
+    } finally {
+      setIsMeasuring(false);
+    }
+  };
+
+  const analyzeExecutables = async () => {
+    setIsAnalyzing(true);
+    try {
+      const [analyzed] = await invoke<ProgramInfo[]>('analyze_executables', { programs: [program] });
+      setExecutables(analyzed?.executables);
+    } catch (error) {
+      toast({
+        title: 'Executable analysis failed',
+        description: `${error}`,
+        status: 'error',
+        duration: 5000,
+      });
+    } finally {
+      setIsAnalyzing(false);
+    }
+  };
+
+  const verifySignatures = async () => {
+    setIsVerifying(true);
+    try {
+      const report = await invoke<SignatureTrustReport>('get_signature_trust_report', {
+        programs: [{ ...program, executables }],
+        trustedRootsPath: settings.trustedRootsBundle || null,
+      });
+      setSignatureChecks(report.entries);
+    } catch (error) {
+      toast({
+        title: 'Signature verification failed',
+        description: `${error}`,
+        status: 'error',
+        duration: 5000,
+      });
+    } finally {
+      setIsVerifying(false);
+    }
+  };
+
+  const hashExecutables = async () => {
+    setIsHashing(true);
+    try {
+      const [hashed] = await invoke<ProgramInfo[]>('hash_executables', {
+        programs: [{ ...program, executables }],
+        iocList: settings.iocListPath || null,
+      });
+      setFileHashes(hashed?.file_hashes);
+    } catch (error) {
+      toast({
+        title: 'Hashing failed',
+        description: `${error}`,
+        status: 'error',
+        duration: 5000,
+      });
+    } finally {
+      setIsHashing(false);
+    }
+  };
+
+  const hashTypeNames: Record<HashType, string> = {
+    Sha256: 'SHA-256',
+    Sha1: 'SHA-1',
+    Md5: 'MD5',
+  };
+
+  const signatureColors: Record<SignatureStatus, string> = {
+    Valid: 'green',
+    Expired: 'orange',
+    Untrusted: 'orange',
+    Invalid: 'red',
+    Unsigned: 'gray',
+  };
+
+  // "64-bit, Gui, .NET v4.0.30319 - signed by Contoso Ltd"
+  const describeImage = (image: ImageInfo) => {
+    const architecture = image.any_cpu ? 'Any CPU' : image.machine;
+    const traits = [architecture, image.subsystem, image.dotnet ? `.NET ${image.dotnet.runtime_version ?? ''}`.trim() : null]
+      .filter(Boolean)
+      .join(', ');
+    const signer = image.signature
+      ? `signed by ${image.signature.signer_name ?? image.signature.signer_subject}`
+      : image.signature_error ? 'unreadable signature' : 'not signed';
+    return `${traits} - ${signer}`;
+  };
+
+  const findLeftovers = async (baselineId?: string) => {
+    setIsFindingLeftovers(true);
+    try {
+      setLeftoverReport(await invoke<LeftoverReport>('find_leftovers', { program, baselineId }));
+    } catch (error) {
       toast({
-        title: 'Modify Command',
-        description: `Would execute: ${program.change_install_string}`,
-        status: 'info',
+        title: 'Leftover search failed',
+        description: `${error}`,
+        status: 'error',
         duration: 5000,
       });
+    } finally {
+      setIsFindingLeftovers(false);
+    }
+  };
+
+  // A baseline taken first lets the leftover search afterwards compare instead of guess
+  const handleUninstall = async () => {
+    if (program.uninstall_string) {
+      onUninstallClose();
+      const baseline = await invoke<LeftoverBaselineSummary>('capture_leftover_baseline', { program }).catch(() => null);
+      const result = await runAction('Uninstall', 'Uninstall');
+      if (result && baseline) {
+        await findLeftovers(baseline.id);
+      }
+    }
+  };
+
+  const handleModify = () => {
+    if (program.change_install_string) {
       onModifyClose();
+      runAction('Modify', 'Modify');
     }
   };
 
@@ -259,6 +420,7 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
                   colorScheme={
                     program.installation_source === 'System' ? 'blue' :
                     program.installation_source === 'User' ? 'green' :
+                    program.installation_source === 'MSIX' ? 'teal' :
                     'orange'
                   }
                 >
@@ -267,9 +429,22 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
                 {program.is_windows_installer && (
                   <Badge colorScheme="purple">Windows Installer</Badge>
                 )}
+                {program.installer_technology && !['WindowsInstaller', 'Msix', 'Unknown'].includes(program.installer_technology.engine) && (
+                  <Badge colorScheme="cyan" title={program.installer_technology.evidence.join('\n')}>
+                    {program.installer_technology.engine}
+                  </Badge>
+                )}
                 {program.is_vf_deployed && (
                   <Badge colorScheme="purple" variant="solid" fontWeight="bold">VF Managed</Badge>
                 )}
+                {program.health && program.health.status !== 'Healthy' && (
+                  <Badge
+                    colorScheme={program.health.status === 'Degraded' ? 'yellow' : 'red'}
+                    title={program.health.issues.map((issue) => issue.path ? `${issue.kind}: ${issue.path}` : issue.kind).join('\n')}
+                  >
+                    {program.health.status}
+                  </Badge>
+                )}
               </HStack>
             </VStack>
           </HStack>
@@ -324,6 +499,7 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
                         colorScheme={
                           program.installation_source === 'System' ? 'blue' :
                           program.installation_source === 'User' ? 'green' :
+                          program.installation_source === 'MSIX' ? 'teal' :
                           'orange'
                         }
                       >
@@ -513,11 +689,102 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
                         </GridItem>
                       </>
                     )}
-                    {program.estimated_size && (
+                    {(program.estimated_size || program.install_location) && (
                       <>
                         <GridItem><Text color="gray.600" fontWeight="medium">Size</Text></GridItem>
                         <GridItem>
-                          <Text>{formatFileSize(program.estimated_size)}</Text>
+                          <HStack>
+                            <Text>{program.estimated_size ? `${formatFileSize(program.estimated_size)} declared` : 'Not declared'}</Text>
+                            {installSize?.measured && (
+                              <Text color={installSize.discrepancy ? 'red.500' : 'gray.600'}>
+                                {formatFileSize(Math.round(installSize.measured.allocated_bytes / 1024))}
+                                {installSize.measured.complete ? '' : '+'} on disk
+                              </Text>
+                            )}
+                            {installSize?.discrepancy && <Badge colorScheme="red">{installSize.discrepancy}</Badge>}
+                            {program.install_location && (
+                              <Button size="xs" onClick={measureInstallSize} isLoading={isMeasuring}>
+                                Measure
+                              </Button>
+                            )}
+                          </HStack>
+                          {installSize?.error && (
+                            <Text fontSize="xs" color="gray.500" mt={1}>{installSize.error}</Text>
+                          )}
+                        </GridItem>
+                      </>
+                    )}
+                    {(program.install_location || program.uninstall_string) && (
+                      <>
+                        <GridItem><Text color="gray.600" fontWeight="medium">Executables</Text></GridItem>
+                        <GridItem>
+                          <HStack>
+                            {executables?.architecture_mismatch && (
+                              <Badge colorScheme="red" title={`Listed as ${program.architecture}`}>Architecture mismatch</Badge>
+                            )}
+                            {executables && !executables.main_executable && !executables.uninstaller && (
+                              <Text color="gray.500">None found</Text>
+                            )}
+                            <Button size="xs" onClick={analyzeExecutables} isLoading={isAnalyzing}>
+                              Analyze
+                            </Button>
+                            <Button size="xs" onClick={verifySignatures} isLoading={isVerifying}>
+                              Verify
+                            </Button>
+                            <Button size="xs" onClick={hashExecutables} isLoading={isHashing}>
+                              Hash
+                            </Button>
+                            {fileHashes && fileHashes.ioc_hits.length > 0 && (
+                              <Badge colorScheme="red">IOC hit</Badge>
+                            )}
+                          </HStack>
+                          {([['Main', executables?.main_executable], ['Uninstaller', executables?.uninstaller]] as [string, ImageInfo | undefined][])
+                            .map(([label, image]) => image && (
+                              <Box key={label} mt={1}>
+                                <Text fontSize="sm">{label}: {describeImage(image)}</Text>
+                                <Text fontSize="xs" fontFamily="monospace" color="gray.500" wordBreak="break-all" title={image.signature?.issuer}>
+                                  {image.path}
+                                </Text>
+                              </Box>
+                            ))}
+                          {signatureChecks?.map(check => (
+                            <Box key={check.path} mt={1}>
+                              <HStack>
+                                <Text fontSize="sm">{check.role === 'MainExecutable' ? 'Main' : 'Uninstaller'}:</Text>
+                                {check.verification
+                                  ? <Badge colorScheme={signatureColors[check.verification.status]} title={check.verification.chain.join('\n')}>{check.verification.status}</Badge>
+                                  : <Badge colorScheme="red">Unreadable</Badge>}
+                                {check.publisher_mismatch && (
+                                  <Badge colorScheme="orange" title={`Signed by ${check.verification?.signer_name ?? 'unknown'}`}>Publisher mismatch</Badge>
+                                )}
+                              </HStack>
+                              {[...(check.verification?.problems ?? []), ...(check.error ? [check.error] : [])].map(problem => (
+                                <Text key={problem} fontSize="xs" color="gray.500">{problem}</Text>
+                              ))}
+                            </Box>
+                          ))}
+                          {([['Main', fileHashes?.main_executable], ['Uninstaller', fileHashes?.uninstaller]] as const)
+                            .map(([label, hashed]) => hashed && (
+                              <HStack key={`hash-${label}`} mt={1}>
+                                <Text fontSize="xs" fontFamily="monospace" wordBreak="break-all" title={`SHA-1 ${hashed.hashes.sha1}\nMD5 ${hashed.hashes.md5}`}>
+                                  {label} SHA-256: {hashed.hashes.sha256}
+                                </Text>
+                                <Button size="xs" variant="ghost" onClick={() => handleCopy(hashed.hashes.sha256, 'SHA-256')}>
+                                  Copy
+                                </Button>
+                              </HStack>
+                            ))}
+                          {fileHashes?.ioc_hits.map(hit => (
+                            <Text key={`${hit.path}-${hit.hash}`} fontSize="xs" color="red.500" mt={1}>
+                              {hit.role === 'MainExecutable' ? 'Main' : 'Uninstaller'} matches {hit.indicator ?? 'an indicator'} ({hashTypeNames[hit.hash_type]} in {hit.ioc_source})
+                            </Text>
+                          ))}
+                          {fileHashes?.errors.map(error => (
+                            <Text key={`hash-${error}`} fontSize="xs" color="gray.500" mt={1}>{error}</Text>
+                          ))}
+                          {executables?.errors.map(error => (
+                            <Text key={error} fontSize="xs" color="gray.500" mt={1}>{error}</Text>
+                          ))}
                         </GridItem>
                       </>
                     )}
@@ -607,6 +874,32 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
                         </Button>
                       </HStack>
                     </GridItem>
+                    {program.registry_provenance && (
+                      <>
+                        <GridItem><Text color="gray.600" fontWeight="medium">Registry View</Text></GridItem>
+                        <GridItem><Text>{program.registry_provenance.view}</Text></GridItem>
+                        {program.registry_provenance.last_write_time && (
+                          <>
+                            <GridItem><Text color="gray.600" fontWeight="medium">Key Last Written</Text></GridItem>
+                            <GridItem><Text>{new Date(program.registry_provenance.last_write_time).toLocaleString()}</Text></GridItem>
+                          </>
+                        )}
+                      </>
+                    )}
+                    {program.merged_from && program.merged_from.length > 0 && (
+                      <>
+                        <GridItem><Text color="gray.600" fontWeight="medium">Merged Entries</Text></GridItem>
+                        <GridItem>
+                          <VStack align="start" spacing={1}>
+                            {program.merged_from.map((entry, index) => (
+                              <Text key={index} fontSize="sm" fontFamily="monospace" wordBreak="break-all">
+                                {entry.registry_path} ({entry.matched_by})
+                              </Text>
+                            ))}
+                          </VStack>
+                        </GridItem>
+                      </>
+                    )}
                     {program.shortcuts && program.shortcuts.length > 0 && (
                       <>
                         <GridItem><Text color="gray.600" fontWeight="medium">Shortcuts</Text></GridItem>
@@ -768,6 +1061,64 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
                           </GridItem>
                         </>
                       )}
+                      {uninstallAnalysis?.uninstall && (
+                        <>
+                          <GridItem><Text color="gray.600" fontWeight="medium">Executable</Text></GridItem>
+                          <GridItem>
+                            <Text fontSize="sm" fontFamily="monospace" wordBreak="break-all">
+                              {uninstallAnalysis.uninstall.executable}
+                            </Text>
+                          </GridItem>
+                          {uninstallAnalysis.uninstall.arguments.length > 0 && (
+                            <>
+                              <GridItem><Text color="gray.600" fontWeight="medium">Arguments</Text></GridItem>
+                              <GridItem>
+                                <HStack spacing={1} flexWrap="wrap">
+                                  {uninstallAnalysis.uninstall.arguments.map((argument, index) => (
+                                    <Badge key={index} variant="outline" fontFamily="monospace" textTransform="none">
+                                      {argument}
+                                    </Badge>
+                                  ))}
+                                </HStack>
+                              </GridItem>
+                            </>
+                          )}
+                        </>
+                      )}
+                      {uninstallAnalysis && uninstallAnalysis.engine !== 'Unknown' && (
+                        <>
+                          <GridItem><Text color="gray.600" fontWeight="medium">Installer Engine</Text></GridItem>
+                          <GridItem>
+                            <Text fontSize="sm">
+                              {uninstallAnalysis.engine} ({uninstallAnalysis.engine_confidence} confidence)
+                            </Text>
+                          </GridItem>
+                        </>
+                      )}
+                      {uninstallAnalysis?.silent_uninstall?.origin === 'Synthesized' && (
+                        <>
+                          <GridItem><Text color="gray.600" fontWeight="medium">Proposed Silent</Text></GridItem>
+                          <GridItem>
+                            <HStack>
+                              <Text fontSize="sm" fontFamily="monospace" wordBreak="break-all">
+                                {uninstallAnalysis.silent_uninstall.command}
+                              </Text>
+                              <Badge colorScheme={uninstallAnalysis.silent_uninstall.confidence === 'High' ? 'green' : uninstallAnalysis.silent_uninstall.confidence === 'Medium' ? 'yellow' : 'red'}>
+                                {uninstallAnalysis.silent_uninstall.confidence}
+                              </Badge>
+                              <Button 
+                                size="xs"
+                                onClick={() => handleCopy(uninstallAnalysis.silent_uninstall!.command, 'Proposed silent uninstall command')}
+                              >
+                                Copy
+                              </Button>
+                            </HStack>
+                            {uninstallAnalysis.silent_uninstall.note && (
+                              <Text fontSize="xs" color="gray.500" mt={1}>{uninstallAnalysis.silent_uninstall.note}</Text>
+                            )}
+                          </GridItem>
+                        </>
+                      )}
                     </Grid>
                   </AccordionPanel>
                 </AccordionItem>
@@ -800,10 +1151,55 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
                         Modify
                       </Button>
                     )}
+                    {program.is_windows_installer && (
+                      <Button
+                        colorScheme="purple"
+                        variant="outline"
+                        onClick={() => runAction('Repair', 'Repair')}
+                        size="sm"
+                      >
+                        Repair
+                      </Button>
+                    )}
+                    <Button
+                      variant="outline"
+                      onClick={() => findLeftovers()}
+                      isLoading={isFindingLeftovers}
+                      size="sm"
+                    >
+                      Find Leftovers
+                    </Button>
                   </HStack>
                 </Box>
               )}
 
+              {/* Leftovers */}
+              {leftoverReport && (
+                <Box>
+                  <Heading size="sm" mb={1}>Leftovers</Heading>
+                  <Text fontSize="sm" color="gray.600" mb={3}>
+                    {leftoverReport.baseline_id
+                      ? `Compared with the state before the uninstall: ${leftoverReport.leftovers.length} left, ${leftoverReport.removed} removed`
+                      : `${leftoverReport.leftovers.length} candidates found by install path, name and publisher`}
+                    {leftoverReport.still_registered && ' (the program is still registered)'}
+                  </Text>
+                  <VStack align="stretch" spacing={1}>
+                    {leftoverReport.leftovers.map((item) => (
+                      <HStack key={`${item.kind}:${item.path}`} spacing={2}>
+                        <Badge>{item.kind}</Badge>
+                        <Badge colorScheme={item.confidence === 'High' ? 'green' : item.confidence === 'Medium' ? 'yellow' : 'red'}>
+                          {item.confidence}
+                        </Badge>
+                        <Text fontSize="sm" fontFamily="monospace" wordBreak="break-all" title={item.detail}>
+                          {item.path}
+                        </Text>
+                        <Button size="xs" onClick={() => handleCopy(item.path, 'Path')}>Copy</Button>
+                      </HStack>
+                    ))}
+                  </VStack>
+                </Box>
+              )}
+
               {/* Additional Information */}
               {(program.comments || program.about_url || program.installed_for) && (
                 <Box>
@@ -815,6 +1211,32 @@ export const ProgramDetails: React.FC<ProgramDetailsProps> = ({ program, isOpen,
                         <GridItem><Text>{program.installed_for}</Text></GridItem>
                       </>
                     )}
+                    {program.user_sid && (
+                      <>
+                        <GridItem><Text color="gray.600">User SID</Text></GridItem>
+                        <GridItem><Text fontSize="sm" fontFamily="monospace">{program.user_sid}</Text></GridItem>
+                      </>
+                    )}
+                    {program.msi && (
+                      <>
+                        <GridItem><Text color="gray.600">MSI Product Code</Text></GridItem>
+                        <GridItem><Text fontSize="sm" fontFamily="monospace">{program.msi.product_code}</Text></GridItem>
+                        {program.msi.upgrade_code && (
+                          <>
+                            <GridItem><Text color="gray.600">MSI Upgrade Code</Text></GridItem>
+                            <GridItem><Text fontSize="sm" fontFamily="monospace">{program.msi.upgrade_code}</Text></GridItem>
+                          </>
+                        )}
+                        {program.msi.local_package && (
+                          <>
+                            <GridItem><Text color="gray.600">MSI Local Package</Text></GridItem>
+                            <GridItem><Text fontSize="sm" fontFamily="monospace">{program.msi.local_package}</Text></GridItem>
+                          </>
+                        )}
+                        <GridItem><Text color="gray.600">MSI Context</Text></GridItem>
+                        <GridItem><Text>{program.msi.context}</Text></GridItem>
+                      </>
+                    )}
                     {program.comments && (
                       <>
                         <GridItem><Text color="gray.600">Comments</Text></GridItem>
@@ -1,5 +1,6 @@
 import React, { useEffect, useState, useMemo } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
 import { save } from '@tauri-apps/api/dialog';
 import { 
   Box,
@@ -33,7 +34,7 @@ import {
   useDisclosure,
   Divider,
 } from '@chakra-ui/react';
-import { ProgramInfo } from '../../types/ProgramInfo';
+import { ProgramInfo, ScanFinished, ScanOptions, ScanProgress } from '../../types/ProgramInfo';
 import { useDebounce } from '../../hooks/useDebounce';
 import { ProgramDetails } from './ProgramDetails';
 import { ChevronDownIcon, ChevronRightIcon, CloseIcon } from '@chakra-ui/icons';
@@ -44,16 +45,19 @@ type SortField = 'name' | 'publisher' | 'install_date' | 'version';
 type SortDirection = 'asc' | 'desc';
 type DateFilter = 'all' | 'last7days' | 'last30days' | 'last90days' | 'custom';
 type ProgramType = 'all' | 'Application' | 'SystemComponent' | 'Update' | 'Portable Application';
-type Architecture = 'all' | '32-bit' | '64-bit' | 'User' | 'Unknown';
-type InstallationSource = 'all' | 'System' | 'User' | 'Filesystem';
+type Architecture = 'all' | '32-bit' | '64-bit' | 'ARM64' | 'ARM' | 'Neutral' | 'User' | 'Unknown';
+type InstallationSource = 'all' | 'System' | 'User' | 'Filesystem' | 'MSIX';
 type VFDeployment = 'all' | 'vf-managed' | 'other-apps';
+type HealthFilter = 'all' | 'problems' | 'Degraded' | 'Broken' | 'Orphaned';
 
 export const ProgramList: React.FC = () => {
-  const { settings } = useSettings();
+  const { settings, isLoading: isLoadingSettings } = useSettings();
   const { isOpen: isFiltersOpen, onToggle: onFiltersToggle } = useDisclosure();
   const [programs, setPrograms] = useState<ProgramInfo[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [scanJobId, setScanJobId] = useState<string | null>(null);
+  const [scanProgress, setScanProgress] = useState<ScanProgress | null>(null);
   const [sortField, setSortField] = useState<SortField>('name');
   const [sortDirection, setSortDirection] = useState<SortDirection>('asc');
   const [viewMode, setViewMode] = useState<'grid' | 'list'>(settings.defaultView);
@@ -70,6 +74,7 @@ export const ProgramList: React.FC = () => {
   const [architecture, setArchitecture] = useState<Architecture>('all');
   const [installationSource, setInstallationSource] = useState<InstallationSource>('all');
   const [vfDeployment, setVfDeployment] = useState<VFDeployment>('vf-managed');
+  const [healthFilter, setHealthFilter] = useState<HealthFilter>('all');
   const [selectedProgram, setSelectedProgram] = useState<ProgramInfo | null>(null);
   const [contextMenu, setContextMenu] = useState<{
     isOpen: boolean;
@@ -96,6 +101,7 @@ export const ProgramList: React.FC = () => {
     if (architecture !== 'all') count++;
     if (installationSource !== 'all') count++;
     if (vfDeployment !== 'all') count++;
+    if (healthFilter !== 'all') count++;
     return count;
   };
 
@@ -107,6 +113,7 @@ export const ProgramList: React.FC = () => {
     setArchitecture('all');
     setInstallationSource('all');
     setVfDeployment('all');
+    setHealthFilter('all');
   };
 
   const getFilterChips = () => {
@@ -129,6 +136,9 @@ export const ProgramList: React.FC = () => {
     if (installationSource !== 'all') {
       chips.push({ key: 'source', label: `Source: ${installationSource}`, onRemove: () => setInstallationSource('all') });
     }
+    if (healthFilter !== 'all') {
+      chips.push({ key: 'health', label: `Health: ${healthFilter}`, onRemove: () => setHealthFilter('all') });
+    }
     if (vfDeployment !== 'all') {
       chips.push({ key: 'vf', label: `VF: ${vfDeployment}`, onRemove: () => setVfDeployment('all') });
     }
@@ -193,7 +203,9 @@ export const ProgramList: React.FC = () => {
 
         // Date filtering
         let matchesDate = true;
-        const installDate = parseInstallDate(program.install_date);
+        const installDate = program.installed_on
+          ? new Date(program.installed_on.date)
+          : parseInstallDate(program.install_date);
 
         if (installDate && dateFilter !== 'all') {
           const now = new Date();
@@ -229,11 +241,17 @@ export const ProgramList: React.FC = () => {
           (vfDeployment === 'vf-managed' && program.is_vf_deployed) ||
           (vfDeployment === 'other-apps' && !program.is_vf_deployed);
 
-        return matchesSearch && matchesPublisher && matchesDate && matchesType && matchesArchitecture && matchesInstallationSource && matchesVFDeployment;
+        const health = program.health?.status ?? 'Healthy';
+        const matchesHealth = healthFilter === 'all' ||
+          (healthFilter === 'problems' && health !== 'Healthy') ||
+          health === healthFilter;
+
+        return matchesSearch && matchesPublisher && matchesDate && matchesType && matchesArchitecture && matchesInstallationSource && matchesVFDeployment && matchesHealth;
       })
       .sort((a, b) => {
-        const aValue = a[sortField] || '';
-        const bValue = b[sortField] || '';
+        // Versions sort on the backend's sort key so 1.10 comes after 1.9
+        const aValue = (sortField === 'version' ? a.version_info?.sort_key : a[sortField]) || '';
+        const bValue = (sortField === 'version' ? b.version_info?.sort_key : b[sortField]) || '';
         return sortDirection === 'asc'
           ? aValue.localeCompare(bValue)
           : bValue.localeCompare(aValue);
@@ -250,6 +268,7 @@ export const ProgramList: React.FC = () => {
     architecture,
     installationSource,
     vfDeployment,
+    healthFilter,
   ]);
 
   // Update view mode when settings change
@@ -271,22 +290,69 @@ export const ProgramList: React.FC = () => {
     }
   }, [contextMenu.isOpen]);
 
-  // Fetch programs
+  // Fetch programs with a background scan job, once saved settings are in since they pick the optional stages
   useEffect(() => {
-    const fetchPrograms = async () => {
+    if (isLoadingSettings) {
+      return;
+    }
+    let jobId: string | null = null;
+    // Only one scan runs at a time, so events arriving before start_scan returns are ours
+    const isOurJob = (eventJobId: string | null) => jobId === null || eventJobId === jobId;
+
+    const unlisteners = [
+      listen<ScanProgress>('scan-progress', (event) => {
+        if (isOurJob(event.payload.job_id)) {
+          setScanProgress(event.payload);
+        }
+      }),
+      listen<ScanFinished>('scan-finished', (event) => {
+        if (!isOurJob(event.payload.job_id)) {
+          return;
+        }
+        if (event.payload.state === 'Failed') {
+          setError(event.payload.error ?? 'Scan failed');
+        } else {
+          setPrograms(event.payload.programs);
+          console.log(`Loaded ${event.payload.programs.length} programs (${event.payload.state})`);
+          if (event.payload.error) {
+            toast({
+              title: 'Scan finished with a problem',
+              description: event.payload.error,
+              status: 'warning',
+              duration: 8000,
+            });
+          }
+        }
+        setLoading(false);
+      }),
+    ];
+
+    const startScan = async () => {
       try {
-        const installedPrograms = await invoke<ProgramInfo[]>('get_installed_programs');
-        setPrograms(installedPrograms);
-        console.log(`Loaded ${installedPrograms.length} programs`);
+        await Promise.all(unlisteners);
+        const options: ScanOptions = {
+          hash_executables: settings.hashExecutablesDuringScan,
+          ioc_list: settings.iocListPath || null,
+        };
+        jobId = await invoke<string>('start_scan', { options });
+        setScanJobId(jobId);
       } catch (err) {
         setError(err as string);
-      } finally {
         setLoading(false);
       }
     };
 
-    fetchPrograms();
-  }, []);
+    startScan();
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((stop) => stop()));
+    };
+  }, [isLoadingSettings]);
+
+  const handleCancelScan = async () => {
+    if (scanJobId) {
+      await invoke('cancel_scan', { jobId: scanJobId });
+    }
+  };
 
   const handleSort = (field: SortField) => {
     if (field === sortField) {
@@ -442,11 +508,18 @@ export const ProgramList: React.FC = () => {
       <Box p={5} textAlign="center">
         <Spinner size="xl" color="blue.500" mb={4} />
         <Text fontSize="lg" color="gray.600">
-          Scanning installed programs...
+          {scanProgress?.current_operation ?? 'Scanning installed programs'}...
         </Text>
         <Text fontSize="sm" color="gray.500" mt={2}>
-          This may take a moment for the first scan
+          {scanProgress && scanProgress.total_keys > 0
+            ? `${scanProgress.scanned_keys} of ${scanProgress.total_keys} items checked, ${scanProgress.programs_found} entries found`
+            : 'This may take a moment for the first scan'}
         </Text>
+        {scanJobId && (
+          <Button size="sm" mt={4} onClick={handleCancelScan}>
+            Cancel Scan
+          </Button>
+        )}
       </Box>
     );
   }
@@ -681,9 +754,24 @@ export const ProgramList: React.FC = () => {
                       <option value="all">All Architectures</option>
                       <option value="32-bit">32-bit</option>
                       <option value="64-bit">64-bit</option>
+                      <option value="ARM64">ARM64</option>
+                      <option value="ARM">ARM</option>
+                      <option value="Neutral">Neutral (Any CPU)</option>
                       <option value="User">User</option>
                       <option value="Unknown">Unknown</option>
                     </Select>
+
+                    <Select
+                      value={healthFilter}
+                      onChange={(e) => setHealthFilter(e.target.value as HealthFilter)}
+                      size="sm"
+                    >
+                      <option value="all">Any Entry Health</option>
+                      <option value="problems">Any Problem</option>
+                      <option value="Degraded">Degraded</option>
+                      <option value="Broken">Broken</option>
+                      <option value="Orphaned">Orphaned</option>
+                    </Select>
                   </VStack>
 
                   {/* Installation Filters */}
@@ -701,6 +789,7 @@ export const ProgramList: React.FC = () => {
                       <option value="System">System</option>
                       <option value="User">User</option>
                       <option value="Filesystem">Filesystem</option>
+                      <option value="MSIX">MSIX / Store</option>
                     </Select>
 
                     <Select
@@ -816,6 +905,7 @@ export const ProgramList: React.FC = () => {
                       colorScheme={
                         program.installation_source === 'System' ? 'blue' :
                         program.installation_source === 'User' ? 'green' :
+                        program.installation_source === 'MSIX' ? 'teal' :
                         'orange'
                       }
                     >
@@ -877,6 +967,7 @@ export const ProgramList: React.FC = () => {
                         colorScheme={
                           program.installation_source === 'System' ? 'blue' :
                           program.installation_source === 'User' ? 'green' :
+                          program.installation_source === 'MSIX' ? 'teal' :
                           'orange'
                         }
                       >
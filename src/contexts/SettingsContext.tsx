@@ -31,6 +31,13 @@ export interface AppSettings {
   enableVfLogViewer: boolean;
   logViewerMaxFileSize: number; // in MB
   
+  // Signature Verification Settings
+  trustedRootsBundle: string; // Empty for trusted-roots.pem in the app data directory
+  
+  // File Hash Settings
+  hashExecutablesDuringScan: boolean;
+  iocListPath: string; // CSV or STIX JSON of file hashes; empty to hash without matching
+  
   // Future Features (placeholders)
   enableRemoteScanning: boolean;
   enableExternalDrives: boolean;
@@ -56,6 +63,9 @@ const defaultSettings: AppSettings = {
   vfLogPath: 'C:\\Windows\\VCLogs',
   enableVfLogViewer: true,
   logViewerMaxFileSize: 10, // 10MB default
+  trustedRootsBundle: '',
+  hashExecutablesDuringScan: false,
+  iocListPath: '',
   enableRemoteScanning: false,
   enableExternalDrives: false,
   enableCLI: false,
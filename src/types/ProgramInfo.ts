@@ -2,9 +2,12 @@ export interface ProgramInfo {
   name: string;
   registry_name: string;
   version?: string;
+  version_info?: ProgramVersion;
   registry_time?: string;
   install_date?: string;
+  installed_on?: InstallDateInfo;
   installed_for?: string;
+  user_sid?: string;
   install_location?: string;
   install_source?: string;
   install_folder_created?: string;
@@ -21,7 +24,8 @@ export interface ProgramInfo {
   install_source_path?: string;
   installer_name?: string;
   release_type?: string;
-  icon_path?: string;
+  icon_path?: string;    // DisplayIcon, resolved; unset when the file is not there
+  display_icon?: string; // DisplayIcon as written
   msi_filename?: string;
   estimated_size?: number;
   attributes?: string;
@@ -30,11 +34,433 @@ export interface ProgramInfo {
   shortcuts?: string[];
   programdata_paths?: string[];
   registry_path: string;
+  registry_provenance?: RegistryProvenance;
   program_type: 'Application' | 'SystemComponent' | 'Update' | 'Portable Application' | 'Unknown';
   is_windows_installer: boolean;
-  architecture: '32-bit' | '64-bit' | 'User' | 'Unknown';
-  installation_source: 'System' | 'User' | 'Filesystem';
+  msi?: MsiProductInfo;
+  installer_technology?: InstallerTechnology; // MSI, NSIS, Inno Setup, ..., with the evidence for it
+  health?: EntryHealthInfo; // Missing uninstaller, cached package, icon or folder; live scans only
+  install_size?: InstallSizeInfo; // Declared and measured size, once measured on request
+  executables?: ExecutableAnalysis; // Machine, subsystem and signer of the main executable and uninstaller, on request
+  file_hashes?: FileHashInventory; // SHA-256, SHA-1 and MD5 of those executables and any IOC hits, on request
+  architecture: '32-bit' | '64-bit' | 'ARM64' | 'ARM' | 'Neutral' | 'User' | 'Unknown';
+  installation_source: 'System' | 'User' | 'Filesystem' | 'MSIX';
   is_vf_deployed: boolean;
+  merged_from?: MergedEntry[];
+  field_sources?: Record<string, string>; // Field name -> registry path of the entry it was taken from
+}
+
+export interface MergedEntry {
+  name: string;
+  registry_path: string;
+  installation_source: string;
+  architecture: string;
+  matched_by: 'ProductCode' | 'InstallLocation' | 'NameVersion';
+}
+
+export interface MergeReport {
+  input_count: number;
+  output_count: number;
+  clusters: {
+    name: string;
+    kept_registry_path: string;
+    folded: MergedEntry[];
+  }[];
+}
+
+export interface ProgramVersion {
+  raw: string;
+  components: number[];
+  suffix?: string;
+  build?: string;
+  is_prerelease: boolean;
+  normalized: string;
+  sort_key: string; // Compares correctly with plain string comparison
+}
+
+export interface InstallDateInfo {
+  date: string; // YYYY-MM-DD
+  timestamp?: string;
+  source: 'InstallDate' | 'MsiInstallProperties' | 'InstallFolderCreated' | 'KeyLastWriteTime';
+  confidence: 'Low' | 'Medium' | 'High';
+}
+
+export interface RegistryProvenance {
+  hive: 'HKEY_LOCAL_MACHINE' | 'HKEY_CURRENT_USER' | 'HKEY_USERS' | 'HKEY_CLASSES_ROOT';
+  view: '64-bit' | '32-bit';
+  key_path: string;
+  last_write_time?: string;
+  value_types: Record<string, string>;
+}
+
+export interface MsiProductInfo {
+  product_code: string;
+  packed_product_code: string;
+  upgrade_code?: string;
+  local_package?: string;
+  context: 'Machine' | 'UserManaged' | 'UserUnmanaged';
+  user_sid?: string;
+}
+
+export interface MsiProductFamily {
+  upgrade_code: string;
+  members: {
+    name: string;
+    version?: string;
+    product_code: string;
+    context: MsiProductInfo['context'];
+    user_sid?: string;
+  }[];
+}
+
+export interface ScanProgress {
+  job_id: string | null;
+  state: 'Idle' | 'Running' | 'Completed' | 'Cancelled' | 'Failed';
+  total_keys: number;
+  scanned_keys: number;
+  programs_found: number; // Raw entries, before duplicates are merged
+  is_complete: boolean;
+  current_operation: string;
+  error?: string;
+}
+
+export interface ScanBatch {
+  job_id: string;
+  source: string;
+  programs: ProgramInfo[];
+}
+
+export interface ScanFinished {
+  job_id: string;
+  state: ScanProgress['state'];
+  programs: ProgramInfo[];
+  snapshot_id?: string; // Completed scans are saved as a snapshot
+  error?: string; // Why the scan failed, or why an optional stage could not run in full
+}
+
+export interface ScanOptions {
+  hash_executables: boolean;
+  ioc_list?: string | null;
+}
+
+export interface SnapshotSummary {
+  format_version: number;
+  id: string;
+  machine: string;
+  created_at: string;
+  sources: string[];
+  program_count: number;
+}
+
+export interface ProgramChange {
+  name: string;
+  registry_path: string;
+  before_version?: string;
+  after_version?: string;
+  changes: { field: string; before?: string; after?: string }[];
+}
+
+export interface SnapshotDiff {
+  from: SnapshotSummary;
+  to: SnapshotSummary;
+  diff: {
+    added: ProgramInfo[];
+    removed: ProgramInfo[];
+    upgraded: ProgramChange[];
+    downgraded: ProgramChange[];
+    changed: ProgramChange[];
+    unchanged_count: number;
+  };
+}
+
+export type InstallerEngine =
+  | 'WindowsInstaller' | 'Nsis' | 'InnoSetup' | 'InstallShield' | 'WixBurn'
+  | 'Squirrel' | 'ClickOnce' | 'Chocolatey' | 'Msix' | 'Portable' | 'Unknown';
+export type Confidence = 'Low' | 'Medium' | 'High';
+
+export interface InstallerTechnology {
+  engine: InstallerEngine;
+  confidence: Confidence;
+  evidence: string[];
+}
+
+export interface ParsedCommandLine {
+  raw: string;
+  executable: string;
+  arguments: string[];
+  argument_string: string; // Arguments as written, with their original quoting
+  kind: 'Executable' | 'Msiexec' | 'Rundll32';
+  msi_action?: 'Install' | 'Uninstall' | 'Repair';
+  product_code?: string;
+  msi_package?: string;
+  dll_path?: string;
+  entry_point?: string;
+}
+
+export interface SilentUninstall {
+  command: string;
+  engine: InstallerEngine;
+  confidence: Confidence;
+  origin: 'QuietUninstallString' | 'Synthesized';
+  note?: string;
+}
+
+export interface UninstallAnalysis {
+  uninstall?: ParsedCommandLine;
+  quiet_uninstall?: ParsedCommandLine;
+  modify?: ParsedCommandLine;
+  engine: InstallerEngine;
+  engine_confidence: Confidence;
+  silent_uninstall?: SilentUninstall;
+}
+
+export type ProgramAction = 'Uninstall' | 'QuietUninstall' | 'Modify' | 'Repair';
+
+export interface ActionResult {
+  name: string;
+  registry_path: string;
+  action: ProgramAction;
+  command: string;
+  exit_code?: number;
+  outcome: 'Success' | 'RebootRequired' | 'RebootInitiated' | 'NotInstalled' | 'InstallInProgress' | 'Failed' | 'TimedOut';
+  message: string;
+  stdout: string;
+  stderr: string;
+  started_at: string;
+  duration_ms: number;
+}
+
+export interface QueueOptions {
+  failure_policy: 'Halt' | 'Continue';
+  max_retries: number;          // Extra attempts while another installation is in progress (1618)
+  retry_delay_seconds: number;
+  timeout_seconds: number;      // Per attempt
+  keep_order: boolean;
+}
+
+export interface QueueItemStatus {
+  job_id: string;
+  position: number;
+  name: string;
+  registry_path: string;
+  state: 'Pending' | 'Running' | 'Retrying' | 'Succeeded' | 'Failed' | 'Skipped' | 'NotRun';
+  attempts: number;
+  command?: string;
+  result?: ActionResult;
+  message?: string;
+}
+
+export interface UninstallReport {
+  job_id: string;
+  started_at: string;
+  finished_at?: string;
+  failure_policy: QueueOptions['failure_policy'];
+  halted: boolean;
+  cancelled: boolean;
+  succeeded: number;
+  failed: number;
+  skipped: number;
+  not_run: number;
+  reboot_required: boolean;
+  items: QueueItemStatus[];
+}
+
+export interface QueueResolution {
+  programs: ProgramInfo[];
+  unresolved: string[];
+  ambiguous: AmbiguousEntry[]; // Names that matched several programs; none of them is queued
+}
+
+export interface AmbiguousEntry {
+  entry: string;
+  candidates: ProgramInfo[];
+}
+
+export type EntryHealth = 'Healthy' | 'Degraded' | 'Broken' | 'Orphaned';
+
+export interface EntryHealthInfo {
+  status: EntryHealth;
+  issues: {
+    kind: 'NoUninstallCommand' | 'UninstallerMissing' | 'LocalPackageMissing' | 'DisplayIconMissing' | 'InstallLocationMissing';
+    path?: string;
+  }[];
+}
+
+export interface EntryHealthReport {
+  checked: number;
+  healthy: number;
+  degraded: number;
+  broken: number;
+  orphaned: number;
+  entries: {
+    name: string;
+    publisher?: string;
+    version?: string;
+    registry_path: string;
+    health: EntryHealthInfo;
+  }[];
+}
+
+export interface DiskUsage {
+  logical_bytes: number;
+  allocated_bytes: number;
+  file_count: number;
+  folder_count: number;
+  hard_link_duplicates: number;
+  sparse_files: number;
+  skipped_links: number;
+  unreadable: number;
+  complete: boolean;
+  elapsed_ms: number;
+}
+
+export interface InstallSizeInfo {
+  declared_kb?: number; // EstimatedSize, in KB
+  declared_bytes?: number;
+  measured_path?: string;
+  measured?: DiskUsage;
+  error?: string;
+  discrepancy?: 'Understated' | 'Overstated' | 'Undeclared';
+  measured_at: string;
+}
+
+export interface SignatureInfo {
+  signer_subject: string; // "CN=..., O=..., C=..."
+  signer_name?: string;
+  issuer: string;
+  serial_number: string;
+  digest_algorithm: string;
+  valid_from?: string;
+  valid_to?: string;
+  timestamp?: string;
+  timestamp_kind?: 'Rfc3161' | 'Authenticode';
+  timestamp_authority?: string;
+  certificate_count: number;
+  nested: SignatureInfo[];
+}
+
+export interface ImageInfo {
+  path: string;
+  machine: 'X86' | 'X64' | 'Arm' | 'Arm64' | 'Itanium' | 'Other';
+  machine_code: number;
+  is_64_bit: boolean;
+  subsystem: 'Gui' | 'Console' | 'Native' | 'Efi' | 'Other';
+  is_dll: boolean;
+  dotnet?: {
+    runtime_version?: string;
+    il_only: boolean;
+    requires_32_bit: boolean;
+    prefers_32_bit: boolean;
+  };
+  any_cpu: boolean;
+  signature?: SignatureInfo;
+  signature_error?: string;
+}
+
+export interface ExecutableAnalysis {
+  main_executable?: ImageInfo;
+  uninstaller?: ImageInfo;
+  errors: string[];
+  architecture_mismatch: boolean; // The main executable is not built for the registry view it is listed in
+  analyzed_at: string;
+}
+
+export type SignatureStatus = 'Valid' | 'Expired' | 'Untrusted' | 'Invalid' | 'Unsigned';
+
+export interface SignatureVerification {
+  status: SignatureStatus;
+  image_hash_valid: boolean; // The signed digest matches the file as it is now
+  signature_valid: boolean;
+  signer_name?: string;
+  signer_organization?: string;
+  chain: string[]; // Subjects from the signer up to the root
+  trusted_root?: string;
+  timestamp?: string;
+  timestamp_valid?: boolean;
+  checked_at: string; // When certificate validity was judged: the timestamp if it checks out, else now
+  problems: string[];
+}
+
+export interface SignatureTrustItem {
+  name: string;
+  publisher?: string;
+  registry_path: string;
+  role: 'MainExecutable' | 'Uninstaller';
+  path: string;
+  verification?: SignatureVerification;
+  error?: string;
+  publisher_mismatch: boolean; // Validly signed, but not by the registered publisher
+}
+
+export interface SignatureTrustReport {
+  checked: number;
+  executables: number;
+  valid: number;
+  unsigned: number;
+  invalid: number;
+  expired: number;
+  untrusted: number;
+  publisher_mismatches: number;
+  trusted_roots: string;
+  trusted_root_count: number;
+  entries: SignatureTrustItem[]; // Problems first
+}
+
+export type HashType = 'Sha256' | 'Sha1' | 'Md5';
+
+export interface HashedFile {
+  path: string;
+  size: number;
+  modified?: string;
+  hashes: {
+    sha256: string;
+    sha1: string;
+    md5: string;
+  };
+  from_cache: boolean;
+}
+
+export interface IocHit {
+  role: 'MainExecutable' | 'Uninstaller';
+  path: string;
+  hash_type: HashType;
+  hash: string;
+  indicator?: string; // The feed's name for it
+  ioc_source: string;
+}
+
+export interface FileHashInventory {
+  main_executable?: HashedFile;
+  uninstaller?: HashedFile;
+  ioc_hits: IocHit[];
+  ioc_checked: boolean; // False when no IOC list was given
+  errors: string[];
+  hashed_at: string;
+}
+
+export interface Leftover {
+  kind: 'Folder' | 'File' | 'Shortcut' | 'RegistryKey';
+  path: string;
+  reason: 'InstallLocation' | 'Uninstaller' | 'LocalPackage' | 'UninstallKey' | 'MsiRegistration' | 'ShortcutTarget' | 'NameMatch' | 'PublisherMatch';
+  confidence: Confidence;
+  detail: string;
+}
+
+export interface LeftoverBaselineSummary {
+  id: string;
+  name: string;
+  registry_path: string;
+  created_at: string;
+  item_count: number;
+}
+
+export interface LeftoverReport {
+  name: string;
+  registry_path: string;
+  still_registered: boolean;
+  baseline_id?: string;
+  removed: number;
+  leftovers: Leftover[];
 }
 
 export interface AteaInformation {